@@ -26,6 +26,7 @@ import seaborn as sns
 from pathlib import Path
 import warnings
 import sys
+import os
 warnings.filterwarnings('ignore')
 
 # Configuração de estilo
@@ -34,18 +35,59 @@ sns.set_palette("husl")
 plt.rcParams['figure.figsize'] = (12, 8)
 plt.rcParams['font.size'] = 10
 
-def carregar_dados():
-    """Carrega os dados do experimento com análise de normalidade"""
-    results_dir = Path("../results")
-    pattern = "resultados_normality_check_*.csv"
-    
-    arquivos = list(results_dir.glob(pattern))
-    if not arquivos:
-        print("Nenhum arquivo de resultados de normalidade encontrado!")
-        return None, None
-    
-    # Pega o arquivo mais recente
-    arquivo_mais_recente = max(arquivos, key=lambda x: x.stat().st_mtime)
+# Versões do esquema de colunas (schema_version) que este script entende
+SCHEMAS_SUPORTADOS = {1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33}
+
+
+def verificar_schema(df, arquivo):
+    """Recusa CSVs com versão de esquema desconhecida"""
+    if 'schema_version' not in df.columns:
+        print(f"Aviso: {arquivo} não possui schema_version (formato anterior ao versionamento)")
+        return True
+    versoes = set(df['schema_version'].unique())
+    desconhecidas = versoes - SCHEMAS_SUPORTADOS
+    if desconhecidas:
+        print(f"Erro: {arquivo} usa schema_version {sorted(desconhecidas)}; "
+              f"este script suporta {sorted(SCHEMAS_SUPORTADOS)}")
+        return False
+    return True
+
+
+def renomear_colunas_banda(df):
+    """Renomeia as colunas de banda de CSVs anteriores ao esquema 16 (totais em bytes)"""
+    renomear = {}
+    for coluna in df.columns:
+        if coluna.startswith(('kem_bw_', 'msg_bw_')) and not coluna.endswith('_bps') \
+                and not coluna.startswith(('kem_bw_bytes_', 'msg_bw_bytes_')):
+            renomear[coluna] = coluna.replace('_bw_', '_bw_bytes_', 1)
+    if 'rekey_bw_mean' in df.columns:
+        renomear['rekey_bw_mean'] = 'rekey_bw_bytes_mean'
+    return df.rename(columns=renomear)
+
+
+def carregar_dados(arquivo=None):
+    """Carrega os dados do experimento com análise de normalidade
+
+    O experimento Rust passa o CSV da execução como primeiro argumento; sem ele,
+    usa o resultado mais recente da pasta de resultados.
+    """
+    if arquivo is not None:
+        arquivo_mais_recente = Path(arquivo)
+        if not arquivo_mais_recente.is_file():
+            print(f"Arquivo de resultados não encontrado: {arquivo_mais_recente}")
+            return None, None
+    else:
+        # A pasta pode ser definida pelo experimento Rust (--output-dir)
+        results_dir = Path(os.environ.get("PQC_RESULTS_DIR", "../results"))
+        pattern = "resultados_normality_check_*.csv"
+
+        arquivos = list(results_dir.glob(pattern))
+        if not arquivos:
+            print("Nenhum arquivo de resultados de normalidade encontrado!")
+            return None, None
+
+        # Pega o arquivo mais recente
+        arquivo_mais_recente = max(arquivos, key=lambda x: x.stat().st_mtime)
     print(f"Carregando dados de: {arquivo_mais_recente}")
     
     # Extrai timestamp do nome do arquivo
@@ -53,6 +95,9 @@ def carregar_dados():
     timestamp_part = filename.replace('resultados_normality_check_', '').replace('.csv', '')
     
     df = pd.read_csv(arquivo_mais_recente)
+    if not verificar_schema(df, arquivo_mais_recente):
+        return None, None
+    df = renomear_colunas_banda(df)
     
     # Calcula métricas adicionais
     df['throughput'] = df['num_msgs'] / (df['cipher_ms_mean'] / 1000)
@@ -62,8 +107,8 @@ def carregar_dados():
     # Calcula porcentagem de outliers
     df['kem_outliers_pct'] = (df['kem_outliers'] / (df['kem_sample_size'] + df['kem_outliers'])) * 100
     df['cipher_outliers_pct'] = (df['cipher_outliers'] / (df['cipher_sample_size'] + df['cipher_outliers'])) * 100
-    df['kem_bw_outliers_pct'] = (df['kem_bw_outliers'] / (df['kem_bw_sample_size'] + df['kem_bw_outliers'])) * 100
-    df['msg_bw_outliers_pct'] = (df['msg_bw_outliers'] / (df['msg_bw_sample_size'] + df['msg_bw_outliers'])) * 100
+    df['kem_bw_bytes_outliers_pct'] = (df['kem_bw_bytes_outliers'] / (df['kem_bw_bytes_sample_size'] + df['kem_bw_bytes_outliers'])) * 100
+    df['msg_bw_bytes_outliers_pct'] = (df['msg_bw_bytes_outliers'] / (df['msg_bw_bytes_sample_size'] + df['msg_bw_bytes_outliers'])) * 100
     
     return df, timestamp_part
 
@@ -73,7 +118,7 @@ def grafico_analise_normalidade(df, plots_subdir):
     fig.suptitle('Análise de Normalidade das Métricas', fontsize=16, fontweight='bold')
     
     # Contagem de normalidade por métrica
-    metricas = ['kem_normal', 'cipher_normal', 'kem_bw_normal', 'msg_bw_normal']
+    metricas = ['kem_normal', 'cipher_normal', 'kem_bw_bytes_normal', 'msg_bw_bytes_normal']
     labels = ['KEM Times', 'Cipher Times', 'KEM Bandwidth', 'Msg Bandwidth']
     
     for i, (metrica, label) in enumerate(zip(metricas, labels)):
@@ -116,7 +161,7 @@ def grafico_distribuicao_outliers(df, plots_subdir):
     fig.suptitle('Distribuição de Outliers por Métrica', fontsize=16, fontweight='bold')
     
     # Métricas de outliers
-    metricas = ['kem_outliers_pct', 'cipher_outliers_pct', 'kem_bw_outliers_pct', 'msg_bw_outliers_pct']
+    metricas = ['kem_outliers_pct', 'cipher_outliers_pct', 'kem_bw_bytes_outliers_pct', 'msg_bw_bytes_outliers_pct']
     labels = ['KEM Times', 'Cipher Times', 'KEM Bandwidth', 'Msg Bandwidth']
     
     for i, (metrica, label) in enumerate(zip(metricas, labels)):
@@ -143,7 +188,7 @@ def grafico_comparacao_estatisticas(df, plots_subdir):
     fig.suptitle('Comparação: Estatísticas Paramétricas vs Robustas', fontsize=16, fontweight='bold')
     
     # Métricas de tipo de estatística
-    metricas = ['kem_stat_type', 'cipher_stat_type', 'kem_bw_stat_type', 'msg_bw_stat_type']
+    metricas = ['kem_stat_type', 'cipher_stat_type', 'kem_bw_bytes_stat_type', 'msg_bw_bytes_stat_type']
     labels = ['KEM Times', 'Cipher Times', 'KEM Bandwidth', 'Msg Bandwidth']
     
     for i, (metrica, label) in enumerate(zip(metricas, labels)):
@@ -186,7 +231,7 @@ def grafico_qualidade_amostras(df, plots_subdir):
     fig.suptitle('Qualidade das Amostras Após Limpeza', fontsize=16, fontweight='bold')
     
     # Tamanhos das amostras
-    sample_sizes = ['kem_sample_size', 'cipher_sample_size', 'kem_bw_sample_size', 'msg_bw_sample_size']
+    sample_sizes = ['kem_sample_size', 'cipher_sample_size', 'kem_bw_bytes_sample_size', 'msg_bw_bytes_sample_size']
     labels = ['KEM Times', 'Cipher Times', 'KEM Bandwidth', 'Msg Bandwidth']
     
     for i, (sample_size, label) in enumerate(zip(sample_sizes, labels)):
@@ -215,7 +260,7 @@ def grafico_outliers_extremos(df, plots_subdir):
     fig.suptitle('Análise de Outliers Extremos', fontsize=16, fontweight='bold')
     
     # Métricas de outliers extremos
-    metricas = ['kem_extreme_outliers', 'cipher_extreme_outliers', 'kem_bw_extreme_outliers', 'msg_bw_extreme_outliers']
+    metricas = ['kem_extreme_outliers', 'cipher_extreme_outliers', 'kem_bw_bytes_extreme_outliers', 'msg_bw_bytes_extreme_outliers']
     labels = ['KEM Times', 'Cipher Times', 'KEM Bandwidth', 'Msg Bandwidth']
     
     for i, (metrica, label) in enumerate(zip(metricas, labels)):
@@ -270,7 +315,7 @@ def grafico_correlacao_metricas(df, plots_subdir):
     fig.suptitle('Correlação entre Métricas Estatísticas', fontsize=16, fontweight='bold')
     
     # Correlação entre outliers e normalidade
-    df_corr = df[['kem_outliers_pct', 'cipher_outliers_pct', 'kem_bw_outliers_pct', 'msg_bw_outliers_pct']].corr()
+    df_corr = df[['kem_outliers_pct', 'cipher_outliers_pct', 'kem_bw_bytes_outliers_pct', 'msg_bw_bytes_outliers_pct']].corr()
     sns.heatmap(df_corr, annot=True, cmap='coolwarm', center=0, ax=axes[0,0])
     axes[0,0].set_title('Correlação entre % de Outliers')
     
@@ -290,7 +335,7 @@ def grafico_correlacao_metricas(df, plots_subdir):
     axes[1,0].legend()
     
     # Distribuição de intervalos de confiança
-    ic_data = [df['kem_ms_ci95'], df['cipher_ms_ci95'], df['kem_bw_ci95'], df['msg_bw_ci95']]
+    ic_data = [df['kem_ms_ci95'], df['cipher_ms_ci95'], df['kem_bw_bytes_ci95'], df['msg_bw_bytes_ci95']]
     ic_labels = ['KEM Time', 'Cipher Time', 'KEM BW', 'Msg BW']
     
     # Remove possíveis valores NaN ou infinitos
@@ -336,7 +381,7 @@ def grafico_desempenho_por_cenario(df, plots_subdir):
     axes[0,1].tick_params(axis='x', rotation=45)
     
     # Largura de banda por cenário
-    sns.boxplot(data=df, x='cenario', y='msg_bw_mean', ax=axes[1,0])
+    sns.boxplot(data=df, x='cenario', y='msg_bw_bytes_mean', ax=axes[1,0])
     axes[1,0].set_title('Largura de Banda por Cenário')
     axes[1,0].set_ylabel('Bytes')
     axes[1,0].tick_params(axis='x', rotation=45)
@@ -375,7 +420,7 @@ def grafico_comparacao_algoritmos(df, plots_subdir):
     axes[1,0].tick_params(axis='x', rotation=45)
     
     # Largura de banda KEM por algoritmo
-    sns.boxplot(data=df, x='cifra', y='kem_bw_mean', ax=axes[1,1])
+    sns.boxplot(data=df, x='cifra', y='kem_bw_bytes_mean', ax=axes[1,1])
     axes[1,1].set_title('Largura de Banda KEM por Algoritmo')
     axes[1,1].set_ylabel('Largura de Banda (bytes)')
     axes[1,1].tick_params(axis='x', rotation=45)
@@ -393,7 +438,7 @@ def grafico_analise_acordos_chave(df, plots_subdir):
     axes[0].set_title('Tempo de KEM por Tipo de Acordo (Boxplot)')
     axes[0].set_ylabel('Tempo de KEM (ms)')
     axes[0].set_xlabel('Tipo de Acordo')
-    sns.boxplot(data=df, x='acordo', y='kem_bw_mean', ax=axes[1], palette=colors[:len(acordo_types)])
+    sns.boxplot(data=df, x='acordo', y='kem_bw_bytes_mean', ax=axes[1], palette=colors[:len(acordo_types)])
     axes[1].set_title('Largura de Banda KEM por Tipo de Acordo (Boxplot)')
     axes[1].set_ylabel('Largura de Banda KEM (bytes)')
     axes[1].set_xlabel('Tipo de Acordo')
@@ -446,7 +491,7 @@ def grafico_analise_acordos_chave(df, plots_subdir):
     plt.close()
 
     # Largura de banda KEM por tipo de acordo
-    plot_bar_ic('kem_bw_mean', 'kem_bw_ci95', 'Largura de Banda KEM (bytes)',
+    plot_bar_ic('kem_bw_bytes_mean', 'kem_bw_bytes_ci95', 'Largura de Banda KEM (bytes)',
                 'Largura de Banda KEM por Tipo de Acordo (com IC95)', 'acordos_kem_bw.png')
 
     # Latência total por tipo de acordo
@@ -466,7 +511,7 @@ def grafico_overhead_protocolos(df, plots_dir):
     axes[0].set_title('Tempo de Estabelecimento de Chaves (KEM) por Protocolo')
     axes[0].set_ylabel('Tempo (ms)')
     axes[0].set_xlabel('Protocolo de Acordo')
-    sns.boxplot(data=df, x='acordo', y='kem_bw_mean', ax=axes[1])
+    sns.boxplot(data=df, x='acordo', y='kem_bw_bytes_mean', ax=axes[1])
     axes[1].set_title('Largura de Banda KEM por Protocolo')
     axes[1].set_ylabel('Bytes')
     axes[1].set_xlabel('Protocolo de Acordo')
@@ -573,9 +618,9 @@ def grafico_intervalos_confianca(df, plots_dir):
     axes[0,0].set_title('IC95 KEM (ms)')
     sns.boxplot(data=df, x='cenario', y='cipher_ms_ci95', ax=axes[0,1])
     axes[0,1].set_title('IC95 Cipher (ms)')
-    sns.boxplot(data=df, x='cenario', y='kem_bw_ci95', ax=axes[1,0])
+    sns.boxplot(data=df, x='cenario', y='kem_bw_bytes_ci95', ax=axes[1,0])
     axes[1,0].set_title('IC95 KEM BW (bytes)')
-    sns.boxplot(data=df, x='cenario', y='msg_bw_ci95', ax=axes[1,1])
+    sns.boxplot(data=df, x='cenario', y='msg_bw_bytes_ci95', ax=axes[1,1])
     axes[1,1].set_title('IC95 Msg BW (bytes)')
     plt.tight_layout()
     plt.savefig(plots_dir / 'intervalos_confianca.png', dpi=300)
@@ -584,7 +629,7 @@ def grafico_intervalos_confianca(df, plots_dir):
 def grafico_correlacao_kem_bw(df, plots_dir):
     """Gráfico de correlação entre tempo de KEM e largura de banda KEM"""
     plt.figure(figsize=(8,6))
-    sns.scatterplot(data=df, x='kem_ms_mean', y='kem_bw_mean', hue='acordo')
+    sns.scatterplot(data=df, x='kem_ms_mean', y='kem_bw_bytes_mean', hue='acordo')
     plt.title('Correlação entre Tempo de KEM e Largura de Banda')
     plt.xlabel('Tempo KEM (ms)')
     plt.ylabel('Largura de Banda KEM (bytes)')
@@ -606,6 +651,9 @@ def grafico_tamanho_amostras(df, plots_dir):
 def grafico_proporcao_tipos_mensagem(df, plots_dir):
     """Gráfico da proporção de tipos de mensagem por cenário"""
     tipos = ['text_msgs', 'image_msgs', 'file_msgs', 'system_msgs']
+    # voice_msgs existe a partir do schema_version 9 (antes, a voz era contada como texto)
+    if 'voice_msgs' in df.columns:
+        tipos.append('voice_msgs')
     df_tipos = df.groupby('cenario')[tipos].sum()
     df_tipos_pct = df_tipos.div(df_tipos.sum(axis=1), axis=0)
     df_tipos_pct.plot(kind='bar', stacked=True, figsize=(12,7), colormap='tab20')
@@ -619,7 +667,7 @@ def grafico_proporcao_tipos_mensagem(df, plots_dir):
 
 def grafico_heatmap_correlacao(df, plots_dir):
     """Gráfico de heatmap de correlação das métricas"""
-    cols = ['kem_ms_mean', 'kem_bw_mean', 'cipher_ms_mean', 'msg_bw_mean', 'rotacoes']
+    cols = ['kem_ms_mean', 'kem_bw_bytes_mean', 'cipher_ms_mean', 'msg_bw_bytes_mean', 'rotacoes']
     corr = df[cols].corr()
     plt.figure(figsize=(8,6))
     sns.heatmap(corr, annot=True, cmap='coolwarm', center=0)
@@ -691,7 +739,7 @@ def grafico_largura_banda_kem_por_rotacao(df, plots_subdir):
     for protocolo, cor in zip(protocolos, cores):
         sub = dados[dados['acordo'] == protocolo].sort_values('msgs_por_rotacao')
         x = sub['msgs_por_rotacao']
-        y = sub['kem_bw_mean'] / (1024*1024)  # bytes para MB
+        y = sub['kem_bw_bytes_mean'] / (1024*1024)  # bytes para MB
         cenarios = sub['cenario']
         plt.plot(x, y, marker='o', label=protocolo, color=cor)
         for xi, yi, cenario in zip(x, y, cenarios):
@@ -727,7 +775,7 @@ def grafico_largura_banda_kem_vs_rotacoes(df, plots_subdir):
             sub_c = sub[sub['cenario'] == c]
             if not sub_c.empty:
                 xi = sub_c['rotacoes'].values[0] + jitter[protocolo]
-                yi = sub_c['kem_bw_mean'].values[0] / 1024  # bytes para KB
+                yi = sub_c['kem_bw_bytes_mean'].values[0] / 1024  # bytes para KB
                 x.append(xi)
                 y.append(yi)
                 labels.append(c)
@@ -779,7 +827,7 @@ def grafico_overhead_largura_banda_por_cenario(df, plots_dir):
     protocolos = ['Olm-Clássico', 'Olm-Híbrido']
     x = np.arange(len(cenarios))
     width = 0.35
-    means = {p: [df[(df['cenario'] == c) & (df['acordo'] == p)]['kem_bw_mean'].mean()/1024 for c in cenarios] for p in protocolos}
+    means = {p: [df[(df['cenario'] == c) & (df['acordo'] == p)]['kem_bw_bytes_mean'].mean()/1024 for c in cenarios] for p in protocolos}
     plt.figure(figsize=(10,6))
     plt.bar(x - width/2, means['Olm-Clássico'], width, label='Olm-Clássico')
     plt.bar(x + width/2, means['Olm-Híbrido'], width, label='Olm-Híbrido')
@@ -791,6 +839,49 @@ def grafico_overhead_largura_banda_por_cenario(df, plots_dir):
     plt.savefig(plots_dir / 'overhead_largura_banda_por_cenario.png', dpi=300)
     plt.close()
 
+def grafico_largura_banda_acumulada(timestamp, plots_dir):
+    """Largura de banda acumulada por mensagem, clássico vs híbrido (--bandwidth-series)"""
+    results_dir = Path(os.environ.get("PQC_RESULTS_DIR", "../results"))
+    arquivo = results_dir / f"largura_banda_acumulada_{timestamp}.csv"
+    if not arquivo.exists():
+        print(f"  {arquivo.name} não encontrado (execute com --bandwidth-series); gráfico ignorado")
+        return
+    serie = pd.read_csv(arquivo)
+    cenarios = serie['cenario'].unique()
+    fig, axes = plt.subplots(1, len(cenarios), figsize=(6 * len(cenarios), 5), squeeze=False)
+    for ax, cenario in zip(axes[0], cenarios):
+        dados = serie[serie['cenario'] == cenario]
+        # Média entre padrões de tráfego e cifras, por acordo e índice de mensagem
+        media = dados.groupby(['acordo', 'indice_mensagem'])['bytes_acumulados'].mean().reset_index()
+        for acordo, grupo in media.groupby('acordo'):
+            ax.plot(grupo['indice_mensagem'], grupo['bytes_acumulados'] / 1024, label=acordo)
+        ax.set_title(cenario)
+        ax.set_xlabel('Mensagens enviadas')
+        ax.set_ylabel('Bytes acumulados (KB)')
+        ax.legend()
+    plt.suptitle('Largura de Banda Acumulada por Mensagem')
+    plt.tight_layout()
+    plt.savefig(plots_dir / 'largura_banda_acumulada.png', dpi=300)
+    plt.close()
+
+    # Bytes por mensagem suavizados pela média móvel de --series-window (arquivos novos)
+    if 'bytes_mensagem_media_movel' not in serie.columns:
+        return
+    fig, axes = plt.subplots(1, len(cenarios), figsize=(6 * len(cenarios), 5), squeeze=False)
+    for ax, cenario in zip(axes[0], cenarios):
+        dados = serie[serie['cenario'] == cenario]
+        media = dados.groupby(['acordo', 'indice_mensagem'])['bytes_mensagem_media_movel'].mean().reset_index()
+        for acordo, grupo in media.groupby('acordo'):
+            ax.plot(grupo['indice_mensagem'], grupo['bytes_mensagem_media_movel'], label=acordo)
+        ax.set_title(cenario)
+        ax.set_xlabel('Mensagens enviadas')
+        ax.set_ylabel('Bytes por mensagem (média móvel)')
+        ax.legend()
+    plt.suptitle('Bytes por Mensagem (Média Móvel)')
+    plt.tight_layout()
+    plt.savefig(plots_dir / 'bytes_por_mensagem.png', dpi=300)
+    plt.close()
+
 def grafico_eficiencia_pcs_fs_radar(df, plots_dir):
     """Gráfico radar de eficiência PCS/FS por cenário"""
     import matplotlib.pyplot as plt
@@ -871,15 +962,15 @@ def grafico_sensibilidade_rotacao(df, plots_dir):
     plt.close()
 
 if __name__ == "__main__":
-    # Carrega os dados
-    df, timestamp = carregar_dados()
+    # Carrega os dados (o CSV de resultados, se informado, é o primeiro argumento)
+    df, timestamp = carregar_dados(sys.argv[1] if len(sys.argv) > 1 else None)
     if df is None:
         sys.exit(1)  
 
     # Debug: mostra info dos dados carregados
     print(f"\nDados carregados: {len(df)} amostras")
     print("\nColunas de normalidade disponíveis:")
-    for col in ['kem_normal', 'cipher_normal', 'kem_bw_normal', 'msg_bw_normal']:
+    for col in ['kem_normal', 'cipher_normal', 'kem_bw_bytes_normal', 'msg_bw_bytes_normal']:
         if col in df.columns:
             counts = df[col].value_counts()
             print(f"  {col}: {dict(counts)}")
@@ -887,7 +978,7 @@ if __name__ == "__main__":
             print(f"  {col}: COLUNA NÃO ENCONTRADA")
     
     print("\nColunas de tipo estatístico disponíveis:")
-    for col in ['kem_stat_type', 'cipher_stat_type', 'kem_bw_stat_type', 'msg_bw_stat_type']:
+    for col in ['kem_stat_type', 'cipher_stat_type', 'kem_bw_bytes_stat_type', 'msg_bw_bytes_stat_type']:
         if col in df.columns:
             counts = df[col].value_counts()
             print(f"  {col}: {dict(counts)}")
@@ -896,7 +987,7 @@ if __name__ == "__main__":
     
     # Debug adicional: mostra resumo das métricas numéricas
     print(f"\nResumo das métricas de outliers:")
-    outlier_cols = ['kem_outliers_pct', 'cipher_outliers_pct', 'kem_bw_outliers_pct', 'msg_bw_outliers_pct']
+    outlier_cols = ['kem_outliers_pct', 'cipher_outliers_pct', 'kem_bw_bytes_outliers_pct', 'msg_bw_bytes_outliers_pct']
     for col in outlier_cols:
         if col in df.columns:
             print(f"  {col}: min={df[col].min():.1f}%, max={df[col].max():.1f}%, média={df[col].mean():.1f}%")
@@ -904,8 +995,8 @@ if __name__ == "__main__":
             print(f"  {col}: COLUNA NÃO ENCONTRADA")
     
     # Cria pasta de plots se não existir
-    plots_dir = Path("../plots")
-    plots_dir.mkdir(exist_ok=True)
+    plots_dir = Path(os.environ.get("PQC_PLOTS_DIR", "../plots"))
+    plots_dir.mkdir(parents=True, exist_ok=True)
     
     # Cria subdiretório com timestamp do experimento
     plots_subdir = plots_dir / f"graficos_normality_check_{timestamp}"
@@ -1001,6 +1092,9 @@ if __name__ == "__main__":
         print("Gerando gráfico do overhead de largura de banda por cenário...")
         grafico_overhead_largura_banda_por_cenario(df, plots_subdir)
         
+        print("Gerando gráfico da largura de banda acumulada por mensagem...")
+        grafico_largura_banda_acumulada(timestamp, plots_subdir)
+
         print("Gerando gráfico radar de eficiência PCS/FS por cenário...")
         grafico_eficiencia_pcs_fs_radar(df, plots_subdir)
         
@@ -1034,20 +1128,20 @@ if __name__ == "__main__":
     
     print("\nANÁLISE ESTATÍSTICA:")
     normal_count = sum([df['kem_normal'].sum(), df['cipher_normal'].sum(), 
-                       df['kem_bw_normal'].sum(), df['msg_bw_normal'].sum()])
+                       df['kem_bw_bytes_normal'].sum(), df['msg_bw_bytes_normal'].sum()])
     total_tests = len(df) * 4  # 4 métricas por linha
     print(f"  • Testes de normalidade: {normal_count}/{total_tests} passaram ({100*normal_count/total_tests:.1f}%)")
     
     parametric_count = sum([
         (df['kem_stat_type'] == 'parametric').sum(),
         (df['cipher_stat_type'] == 'parametric').sum(),
-        (df['kem_bw_stat_type'] == 'parametric').sum(),
-        (df['msg_bw_stat_type'] == 'parametric').sum()
+        (df['kem_bw_bytes_stat_type'] == 'parametric').sum(),
+        (df['msg_bw_bytes_stat_type'] == 'parametric').sum()
     ])
     print(f"  • Estatísticas paramétricas: {parametric_count}/{total_tests} aplicadas ({100*parametric_count/total_tests:.1f}%)")
     
     print("\nOUTLIERS DETECTADOS:")
-    for col in ['kem_outliers', 'cipher_outliers', 'kem_bw_outliers', 'msg_bw_outliers']:
+    for col in ['kem_outliers', 'cipher_outliers', 'kem_bw_bytes_outliers', 'msg_bw_bytes_outliers']:
         if col in df.columns:
             total_outliers = df[col].sum()
             print(f"  • {col.replace('_', ' ').title()}: {total_outliers} no total")
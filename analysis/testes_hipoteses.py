@@ -25,6 +25,36 @@ class DualWriter:
         self.log.flush()
 
 
+# Versões do esquema de colunas (schema_version) que este script entende
+SCHEMAS_SUPORTADOS = {1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31, 32, 33}
+
+
+def verificar_schema(df, arquivo):
+    """Recusa CSVs com versão de esquema desconhecida"""
+    if 'schema_version' not in df.columns:
+        print(f"Aviso: {arquivo} não possui schema_version (formato anterior ao versionamento)")
+        return True
+    versoes = set(df['schema_version'].unique())
+    desconhecidas = versoes - SCHEMAS_SUPORTADOS
+    if desconhecidas:
+        print(f"Erro: {arquivo} usa schema_version {sorted(desconhecidas)}; "
+              f"este script suporta {sorted(SCHEMAS_SUPORTADOS)}")
+        return False
+    return True
+
+
+def renomear_colunas_banda(df):
+    """Renomeia as colunas de banda de CSVs anteriores ao esquema 16 (totais em bytes)"""
+    renomear = {}
+    for coluna in df.columns:
+        if coluna.startswith(('kem_bw_', 'msg_bw_')) and not coluna.endswith('_bps') \
+                and not coluna.startswith(('kem_bw_bytes_', 'msg_bw_bytes_')):
+            renomear[coluna] = coluna.replace('_bw_', '_bw_bytes_', 1)
+    if 'rekey_bw_mean' in df.columns:
+        renomear['rekey_bw_mean'] = 'rekey_bw_bytes_mean'
+    return df.rename(columns=renomear)
+
+
 def load_data():
     """Carregar dados do experimento"""
     results_dir = Path("../results")
@@ -44,6 +74,9 @@ def load_data():
     timestamp_part = filename.replace('resultados_normality_check_', '').replace('.csv', '')
     
     df = pd.read_csv(arquivo_mais_recente)
+    if not verificar_schema(df, arquivo_mais_recente):
+        return None
+    df = renomear_colunas_banda(df)
     return df
 
 def test_protocol_difference(df):
@@ -93,8 +126,8 @@ def test_protocol_difference(df):
     
     # Teste de largura de banda
     print(f"\n--- Largura de Banda ---")
-    bw_classico = df[df['acordo'] == 'Olm-Clássico']['kem_bw_mean']
-    bw_hibrido = df[df['acordo'] == 'Olm-Híbrido']['kem_bw_mean']
+    bw_classico = df[df['acordo'] == 'Olm-Clássico']['kem_bw_bytes_mean']
+    bw_hibrido = df[df['acordo'] == 'Olm-Híbrido']['kem_bw_bytes_mean']
     # Calcula o overhead de largura de banda para o protocolo híbrido
     overhead_bw = ((bw_hibrido.mean() - bw_classico.mean()) / bw_classico.mean()) * 100
     print(f"  Overhead de largura de banda: {overhead_bw:.1f}%")
@@ -217,8 +250,8 @@ def test_correlation(df):
         print("Coluna 'num_msgs' não encontrada.")
 
     # Correlação de Pearson entre rotações e largura de banda
-    corr2, p_value2 = stats.pearsonr(df['rotacoes'], df['kem_bw_mean'])
-    print(f"\nCorrelação: rotacoes vs kem_bw_mean")
+    corr2, p_value2 = stats.pearsonr(df['rotacoes'], df['kem_bw_bytes_mean'])
+    print(f"\nCorrelação: rotacoes vs kem_bw_bytes_mean")
     print(f"  r = {corr2:.4f}, p = {p_value2:.6f}")
 
 def main():
@@ -231,6 +264,8 @@ def main():
     print("="*60)
     
     df = load_data()
+    if df is None:
+        return
     print(f"Dados carregados: {len(df)} observações")
     
     test_protocol_difference(df)
@@ -0,0 +1,124 @@
+//! Microbenchmarks Criterion das primitivas criptográficas do experimento
+//!
+//! Mede cada acordo de chaves e cada cifra de forma isolada, sem o workload
+//! completo nem a análise estatística do binário principal:
+//! - Geração de chaves do destinatário (X25519 e X25519 + Kyber768)
+//! - Acordo de chaves completo (inclui encapsulamento/decapsulamento no híbrido), com o
+//!   segredo X25519 de Alice novo a cada acordo ou estático (`--ephemeral static`)
+//! - Cifragem de um payload de tamanho fixo gerado pelo módulo de workload, alocando o
+//!   texto cifrado, no próprio buffer (`--in-place`) ou com a tag destacada
+//!   (`--tag-layout detached`)
+//! - Verificação da tag HMAC da Megolm-Like e o custo isolado da comparação em tempo
+//!   constante (`subtle::ConstantTimeEq`), com `==` como referência
+//!
+//! As cifras e os acordos são buscados nos registros fora da medição, como no binário.
+//!
+//! Execução: `cargo bench` (relatórios em `target/criterion/`)
+
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+
+use pq_crypto_matrix::crypto::{
+    AGREEMENTS, AgreementRegistry, CIPHERS, CipherRegistry, CtrWidth, EphemeralMode, RecipientKeys,
+    MAX_TAG_SIZE, MegolmLikeCipher, SenderKeys, SymmetricCipher, perform_key_agreement, tags_equal,
+};
+use pq_crypto_matrix::workload::{MessageGenerator, UsageScenario};
+
+/// Tamanho fixo do payload cifrado nos benchmarks de cifra (16 KiB)
+const PAYLOAD_SIZE: usize = 16 * 1024;
+
+fn bench_keygen(c: &mut Criterion) {
+    let mut group = c.benchmark_group("keygen");
+    for agreement in AGREEMENTS {
+        group.bench_function(BenchmarkId::from_parameter(agreement), |b| {
+            b.iter(|| RecipientKeys::generate(black_box(agreement)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_key_agreement(c: &mut Criterion) {
+    let mut group = c.benchmark_group("key_agreement");
+    let registry = AgreementRegistry::standard(&[]).expect("Acordos padrão válidos");
+    for agreement in AGREEMENTS {
+        let bob = RecipientKeys::generate(agreement).expect("Acordo padrão válido");
+        group.bench_function(BenchmarkId::from_parameter(agreement), |b| {
+            b.iter(|| perform_key_agreement(black_box(agreement), &bob))
+        });
+        // Segredo de Alice gerado fora do laço: mede apenas o ECDH (e o KEM, se houver)
+        let alice = SenderKeys::generate_with_rng(EphemeralMode::Static, &mut rand::thread_rng());
+        group.bench_function(BenchmarkId::new(agreement, EphemeralMode::Static.name()), |b| {
+            let agreement = registry.lookup(agreement).expect("Acordo registrado");
+            b.iter(|| agreement.agree(&bob, &alice, false, 1, &mut rand::thread_rng()))
+        });
+        // Efêmero-efêmero: a geração do par de Bob entra no laço, junto com a de Alice
+        let alice = SenderKeys::generate_with_rng(EphemeralMode::Both, &mut rand::thread_rng());
+        group.bench_function(BenchmarkId::new(agreement, EphemeralMode::Both.name()), |b| {
+            let agreement = registry.lookup(agreement).expect("Acordo registrado");
+            b.iter(|| agreement.agree(&bob, &alice, false, 1, &mut rand::thread_rng()))
+        });
+    }
+    group.finish();
+}
+
+fn bench_encrypt(c: &mut Criterion) {
+    let mut message_gen = MessageGenerator::new(UsageScenario::MediumGroup);
+    let plaintext = message_gen.random_payload(PAYLOAD_SIZE);
+    let key = [0x42u8; 32];
+    let nonce = [0x24u8; 16];
+
+    let mut group = c.benchmark_group("encrypt");
+    group.throughput(Throughput::Bytes(PAYLOAD_SIZE as u64));
+    let registry = CipherRegistry::standard(true, CtrWidth::default());
+    for name in CIPHERS {
+        let cipher = registry.lookup(name).expect("Cifra registrada");
+        group.bench_function(BenchmarkId::new(name, PAYLOAD_SIZE), |b| {
+            b.iter(|| cipher.encrypt(&key, &nonce, black_box(&plaintext)))
+        });
+        // Cópia do texto claro para um buffer com folga para a tag feita fora da medição
+        group.bench_function(BenchmarkId::new(format!("{}/in-place", name), PAYLOAD_SIZE), |b| {
+            b.iter_batched_ref(
+                || {
+                    let mut buffer = Vec::with_capacity(PAYLOAD_SIZE + 32);
+                    buffer.extend_from_slice(&plaintext);
+                    buffer
+                },
+                |buffer| cipher.encrypt_in_place(&key, &nonce, buffer),
+                BatchSize::SmallInput,
+            )
+        });
+        // Mesma alocação do texto cifrado que a variante anexada; só a tag sai à parte
+        group.bench_function(BenchmarkId::new(format!("{}/detached", name), PAYLOAD_SIZE), |b| {
+            b.iter(|| cipher.encrypt_detached(&key, &nonce, black_box(&plaintext)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_tag_verification(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tag_verify");
+    // Tags iguais: o pior caso da comparação com `==`, que percorre todos os bytes
+    let expected = [0x5au8; MAX_TAG_SIZE];
+    let received = expected;
+    group.bench_function(BenchmarkId::new("ct_eq", MAX_TAG_SIZE), |b| {
+        b.iter(|| tags_equal(black_box(&expected), black_box(&received)))
+    });
+    group.bench_function(BenchmarkId::new("variable_time", MAX_TAG_SIZE), |b| {
+        b.iter(|| black_box(&expected) == black_box(&received))
+    });
+
+    // Decifragem completa: HMAC recalculado, comparação em tempo constante e AES-CTR
+    let mut message_gen = MessageGenerator::new(UsageScenario::MediumGroup);
+    let plaintext = message_gen.random_payload(PAYLOAD_SIZE);
+    let (key, iv) = ([0x42u8; 32], [0x24u8; 16]);
+    let cipher = MegolmLikeCipher { authenticate: true, ctr_width: CtrWidth::default() };
+    let (message, _) = cipher.encrypt(&key, &iv, &plaintext).expect("Payload dentro do limite");
+    group.throughput(Throughput::Bytes(PAYLOAD_SIZE as u64));
+    group.bench_function(BenchmarkId::new("Megolm-Like/decrypt", PAYLOAD_SIZE), |b| {
+        b.iter_batched_ref(|| message.clone(), |message| cipher.decrypt(&key, &iv, message), BatchSize::SmallInput)
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_keygen, bench_key_agreement, bench_encrypt, bench_tag_verification);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+//! Script de build: registra as versões exatas das bibliotecas criptográficas
+//!
+//! Lê o `Cargo.lock` e exporta a versão resolvida de cada crate de primitiva como
+//! variável de ambiente de compilação (`PQC_DEP_<CRATE>`), consumida em
+//! `crypto::PRIMITIVE_VERSIONS`. Assim a procedência das primitivas (que afeta
+//! diretamente o desempenho, ex: caminhos AVX2) fica embutida no binário.
+
+use std::fs;
+use std::path::Path;
+
+/// Crates cujas versões são registradas nos resultados
+const TRACKED_CRATES: [&str; 10] = [
+    "pqcrypto-kyber", "x25519-dalek", "aes-gcm", "chacha20poly1305",
+    "aes", "ctr", "hmac", "hkdf", "sha2", "subtle",
+];
+
+fn main() {
+    let lock_path = Path::new(&std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lock_path.display());
+    println!("cargo:rerun-if-changed=build.rs");
+
+    let lock = fs::read_to_string(&lock_path).unwrap_or_default();
+    for name in TRACKED_CRATES {
+        let version = locked_version(&lock, name).unwrap_or_else(|| "desconhecida".to_string());
+        let var = format!("PQC_DEP_{}", name.to_uppercase().replace('-', "_"));
+        println!("cargo:rustc-env={}={}", var, version);
+    }
+}
+
+/// Procura a versão de um pacote no Cargo.lock (blocos `[[package]]`)
+///
+/// Se houver mais de uma versão do mesmo crate, retorna todas separadas por `/`.
+fn locked_version(lock: &str, name: &str) -> Option<String> {
+    let versions: Vec<&str> = lock
+        .split("[[package]]")
+        .filter(|block| block.lines().any(|line| line.trim() == format!("name = \"{}\"", name)))
+        .filter_map(|block| {
+            block.lines()
+                .find_map(|line| line.trim().strip_prefix("version = \""))
+                .map(|v| v.trim_end_matches('"'))
+        })
+        .collect();
+    if versions.is_empty() { None } else { Some(versions.join("/")) }
+}
@@ -0,0 +1,106 @@
+//! Esvaziamento das caches da CPU entre operações criptográficas (`--flush-cache`)
+//!
+//! No laço de mensagens, as chaves, tabelas e buffers de cada operação continuam nas
+//! caches desde a operação anterior, e os tempos medidos são os de uma máquina dedicada
+//! ao experimento. Em dispositivos com pouca memória, ou disputados por outros
+//! processos, o conjunto de trabalho costuma ter sido expulso entre uma mensagem e a
+//! seguinte. O `CacheFlusher` modela esse extremo pessimista: antes de cada operação
+//! medida, percorre um buffer maior que a cache de último nível, escrevendo em cada
+//! linha, e a operação começa com as caches frias.
+
+use std::cell::Cell;
+use std::hint::black_box;
+
+/// Tamanho padrão do buffer de esvaziamento, em MiB (acima da LLC de CPUs comuns)
+pub const DEFAULT_FLUSH_MIB: usize = 64;
+
+/// Passo da varredura: uma escrita por linha de cache de 64 bytes
+const CACHE_LINE: usize = 64;
+
+thread_local! {
+    /// Esvaziador devolvido pela última sessão da thread, à espera da próxima
+    static SPARE: Cell<Option<CacheFlusher>> = const { Cell::new(None) };
+}
+
+/// Buffer de rascunho percorrido para expulsar o conjunto de trabalho das caches
+pub struct CacheFlusher {
+    buffer: Vec<u8>,
+    pass: u8, // Valor escrito na varredura atual, diferente a cada chamada
+}
+
+impl CacheFlusher {
+    /// Cria o esvaziador com um buffer de `mib` MiB (ao menos uma linha de cache)
+    ///
+    /// O buffer é alocado e tocado aqui, fora de qualquer medição, para que as
+    /// falhas de página da primeira varredura não caiam no laço de mensagens.
+    pub fn new(mib: usize) -> Self {
+        let mut flusher = CacheFlusher { buffer: vec![0; buffer_len(mib)], pass: 0 };
+        flusher.flush();
+        flusher
+    }
+
+    /// Esvaziador de `mib` MiB da thread atual: o devolvido pela sessão anterior, se
+    /// tiver o mesmo tamanho, ou um novo
+    ///
+    /// Com `release` ao fim de cada sessão, cada thread aloca o buffer uma única vez.
+    pub fn acquire(mib: usize) -> Self {
+        match SPARE.take() {
+            Some(flusher) if flusher.buffer.len() == buffer_len(mib) => flusher,
+            _ => CacheFlusher::new(mib),
+        }
+    }
+
+    /// Guarda o esvaziador para a próxima sessão da thread atual
+    pub fn release(self) {
+        SPARE.set(Some(self));
+    }
+
+    /// Escreve em cada linha de cache do buffer, expulsando o conteúdo anterior
+    ///
+    /// Cada varredura escreve um valor novo, e o `black_box` impede o compilador
+    /// de descartar as escritas como mortas.
+    pub fn flush(&mut self) {
+        self.pass = self.pass.wrapping_add(1);
+        let pass = self.pass;
+        for line in self.buffer.iter_mut().step_by(CACHE_LINE) {
+            *line = line.wrapping_add(pass);
+        }
+        black_box(&mut self.buffer);
+    }
+}
+
+/// Tamanho do buffer de `mib` MiB, em bytes (ao menos uma linha de cache)
+fn buffer_len(mib: usize) -> usize {
+    (mib << 20).max(CACHE_LINE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_flush_touches_every_cache_line() {
+        let mut flusher = CacheFlusher::new(1);
+        assert_eq!(flusher.buffer.len(), 1 << 20);
+        // A construção já fez a primeira varredura (valor 1); a segunda soma 2
+        flusher.flush();
+        assert!(flusher.buffer.iter().step_by(CACHE_LINE).all(|&byte| byte == 3));
+        assert!(flusher.buffer.iter().enumerate().filter(|(i, _)| i % CACHE_LINE != 0).all(|(_, &byte)| byte == 0));
+
+        // Mesmo com 0 MiB, o buffer cobre uma linha
+        assert_eq!(CacheFlusher::new(0).buffer.len(), CACHE_LINE);
+    }
+
+    #[test]
+    fn test_acquire_reuses_released_buffer() {
+        let flusher = CacheFlusher::acquire(1);
+        let buffer = flusher.buffer.as_ptr();
+        flusher.release();
+        // Mesmo tamanho: o buffer da sessão anterior, sem nova alocação
+        let flusher = CacheFlusher::acquire(1);
+        assert_eq!(flusher.buffer.as_ptr(), buffer);
+        flusher.release();
+        // Outro tamanho: um buffer novo
+        assert_eq!(CacheFlusher::acquire(2).buffer.len(), 2 << 20);
+    }
+}
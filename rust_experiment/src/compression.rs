@@ -0,0 +1,76 @@
+//! Compressão opcional do texto claro antes da cifragem
+//!
+//! Clientes reais frequentemente comprimem o conteúdo antes de cifrá-lo, o que
+//! reduz o tamanho do texto cifrado ao custo de CPU adicional. Texto e mensagens
+//! de sistema são compressíveis; imagens, arquivos e voz são bytes aleatórios
+//! (incompressíveis por construção), o que reproduz a assimetria observada na prática.
+//! Com `--payload-entropy`, as mídias passam a ter entropia limitada e também comprimem.
+
+use flate2::Compression as GzipLevel;
+use flate2::write::GzEncoder;
+use std::borrow::Cow;
+use std::io::Write;
+
+use crate::error::ExperimentError;
+
+/// Nível do zstd: o padrão da biblioteca, equilíbrio usual entre taxa e velocidade
+const ZSTD_LEVEL: i32 = 3;
+
+/// Algoritmo de compressão aplicado ao texto claro
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Compression {
+    /// Sem compressão (comportamento original)
+    #[default]
+    None,
+    /// Zstandard (nível 3)
+    Zstd,
+    /// gzip/DEFLATE (nível padrão)
+    Gzip,
+}
+
+impl Compression {
+    /// Nome do algoritmo como registrado no CSV
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Zstd => "zstd",
+            Compression::Gzip => "gzip",
+        }
+    }
+
+    /// Comprime os dados com o algoritmo selecionado
+    ///
+    /// Retorna os próprios bytes, emprestados e sem cópia, quando a compressão está
+    /// desativada; falhas do codificador chegam como `ExperimentError::Io`.
+    pub fn compress<'a>(&self, data: &'a [u8]) -> Result<Cow<'a, [u8]>, ExperimentError> {
+        match self {
+            Compression::None => Ok(Cow::Borrowed(data)),
+            Compression::Zstd => Ok(Cow::Owned(zstd::bulk::compress(data, ZSTD_LEVEL)?)),
+            Compression::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::with_capacity(data.len() / 2), GzipLevel::default());
+                encoder.write_all(data)?;
+                Ok(Cow::Owned(encoder.finish()?))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+/// Testes da compressão: texto repetitivo encolhe, bytes aleatórios não
+mod tests {
+    use super::*;
+    use rand::RngCore;
+
+    #[test]
+    fn test_compression_asymmetry() {
+        let text = "hello team meeting update ".repeat(40).into_bytes();
+        let mut random = vec![0u8; 4096];
+        rand::thread_rng().fill_bytes(&mut random);
+
+        assert!(matches!(Compression::None.compress(&text).unwrap(), Cow::Borrowed(bytes) if bytes == text));
+        for algorithm in [Compression::Zstd, Compression::Gzip] {
+            assert!(algorithm.compress(&text).unwrap().len() < text.len() / 4);
+            assert!(algorithm.compress(&random).unwrap().len() >= random.len());
+        }
+    }
+}
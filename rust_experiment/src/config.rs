@@ -0,0 +1,904 @@
+//! Opções de linha de comando do experimento
+//!
+//! Centraliza os parâmetros configuráveis da execução. Os valores padrão preservam
+//! o comportamento original (execução a partir de `rust_experiment/`), mas todos os
+//! caminhos são resolvidos para absolutos antes do uso, permitindo invocar o binário
+//! a partir de qualquer diretório.
+
+use base64::prelude::{BASE64_STANDARD, Engine as _};
+use clap::parser::ValueSource;
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use pq_crypto_matrix::cache::DEFAULT_FLUSH_MIB;
+use pq_crypto_matrix::compression::Compression;
+use pq_crypto_matrix::crypto::{AGREEMENTS, AesBackendMode, CIPHERS, CtrWidth, EphemeralMode, KeygenPolicy, TagLayout, agreement_components, hybrid_agreement_name};
+use pq_crypto_matrix::envelope::Envelope;
+use pq_crypto_matrix::error::ExperimentError;
+use pq_crypto_matrix::seed::EntropySource;
+use pq_crypto_matrix::telemetry::MetricsExporter;
+use pq_crypto_matrix::throttle::DEFAULT_THRESHOLD;
+use pq_crypto_matrix::workload::{RotationPolicy, TrafficPattern, UsageScenario};
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+
+/// Experimento de desempenho criptográfico: Olm clássico vs híbrido pós-quântico
+#[derive(Parser, Debug, Clone)]
+#[command(name = "pq_crypto_matrix", version, about, args_conflicts_with_subcommands = true)]
+pub struct ExperimentConfig {
+    /// Subcomando sem medições; sem ele, o experimento é executado
+    #[command(subcommand)]
+    pub command: Option<ExperimentCommand>,
+
+    /// Pasta onde os arquivos CSV de resultados são gravados
+    #[arg(long, value_name = "DIR", default_value = "../results")]
+    pub output_dir: PathBuf,
+
+    /// Pasta que contém os scripts de análise em Python (gerar_graficos.py)
+    #[arg(long, value_name = "DIR", default_value = "../analysis")]
+    pub analysis_dir: PathBuf,
+
+    /// Script de análise em Python executado ao final, com o CSV de resultados como
+    /// primeiro argumento; sem a opção, gerar_graficos.py em --analysis-dir
+    #[arg(long, value_name = "ARQUIVO")]
+    pub analysis_script: Option<PathBuf>,
+
+    /// Argumentos extras do script de análise, separados por espaço e passados depois
+    /// do CSV de resultados (ex: --analysis-args "--formato pdf")
+    #[arg(long, value_name = "ARGS", allow_hyphen_values = true)]
+    pub analysis_args: Option<String>,
+
+    /// Pasta onde os gráficos gerados são salvos
+    #[arg(long, value_name = "DIR", default_value = "../plots")]
+    pub plots_dir: PathBuf,
+
+    /// Reexecuta a configuração registrada nos metadados de uma execução anterior
+    /// (`*_metadata.json`, chave reproducao.argumentos); opções dadas junto com esta
+    /// substituem as registradas
+    #[arg(long, value_name = "ARQUIVO")]
+    pub from_config: Option<PathBuf>,
+
+    /// Argumentos efetivos da execução (sem o programa e sem --from-config), em forma
+    /// canônica, gravados nos metadados para que `--from-config` a reproduza
+    #[arg(skip)]
+    pub arguments: Vec<String>,
+
+    /// Margem de equivalência (ms) para o teste TOST entre AES-GCM e ChaCha20;
+    /// quando informada, os vereditos são gravados em equivalencia_tost_<timestamp>.csv
+    #[arg(long, value_name = "MS", value_parser = parse_margin)]
+    pub equivalence_margin: Option<f64>,
+
+    /// Valida a estabilidade das estatísticas: divide as amostras de cada configuração
+    /// em duas metades aleatórias (semeadas) e grava em estabilidade_metades_<timestamp>.csv
+    /// a diferença entre as metades e se os seus IC95 se sobrepõem
+    #[arg(long)]
+    pub split_half: bool,
+
+    /// Compara cada acordo ao Olm-Clássico da mesma célula e cifra (Welch, Mann-Whitney,
+    /// d de Cohen) e grava em comparacoes_<timestamp>.csv
+    #[arg(long)]
+    pub pairwise_comparisons: bool,
+
+    /// Grava o custo total (KEM + cifragem) em grade cenário × acordo/cifra, normalizado
+    /// pela combinação mais barata de cada cenário, em mapa_calor_custo_<timestamp>.csv
+    #[arg(long)]
+    pub cost_heatmap: bool,
+
+    /// Número de configurações executadas simultaneamente; com mais de um worker
+    /// o CSV continua sendo gravado na ordem canônica das configurações
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = parse_positive_count)]
+    pub jobs: usize,
+
+    /// Desativa o HMAC-SHA256 (encrypt-then-MAC) da cifra Megolm-Like, medindo
+    /// apenas o AES-CTR puro; útil para estudar o throughput bruto do modo CTR
+    #[arg(long)]
+    pub megolm_unauthenticated: bool,
+
+    /// Largura, em bits, do contador do AES-CTR da cifra Megolm-Like (32, 64 ou 128);
+    /// define o maior texto claro cifrável antes de o contador dar a volta
+    #[arg(long, value_enum, value_name = "BITS", default_value_t = CtrWidth::Ctr64)]
+    pub ctr_width: CtrWidth,
+
+    /// Segredo X25519 de Alice: fresh gera um novo a cada acordo (padrão, com a geração
+    /// no tempo da rotação); static gera um por sessão e o reutiliza em todas as rotações;
+    /// both é efêmero-efêmero, com Bob também gerando um segredo novo a cada acordo
+    #[arg(long, value_enum, value_name = "MODO", default_value_t = EphemeralMode::Fresh)]
+    pub ephemeral: EphemeralMode,
+
+    /// Backend de AES: auto (padrão, AES-NI quando a CPU tem) ou software, que exige um
+    /// binário compilado com RUSTFLAGS="--cfg aes_force_soft" e recusa a execução caso contrário
+    #[arg(long, value_enum, value_name = "MODO", default_value_t = AesBackendMode::Auto)]
+    pub aes_backend: AesBackendMode,
+
+    /// Geração das chaves de Bob: per-rotation (a cada rotação, dentro do tempo de KEM),
+    /// per-session (uma vez por sessão, fora do tempo; padrão) ou cached (um par por
+    /// configuração, reutilizado em todas as repetições)
+    #[arg(long, value_enum, value_name = "POLITICA", default_value_t = KeygenPolicy::PerSession)]
+    pub keygen_policy: KeygenPolicy,
+
+    /// Deriva uma chave e um nonce novos para cada mensagem (HKDF-Expand sobre a chave
+    /// de sessão e o contador da mensagem), com o custo incluído no tempo de cifragem
+    #[arg(long)]
+    pub per_message_kdf: bool,
+
+    /// Gera apenas mensagens de texto e imagem com exatamente BYTES bytes, em vez da
+    /// distribuição realista de tamanhos; útil para varrer o throughput por tamanho
+    #[arg(long, value_name = "BYTES")]
+    pub fixed_size: Option<usize>,
+
+    /// Entropia (0 a 8 bits por byte) das mídias geradas; sem a opção, os bytes são
+    /// uniformes (8 bits, incompressíveis). Valores menores as tornam compressíveis
+    #[arg(long, value_name = "BITS", value_parser = parse_entropy)]
+    pub payload_entropy: Option<f64>,
+
+    /// Registra o par (tamanho, tempo de cifragem) de cada mensagem e grava as correlações
+    /// de Pearson e Spearman por cifra em correlacao_tamanho_tempo_<timestamp>.csv
+    #[arg(long)]
+    pub size_time_correlation: bool,
+
+    /// Calcula também a latência média de cifragem por mensagem ponderada pelo tamanho do
+    /// texto claro (latencia_cifragem_msg_ponderada_us), em que as mensagens grandes
+    /// pesam como pesam no custo total
+    #[arg(long)]
+    pub size_weighted: bool,
+
+    /// Calcula as estatísticas do tempo de cifragem e dos bytes de cada mensagem separadas
+    /// por tipo (texto, imagem, ...), sem a mistura que torna a distribuição multimodal,
+    /// e as grava nas colunas cipher_us_<tipo>_* e msg_bytes_<tipo>_* do CSV principal
+    #[arg(long)]
+    pub per_type_stats: bool,
+
+    /// Guarda o SHA-256 de cada payload de mídia (imagem, arquivo, voz) e conta os
+    /// repetidos em cada configuração (coluna payloads_duplicados); 32 bytes por mensagem.
+    /// Incompatível com --corpus, que repete os arquivos por construção
+    #[arg(long, conflicts_with = "corpus")]
+    pub detect_duplicates: bool,
+
+    /// Número de dispositivos de Bob; cada rotação faz um acordo de chaves por dispositivo,
+    /// somando tempo e largura de banda (fan-out multi-dispositivo do Matrix)
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = parse_positive_count)]
+    pub devices_per_user: usize,
+
+    /// Orçamento de tempo de parede (segundos); ao se esgotar, nenhuma nova configuração
+    /// é iniciada e as já concluídas são gravadas no CSV antes de encerrar
+    #[arg(long, value_name = "SECONDS", value_parser = parse_runtime)]
+    pub max_runtime: Option<f64>,
+
+    /// Comprime o texto claro antes da cifragem; o tempo de compressão é medido à parte
+    /// e a largura de banda passa a refletir o tamanho comprimido
+    #[arg(long, value_enum, value_name = "ALGORITMO", default_value_t = Compression::None)]
+    pub compress: Compression,
+
+    /// Envolve cada mensagem em um evento Matrix em JSON (type, content, sender,
+    /// origin_server_ts) antes da compressão e da cifragem, com a mídia em base64;
+    /// a largura de banda passa a incluir o overhead do envelope
+    #[arg(long, value_enum, value_name = "ENVELOPE", default_value_t = Envelope::None)]
+    pub envelope: Envelope,
+
+    /// Codifica imagens, arquivos e voz em base64 antes da cifragem (~4/3 dos bytes), como
+    /// os protocolos que transportam anexos em texto; textos e mensagens de sistema não mudam.
+    /// Incompatível com --envelope, cujo matrix-json já codifica a mídia
+    #[arg(long, conflicts_with = "envelope")]
+    pub base64_media: bool,
+
+    /// Medida de tendência central usada para métricas não normais: mediana (padrão),
+    /// média aritmética ou moda (estimador half-sample mode, robusto a multimodalidade)
+    #[arg(long, value_enum, value_name = "ESTIMADOR", default_value_t = CentralEstimator::Median)]
+    pub central: CentralEstimator,
+
+    /// Não remove outliers extremos antes das estatísticas: todas as métricas são
+    /// resumidas sobre a amostra completa, e as contagens de outliers continuam no CSV
+    #[arg(long)]
+    pub no_outlier_removal: bool,
+
+    /// Número de remetentes simultâneos; cada um executa o laço de mensagens em sua
+    /// própria thread, medindo throughput e latência sob contenção de CPU
+    #[arg(long, value_name = "N", default_value_t = 1, value_parser = parse_positive_count)]
+    pub senders: usize,
+
+    /// Grava as decisões do workload (envio, tipo e tamanho de cada mensagem) em um
+    /// arquivo JSON-lines, para reprodução posterior com --replay
+    #[arg(long, value_name = "ARQUIVO", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Conduz o laço de mensagens por um arquivo gravado com --record em vez dos
+    /// geradores aleatórios, isolando o tempo criptográfico da aleatoriedade do workload
+    #[arg(long, value_name = "ARQUIVO")]
+    pub replay: Option<PathBuf>,
+
+    /// Diretório de arquivos reais usados como conteúdo das mensagens: as mídias vêm dos
+    /// arquivos, agrupados por classe de tamanho, e os textos das linhas de `chat.txt`
+    #[arg(long, value_name = "DIR")]
+    pub corpus: Option<PathBuf>,
+
+    /// Anexa a cada texto cifrado um compromisso de chave SHA-256 (32 bytes), mitigação
+    /// para AEADs não comprometedoras; independe da cifra e tem o tempo medido à parte
+    #[arg(long)]
+    pub key_commitment: bool,
+
+    /// Cifra cada mensagem no próprio buffer (APIs in-place das AEADs, tag destacada),
+    /// reutilizado entre as mensagens, excluindo do tempo de cifragem a alocação do texto cifrado
+    #[arg(long)]
+    pub in_place: bool,
+
+    /// Esvazia as caches da CPU antes de cada operação criptográfica medida (acordo de
+    /// chaves de cada rotação e cifragem de cada mensagem), percorrendo um buffer de MIB
+    /// MiB (padrão 64) fora do tempo medido; modela dispositivos com pouca memória ou
+    /// disputados, com tempos pessimistas que não devem ser misturados aos de cache quente
+    #[arg(long, value_name = "MIB", value_parser = parse_flush_size)]
+    pub flush_cache: Option<Option<usize>>,
+
+    /// Disposição da tag das cifras: attached (padrão, anexada ao texto cifrado) ou
+    /// detached (APIs *_detached das AEADs, tag em um campo à parte, mesmo total de bytes)
+    #[arg(long, value_enum, value_name = "LAYOUT", default_value_t = TagLayout::Attached,
+          conflicts_with = "in_place")]
+    pub tag_layout: TagLayout,
+
+    /// Cifra de referência da velocidade relativa: em cada célula, o cipher_ms_mean
+    /// das demais cifras é comparado ao desta (coluna velocidade_relativa)
+    #[arg(long, value_name = "CIFRA", default_value = "AES-GCM",
+          value_parser = clap::builder::PossibleValuesParser::new(CIPHERS))]
+    pub baseline_cipher: String,
+
+    /// Execução reduzida para testes de fumaça: apenas o cenário SmallChat, 10 repetições
+    /// e 4 mensagens por configuração (rotação a cada 2); não serve para conclusões
+    #[arg(long)]
+    pub quick: bool,
+
+    /// Amostragem adaptativa: roda repetições em lotes de 10 até que a margem de erro
+    /// relativa (ci95 / média) dos tempos de KEM e do laço fique abaixo desta fração
+    #[arg(long, value_name = "FRACAO", value_parser = parse_fraction, conflicts_with = "quick")]
+    pub target_moe: Option<f64>,
+
+    /// Teto de repetições por configuração da amostragem adaptativa (--target-moe)
+    #[arg(long, value_name = "N", default_value_t = 500, requires = "target_moe")]
+    pub max_repetitions: usize,
+
+    /// Intercala as configurações: cada rodada executa uma repetição de todas elas, em
+    /// vez de todas as repetições de uma antes da próxima, cancelando derivas lentas
+    /// (aquecimento, throttling) que de outro modo se confundem com a configuração
+    #[arg(long)]
+    pub interleave: bool,
+
+    /// Executa as configurações em ordem aleatória, sorteada a partir da semente base
+    /// (uma permutação nova por rodada com --interleave); o CSV mantém a ordem canônica
+    #[arg(long)]
+    pub shuffle_configs: bool,
+
+    /// Atraso (ms) após cada mensagem enviada, modelando a cadência de uma conversa real;
+    /// 0 (padrão) processa as mensagens tão rápido quanto o padrão de tráfego permite
+    #[arg(long, value_name = "MS", default_value_t = 0.0, value_parser = parse_delay)]
+    pub inter_message_delay: f64,
+
+    /// Como o atraso entre mensagens é aplicado: simulated (padrão) avança um relógio
+    /// simulado visto pelo padrão de tráfego e pela rotação, sem inflar o tempo do laço;
+    /// sleep dorme de fato, e o atraso entra no tempo de parede medido
+    #[arg(long, value_enum, value_name = "MODO", default_value_t = Pacing::Simulated)]
+    pub pacing: Pacing,
+
+    /// Fração (0 a 1) dos textos cifrados Kyber corrompidos antes do decapsulamento;
+    /// o caminho de rejeição implícita é verificado e cronometrado à parte do de sucesso
+    #[arg(long, value_name = "TAXA", value_parser = parse_fraction)]
+    pub inject_kem_failures: Option<f64>,
+
+    /// Decapsulamentos por encapsulamento (destinatários de um mesmo texto cifrado KEM);
+    /// o tempo de KEM é reportado à parte para o remetente e para os receptores somados
+    #[arg(long, value_name = "M", default_value_t = 1, value_parser = parse_decapsulations)]
+    pub decaps_per_encap: usize,
+
+    /// Probabilidade (0 a 1), a cada mensagem, de que um membro saia da sala; cada saída
+    /// força o re-key completo do grupo, medido à parte da rotação periódica
+    #[arg(long, value_name = "TAXA", value_parser = parse_fraction)]
+    pub member_leave_rate: Option<f64>,
+
+    /// Probabilidade (0 a 1), a cada mensagem, de que a última mensagem de texto enviada
+    /// seja editada: o conteúdo é recifrado com a chave atual e um nonce novo, e o custo
+    /// é reportado à parte (edit_events, edit_ms_mean, edit_bw_bytes_mean)
+    #[arg(long, value_name = "TAXA", value_parser = parse_fraction)]
+    pub edit_rate: Option<f64>,
+
+    /// Após cada rotação, cada sessão nova confirma a chave: o receptor devolve um token
+    /// fixo cifrado com a chave derivada, a ida e volta extra de um handshake completo;
+    /// o custo é reportado à parte (confirm_ms_mean, confirm_bw_bytes_mean)
+    #[arg(long)]
+    pub key_confirmation: bool,
+
+    /// Cada chave de sessão nova (rotação ou re-key) é cifrada para uma chave de recuperação
+    /// e enviada ao backup do servidor, como no SSSS do Matrix; o custo é reportado à parte
+    /// (backup_ops, backup_ms_mean, backup_bw_bytes_mean)
+    #[arg(long)]
+    pub key_backup: bool,
+
+    /// Cada rotação distribui a sessão a todos os membros do grupo do cenário (uma sessão
+    /// Olm por membro e dispositivo), em vez de apenas a Bob
+    #[arg(long)]
+    pub group_fanout: bool,
+
+    /// Estratégia de rotação da sessão: count, time, bytes ou hybrid (padrão, N mensagens
+    /// ou 7 dias); CENARIO=ESTRATEGIA (ex: LargeChannel=bytes) vale só para aquele cenário.
+    /// Pode ser repetido
+    #[arg(long, value_name = "ESTRATEGIA", value_parser = parse_rotation_selection)]
+    pub rotation_strategy: Vec<RotationSelection>,
+
+    /// Rotações por sessão: o intervalo de rotação passa a ser o total de mensagens da
+    /// sessão dividido por R (arredondado para cima), igual em todos os cenários; sem a
+    /// opção, cada cenário usa o seu intervalo padrão
+    #[arg(long, value_name = "R", value_parser = parse_rotation_ratio)]
+    pub rotation_ratio: Option<f64>,
+
+    /// Acrescenta à matriz um acordo híbrido de N componentes (ex: X25519+Kyber768+Kyber1024),
+    /// com os segredos combinados via HKDF em ordem canônica; pode ser repetido
+    #[arg(long, value_name = "COMPONENTES", value_parser = parse_hybrid)]
+    pub hybrid: Vec<String>,
+
+    /// Semente base; cada configuração usa a sua própria semente derivada desta e da
+    /// tupla da configuração (coluna config_seed). Sem a opção, uma semente aleatória é sorteada
+    #[arg(long, value_name = "SEMENTE")]
+    pub seed: Option<u64>,
+
+    /// Gera chaves X25519 e nonces direto do sistema operacional (OsRng, via getrandom),
+    /// sem o gerador semeado; incompatível com --seed, pois o fluxo criptográfico deixa
+    /// de ser reproduzível
+    #[arg(long, conflicts_with = "seed")]
+    pub os_rng: bool,
+
+    /// Grava os tempos brutos de cada repetição em nanossegundos inteiros (Duration::as_nanos)
+    /// em amostras_ns_<timestamp>.csv, sem o arredondamento da conversão para ms em f64
+    #[arg(long)]
+    pub raw_samples_ns: bool,
+
+    /// Grava o instante de início (Unix, em ns) e os tempos de KEM e do laço de cada
+    /// repetição em deriva_<timestamp>.csv, em ordem cronológica, para diagnosticar
+    /// deriva térmica ou do escalonador ao longo da execução
+    #[arg(long)]
+    pub drift_log: bool,
+
+    /// Ao final, grava um resumo em Markdown: tabela por cenário com a melhor e a pior
+    /// cifra e o overhead do híbrido sobre o clássico, além dos números de destaque
+    #[arg(long, value_name = "ARQUIVO")]
+    pub report: Option<PathBuf>,
+
+    /// Ao final, grava uma tabela LaTeX (tabular) com o tempo e a largura de banda de KEM
+    /// do clássico e do híbrido por cenário, mediana (ou --central) ± IC95 em \num{} (siunitx)
+    #[arg(long, value_name = "ARQUIVO")]
+    pub latex: Option<PathBuf>,
+
+    /// Grava também os resultados em formato longo (tidy), uma linha por configuração,
+    /// métrica e estatística, em resultados_tidy_<timestamp>.csv; o CSV largo é mantido
+    #[arg(long)]
+    pub tidy: bool,
+
+    /// Acrescenta os resultados a um banco SQLite: uma linha por execução na tabela runs
+    /// (semente e metadados) e uma por configuração na tabela results; o CSV é mantido
+    #[arg(long, value_name = "ARQUIVO")]
+    pub sqlite: Option<PathBuf>,
+
+    /// Modo estrito: ao fim da execução, termina com código 3 se alguma métrica de alguma
+    /// configuração foi classificada como não normal; sem valores, vale para todas as
+    /// métricas (kem, cipher, kem_bw_bytes, msg_bw_bytes, cipher_only). Incompatível com
+    /// --message-counts, cuja varredura não classifica normalidade
+    #[arg(long, value_name = "METRICA,...", num_args = 0.., value_delimiter = ',',
+          value_parser = parse_normality_metric, conflicts_with = "message_counts")]
+    pub fail_on_nonnormal: Option<Vec<String>>,
+
+    /// Grava um exemplo de cifragem por configuração (primeira mensagem da primeira
+    /// repetição) em amostras_cifradas_<timestamp>.csv: hash do texto claro, chave
+    /// (como SHA-256, salvo --dump-keys-insecure), nonce e texto cifrado em hex ou base64
+    #[arg(long, value_enum, value_name = "CODIFICACAO", num_args = 0..=1, default_missing_value = "hex")]
+    pub dump_sample: Option<DumpEncoding>,
+
+    /// Grava a chave da mensagem em claro nas amostras de --dump-sample, em vez do seu
+    /// SHA-256; use apenas com chaves de teste, pois a chave decifra o exemplo
+    #[arg(long, requires = "dump_sample")]
+    pub dump_keys_insecure: bool,
+
+    /// Registra a largura de banda acumulada ao longo das mensagens da primeira repetição
+    /// de cada configuração e a grava em largura_banda_acumulada_<timestamp>.csv
+    #[arg(long)]
+    pub bandwidth_series: bool,
+
+    /// Janela, em mensagens, da média móvel dos bytes por mensagem gravada na série de
+    /// --bandwidth-series (coluna bytes_mensagem_media_movel); 1 mantém a série bruta
+    #[arg(long, value_name = "N", default_value_t = NonZeroUsize::MIN, value_parser = parse_series_window,
+          requires = "bandwidth_series")]
+    pub series_window: NonZeroUsize,
+
+    /// Modo de amortização: em vez da matriz, executa uma célula com cada acordo para cada
+    /// total de mensagens da lista (ex: 50,100,500) e grava o custo de KEM por mensagem e
+    /// a fração de banda do acordo em amortizacao_<timestamp>.csv
+    #[arg(long, value_name = "N,...", value_delimiter = ',', value_parser = parse_message_count,
+          conflicts_with_all = ["replay", "record", "interleave", "shuffle_configs"])]
+    pub message_counts: Vec<usize>,
+
+    /// Cenário da célula executada por --message-counts
+    #[arg(long, value_name = "CENARIO", default_value = "MediumGroup", value_parser = parse_scenario,
+          requires = "message_counts")]
+    pub sweep_scenario: UsageScenario,
+
+    /// Padrão de tráfego da célula executada por --message-counts
+    #[arg(long, value_name = "PADRAO", default_value = "Random", value_parser = parse_pattern,
+          requires = "message_counts")]
+    pub sweep_pattern: TrafficPattern,
+
+    /// Cifra da célula executada por --message-counts
+    #[arg(long, value_name = "CIFRA", default_value = "AES-GCM", requires = "message_counts",
+          value_parser = clap::builder::PossibleValuesParser::new(CIPHERS))]
+    pub sweep_cipher: String,
+
+    /// Amostra a frequência da CPU entre configurações e avisa se houver queda
+    /// sustentada (throttling térmico), registrando `throttle_suspected` nos metadados
+    #[arg(long)]
+    pub monitor_throttle: bool,
+
+    /// Queda relativa da frequência (0 a 1) a partir da qual o throttling é sinalizado
+    #[arg(long, value_name = "FRACAO", value_parser = parse_fraction,
+          default_value_t = DEFAULT_THRESHOLD, requires = "monitor_throttle")]
+    pub throttle_threshold: f64,
+
+    /// Publica o progresso e as métricas da última configuração concluída, enviados
+    /// periodicamente a um Pushgateway do Prometheus (http:// ou https://)
+    #[arg(long, value_name = "URL", value_parser = MetricsExporter::new)]
+    pub metrics_endpoint: Option<MetricsExporter>,
+}
+
+/// Valida uma lista de componentes e a normaliza para o nome canônico do acordo
+///
+/// Recusa listas com um único componente (não são híbridos) e combinações que já
+/// são um acordo de `AGREEMENTS` (ex: `X25519+Kyber768` é o Olm-Híbrido), que
+/// apenas repetiriam configurações da matriz.
+fn parse_hybrid(value: &str) -> Result<String, ExperimentError> {
+    let components = agreement_components(value)?;
+    if components.len() < 2 {
+        return Err(ExperimentError::Config(format!(
+            "um híbrido precisa de ao menos dois componentes: '{}'", value
+        )));
+    }
+    if let Some(standard) = AGREEMENTS.iter().find(|name| agreement_components(name).is_ok_and(|c| c == components)) {
+        return Err(ExperimentError::Config(format!("'{}' já é o acordo {} da matriz", value, standard)));
+    }
+    Ok(hybrid_agreement_name(&components))
+}
+
+/// Estratégia de rotação escolhida, opcionalmente restrita a um cenário
+#[derive(Debug, Clone, PartialEq)]
+pub struct RotationSelection {
+    pub scenario: Option<UsageScenario>,
+    pub policy: RotationPolicy,
+}
+
+/// Interpreta `ESTRATEGIA` ou `CENARIO=ESTRATEGIA` (nomes sem distinção de maiúsculas)
+fn parse_rotation_selection(value: &str) -> Result<RotationSelection, String> {
+    let (scenario, policy) = match value.split_once('=') {
+        Some((scenario, policy)) => {
+            (Some(parse_scenario(scenario)?), policy)
+        }
+        None => (None, value),
+    };
+    let policy = RotationPolicy::from_str(policy.trim(), true)
+        .map_err(|_| format!("estratégia desconhecida: '{}' (use count, time, bytes ou hybrid)", policy))?;
+    Ok(RotationSelection { scenario, policy })
+}
+
+/// Interpreta o nome de um cenário (sem distinção de maiúsculas)
+fn parse_scenario(value: &str) -> Result<UsageScenario, String> {
+    UsageScenario::ALL
+        .into_iter()
+        .find(|candidate| format!("{:?}", candidate).eq_ignore_ascii_case(value.trim()))
+        .ok_or_else(|| format!("cenário desconhecido: '{}'", value))
+}
+
+/// Interpreta o nome de um padrão de tráfego (sem distinção de maiúsculas)
+fn parse_pattern(value: &str) -> Result<TrafficPattern, String> {
+    TrafficPattern::ALL
+        .into_iter()
+        .find(|candidate| format!("{:?}", candidate).eq_ignore_ascii_case(value.trim()))
+        .ok_or_else(|| format!("padrão de tráfego desconhecido: '{}'", value))
+}
+
+/// Valida um total de mensagens do modo de amortização (positivo)
+fn parse_message_count(value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(0) => Err("o total de mensagens deve ser positivo".to_string()),
+        Ok(count) => Ok(count),
+        Err(_) => Err(format!("'{}' não é um número inteiro", value)),
+    }
+}
+
+/// Valida o número de decapsulamentos por encapsulamento (ao menos um)
+fn parse_decapsulations(value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(0) => Err("cada encapsulamento precisa de ao menos um decapsulamento".to_string()),
+        Ok(count) => Ok(count),
+        Err(_) => Err(format!("'{}' não é um número inteiro", value)),
+    }
+}
+
+/// Valida uma contagem que precisa ser ao menos 1 (workers, dispositivos, remetentes)
+fn parse_positive_count(value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(0) => Err("o valor precisa ser ao menos 1".to_string()),
+        Ok(count) => Ok(count),
+        Err(_) => Err(format!("'{}' não é um número inteiro", value)),
+    }
+}
+
+/// Valida a janela da média móvel de --series-window (ao menos 1 mensagem)
+fn parse_series_window(value: &str) -> Result<NonZeroUsize, String> {
+    match value.trim().parse::<usize>().map(NonZeroUsize::new) {
+        Ok(Some(window)) => Ok(window),
+        Ok(None) => Err("o valor precisa ser ao menos 1".to_string()),
+        Err(_) => Err(format!("'{}' não é um número inteiro", value)),
+    }
+}
+
+/// Valida o tamanho do buffer de --flush-cache, em MiB (positivo)
+fn parse_flush_size(value: &str) -> Result<usize, String> {
+    match value.trim().parse::<usize>() {
+        Ok(0) => Err("o buffer de esvaziamento precisa de ao menos 1 MiB".to_string()),
+        Ok(mib) => Ok(mib),
+        Err(_) => Err(format!("'{}' não é um número inteiro", value)),
+    }
+}
+
+/// Métricas com diagnóstico de normalidade no CSV (prefixos das colunas `*_normal`)
+pub const NORMALITY_METRICS: [&str; 5] = ["kem", "cipher", "kem_bw_bytes", "msg_bw_bytes", "cipher_only"];
+
+/// Valida o nome de uma métrica de `--fail-on-nonnormal`
+fn parse_normality_metric(value: &str) -> Result<String, String> {
+    let metric = value.trim();
+    if NORMALITY_METRICS.contains(&metric) {
+        Ok(metric.to_string())
+    } else {
+        Err(format!("métrica '{}' desconhecida (use {})", value, NORMALITY_METRICS.join(", ")))
+    }
+}
+
+/// Valida uma fração no intervalo [0, 1]
+fn parse_fraction(value: &str) -> Result<f64, String> {
+    let fraction: f64 = value.parse().map_err(|_| format!("'{}' não é um número", value))?;
+    if (0.0..=1.0).contains(&fraction) {
+        Ok(fraction)
+    } else {
+        Err(format!("{} fora do intervalo [0, 1]", fraction))
+    }
+}
+
+/// Valida um orçamento de tempo em segundos, finito, positivo e representável como `Duration`
+fn parse_runtime(value: &str) -> Result<f64, String> {
+    let seconds: f64 = value.parse().map_err(|_| format!("'{}' não é um número", value))?;
+    if seconds > 0.0 && std::time::Duration::try_from_secs_f64(seconds).is_ok() {
+        Ok(seconds)
+    } else {
+        Err(format!("orçamento de tempo inválido: {} (use um valor finito > 0)", seconds))
+    }
+}
+
+/// Valida uma margem de equivalência (ms): finita e maior que zero
+fn parse_margin(value: &str) -> Result<f64, String> {
+    let margin: f64 = value.parse().map_err(|_| format!("'{}' não é um número", value))?;
+    if margin.is_finite() && margin > 0.0 {
+        Ok(margin)
+    } else {
+        Err(format!("margem de equivalência inválida: {} (use um valor finito > 0)", margin))
+    }
+}
+
+/// Valida uma entropia em bits por byte, entre 0 e 8
+fn parse_entropy(value: &str) -> Result<f64, String> {
+    let bits: f64 = value.parse().map_err(|_| format!("'{}' não é um número", value))?;
+    if (0.0..=8.0).contains(&bits) {
+        Ok(bits)
+    } else {
+        Err(format!("{} fora do intervalo [0, 8] bits por byte", bits))
+    }
+}
+
+/// Valida um atraso em milissegundos, não negativo e representável como `Duration`
+fn parse_delay(value: &str) -> Result<f64, String> {
+    let delay: f64 = value.parse().map_err(|_| format!("'{}' não é um número", value))?;
+    if std::time::Duration::try_from_secs_f64(delay / 1000.0).is_ok() {
+        Ok(delay)
+    } else {
+        Err(format!("atraso inválido: {} (use um valor finito >= 0)", delay))
+    }
+}
+
+/// Argumentos sem `--from-config ARQUIVO` (ou `--from-config=ARQUIVO`)
+fn without_from_config(args: &[String]) -> Vec<String> {
+    let mut kept = Vec::with_capacity(args.len());
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--from-config" {
+            args.next();
+        } else if !arg.starts_with("--from-config=") {
+            kept.push(arg.clone());
+        }
+    }
+    kept
+}
+
+/// Argumentos efetivos de uma leitura das opções, em forma canônica
+///
+/// Cada opção dada (na linha de comando ou no arquivo de `--from-config`) aparece uma
+/// única vez, pelo nome longo e com o valor que prevaleceu, na ordem de declaração.
+/// Opções no valor padrão ficam de fora, assim como o próprio `--from-config`.
+fn effective_arguments(command: &clap::Command, matches: &clap::ArgMatches) -> Vec<String> {
+    let mut arguments = Vec::new();
+    for arg in command.get_arguments() {
+        let id = arg.get_id().as_str();
+        let Some(long) = arg.get_long() else { continue };
+        if id == "from_config" || matches.value_source(id) != Some(ValueSource::CommandLine) {
+            continue;
+        }
+        let flag = format!("--{}", long);
+        let values: Vec<String> = matches.get_raw(id)
+            .into_iter()
+            .flatten()
+            .map(|value| value.to_string_lossy().into_owned())
+            .collect();
+        match (arg.get_action(), arg.get_value_delimiter()) {
+            (ArgAction::SetTrue, _) => arguments.push(flag),
+            (_, _) if values.is_empty() => arguments.push(flag),
+            (_, Some(delimiter)) => arguments.extend([flag, values.join(&delimiter.to_string())]),
+            (_, None) => {
+                for value in values {
+                    arguments.extend([flag.clone(), value]);
+                }
+            }
+        }
+    }
+    arguments
+}
+
+/// Argumentos registrados em `reproducao.argumentos` de um arquivo de metadados
+fn recorded_arguments(path: &std::path::Path) -> Result<Vec<String>, ExperimentError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| ExperimentError::from(e).context(format!("não foi possível ler {}", path.display())))?;
+    let metadata: serde_json::Value = serde_json::from_str(&text)
+        .map_err(|e| ExperimentError::Config(format!("{} não é um JSON válido: {}", path.display(), e)))?;
+    let arguments = metadata["reproducao"]["argumentos"].as_array()
+        .ok_or_else(|| ExperimentError::Config(format!(
+            "{} não tem reproducao.argumentos (metadados anteriores ao registro?)", path.display()
+        )))?;
+    arguments
+        .iter()
+        .map(|arg| arg.as_str().map(str::to_string)
+            .ok_or_else(|| ExperimentError::Config(format!("argumento não textual em {}: {}", path.display(), arg))))
+        .collect()
+}
+
+/// Valida o número de rotações por sessão de `--rotation-ratio` (finito e positivo)
+fn parse_rotation_ratio(value: &str) -> Result<f64, String> {
+    let ratio: f64 = value.parse().map_err(|_| format!("'{}' não é um número", value))?;
+    if ratio.is_finite() && ratio > 0.0 {
+        Ok(ratio)
+    } else {
+        Err(format!("razão de rotação inválida: {} (use um valor finito > 0)", ratio))
+    }
+}
+
+/// Valida o intervalo entre consultas do diagnóstico `inter-arrival` (ms, positivo)
+fn parse_poll_interval(value: &str) -> Result<f64, String> {
+    let interval = parse_delay(value)?;
+    if interval > 0.0 { Ok(interval) } else { Err("o intervalo entre consultas deve ser positivo".to_string()) }
+}
+
+/// Aplicação do atraso entre mensagens (`--inter-message-delay`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Pacing {
+    /// Avança um relógio simulado, sem dormir
+    Simulated,
+    /// Dorme pelo atraso (tempo de parede real)
+    Sleep,
+}
+
+impl Pacing {
+    /// Nome do modo como registrado nos metadados
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pacing::Simulated => "simulated",
+            Pacing::Sleep => "sleep",
+        }
+    }
+}
+
+/// Codificação dos bytes gravados por `--dump-sample`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum DumpEncoding {
+    /// Hexadecimal minúsculo
+    Hex,
+    /// Base64 padrão (RFC 4648, com preenchimento)
+    Base64,
+}
+
+impl DumpEncoding {
+    /// Nome da codificação como registrado nos metadados
+    pub fn name(&self) -> &'static str {
+        match self {
+            DumpEncoding::Hex => "hex",
+            DumpEncoding::Base64 => "base64",
+        }
+    }
+
+    /// Codifica os bytes como texto
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        match self {
+            DumpEncoding::Hex => hex::encode(bytes),
+            DumpEncoding::Base64 => BASE64_STANDARD.encode(bytes),
+        }
+    }
+}
+
+/// Estimador de tendência central do caminho robusto (dados não normais)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum CentralEstimator {
+    /// Média aritmética
+    Mean,
+    /// Mediana (comportamento original)
+    Median,
+    /// Moda pelo half-sample mode (HSM)
+    Mode,
+}
+
+impl CentralEstimator {
+    /// Nome do estimador como registrado no CSV
+    pub fn name(&self) -> &'static str {
+        match self {
+            CentralEstimator::Mean => "mean",
+            CentralEstimator::Median => "median",
+            CentralEstimator::Mode => "mode",
+        }
+    }
+}
+
+/// Subcomandos que reaproveitam resultados gravados, sem executar medições
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+pub enum ExperimentCommand {
+    /// Lê um arquivo amostras_ns_<timestamp>.csv (--raw-samples-ns) e grava apenas as
+    /// comparações entre acordos (Welch, Mann-Whitney, d de Cohen)
+    Analyze {
+        /// Arquivo de amostras brutas de uma execução anterior
+        #[arg(value_name = "ARQUIVO")]
+        raw_samples: PathBuf,
+
+        /// Pasta onde o CSV de comparações é gravado
+        #[arg(long, value_name = "DIR", default_value = "../results")]
+        output_dir: PathBuf,
+    },
+    /// Lista as cifras, acordos, componentes de --hybrid, cenários e padrões de tráfego
+    /// aceitos, com os parâmetros de cada cenário
+    List,
+    /// Diagnóstico dos padrões de tráfego: consulta cada gerador sob relógio simulado e
+    /// grava o histograma dos intervalos entre envios, sem executar criptografia
+    InterArrival {
+        /// Envios simulados por padrão de tráfego
+        #[arg(long, value_name = "N", default_value_t = 10_000)]
+        sends: usize,
+
+        /// Intervalo simulado entre consultas ao gerador (ms), a resolução dos intervalos
+        #[arg(long, value_name = "MS", default_value_t = 1.0, value_parser = parse_poll_interval)]
+        poll_ms: f64,
+
+        /// Número de classes do histograma de cada padrão
+        #[arg(long, value_name = "N", default_value_t = 30)]
+        bins: usize,
+
+        /// Semente dos sorteios dos geradores
+        #[arg(long, value_name = "SEMENTE", default_value_t = 1)]
+        seed: u64,
+
+        /// Pasta onde o CSV de histogramas é gravado
+        #[arg(long, value_name = "DIR", default_value = "../results")]
+        output_dir: PathBuf,
+    },
+}
+
+impl ExperimentConfig {
+    /// Origem da aleatoriedade criptográfica das sessões (`--os-rng`)
+    pub fn entropy_source(&self) -> EntropySource {
+        if self.os_rng { EntropySource::Os } else { EntropySource::Seeded }
+    }
+
+    /// Estratégia de rotação de um cenário: a última seleção específica do cenário,
+    /// senão a última seleção geral, senão a híbrida (comportamento original)
+    pub fn rotation_policy(&self, scenario: &UsageScenario) -> RotationPolicy {
+        let last = |specific: bool| {
+            self.rotation_strategy
+                .iter()
+                .rev()
+                .find(|selection| match &selection.scenario {
+                    Some(selected) => specific && selected == scenario,
+                    None => !specific,
+                })
+                .map(|selection| selection.policy)
+        };
+        last(true).or_else(|| last(false)).unwrap_or_default()
+    }
+
+    /// Lê as opções de `args` (sem o nome do programa), aplicando `--from-config`
+    ///
+    /// Com `--from-config`, os argumentos registrados no arquivo vêm primeiro e os de
+    /// `args` depois, substituindo os registrados. Os argumentos efetivos, já resolvidos
+    /// (`effective_arguments`), ficam em `arguments`. Erros de sintaxe saem pelo clap,
+    /// como em `parse`.
+    pub fn from_arguments(program: &str, args: &[String]) -> Result<Self, ExperimentError> {
+        let parsed = Self::parse_from(std::iter::once(program).chain(args.iter().map(String::as_str)));
+        let (mut command, mut arguments) = match &parsed.from_config {
+            Some(path) => (Self::command().args_override_self(true), recorded_arguments(path)?),
+            None => (Self::command(), Vec::new()),
+        };
+        arguments.extend(without_from_config(args));
+        let matches = command
+            .try_get_matches_from_mut(std::iter::once(program).chain(arguments.iter().map(String::as_str)))
+            .unwrap_or_else(|error| error.exit());
+        let mut config = Self::from_arg_matches(&matches).unwrap_or_else(|error| error.exit());
+        config.from_config = parsed.from_config;
+        config.arguments = effective_arguments(&command, &matches);
+        Ok(config)
+    }
+
+    /// Argumentos que reproduzem a execução: os efetivos e, se a semente base foi
+    /// sorteada, `--seed` com o valor sorteado
+    pub fn reproduction_arguments(&self) -> Vec<String> {
+        let mut arguments = self.arguments.clone();
+        let seed_given = arguments.iter().any(|arg| arg == "--seed" || arg.starts_with("--seed="));
+        if let (false, Some(seed)) = (seed_given, self.seed) {
+            arguments.extend(["--seed".to_string(), seed.to_string()]);
+        }
+        arguments
+    }
+
+    /// Tamanho do buffer de --flush-cache, em MiB; `DEFAULT_FLUSH_MIB` com a opção sem valor
+    pub fn flush_cache_mib(&self) -> Option<usize> {
+        self.flush_cache.map(|mib| mib.unwrap_or(DEFAULT_FLUSH_MIB))
+    }
+
+    /// Script de análise a executar: o de --analysis-script ou o gerar_graficos.py padrão
+    pub fn analysis_script_path(&self) -> PathBuf {
+        self.analysis_script.clone().unwrap_or_else(|| self.analysis_dir.join("gerar_graficos.py"))
+    }
+
+    /// Argumentos do script de análise: o CSV de resultados seguido dos de --analysis-args
+    pub fn analysis_arguments(&self, results_filename: &str) -> Vec<String> {
+        let extra = self.analysis_args.as_deref().unwrap_or_default().split_whitespace();
+        std::iter::once(results_filename).chain(extra).map(str::to_string).collect()
+    }
+
+    /// Resolve todos os caminhos configurados para caminhos absolutos
+    ///
+    /// Caminhos relativos são interpretados a partir do diretório de trabalho atual,
+    /// exatamente como antes; a diferença é que, depois de resolvidos, deixam de
+    /// depender do `current_dir` de processos filhos (ex: o script de gráficos).
+    pub fn resolve_paths(&mut self) -> std::io::Result<()> {
+        self.output_dir = std::path::absolute(&self.output_dir)?;
+        self.analysis_dir = std::path::absolute(&self.analysis_dir)?;
+        if let Some(path) = &self.analysis_script {
+            self.analysis_script = Some(std::path::absolute(path)?);
+        }
+        self.plots_dir = std::path::absolute(&self.plots_dir)?;
+        if let Some(path) = &self.record {
+            self.record = Some(std::path::absolute(path)?);
+        }
+        if let Some(path) = &self.replay {
+            self.replay = Some(std::path::absolute(path)?);
+        }
+        if let Some(ExperimentCommand::Analyze { raw_samples, output_dir }) = &mut self.command {
+            *raw_samples = std::path::absolute(&*raw_samples)?;
+            *output_dir = std::path::absolute(&*output_dir)?;
+        }
+        if let Some(ExperimentCommand::InterArrival { output_dir, .. }) = &mut self.command {
+            *output_dir = std::path::absolute(&*output_dir)?;
+        }
+        if let Some(path) = &self.corpus {
+            self.corpus = Some(std::path::absolute(path)?);
+        }
+        if let Some(path) = &self.report {
+            self.report = Some(std::path::absolute(path)?);
+        }
+        if let Some(path) = &self.latex {
+            self.latex = Some(std::path::absolute(path)?);
+        }
+        if let Some(path) = &self.sqlite {
+            self.sqlite = Some(std::path::absolute(path)?);
+        }
+        Ok(())
+    }
+}
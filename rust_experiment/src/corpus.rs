@@ -0,0 +1,135 @@
+//! Corpus de payloads reais lido do disco (`--corpus`)
+//!
+//! Payloads aleatórios são incompressíveis e não têm os padrões de dados reais, o que
+//! distorce as dimensões de compressão e cache do experimento. Com um corpus, o
+//! `MessageGenerator` mantém a seleção de tipo e tamanho das suas distribuições, mas
+//! tira o conteúdo de arquivos reais:
+//! - Mídias (imagem, arquivo, voz): arquivos do diretório agrupados em classes de
+//!   tamanho (potências de 2); cada tamanho sorteado usa a classe mais próxima
+//! - Texto: linhas não vazias de `chat.txt`, se o arquivo existir no diretório
+//!
+//! Dentro de cada classe os arquivos são usados em ciclo, na ordem dos nomes. Os
+//! conteúdos ficam em `Arc<[u8]>` e são compartilhados pelas mensagens, sem cópia.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::ExperimentError;
+
+/// Nome do registro de conversa do corpus, de onde vêm as mensagens de texto
+pub const CHAT_LOG: &str = "chat.txt";
+
+/// Arquivos do corpus, indexados por classe de tamanho
+#[derive(Debug, Default)]
+pub struct PayloadCorpus {
+    buckets: BTreeMap<u32, Vec<Arc<[u8]>>>, // floor(log2(tamanho)) -> conteúdos
+    chat_lines: Vec<String>,
+}
+
+impl PayloadCorpus {
+    /// Lê os arquivos regulares de `dir` (sem recursão)
+    ///
+    /// Arquivos vazios são ignorados. Falha (`ExperimentError::Config`) se o diretório
+    /// não tiver nenhum arquivo de mídia nem linhas de texto em `chat.txt`.
+    pub fn load(dir: &Path) -> Result<Self, ExperimentError> {
+        let mut entries: Vec<_> = fs::read_dir(dir)?
+            .collect::<io::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_file()))
+            .map(|entry| entry.path())
+            .collect();
+        entries.sort();
+
+        let mut corpus = PayloadCorpus::default();
+        for path in entries {
+            if path.file_name().is_some_and(|name| name == CHAT_LOG) {
+                let log = String::from_utf8_lossy(&fs::read(&path)?).into_owned();
+                corpus.chat_lines = log.lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                continue;
+            }
+            let data = fs::read(&path)?;
+            if !data.is_empty() {
+                corpus.buckets.entry(size_class(data.len())).or_default().push(data.into());
+            }
+        }
+
+        if corpus.is_empty() {
+            return Err(ExperimentError::Config(format!("Corpus sem arquivos utilizáveis em {}", dir.display())));
+        }
+        Ok(corpus)
+    }
+
+    /// Número de arquivos de mídia do corpus
+    pub fn file_count(&self) -> usize {
+        self.buckets.values().map(Vec::len).sum()
+    }
+
+    /// Número de linhas de texto de `chat.txt`
+    pub fn chat_line_count(&self) -> usize {
+        self.chat_lines.len()
+    }
+
+    /// Indica se o corpus não tem nem mídias nem texto
+    pub fn is_empty(&self) -> bool {
+        self.buckets.is_empty() && self.chat_lines.is_empty()
+    }
+
+    /// Classe de tamanho não vazia mais próxima de `size` (empate favorece a menor)
+    pub fn nearest_class(&self, size: usize) -> Option<u32> {
+        let target = size_class(size);
+        let below = self.buckets.range(..=target).next_back().map(|(class, _)| *class);
+        let above = self.buckets.range(target..).next().map(|(class, _)| *class);
+        match (below, above) {
+            (Some(below), Some(above)) => Some(if target - below <= above - target { below } else { above }),
+            (below, above) => below.or(above),
+        }
+    }
+
+    /// Arquivos de uma classe de tamanho
+    pub fn class_files(&self, class: u32) -> &[Arc<[u8]>] {
+        self.buckets.get(&class).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Linhas de texto do registro de conversa
+    pub fn chat_lines(&self) -> &[String] {
+        &self.chat_lines
+    }
+}
+
+/// Classe de tamanho de um payload: floor(log2(tamanho)), com 0 para tamanhos 0 e 1
+fn size_class(size: usize) -> u32 {
+    size.max(1).ilog2()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corpus_buckets_by_size_and_reads_chat_log() {
+        let dir = std::env::temp_dir().join(format!("pq_corpus_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.bin"), vec![1u8; 1000]).unwrap();
+        fs::write(dir.join("b.bin"), vec![2u8; 100_000]).unwrap();
+        fs::write(dir.join("vazio.bin"), []).unwrap();
+        fs::write(dir.join(CHAT_LOG), "oi\n\n  tudo bem?  \n").unwrap();
+
+        let corpus = PayloadCorpus::load(&dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(corpus.file_count(), 2);
+        assert_eq!(corpus.chat_lines(), ["oi", "tudo bem?"]);
+        // 1000 bytes é a classe 9 e 100 kB a classe 16; 15 kB (classe 13) fica mais perto da 16
+        assert_eq!(corpus.nearest_class(1000), Some(9));
+        assert_eq!(corpus.nearest_class(15_000), Some(16));
+        assert_eq!(corpus.nearest_class(10_000_000), Some(16));
+        assert_eq!(corpus.class_files(16)[0].len(), 100_000);
+    }
+}
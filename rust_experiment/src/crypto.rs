@@ -0,0 +1,1847 @@
+//! Primitivas criptográficas do experimento: acordos de chave e cifras simétricas
+//!
+//! Concentra o código criptográfico compartilhado entre o experimento estatístico
+//! (`main`) e os microbenchmarks Criterion (`benches/`), garantindo que ambos meçam
+//! exatamente as mesmas operações.
+//!
+//! ACORDOS DE CHAVE:
+//! - Olm-Clássico: X25519 ECDH (efêmero de Alice, estático de Bob); com
+//!   `EphemeralMode::Static`, Alice reutiliza um segredo próprio entre rotações, e com
+//!   `EphemeralMode::Both`, Bob também gera um segredo X25519 novo a cada acordo
+//! - Olm-Híbrido: X25519 ECDH + Kyber768 KEM, segredos concatenados
+//! - Híbridos de N componentes (`X25519+Kyber768+Kyber1024`, ...), segredos
+//!   concatenados em ordem canônica e combinados via HKDF-Extract
+//!
+//! Cada segredo entra no material do HKDF precedido do seu tamanho em 2 bytes
+//! big-endian (`append_shared_secret`), com o X25519 primeiro e os KEMs em seguida:
+//! o layout não depende da arquitetura e a chave derivada é a mesma em qualquer plataforma.
+//!
+//! CIFRAS SIMÉTRICAS:
+//! - AES-GCM (AES-256, nonce de 12 bytes, tag de 16 bytes)
+//! - ChaCha20 (ChaCha20-Poly1305, nonce de 12 bytes, tag de 16 bytes)
+//! - Megolm-Like (AES-256-CTR com IV de 16 bytes + HMAC-SHA256 opcional de 32 bytes),
+//!   com contador de 32, 64 (padrão) ou 128 bits
+//!
+//! COMPROMETIMENTO DE CHAVE (opcional, independente da cifra):
+//! - SHA-256 da chave e do nonce anexado ao texto cifrado (32 bytes)
+//!
+//! BACKUP DE CHAVES (opcional, `RecoveryKey`):
+//! - Cada chave de sessão nova cifrada para a chave de recuperação (X25519 efêmero +
+//!   HKDF + AES-256-CTR + HMAC-SHA256 truncado), como o backup SSSS do Matrix
+//!
+//! REGISTROS:
+//! - `CipherRegistry` (objetos `SymmetricCipher`) e `AgreementRegistry` (objetos
+//!   `KeyAgreement`), consultados por nome; `encrypt_message` e
+//!   `perform_key_agreement*` são atalhos que montam o registro a cada chamada
+
+// --- BIBLIOTECAS DE CRIPTOGRAFIA SIMÉTRICA ---
+use aes::Aes256;
+use aes_gcm::aead::{Aead, AeadInPlace, KeyInit};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use chacha20poly1305::{ChaCha20Poly1305, Key as ChaKey, Nonce as ChaNonce};
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use hkdf::Hkdf;
+use hmac::{Hmac, Mac};
+use rand_core::CryptoRngCore;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+// --- BIBLIOTECAS DE CRIPTOGRAFIA ASSIMÉTRICA (KEMs) ---
+use pqcrypto_kyber::{kyber1024, kyber512, kyber768};
+use pqcrypto_traits::kem::{Ciphertext as KemCiphertext, PublicKey as KemPublicKey, SharedSecret as KemSharedSecret};
+
+// --- CURVAS ELÍPTICAS CLÁSSICAS (X25519) ---
+use x25519_dalek::{EphemeralSecret, PublicKey as X25519PublicKey, StaticSecret};
+
+use crate::error::ExperimentError;
+
+/// Nomes dos acordos de chave avaliados, na ordem canônica do experimento
+pub const AGREEMENTS: [&str; 2] = ["Olm-Clássico", "Olm-Híbrido"];
+
+/// Nomes das cifras simétricas avaliadas, na ordem canônica do experimento
+pub const CIPHERS: [&str; 3] = ["AES-GCM", "ChaCha20", "Megolm-Like"];
+
+/// Largura do contador do AES-CTR usado pela cifra Megolm-Like
+///
+/// O contador ocupa os `bits` menos significativos do bloco de 16 bytes, somado ao
+/// IV; o restante do IV é o nonce fixo. Uma mensagem não pode exceder 2^bits - 1
+/// blocos de 16 bytes sem que o contador dê a volta e repita o fluxo de chave.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum CtrWidth {
+    /// Contador de 32 bits (Ctr32BE): até ~64 GiB por mensagem
+    #[value(name = "32")]
+    Ctr32,
+    /// Contador de 64 bits (Ctr64BE, comportamento original)
+    #[default]
+    #[value(name = "64")]
+    Ctr64,
+    /// Contador de 128 bits (Ctr128BE): o IV inteiro é o contador
+    #[value(name = "128")]
+    Ctr128,
+}
+
+impl CtrWidth {
+    /// Largura do contador em bits
+    pub fn bits(&self) -> u32 {
+        match self {
+            CtrWidth::Ctr32 => 32,
+            CtrWidth::Ctr64 => 64,
+            CtrWidth::Ctr128 => 128,
+        }
+    }
+
+    /// Maior mensagem, em bytes, cifrável antes do contador dar a volta
+    ///
+    /// Satura em `u128::MAX` para o contador de 128 bits.
+    pub fn max_message_bytes(&self) -> u128 {
+        let max_blocks = u128::MAX >> (128 - self.bits());
+        max_blocks.saturating_mul(16)
+    }
+}
+
+/// Ciclo de vida do segredo X25519 de Alice entre acordos de chave
+///
+/// O Olm gera um segredo efêmero novo a cada sessão; algumas implementações
+/// reutilizam uma chave estática. A escolha muda o que o tempo de rotação mede.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum EphemeralMode {
+    /// Segredo novo a cada acordo (comportamento original): a geração entra no tempo da rotação
+    #[default]
+    Fresh,
+    /// Segredo gerado uma vez por sessão e reutilizado: a geração fica fora das rotações
+    Static,
+    /// Efêmero-efêmero: Alice e Bob geram segredos X25519 novos a cada acordo, e as duas
+    /// gerações entram no tempo da rotação
+    Both,
+}
+
+impl EphemeralMode {
+    /// Nome do modo, como usado em `--ephemeral` e no CSV
+    pub fn name(&self) -> &'static str {
+        match self {
+            EphemeralMode::Fresh => "fresh",
+            EphemeralMode::Static => "static",
+            EphemeralMode::Both => "both",
+        }
+    }
+
+    /// Variante do Diffie-Hellman X25519 (Alice-Bob), como registrada no CSV
+    pub fn dh_variant(&self) -> &'static str {
+        match self {
+            EphemeralMode::Fresh => "ephemeral-static",
+            EphemeralMode::Static => "static-static",
+            EphemeralMode::Both => "ephemeral-ephemeral",
+        }
+    }
+}
+
+/// Frequência de geração das chaves de Bob e se o custo entra no tempo de KEM
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum KeygenPolicy {
+    /// Novo par de chaves a cada rotação, com a geração dentro do tempo de KEM
+    PerRotation,
+    /// Um par por sessão, gerado fora do tempo de KEM (comportamento original)
+    #[default]
+    PerSession,
+    /// Um par por configuração, reutilizado em todas as repetições e remetentes
+    Cached,
+}
+
+impl KeygenPolicy {
+    /// Nome da política, como usado em `--keygen-policy` e no CSV
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeygenPolicy::PerRotation => "per-rotation",
+            KeygenPolicy::PerSession => "per-session",
+            KeygenPolicy::Cached => "cached",
+        }
+    }
+}
+
+/// Backend de AES exigido para a execução (`--aes-backend`)
+///
+/// O crate `aes` escolhe a implementação em tempo de execução: AES-NI (x86) ou as
+/// instruções de criptografia do ARMv8 quando a CPU as tem, e a versão em software
+/// (bitsliced, de tempo constante) caso contrário. Forçar o software só é possível na
+/// compilação, com `RUSTFLAGS="--cfg aes_force_soft"`; o modo `software` apenas
+/// verifica que o binário foi compilado assim.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum AesBackendMode {
+    /// Escolha do crate `aes` (hardware quando disponível)
+    #[default]
+    Auto,
+    /// Exige o backend em software, compilado com `--cfg aes_force_soft`
+    Software,
+}
+
+impl AesBackendMode {
+    /// Nome do modo, como usado em `--aes-backend`
+    pub fn name(&self) -> &'static str {
+        match self {
+            AesBackendMode::Auto => "auto",
+            AesBackendMode::Software => "software",
+        }
+    }
+}
+
+/// Disposição da tag de autenticação em relação ao texto cifrado (`--tag-layout`)
+///
+/// O total de bytes é o mesmo nos dois layouts; muda apenas a API usada e onde a
+/// tag fica (anexada ao texto cifrado ou em um campo à parte, como em alguns formatos
+/// do Matrix).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum TagLayout {
+    /// Tag anexada ao texto cifrado (`encrypt` das AEADs)
+    #[default]
+    Attached,
+    /// Tag devolvida à parte (`encrypt_in_place_detached` das AEADs)
+    Detached,
+}
+
+impl TagLayout {
+    /// Nome do layout, como usado em `--tag-layout` e na coluna `layout_tag`
+    pub fn name(&self) -> &'static str {
+        match self {
+            TagLayout::Attached => "attached",
+            TagLayout::Detached => "detached",
+        }
+    }
+}
+
+/// Maior tag entre as cifras do experimento (HMAC-SHA256 da Megolm-Like)
+pub const MAX_TAG_SIZE: usize = 32;
+
+/// Tag de autenticação devolvida à parte do texto cifrado, sem alocação
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DetachedTag {
+    bytes: [u8; MAX_TAG_SIZE],
+    len: usize,
+}
+
+impl DetachedTag {
+    /// Tag vazia, das cifras sem autenticação (`--megolm-unauthenticated`)
+    pub const EMPTY: DetachedTag = DetachedTag { bytes: [0; MAX_TAG_SIZE], len: 0 };
+
+    /// Copia a tag (no máximo `MAX_TAG_SIZE` bytes)
+    pub fn from_slice(tag: &[u8]) -> Self {
+        let mut bytes = [0; MAX_TAG_SIZE];
+        bytes[..tag.len()].copy_from_slice(tag);
+        Self { bytes, len: tag.len() }
+    }
+
+    /// Bytes da tag
+    pub fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+/// Se o binário foi compilado com o backend de AES em software forçado
+pub fn aes_software_forced() -> bool {
+    cfg!(aes_force_soft)
+}
+
+/// Backend de AES ativo nesta máquina, como o crate `aes` o escolhe
+///
+/// Vale para AES-GCM e para o AES-CTR da cifra Megolm-Like. Replica a detecção do
+/// crate (`aes` e `sse2` no x86, `aes` no ARMv8); sem as instruções, ou com
+/// `aes_force_soft`, o backend é o bitsliced em software.
+pub fn aes_backend() -> &'static str {
+    if aes_software_forced() {
+        return "software";
+    }
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    if std::arch::is_x86_feature_detected!("aes") && std::arch::is_x86_feature_detected!("sse2") {
+        return "aes-ni";
+    }
+    #[cfg(target_arch = "aarch64")]
+    if std::arch::is_aarch64_feature_detected!("aes") {
+        return "armv8";
+    }
+    "software"
+}
+
+/// Limite de texto claro do AES-GCM por par chave/nonce: 2^39 - 256 bits (NIST SP 800-38D)
+pub const AES_GCM_MAX_PLAINTEXT: u128 = (1 << 36) - 32;
+
+/// Limite de texto claro do ChaCha20-Poly1305 por par chave/nonce: contador de 32 bits
+/// com blocos de 64 bytes, menos o bloco da chave do Poly1305 (RFC 8439)
+pub const CHACHA20_MAX_PLAINTEXT: u128 = ((1 << 32) - 1) * 64;
+
+/// Maior texto claro, em bytes, que a cifra aceita com uma única chave e nonce/IV
+///
+/// Na Megolm-Like, o limite depende da largura do contador do AES-CTR. Cifras fora
+/// do registro não têm limite conhecido (0).
+pub fn max_plaintext_bytes(cipher_name: &str, ctr_width: CtrWidth) -> u128 {
+    CipherRegistry::standard(true, ctr_width)
+        .get(cipher_name)
+        .map_or(0, |cipher| cipher.max_plaintext_bytes())
+}
+
+/// Verifica se um texto claro de `size` bytes está dentro do limite seguro da cifra
+///
+/// O erro (`ExperimentError::Crypto`) informa a cifra, o limite e o tamanho recusado.
+pub fn check_plaintext_size(cipher_name: &str, ctr_width: CtrWidth, size: usize) -> Result<(), ExperimentError> {
+    CipherRegistry::standard(true, ctr_width).lookup(cipher_name)?.check_plaintext_size(size)
+}
+
+/// Tamanho mínimo, em bytes, do segredo compartilhado de cada componente do acordo
+///
+/// Corresponde aos 256 bits da chave de sessão derivada pelo combinador. Um componente
+/// que devolva menos contribuiria com menos entropia do que a chave aparenta ter.
+pub const MIN_SHARED_SECRET_BYTES: usize = 32;
+
+/// Bytes do prefixo de tamanho de cada segredo no material do combinador
+pub const SECRET_LENGTH_PREFIX_BYTES: usize = 2;
+
+/// Acrescenta um segredo ao material do combinador no layout canônico
+///
+/// O segredo é precedido do seu tamanho como `u16` big-endian, de modo que a fronteira
+/// entre componentes fica explícita e o layout independe da ordem de bytes da plataforma.
+/// Segredos acima de 65535 bytes não cabem no prefixo e são recusados.
+pub fn append_shared_secret(material: &mut Vec<u8>, secret: &[u8]) -> Result<(), ExperimentError> {
+    let length = u16::try_from(secret.len()).map_err(|_| ExperimentError::Crypto(format!(
+        "segredo compartilhado de {} bytes acima do limite de 65535 bytes do prefixo", secret.len()
+    )))?;
+    material.extend_from_slice(&length.to_be_bytes());
+    material.extend_from_slice(secret);
+    Ok(())
+}
+
+/// Verifica se o segredo de um componente tem o tamanho mínimo exigido pelo combinador
+///
+/// O erro informa o acordo, o componente e os tamanhos obtido e exigido.
+pub fn check_shared_secret(agreement: &str, component: KemComponent, size: usize) -> Result<(), ExperimentError> {
+    if size < MIN_SHARED_SECRET_BYTES {
+        return Err(ExperimentError::Crypto(format!(
+            "segredo do componente {} do acordo '{}' tem {} bytes, abaixo do mínimo de {} bytes",
+            component.name(), agreement, size, MIN_SHARED_SECRET_BYTES
+        )));
+    }
+    Ok(())
+}
+
+/// Componente de um acordo de chaves (híbrido ou não)
+///
+/// A ordem das variantes é a ordem canônica do combinador: o X25519 vem primeiro e
+/// os KEMs seguem em nível de segurança crescente. Novas primitivas entram como
+/// novas variantes, sem alterar o caminho genérico de `perform_key_agreement`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum KemComponent {
+    X25519,
+    Kyber512,
+    Kyber768,
+    Kyber1024,
+}
+
+impl KemComponent {
+    /// Todos os componentes disponíveis, na ordem canônica
+    pub const ALL: [KemComponent; 4] =
+        [KemComponent::X25519, KemComponent::Kyber512, KemComponent::Kyber768, KemComponent::Kyber1024];
+
+    /// Nome do componente, como usado em `--hybrid`
+    pub fn name(&self) -> &'static str {
+        match self {
+            KemComponent::X25519 => "X25519",
+            KemComponent::Kyber512 => "Kyber512",
+            KemComponent::Kyber768 => "Kyber768",
+            KemComponent::Kyber1024 => "Kyber1024",
+        }
+    }
+
+    /// Categoria NIST PQC do componente; `None` para o X25519 (sem segurança pós-quântica)
+    pub fn nist_category(&self) -> Option<u8> {
+        match self {
+            KemComponent::X25519 => None,
+            KemComponent::Kyber512 => Some(1),
+            KemComponent::Kyber768 => Some(3),
+            KemComponent::Kyber1024 => Some(5),
+        }
+    }
+}
+
+/// Componentes de um acordo de chaves, na ordem canônica do combinador
+///
+/// Olm-Clássico é `[X25519]` e Olm-Híbrido é `[X25519, Kyber768]`. Qualquer outro
+/// nome é lido como uma lista `A+B+C` de componentes (ex: `X25519+Kyber768+Kyber1024`),
+/// sem distinção de maiúsculas. A lista é ordenada canonicamente, de modo que a mesma
+/// combinação sempre alimenta o combinador na mesma ordem; componentes repetidos são recusados
+/// (`ExperimentError::Config`).
+pub fn agreement_components(agreement: &str) -> Result<Vec<KemComponent>, ExperimentError> {
+    match agreement {
+        "Olm-Clássico" => return Ok(vec![KemComponent::X25519]),
+        "Olm-Híbrido" => return Ok(vec![KemComponent::X25519, KemComponent::Kyber768]),
+        _ => {}
+    }
+
+    let mut components = agreement
+        .split('+')
+        .map(|name| {
+            KemComponent::ALL
+                .into_iter()
+                .find(|component| component.name().eq_ignore_ascii_case(name.trim()))
+                .ok_or_else(|| ExperimentError::Config(format!(
+                    "componente desconhecido '{}' (disponíveis: {})",
+                    name.trim(),
+                    KemComponent::ALL.map(|component| component.name()).join(", ")
+                )))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    components.sort();
+    if components.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err(ExperimentError::Config(format!("componente repetido em '{}'", agreement)));
+    }
+    Ok(components)
+}
+
+/// Nome canônico de um acordo montado a partir de componentes (ex: `X25519+Kyber768+Kyber1024`)
+pub fn hybrid_agreement_name(components: &[KemComponent]) -> String {
+    components.iter().map(|component| component.name()).collect::<Vec<_>>().join("+")
+}
+
+/// Nível de segurança de um acordo de chaves, pelas categorias do NIST PQC
+///
+/// - Category1Classical: apenas X25519, ~128 bits contra adversário clássico (≈ Categoria 1),
+///   sem segurança contra adversário quântico
+/// - Category1 / Category3 / Category5: maior categoria entre os KEMs do acordo
+///   (Kyber512, Kyber768 e Kyber1024); no híbrido, o X25519 não reduz o nível
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecurityLevel {
+    Category1Classical,
+    Category1,
+    Category3,
+    Category5,
+}
+
+impl SecurityLevel {
+    /// Nível correspondente ao nome de um acordo (ver `agreement_components`)
+    pub fn of_agreement(agreement: &str) -> Self {
+        let category = agreement_components(agreement)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|component| component.nist_category())
+            .max();
+        match category {
+            None => SecurityLevel::Category1Classical,
+            Some(1) => SecurityLevel::Category1,
+            Some(3) => SecurityLevel::Category3,
+            Some(_) => SecurityLevel::Category5,
+        }
+    }
+
+    /// Categoria numérica do NIST (1 a 5)
+    pub fn category(&self) -> u8 {
+        match self {
+            SecurityLevel::Category1Classical | SecurityLevel::Category1 => 1,
+            SecurityLevel::Category3 => 3,
+            SecurityLevel::Category5 => 5,
+        }
+    }
+
+    /// Nome do nível como registrado no CSV
+    pub fn name(&self) -> &'static str {
+        match self {
+            SecurityLevel::Category1Classical => "cat1-classico",
+            SecurityLevel::Category1 => "cat1",
+            SecurityLevel::Category3 => "cat3",
+            SecurityLevel::Category5 => "cat5",
+        }
+    }
+}
+
+/// Versões exatas (resolvidas no Cargo.lock) das bibliotecas de cada primitiva
+///
+/// Capturadas em tempo de compilação pelo `build.rs`, para citação e reprodução:
+/// versões diferentes podem ter caminhos de código (ex: AVX2) com desempenho distinto.
+pub const PRIMITIVE_VERSIONS: [(&str, &str); 10] = [
+    ("pqcrypto-kyber", env!("PQC_DEP_PQCRYPTO_KYBER")),
+    ("x25519-dalek", env!("PQC_DEP_X25519_DALEK")),
+    ("aes-gcm", env!("PQC_DEP_AES_GCM")),
+    ("chacha20poly1305", env!("PQC_DEP_CHACHA20POLY1305")),
+    ("aes", env!("PQC_DEP_AES")),
+    ("ctr", env!("PQC_DEP_CTR")),
+    ("hmac", env!("PQC_DEP_HMAC")),
+    ("hkdf", env!("PQC_DEP_HKDF")),
+    ("sha2", env!("PQC_DEP_SHA2")),
+    ("subtle", env!("PQC_DEP_SUBTLE")),
+];
+
+/// Lista as bibliotecas (com versão) usadas por uma combinação de acordo e cifra
+///
+/// Retorna uma string no formato `crate@versão` separada por `;`, segura para CSV.
+/// O HKDF aparece apenas quando `per_message_kdf` está ativo.
+pub fn primitive_versions(agreement: &str, cipher: &str, per_message_kdf: bool) -> String {
+    let components = agreement_components(agreement).unwrap_or_default();
+    let mut crates = Vec::new();
+    if components.contains(&KemComponent::X25519) {
+        crates.push("x25519-dalek");
+    }
+    if components.iter().any(|component| component.nist_category().is_some()) {
+        crates.push("pqcrypto-kyber");
+    }
+    if let Some(cipher) = CipherRegistry::standard(true, CtrWidth::default()).get(cipher) {
+        crates.extend(cipher.crates());
+    }
+    if per_message_kdf {
+        crates.push("hkdf");
+        if !crates.contains(&"sha2") {
+            crates.push("sha2");
+        }
+    }
+
+    crates.iter()
+        .filter_map(|name| PRIMITIVE_VERSIONS.iter().find(|(crate_name, _)| crate_name == name))
+        .map(|(name, version)| format!("{}@{}", name, version))
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// Material de chave do destinatário (Bob) para um acordo de chaves
+///
+/// Um par de chaves por componente do acordo (ver `agreement_components`): Olm-Clássico
+/// usa apenas X25519; Olm-Híbrido acrescenta um par Kyber768.
+/// As chaves são geradas com o gerador de números aleatórios do sistema.
+pub struct RecipientKeys {
+    components: Vec<ComponentKeys>,
+}
+
+/// Chaves de Bob para um único componente (pares Kyber no heap, pelo tamanho)
+enum ComponentKeys {
+    X25519(X25519PublicKey),
+    Kyber512(Box<(kyber512::PublicKey, kyber512::SecretKey)>),
+    Kyber768(Box<(kyber768::PublicKey, kyber768::SecretKey)>),
+    Kyber1024(Box<(kyber1024::PublicKey, kyber1024::SecretKey)>),
+}
+
+impl ComponentKeys {
+    /// Componente do acordo a que estas chaves pertencem
+    fn component(&self) -> KemComponent {
+        match self {
+            ComponentKeys::X25519(_) => KemComponent::X25519,
+            ComponentKeys::Kyber512(_) => KemComponent::Kyber512,
+            ComponentKeys::Kyber768(_) => KemComponent::Kyber768,
+            ComponentKeys::Kyber1024(_) => KemComponent::Kyber1024,
+        }
+    }
+}
+
+impl RecipientKeys {
+    /// Gera as chaves de Bob necessárias para o acordo informado
+    ///
+    /// Erro se o nome do acordo for inválido (ver `agreement_components`).
+    pub fn generate(agreement: &str) -> Result<Self, ExperimentError> {
+        Self::generate_with_rng(agreement, &mut rand::thread_rng())
+    }
+
+    /// Gera as chaves de Bob com um gerador explícito (ex: semeado por `--seed`)
+    ///
+    /// O gerador alimenta as chaves X25519; os pares Kyber usam o `randombytes`
+    /// interno do PQClean (entropia do sistema), que não aceita semente externa.
+    pub fn generate_with_rng(agreement: &str, rng: &mut impl CryptoRngCore) -> Result<Self, ExperimentError> {
+        let components = agreement_components(agreement)?;
+        Ok(Self::generate_components(&components, rng))
+    }
+
+    /// Gera um par de chaves por componente, na ordem dada
+    fn generate_components(components: &[KemComponent], rng: &mut impl CryptoRngCore) -> Self {
+        let components = components
+            .iter()
+            .map(|component| match component {
+                KemComponent::X25519 => {
+                    let x25519_secret = EphemeralSecret::random_from_rng(&mut *rng);
+                    ComponentKeys::X25519(X25519PublicKey::from(&x25519_secret))
+                }
+                KemComponent::Kyber512 => {
+                    ComponentKeys::Kyber512(Box::new(kyber512::keypair()))
+                }
+                KemComponent::Kyber768 => {
+                    ComponentKeys::Kyber768(Box::new(kyber768::keypair()))
+                }
+                KemComponent::Kyber1024 => {
+                    ComponentKeys::Kyber1024(Box::new(kyber1024::keypair()))
+                }
+            })
+            .collect();
+        Self { components }
+    }
+}
+
+/// Material de chave do remetente (Alice) mantido entre acordos de chave
+///
+/// No modo `Fresh` (e no `Default`) não guarda nada: cada acordo gera seu próprio
+/// segredo efêmero. No modo `Both`, também não, mas cada acordo gera ainda um segredo
+/// X25519 novo para Bob no lugar da sua chave pública pré-publicada.
+#[derive(Default)]
+pub struct SenderKeys {
+    x25519_static: Option<StaticSecret>,
+    ephemeral_recipient: bool,
+}
+
+impl SenderKeys {
+    /// Prepara as chaves de Alice; no modo `Static`, gera aqui o segredo reutilizado
+    pub fn generate_with_rng(mode: EphemeralMode, rng: &mut impl CryptoRngCore) -> Self {
+        let x25519_static = match mode {
+            EphemeralMode::Fresh | EphemeralMode::Both => None,
+            EphemeralMode::Static => Some(StaticSecret::random_from_rng(&mut *rng)),
+        };
+        Self { x25519_static, ephemeral_recipient: mode == EphemeralMode::Both }
+    }
+
+    /// Modo em que as chaves foram preparadas
+    pub fn mode(&self) -> EphemeralMode {
+        match (&self.x25519_static, self.ephemeral_recipient) {
+            (Some(_), _) => EphemeralMode::Static,
+            (None, true) => EphemeralMode::Both,
+            (None, false) => EphemeralMode::Fresh,
+        }
+    }
+}
+
+/// Bytes trafegados em um acordo de chaves, separados por direção
+///
+/// A perspectiva é a de Alice (quem inicia e encapsula): o download são as chaves
+/// públicas de Bob (X25519 e, no híbrido, a chave pública Kyber); o upload é o que
+/// Alice envia a Bob (o texto cifrado Kyber).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HandshakeBytes {
+    pub upload: usize,
+    pub download: usize,
+}
+
+impl HandshakeBytes {
+    /// Total de bytes trafegados, independente da direção
+    pub fn total(&self) -> usize {
+        self.upload + self.download
+    }
+}
+
+/// Decapsulamento medido de um acordo com KEM
+///
+/// Com vários KEMs no acordo, o tempo é a soma dos decapsulamentos de todos eles; com
+/// vários destinatários por encapsulamento (`perform_key_agreement_decapsulating`), a soma
+/// dos decapsulamentos de todos os destinatários.
+#[derive(Debug, Clone, Copy)]
+pub struct Decapsulation {
+    pub time: Duration,   // Tempo apenas das chamadas `decapsulate`
+    pub rejected: bool,   // Texto cifrado corrompido, tratado pela rejeição implícita
+}
+
+/// Executa um acordo de chaves completo com Bob
+///
+/// Para acordos com KEM, inclui encapsulamento (Alice) e decapsulamento (Bob).
+///
+/// Retorna:
+/// - Tupla contendo: (segredo compartilhado, bytes trafegados no acordo por direção)
+/// - Erro se o acordo for inválido ou um componente falhar (ver `perform_key_agreement_injecting`)
+pub fn perform_key_agreement(agreement: &str, bob: &RecipientKeys) -> Result<(Vec<u8>, HandshakeBytes), ExperimentError> {
+    let (shared_secret, bandwidth, _) = perform_key_agreement_injecting(
+        agreement, bob, &SenderKeys::default(), false, &mut rand::thread_rng(),
+    )?;
+    Ok((shared_secret, bandwidth))
+}
+
+/// Executa um acordo de chaves, opcionalmente corrompendo os textos cifrados dos KEMs
+///
+/// Cada componente do acordo é executado na ordem canônica e os segredos são
+/// concatenados nessa ordem, cada um com o prefixo de tamanho de `append_shared_secret`,
+/// formando o material de entrada do combinador (`combine_shared_secrets`); a largura
+/// de banda é somada entre os componentes.
+/// Cada segredo deve ter ao menos `MIN_SHARED_SECRET_BYTES`; um componente que devolva
+/// menos resulta em `ExperimentError::Crypto` com o nome do acordo e do componente.
+/// O Olm-Híbrido é o caso de dois componentes (X25519 ‖ Kyber768).
+///
+/// Com `corrupt_ciphertext`, um byte de cada texto cifrado KEM é invertido antes do
+/// `decapsulate`. O Kyber não sinaliza erro nesse caso: pela rejeição implícita,
+/// devolve um segredo pseudoaleatório derivado da chave secreta, que nunca coincide
+/// com o segredo encapsulado por Alice. Essa propriedade é verificada a cada
+/// chamada (um resultado inconsistente é `ExperimentError::Crypto`), e o tempo do
+/// decapsulamento é devolvido para comparar os dois caminhos.
+/// Acordos sem KEM (Olm-Clássico) ignoram o argumento.
+///
+/// `rng` gera o segredo efêmero X25519 de Alice, a menos que `alice` traga um segredo
+/// estático (`EphemeralMode::Static`), e, no modo `EphemeralMode::Both`, também o de
+/// Bob; os KEMs usam a entropia do sistema.
+///
+/// Retorna:
+/// - Tupla contendo: (segredos concatenados, bytes por direção, decapsulamento medido se houver KEM)
+/// - Erro se o nome do acordo for inválido (`ExperimentError::Config`) ou um componente falhar
+pub fn perform_key_agreement_injecting(
+    agreement: &str,
+    bob: &RecipientKeys,
+    alice: &SenderKeys,
+    corrupt_ciphertext: bool,
+    rng: &mut impl CryptoRngCore,
+) -> Result<(Vec<u8>, HandshakeBytes, Option<Decapsulation>), ExperimentError> {
+    perform_key_agreement_decapsulating(agreement, bob, alice, corrupt_ciphertext, 1, rng)
+}
+
+/// Executa um acordo de chaves em que cada encapsulamento é decapsulado `decapsulations` vezes
+///
+/// Modela o lado receptor de um grupo: um único encapsulamento de Alice corresponde a
+/// vários destinatários, cada um pagando o seu decapsulamento. Todas as chamadas usam o
+/// mesmo texto cifrado e a mesma chave secreta de Bob, de modo que o custo medido é o
+/// de `decapsulations` decapsulamentos; cada resultado é verificado como em
+/// `perform_key_agreement_injecting`, que é o caso de um único destinatário. O valor 0
+/// é tratado como 1. A largura de banda não muda: o texto cifrado é transmitido uma vez.
+pub fn perform_key_agreement_decapsulating(
+    agreement: &str,
+    bob: &RecipientKeys,
+    alice: &SenderKeys,
+    corrupt_ciphertext: bool,
+    decapsulations: usize,
+    rng: &mut impl CryptoRngCore,
+) -> Result<(Vec<u8>, HandshakeBytes, Option<Decapsulation>), ExperimentError> {
+    ComponentAgreement::new(agreement)?.agree(bob, alice, corrupt_ciphertext, decapsulations, rng)
+}
+
+/// Executa os componentes de um acordo sobre as chaves de Bob (corpo de `KeyAgreement::agree`)
+fn agree_components(
+    agreement: &str,
+    bob: &RecipientKeys,
+    alice: &SenderKeys,
+    corrupt_ciphertext: bool,
+    decapsulations: usize,
+    rng: &mut dyn CryptoRngCore,
+) -> Result<(Vec<u8>, HandshakeBytes, Option<Decapsulation>), ExperimentError> {
+    let decapsulations = decapsulations.max(1);
+    debug_assert_eq!(
+        agreement_components(agreement).map(|components| components.len()).ok(),
+        Some(bob.components.len()),
+        "Chaves de Bob geradas para outro acordo"
+    );
+    let mut combined_secret = Vec::with_capacity((SECRET_LENGTH_PREFIX_BYTES + 32) * bob.components.len());
+    let mut bandwidth = HandshakeBytes::default();
+    let mut decapsulation: Option<Decapsulation> = None;
+
+    for keys in &bob.components {
+        let (secret, component_bandwidth, decap_time) = match keys {
+            ComponentKeys::X25519(bob_public) => {
+                // Efêmero-efêmero: Bob gera um par novo em vez de usar a chave pré-publicada
+                let fresh_public = alice.ephemeral_recipient
+                    .then(|| X25519PublicKey::from(&EphemeralSecret::random_from_rng(&mut *rng)));
+                let bob_public = fresh_public.as_ref().unwrap_or(bob_public);
+                // ECDH: Alice baixa a chave pública de Bob
+                let shared = match &alice.x25519_static {
+                    Some(alice_secret) => alice_secret.diffie_hellman(bob_public),
+                    None => EphemeralSecret::random_from_rng(&mut *rng).diffie_hellman(bob_public),
+                };
+                let bytes = HandshakeBytes { upload: 0, download: bob_public.as_bytes().len() };
+                (shared.as_bytes().to_vec(), bytes, None)
+            }
+            ComponentKeys::Kyber512(keys) =>
+                kem_round(&keys.0, &keys.1, kyber512::encapsulate, kyber512::decapsulate, corrupt_ciphertext, decapsulations)?,
+            ComponentKeys::Kyber768(keys) =>
+                kem_round(&keys.0, &keys.1, kyber768::encapsulate, kyber768::decapsulate, corrupt_ciphertext, decapsulations)?,
+            ComponentKeys::Kyber1024(keys) =>
+                kem_round(&keys.0, &keys.1, kyber1024::encapsulate, kyber1024::decapsulate, corrupt_ciphertext, decapsulations)?,
+        };
+        // Um segredo curto enfraqueceria a chave de sessão sem nenhum sinal: falha aqui,
+        // nomeando o acordo e o componente, em vez de seguir com o combinador
+        check_shared_secret(agreement, keys.component(), secret.len())?;
+        append_shared_secret(&mut combined_secret, &secret)?;
+        bandwidth.upload += component_bandwidth.upload;
+        bandwidth.download += component_bandwidth.download;
+        if let Some(time) = decap_time {
+            let total = decapsulation.map_or(Duration::ZERO, |decap| decap.time) + time;
+            decapsulation = Some(Decapsulation { time: total, rejected: corrupt_ciphertext });
+        }
+    }
+    Ok((combined_secret, bandwidth, decapsulation))
+}
+
+/// Executa um encapsulamento e `decapsulations` decapsulamentos de um KEM genérico
+///
+/// Retorna:
+/// - Tupla contendo: (segredo de Alice, bytes por direção, tempo somado dos decapsulamentos)
+/// - Erro se um decapsulamento contradisser a corrupção do texto cifrado
+fn kem_round<PK, SK, SS, CT>(
+    public_key: &PK,
+    secret_key: &SK,
+    encapsulate: fn(&PK) -> (SS, CT),
+    decapsulate: fn(&CT, &SK) -> SS,
+    corrupt_ciphertext: bool,
+    decapsulations: usize,
+) -> Result<(Vec<u8>, HandshakeBytes, Option<Duration>), ExperimentError>
+where
+    PK: KemPublicKey,
+    SS: KemSharedSecret,
+    CT: KemCiphertext + Copy,
+{
+    let (shared, ciphertext) = encapsulate(public_key);
+    let received_ct = if corrupt_ciphertext {
+        let mut bytes = ciphertext.as_bytes().to_vec();
+        bytes[0] ^= 0x01;
+        CT::from_bytes(&bytes)
+            .map_err(|e| ExperimentError::Crypto(format!("texto cifrado KEM corrompido inválido: {}", e)))?
+    } else {
+        ciphertext
+    };
+
+    let mut decap_time = Duration::ZERO;
+    for _ in 0..decapsulations {
+        let start_decap = Instant::now();
+        let decapsulated = decapsulate(&received_ct, secret_key);
+        decap_time += start_decap.elapsed();
+
+        // Caminho de sucesso: segredos iguais; caminho de falha: rejeição implícita
+        let secrets_match = decapsulated.as_bytes() == shared.as_bytes();
+        if secrets_match == corrupt_ciphertext {
+            return Err(ExperimentError::Crypto(format!(
+                "decapsulamento KEM inconsistente (texto cifrado corrompido: {})", corrupt_ciphertext
+            )));
+        }
+    }
+
+    // Alice baixa a chave pública de Bob e envia o texto cifrado
+    let bytes = HandshakeBytes { upload: received_ct.as_bytes().len(), download: public_key.as_bytes().len() };
+    Ok((shared.as_bytes().to_vec(), bytes, Some(decap_time)))
+}
+
+/// Combinador híbrido: deriva a chave de sessão de 32 bytes dos segredos concatenados
+///
+/// HKDF-Extract (SHA-256) sobre o material de `perform_key_agreement`, com um rótulo
+/// fixo como sal: todos os componentes contribuem para a chave, em qualquer número,
+/// e a quebra de um deles não expõe a sessão enquanto algum outro resistir.
+/// O material segue o layout canônico de `append_shared_secret`.
+pub fn combine_shared_secrets(combined_secret: &[u8]) -> [u8; 32] {
+    let (session_key, _) = Hkdf::<Sha256>::extract(Some(b"MATRIX_HYBRID_COMBINER"), combined_secret);
+    session_key.into()
+}
+
+/// Chave de sessão de 32 bytes a partir do material de `perform_key_agreement`
+///
+/// Com um único componente (Olm-Clássico), a chave é o próprio segredo X25519, como
+/// no Olm, sem passar pelo combinador; com dois ou mais, é `combine_shared_secrets`.
+pub fn session_key(components: &[KemComponent], combined_secret: &[u8]) -> [u8; 32] {
+    match components {
+        [_] => {
+            let mut key = [0u8; 32];
+            key.copy_from_slice(&combined_secret[SECRET_LENGTH_PREFIX_BYTES..SECRET_LENGTH_PREFIX_BYTES + 32]);
+            key
+        }
+        _ => combine_shared_secrets(combined_secret),
+    }
+}
+
+/// Deriva a chave e o nonce de uma mensagem a partir da chave de sessão (HKDF-Expand)
+///
+/// Modela a derivação por mensagem do Megolm/Olm: a chave de sessão atual é usada
+/// como PRK e o contador da mensagem entra no `info`, produzindo 48 bytes:
+/// 32 de chave e 16 de nonce/IV (as AEADs usam os 12 primeiros).
+pub fn derive_message_key(session_key: &[u8; 32], counter: u64) -> ([u8; 32], [u8; 16]) {
+    let hkdf = Hkdf::<Sha256>::from_prk(session_key).expect("PRK de 32 bytes é sempre válida");
+    let mut okm = [0u8; 48];
+    hkdf.expand_multi_info(&[b"MEGOLM_MSG_KEY", &counter.to_be_bytes()], &mut okm)
+        .expect("48 bytes estão dentro do limite do HKDF-SHA256");
+
+    let mut message_key = [0u8; 32];
+    let mut nonce = [0u8; 16];
+    message_key.copy_from_slice(&okm[..32]);
+    nonce.copy_from_slice(&okm[32..]);
+    (message_key, nonce)
+}
+
+/// Tamanho, em bytes, do compromisso de chave anexado a cada texto cifrado
+pub const KEY_COMMITMENT_SIZE: usize = 32;
+
+/// Token fixo da confirmação de chave (`--key-confirmation`), cifrado com a chave de cada
+/// sessão nova; quem o decifra corretamente prova que derivou a mesma chave
+pub const KEY_CONFIRMATION_TOKEN: &[u8] = b"MATRIX_KEY_CONFIRMATION";
+
+/// Calcula o compromisso de chave de uma mensagem: SHA-256(rótulo ‖ chave ‖ nonce)
+///
+/// AEADs como AES-GCM e ChaCha20-Poly1305 não comprometem a chave: um mesmo texto
+/// cifrado pode ser decifrado com sucesso sob chaves diferentes, o que é explorável
+/// em mensagens de grupo. Anexar este hash e verificá-lo antes de decifrar é a
+/// mitigação usual; por depender só da chave e do nonce, vale para qualquer cifra.
+pub fn key_commitment(key: &[u8; 32], nonce: &[u8]) -> [u8; KEY_COMMITMENT_SIZE] {
+    let mut hasher = Sha256::new();
+    hasher.update(b"MATRIX_KEY_COMMITMENT");
+    hasher.update(key);
+    hasher.update(nonce);
+    hasher.finalize().into()
+}
+
+/// Tamanho, em bytes, da tag HMAC truncada de um backup de chave (como no Matrix)
+pub const BACKUP_MAC_SIZE: usize = 8;
+
+/// Chave de recuperação do backup de chaves do servidor (SSSS, `--key-backup`)
+///
+/// Modela o `m.megolm_backup.v1.curve25519-aes-sha2` do Matrix: cada chave de sessão nova
+/// é cifrada para a chave pública de recuperação e guardada no servidor, e só quem tem a
+/// chave privada (o usuário, em outro dispositivo) a recupera.
+pub struct RecoveryKey {
+    secret: StaticSecret,
+    public: X25519PublicKey,
+}
+
+impl RecoveryKey {
+    /// Gera o par X25519 de recuperação
+    pub fn generate(rng: &mut impl CryptoRngCore) -> Self {
+        let secret = StaticSecret::random_from_rng(&mut *rng);
+        let public = X25519PublicKey::from(&secret);
+        Self { secret, public }
+    }
+
+    /// Cifra uma chave de sessão para o backup: efêmero X25519 ‖ texto cifrado ‖ tag
+    ///
+    /// Um X25519 efêmero com a chave pública de recuperação, HKDF-SHA256 para a chave
+    /// AES, a chave HMAC e o IV, AES-256-CTR sobre a chave de sessão e HMAC-SHA256
+    /// truncado em `BACKUP_MAC_SIZE` bytes. O Matrix usa AES-CBC sobre a sessão em JSON;
+    /// o custo assimétrico, que domina, é o mesmo.
+    pub fn backup(&self, session_key: &[u8; 32], rng: &mut impl CryptoRngCore) -> Vec<u8> {
+        let ephemeral = EphemeralSecret::random_from_rng(&mut *rng);
+        let ephemeral_public = X25519PublicKey::from(&ephemeral);
+        let (aes_key, mac_key, iv) = backup_keys(ephemeral.diffie_hellman(&self.public).as_bytes());
+
+        let mut backup = Vec::with_capacity(32 + session_key.len() + BACKUP_MAC_SIZE);
+        backup.extend_from_slice(ephemeral_public.as_bytes());
+        let mut ciphertext = *session_key;
+        megolm_keystream(&aes_key, &iv, &mut ciphertext, CtrWidth::default());
+        backup.extend_from_slice(&ciphertext);
+        let tag = megolm_tag(&mac_key, &iv, &ciphertext);
+        backup.extend_from_slice(&tag.as_slice()[..BACKUP_MAC_SIZE]);
+        backup
+    }
+
+    /// Recupera a chave de sessão de um backup (`ExperimentError::Crypto` se a tag não confere)
+    pub fn restore(&self, backup: &[u8]) -> Result<[u8; 32], ExperimentError> {
+        if backup.len() != 32 + 32 + BACKUP_MAC_SIZE {
+            return Err(ExperimentError::Crypto(format!("backup de chave com {} bytes", backup.len())));
+        }
+        let ephemeral_public: [u8; 32] = backup[..32].try_into().expect("32 bytes verificados");
+        let shared = self.secret.diffie_hellman(&X25519PublicKey::from(ephemeral_public));
+        let (aes_key, mac_key, iv) = backup_keys(shared.as_bytes());
+        let mut session_key: [u8; 32] = backup[32..64].try_into().expect("32 bytes verificados");
+        let tag = megolm_tag(&mac_key, &iv, &session_key);
+        if !tags_equal(&tag.as_slice()[..BACKUP_MAC_SIZE], &backup[64..]) {
+            return Err(ExperimentError::Crypto("tag HMAC inválida no backup de chave".to_string()));
+        }
+        megolm_keystream(&aes_key, &iv, &mut session_key, CtrWidth::default());
+        Ok(session_key)
+    }
+}
+
+/// Deriva do segredo X25519 do backup a chave AES, a chave HMAC e o IV (HKDF-SHA256)
+fn backup_keys(shared_secret: &[u8; 32]) -> ([u8; 32], [u8; 32], [u8; 16]) {
+    let mut okm = [0u8; 80];
+    Hkdf::<Sha256>::new(Some(&[0u8; 32]), shared_secret)
+        .expand(b"MATRIX_KEY_BACKUP", &mut okm)
+        .expect("80 bytes estão dentro do limite do HKDF-SHA256");
+    let mut aes_key = [0u8; 32];
+    let mut mac_key = [0u8; 32];
+    let mut iv = [0u8; 16];
+    aes_key.copy_from_slice(&okm[..32]);
+    mac_key.copy_from_slice(&okm[32..64]);
+    iv.copy_from_slice(&okm[64..]);
+    (aes_key, mac_key, iv)
+}
+
+/// Cifra uma mensagem com a cifra indicada
+///
+/// As AEADs usam os 12 primeiros bytes de `nonce_material`; o Megolm-Like usa
+/// os 16 bytes como IV do AES-CTR, com contador de `ctr_width` bits, e, se
+/// `authenticate`, anexa uma tag HMAC-SHA256 (encrypt-then-MAC) sobre IV + texto cifrado.
+///
+/// Retorna:
+/// - Tupla contendo: (texto cifrado com tag, tamanho do nonce/IV transmitido)
+/// - Erro se a cifra for desconhecida (`ExperimentError::Config`) ou o texto claro
+///   exceder o limite seguro da cifra (`check_plaintext_size`)
+///
+/// Atalho que monta o registro padrão a cada chamada; laços medidos devem buscar a
+/// cifra uma vez em `CipherRegistry` e chamar `SymmetricCipher::encrypt`.
+pub fn encrypt_message(
+    cipher_name: &str,
+    key: &[u8; 32],
+    nonce_material: &[u8; 16],
+    plaintext: &[u8],
+    authenticate: bool,
+    ctr_width: CtrWidth,
+) -> Result<(Vec<u8>, usize), ExperimentError> {
+    let registry = CipherRegistry::standard(authenticate, ctr_width);
+    let cipher = registry.lookup(cipher_name)?;
+    cipher.check_plaintext_size(plaintext.len())?;
+    cipher.encrypt(key, nonce_material, plaintext)
+}
+
+/// Cifra uma mensagem no próprio buffer, sem alocar o texto cifrado (`--in-place`)
+///
+/// Na entrada `buffer` contém o texto claro; na saída, o texto cifrado seguido da tag.
+/// As AEADs usam `encrypt_in_place_detached` e anexam a tag destacada; com um buffer
+/// reutilizado cuja capacidade já comporta a tag, nenhuma alocação ocorre na chamada.
+///
+/// Retorna:
+/// - Tamanho do nonce/IV transmitido
+/// - Erro se o texto claro exceder o limite seguro da cifra (`check_plaintext_size`)
+pub fn encrypt_message_in_place(
+    cipher_name: &str,
+    key: &[u8; 32],
+    nonce_material: &[u8; 16],
+    buffer: &mut Vec<u8>,
+    authenticate: bool,
+    ctr_width: CtrWidth,
+) -> Result<usize, ExperimentError> {
+    let registry = CipherRegistry::standard(authenticate, ctr_width);
+    let cipher = registry.lookup(cipher_name)?;
+    cipher.check_plaintext_size(buffer.len())?;
+    cipher.encrypt_in_place(key, nonce_material, buffer)
+}
+
+/// Cifra simétrica do experimento, buscada pelo nome em `CipherRegistry`
+///
+/// Uma cifra nova implementa este trait e é registrada em `CipherRegistry::standard`;
+/// o nome também entra em `CIPHERS`, que fixa a ordem das cifras na matriz.
+///
+/// Os métodos de cifragem não verificam o limite de tamanho: o chamador chama
+/// `check_plaintext_size` antes, fora da janela de tempo medida.
+pub trait SymmetricCipher: Send + Sync {
+    /// Nome da cifra, como usado em `CIPHERS` e no CSV
+    fn name(&self) -> &'static str;
+
+    /// Bibliotecas usadas, pelos nomes de `PRIMITIVE_VERSIONS`
+    fn crates(&self) -> &'static [&'static str];
+
+    /// Maior texto claro, em bytes, aceito com uma única chave e nonce/IV
+    fn max_plaintext_bytes(&self) -> u128;
+
+    /// Nome da cifra nas mensagens de erro, com os parâmetros que mudam o limite
+    fn describe(&self) -> String {
+        self.name().to_string()
+    }
+
+    /// Cifra `plaintext`, alocando o texto cifrado
+    ///
+    /// Retorna:
+    /// - Tupla contendo: (texto cifrado com tag, tamanho do nonce/IV transmitido)
+    fn encrypt(&self, key: &[u8; 32], nonce_material: &[u8; 16], plaintext: &[u8]) -> Result<(Vec<u8>, usize), ExperimentError>;
+
+    /// Cifra no próprio buffer: na saída, o texto cifrado seguido da tag
+    ///
+    /// Retorna:
+    /// - Tamanho do nonce/IV transmitido
+    fn encrypt_in_place(&self, key: &[u8; 32], nonce_material: &[u8; 16], buffer: &mut Vec<u8>) -> Result<usize, ExperimentError>;
+
+    /// Cifra `plaintext` com a tag destacada (`--tag-layout detached`), alocando o texto cifrado
+    ///
+    /// Retorna:
+    /// - Tupla contendo: (texto cifrado sem a tag, tag, tamanho do nonce/IV transmitido)
+    fn encrypt_detached(
+        &self,
+        key: &[u8; 32],
+        nonce_material: &[u8; 16],
+        plaintext: &[u8],
+    ) -> Result<(Vec<u8>, DetachedTag, usize), ExperimentError>;
+
+    /// Verifica se um texto claro de `size` bytes está dentro do limite seguro da cifra
+    ///
+    /// Acima do limite, o AEAD recusaria a mensagem e o contador do CTR daria a volta.
+    /// O erro informa a cifra, o limite e o tamanho recusado.
+    fn check_plaintext_size(&self, size: usize) -> Result<(), ExperimentError> {
+        let limit = self.max_plaintext_bytes();
+        if size as u128 > limit {
+            return Err(ExperimentError::Crypto(format!(
+                "texto claro de {} bytes excede o limite de {} bytes da cifra {} por chave/nonce",
+                size, limit, self.describe()
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// AES-256-GCM (nonce de 12 bytes, tag de 16)
+pub struct AesGcmCipher;
+
+impl SymmetricCipher for AesGcmCipher {
+    fn name(&self) -> &'static str {
+        "AES-GCM"
+    }
+
+    fn crates(&self) -> &'static [&'static str] {
+        &["aes-gcm"]
+    }
+
+    fn max_plaintext_bytes(&self) -> u128 {
+        AES_GCM_MAX_PLAINTEXT
+    }
+
+    fn encrypt(&self, key: &[u8; 32], nonce_material: &[u8; 16], plaintext: &[u8]) -> Result<(Vec<u8>, usize), ExperimentError> {
+        let nonce = &nonce_material[..12];
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+        let ciphertext = cipher.encrypt(
+            Nonce::from_slice(nonce),
+            aes_gcm::aead::Payload { msg: plaintext, aad: b"" }
+        ).map_err(|_| ExperimentError::Crypto("Erro na criptografia AES-GCM".to_string()))?;
+        Ok((ciphertext, nonce.len()))
+    }
+
+    fn encrypt_in_place(&self, key: &[u8; 32], nonce_material: &[u8; 16], buffer: &mut Vec<u8>) -> Result<usize, ExperimentError> {
+        let nonce = &nonce_material[..12];
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+        let tag = cipher.encrypt_in_place_detached(Nonce::from_slice(nonce), b"", buffer)
+            .map_err(|_| ExperimentError::Crypto("Erro na criptografia AES-GCM".to_string()))?;
+        buffer.extend_from_slice(&tag);
+        Ok(nonce.len())
+    }
+
+    fn encrypt_detached(
+        &self,
+        key: &[u8; 32],
+        nonce_material: &[u8; 16],
+        plaintext: &[u8],
+    ) -> Result<(Vec<u8>, DetachedTag, usize), ExperimentError> {
+        let nonce = &nonce_material[..12];
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+        let mut ciphertext = plaintext.to_vec();
+        let tag = cipher.encrypt_in_place_detached(Nonce::from_slice(nonce), b"", &mut ciphertext)
+            .map_err(|_| ExperimentError::Crypto("Erro na criptografia AES-GCM".to_string()))?;
+        Ok((ciphertext, DetachedTag::from_slice(&tag), nonce.len()))
+    }
+}
+
+/// ChaCha20-Poly1305 (nonce de 12 bytes, tag de 16)
+pub struct ChaCha20Cipher;
+
+impl SymmetricCipher for ChaCha20Cipher {
+    fn name(&self) -> &'static str {
+        "ChaCha20"
+    }
+
+    fn crates(&self) -> &'static [&'static str] {
+        &["chacha20poly1305"]
+    }
+
+    fn max_plaintext_bytes(&self) -> u128 {
+        CHACHA20_MAX_PLAINTEXT
+    }
+
+    fn encrypt(&self, key: &[u8; 32], nonce_material: &[u8; 16], plaintext: &[u8]) -> Result<(Vec<u8>, usize), ExperimentError> {
+        let nonce = &nonce_material[..12];
+        let cipher = ChaCha20Poly1305::new(ChaKey::from_slice(key));
+        let ciphertext = cipher.encrypt(
+            ChaNonce::from_slice(nonce),
+            chacha20poly1305::aead::Payload { msg: plaintext, aad: b"" }
+        ).map_err(|_| ExperimentError::Crypto("Erro na criptografia ChaCha20".to_string()))?;
+        Ok((ciphertext, nonce.len()))
+    }
+
+    fn encrypt_in_place(&self, key: &[u8; 32], nonce_material: &[u8; 16], buffer: &mut Vec<u8>) -> Result<usize, ExperimentError> {
+        let nonce = &nonce_material[..12];
+        let cipher = ChaCha20Poly1305::new(ChaKey::from_slice(key));
+        let tag = cipher.encrypt_in_place_detached(ChaNonce::from_slice(nonce), b"", buffer)
+            .map_err(|_| ExperimentError::Crypto("Erro na criptografia ChaCha20".to_string()))?;
+        buffer.extend_from_slice(&tag);
+        Ok(nonce.len())
+    }
+
+    fn encrypt_detached(
+        &self,
+        key: &[u8; 32],
+        nonce_material: &[u8; 16],
+        plaintext: &[u8],
+    ) -> Result<(Vec<u8>, DetachedTag, usize), ExperimentError> {
+        let nonce = &nonce_material[..12];
+        let cipher = ChaCha20Poly1305::new(ChaKey::from_slice(key));
+        let mut ciphertext = plaintext.to_vec();
+        let tag = cipher.encrypt_in_place_detached(ChaNonce::from_slice(nonce), b"", &mut ciphertext)
+            .map_err(|_| ExperimentError::Crypto("Erro na criptografia ChaCha20".to_string()))?;
+        Ok((ciphertext, DetachedTag::from_slice(&tag), nonce.len()))
+    }
+}
+
+/// Megolm-Like: AES-256-CTR com IV de 16 bytes e, se `authenticate`, HMAC-SHA256
+///
+/// Como no Megolm, a chave da mensagem não é usada diretamente: as chaves do AES-CTR
+/// e do HMAC são derivadas dela por HKDF com rótulos distintos (`megolm_keys`).
+pub struct MegolmLikeCipher {
+    pub authenticate: bool,
+    pub ctr_width: CtrWidth,
+}
+
+impl SymmetricCipher for MegolmLikeCipher {
+    fn name(&self) -> &'static str {
+        "Megolm-Like"
+    }
+
+    fn crates(&self) -> &'static [&'static str] {
+        &["aes", "ctr", "hmac", "sha2"]
+    }
+
+    fn max_plaintext_bytes(&self) -> u128 {
+        self.ctr_width.max_message_bytes()
+    }
+
+    fn describe(&self) -> String {
+        format!("{} (contador de {} bits)", self.name(), self.ctr_width.bits())
+    }
+
+    fn encrypt(&self, key: &[u8; 32], nonce_material: &[u8; 16], plaintext: &[u8]) -> Result<(Vec<u8>, usize), ExperimentError> {
+        let mut buffer = plaintext.to_vec();
+        megolm_encrypt(key, nonce_material, &mut buffer, self.authenticate, self.ctr_width);
+        Ok((buffer, nonce_material.len()))
+    }
+
+    fn encrypt_in_place(&self, key: &[u8; 32], nonce_material: &[u8; 16], buffer: &mut Vec<u8>) -> Result<usize, ExperimentError> {
+        megolm_encrypt(key, nonce_material, buffer, self.authenticate, self.ctr_width);
+        Ok(nonce_material.len())
+    }
+
+    fn encrypt_detached(
+        &self,
+        key: &[u8; 32],
+        nonce_material: &[u8; 16],
+        plaintext: &[u8],
+    ) -> Result<(Vec<u8>, DetachedTag, usize), ExperimentError> {
+        let mut ciphertext = plaintext.to_vec();
+        let tag = megolm_encrypt_detached(key, nonce_material, &mut ciphertext, self.authenticate, self.ctr_width);
+        Ok((ciphertext, tag, nonce_material.len()))
+    }
+}
+
+/// Registro das cifras simétricas, por nome
+#[derive(Default)]
+pub struct CipherRegistry {
+    ciphers: HashMap<String, Box<dyn SymmetricCipher>>,
+}
+
+impl CipherRegistry {
+    /// Cifras do experimento; `authenticate` e `ctr_width` configuram a Megolm-Like
+    /// (`--megolm-unauthenticated`, `--ctr-width`)
+    pub fn standard(authenticate: bool, ctr_width: CtrWidth) -> Self {
+        let mut registry = Self::default();
+        registry.register(Box::new(AesGcmCipher));
+        registry.register(Box::new(ChaCha20Cipher));
+        registry.register(Box::new(MegolmLikeCipher { authenticate, ctr_width }));
+        registry
+    }
+
+    /// Registra uma cifra pelo seu nome, substituindo outra de mesmo nome
+    pub fn register(&mut self, cipher: Box<dyn SymmetricCipher>) {
+        self.ciphers.insert(cipher.name().to_string(), cipher);
+    }
+
+    /// Cifra registrada com o nome dado
+    pub fn get(&self, name: &str) -> Option<&dyn SymmetricCipher> {
+        self.ciphers.get(name).map(|cipher| cipher.as_ref())
+    }
+
+    /// Como `get`, com um erro que lista as cifras registradas
+    pub fn lookup(&self, name: &str) -> Result<&dyn SymmetricCipher, ExperimentError> {
+        self.get(name).ok_or_else(|| ExperimentError::Config(format!(
+            "cifra desconhecida '{}' (registradas: {})", name, self.names().join(", ")
+        )))
+    }
+
+    /// Nomes registrados, em ordem alfabética
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.ciphers.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+/// Acordo de chaves do experimento, buscado pelo nome em `AgreementRegistry`
+///
+/// O gerador é um objeto de trait (`&mut dyn CryptoRngCore`) para que o trait possa
+/// ser usado como `Box<dyn KeyAgreement>`.
+pub trait KeyAgreement: Send + Sync {
+    /// Nome do acordo, como usado em `AGREEMENTS`, em `--hybrid` e no CSV
+    fn name(&self) -> &str;
+
+    /// Componentes do acordo, na ordem canônica do combinador
+    fn components(&self) -> &[KemComponent];
+
+    /// Gera as chaves de Bob para este acordo
+    fn generate_keys(&self, rng: &mut dyn CryptoRngCore) -> RecipientKeys;
+
+    /// Executa o acordo com as chaves de Bob; ver `perform_key_agreement_decapsulating`
+    fn agree(
+        &self,
+        bob: &RecipientKeys,
+        alice: &SenderKeys,
+        corrupt_ciphertext: bool,
+        decapsulations: usize,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<(Vec<u8>, HandshakeBytes, Option<Decapsulation>), ExperimentError>;
+}
+
+/// Acordo formado por uma lista de componentes (`agreement_components`)
+///
+/// Cobre o Olm-Clássico, o Olm-Híbrido e os híbridos de `--hybrid`; um componente novo
+/// entra em `KemComponent`, sem um acordo próprio.
+pub struct ComponentAgreement {
+    name: String,
+    components: Vec<KemComponent>,
+}
+
+impl ComponentAgreement {
+    /// Acordo com o nome dado, com os componentes lidos de `agreement_components`
+    pub fn new(name: &str) -> Result<Self, ExperimentError> {
+        Ok(Self { name: name.to_string(), components: agreement_components(name)? })
+    }
+}
+
+impl KeyAgreement for ComponentAgreement {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn components(&self) -> &[KemComponent] {
+        &self.components
+    }
+
+    fn generate_keys(&self, mut rng: &mut dyn CryptoRngCore) -> RecipientKeys {
+        RecipientKeys::generate_components(&self.components, &mut rng)
+    }
+
+    fn agree(
+        &self,
+        bob: &RecipientKeys,
+        alice: &SenderKeys,
+        corrupt_ciphertext: bool,
+        decapsulations: usize,
+        rng: &mut dyn CryptoRngCore,
+    ) -> Result<(Vec<u8>, HandshakeBytes, Option<Decapsulation>), ExperimentError> {
+        agree_components(&self.name, bob, alice, corrupt_ciphertext, decapsulations, rng)
+    }
+}
+
+/// Registro dos acordos de chaves, por nome
+#[derive(Default)]
+pub struct AgreementRegistry {
+    agreements: HashMap<String, Box<dyn KeyAgreement>>,
+}
+
+impl AgreementRegistry {
+    /// Acordos de `AGREEMENTS` e os híbridos informados (ex: os de `--hybrid`)
+    pub fn standard(hybrids: &[String]) -> Result<Self, ExperimentError> {
+        let mut registry = Self::default();
+        for name in AGREEMENTS.iter().copied().chain(hybrids.iter().map(String::as_str)) {
+            registry.register(Box::new(ComponentAgreement::new(name)?));
+        }
+        Ok(registry)
+    }
+
+    /// Registra um acordo pelo seu nome, substituindo outro de mesmo nome
+    pub fn register(&mut self, agreement: Box<dyn KeyAgreement>) {
+        self.agreements.insert(agreement.name().to_string(), agreement);
+    }
+
+    /// Acordo registrado com o nome dado
+    pub fn get(&self, name: &str) -> Option<&dyn KeyAgreement> {
+        self.agreements.get(name).map(|agreement| agreement.as_ref())
+    }
+
+    /// Como `get`, com um erro que lista os acordos registrados
+    pub fn lookup(&self, name: &str) -> Result<&dyn KeyAgreement, ExperimentError> {
+        self.get(name).ok_or_else(|| {
+            let mut names: Vec<&str> = self.agreements.keys().map(String::as_str).collect();
+            names.sort_unstable();
+            ExperimentError::Config(format!("acordo de chaves desconhecido '{}' (registrados: {})", name, names.join(", ")))
+        })
+    }
+}
+
+/// Compara duas tags em tempo constante (`subtle::ConstantTimeEq`)
+///
+/// O tempo não depende da posição do primeiro byte diferente, o que uma comparação
+/// com `==` vazaria; tamanhos diferentes são recusados (o tamanho da tag é público).
+pub fn tags_equal(expected: &[u8], received: &[u8]) -> bool {
+    expected.len() == received.len() && bool::from(expected.ct_eq(received))
+}
+
+impl MegolmLikeCipher {
+    /// Verifica a tag HMAC de `message` (texto cifrado seguido da tag) e o decifra no lugar
+    ///
+    /// A tag é recalculada sobre IV + texto cifrado e comparada em tempo constante
+    /// (`tags_equal`) antes de qualquer decifragem; na saída, `message` é o texto claro.
+    /// Sem `authenticate`, apenas aplica o AES-CTR.
+    pub fn decrypt(&self, key: &[u8; 32], iv: &[u8; 16], message: &mut Vec<u8>) -> Result<(), ExperimentError> {
+        if self.authenticate {
+            let ciphertext_len = message.len().checked_sub(MAX_TAG_SIZE)
+                .ok_or_else(|| ExperimentError::Crypto("mensagem Megolm-Like menor que a tag HMAC".to_string()))?;
+            let expected = megolm_tag(&megolm_keys(key).1, iv, &message[..ciphertext_len]);
+            if !tags_equal(expected.as_slice(), &message[ciphertext_len..]) {
+                return Err(ExperimentError::Crypto("tag HMAC inválida na mensagem Megolm-Like".to_string()));
+            }
+            message.truncate(ciphertext_len);
+        }
+        megolm_keystream(&megolm_keys(key).0, iv, message, self.ctr_width);
+        Ok(())
+    }
+}
+
+/// Megolm-Like: aplica o AES-CTR ao buffer e, se `authenticate`, anexa a tag HMAC
+fn megolm_encrypt(key: &[u8; 32], iv: &[u8; 16], buffer: &mut Vec<u8>, authenticate: bool, ctr_width: CtrWidth) {
+    let tag = megolm_encrypt_detached(key, iv, buffer, authenticate, ctr_width);
+    buffer.extend_from_slice(tag.as_slice());
+}
+
+/// Megolm-Like com a tag à parte: aplica o AES-CTR ao buffer e devolve a tag HMAC
+/// (vazia se não `authenticate`)
+fn megolm_encrypt_detached(
+    key: &[u8; 32],
+    iv: &[u8; 16],
+    buffer: &mut [u8],
+    authenticate: bool,
+    ctr_width: CtrWidth,
+) -> DetachedTag {
+    let (aes_key, mac_key) = megolm_keys(key);
+    megolm_keystream(&aes_key, iv, buffer, ctr_width);
+    if authenticate { megolm_tag(&mac_key, iv, buffer) } else { DetachedTag::EMPTY }
+}
+
+/// Chaves do AES-CTR e do HMAC da Megolm-Like, derivadas da chave da mensagem
+///
+/// HKDF-Expand com a chave da mensagem como PRK e um rótulo por uso, para que a
+/// mesma chave nunca sirva à cifragem e à autenticação.
+fn megolm_keys(key: &[u8; 32]) -> ([u8; 32], [u8; 32]) {
+    let hkdf = Hkdf::<Sha256>::from_prk(key).expect("PRK de 32 bytes é sempre válida");
+    let mut aes_key = [0u8; 32];
+    let mut mac_key = [0u8; 32];
+    hkdf.expand(b"MEGOLM_AES_KEY", &mut aes_key)
+        .expect("32 bytes estão dentro do limite do HKDF-SHA256");
+    hkdf.expand(b"MEGOLM_HMAC_KEY", &mut mac_key)
+        .expect("32 bytes estão dentro do limite do HKDF-SHA256");
+    (aes_key, mac_key)
+}
+
+/// Aplica o fluxo de chave AES-256-CTR ao buffer (cifra e decifra)
+fn megolm_keystream(key: &[u8; 32], iv: &[u8; 16], buffer: &mut [u8], ctr_width: CtrWidth) {
+    match ctr_width {
+        CtrWidth::Ctr32 => ctr::Ctr32BE::<Aes256>::new(key.into(), iv.into()).apply_keystream(buffer),
+        CtrWidth::Ctr64 => ctr::Ctr64BE::<Aes256>::new(key.into(), iv.into()).apply_keystream(buffer),
+        CtrWidth::Ctr128 => ctr::Ctr128BE::<Aes256>::new(key.into(), iv.into()).apply_keystream(buffer),
+    }
+}
+
+/// Tag HMAC-SHA256 da Megolm-Like sobre IV + texto cifrado
+///
+/// Encrypt-then-MAC, como no Megolm real. A tag de 32 bytes entra na largura de
+/// banda, anexada ou à parte.
+fn megolm_tag(key: &[u8; 32], iv: &[u8; 16], ciphertext: &[u8]) -> DetachedTag {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key)
+        .expect("HMAC aceita chaves de qualquer tamanho");
+    mac.update(iv);
+    mac.update(ciphertext);
+    DetachedTag::from_slice(&mac.finalize().into_bytes())
+}
+
+#[cfg(test)]
+/// Testes unitários das primitivas criptográficas
+/// Verificam tamanhos de segredo, largura de banda e overhead de cada cifra
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    #[test]
+    fn test_key_agreement_sizes() {
+        let bob = RecipientKeys::generate("Olm-Clássico").unwrap();
+        let (secret, bandwidth) = perform_key_agreement("Olm-Clássico", &bob).unwrap();
+        assert_eq!(secret.len(), SECRET_LENGTH_PREFIX_BYTES + 32);
+        assert_eq!(bandwidth.total(), 32);
+        assert_eq!(bandwidth, HandshakeBytes { upload: 0, download: 32 });
+
+        let bob = RecipientKeys::generate("Olm-Híbrido").unwrap();
+        let (secret, bandwidth) = perform_key_agreement("Olm-Híbrido", &bob).unwrap();
+        assert_eq!(secret.len(), 2 * (SECRET_LENGTH_PREFIX_BYTES + 32));
+        assert_eq!(bandwidth.total(), 32 + kyber768::ciphertext_bytes() + kyber768::public_key_bytes());
+        assert_eq!(bandwidth.upload, kyber768::ciphertext_bytes());
+        assert_eq!(bandwidth.download, 32 + kyber768::public_key_bytes());
+    }
+
+    #[test]
+    fn test_key_backup_round_trip() {
+        let mut rng = rand::thread_rng();
+        let recovery = RecoveryKey::generate(&mut rng);
+        let session_key = [7u8; 32];
+        let backup = recovery.backup(&session_key, &mut rng);
+        assert_eq!(backup.len(), 32 + 32 + BACKUP_MAC_SIZE);
+        assert_eq!(recovery.restore(&backup).unwrap(), session_key);
+        // Cada backup usa um efêmero novo, e outra chave de recuperação não o abre
+        assert_ne!(recovery.backup(&session_key, &mut rng), backup);
+        assert!(RecoveryKey::generate(&mut rng).restore(&backup).is_err());
+    }
+
+    #[test]
+    fn test_injected_kem_failure_uses_implicit_rejection() {
+        let bob = RecipientKeys::generate("Olm-Híbrido").unwrap();
+        let (_, _, decap) = perform_key_agreement_injecting("Olm-Híbrido", &bob, &SenderKeys::default(), false, &mut rand::thread_rng()).unwrap();
+        assert!(!decap.unwrap().rejected);
+        // A verificação interna garante que o segredo rejeitado difere do encapsulado
+        let (secret, _, decap) = perform_key_agreement_injecting("Olm-Híbrido", &bob, &SenderKeys::default(), true, &mut rand::thread_rng()).unwrap();
+        assert_eq!(secret.len(), 2 * (SECRET_LENGTH_PREFIX_BYTES + 32));
+        assert!(decap.unwrap().rejected);
+
+        let bob = RecipientKeys::generate("Olm-Clássico").unwrap();
+        assert!(perform_key_agreement_injecting("Olm-Clássico", &bob, &SenderKeys::default(), true, &mut rand::thread_rng()).unwrap().2.is_none());
+    }
+
+    #[test]
+    fn test_aes_backend_follows_build_configuration() {
+        let backend = aes_backend();
+        assert!(["aes-ni", "armv8", "software"].contains(&backend));
+        if aes_software_forced() {
+            assert_eq!(backend, "software");
+        }
+    }
+
+    #[test]
+    fn test_multiple_decapsulations_share_one_encapsulation() {
+        let bob = RecipientKeys::generate("Olm-Híbrido").unwrap();
+        let agree = |decapsulations: usize| perform_key_agreement_decapsulating(
+            "Olm-Híbrido", &bob, &SenderKeys::default(), false, decapsulations, &mut rand::thread_rng(),
+        ).unwrap();
+        let (single_secret, single_bandwidth, single) = agree(1);
+        let (secret, bandwidth, many) = agree(50);
+        // Um único texto cifrado é transmitido, qualquer que seja o número de destinatários
+        assert_eq!(bandwidth, single_bandwidth);
+        assert_eq!(secret.len(), single_secret.len());
+        assert!(many.unwrap().time > single.unwrap().time);
+
+        let bob = RecipientKeys::generate("Olm-Clássico").unwrap();
+        let (_, _, decap) = perform_key_agreement_decapsulating(
+            "Olm-Clássico", &bob, &SenderKeys::default(), false, 10, &mut rand::thread_rng(),
+        ).unwrap();
+        assert!(decap.is_none());
+    }
+
+    #[test]
+    fn test_static_ephemeral_reuses_alice_secret() {
+        let bob = RecipientKeys::generate("Olm-Clássico").unwrap();
+        let mut rng = rand::thread_rng();
+        let mut agree = |alice: &SenderKeys| {
+            perform_key_agreement_injecting("Olm-Clássico", &bob, alice, false, &mut rng).unwrap().0
+        };
+
+        // Com o segredo estático, o mesmo par de chaves produz sempre o mesmo segredo
+        let alice = SenderKeys::generate_with_rng(EphemeralMode::Static, &mut rand::thread_rng());
+        assert_eq!(alice.mode(), EphemeralMode::Static);
+        assert_eq!(agree(&alice), agree(&alice));
+
+        let alice = SenderKeys::generate_with_rng(EphemeralMode::Fresh, &mut rand::thread_rng());
+        assert_eq!(alice.mode(), EphemeralMode::Fresh);
+        assert_ne!(agree(&alice), agree(&alice));
+    }
+
+    #[test]
+    fn test_ephemeral_ephemeral_draws_bob_secret_per_agreement() {
+        let bob = RecipientKeys::generate("Olm-Clássico").unwrap();
+        let alice = SenderKeys::generate_with_rng(EphemeralMode::Both, &mut rand::thread_rng());
+        assert_eq!((alice.mode(), alice.mode().dh_variant()), (EphemeralMode::Both, "ephemeral-ephemeral"));
+
+        // Mesma semente nos dois modos: o par novo de Bob muda o segredo, mas não os bytes trafegados
+        let agree = |alice: &SenderKeys| {
+            let (secret, bytes, _) =
+                perform_key_agreement_injecting("Olm-Clássico", &bob, alice, false, &mut StdRng::seed_from_u64(5)).unwrap();
+            (secret, bytes.total())
+        };
+        let (ephemeral_static, bytes) = agree(&SenderKeys::default());
+        let (ephemeral_ephemeral, both_bytes) = agree(&alice);
+        assert_ne!(ephemeral_static, ephemeral_ephemeral);
+        assert_eq!(bytes, both_bytes);
+    }
+
+    #[test]
+    fn test_n_way_hybrid_combiner() {
+        // A ordem canônica independe da ordem informada
+        let components = agreement_components("kyber1024+X25519+Kyber768").unwrap();
+        assert_eq!(components, [KemComponent::X25519, KemComponent::Kyber768, KemComponent::Kyber1024]);
+        assert_eq!(hybrid_agreement_name(&components), "X25519+Kyber768+Kyber1024");
+        assert!(agreement_components("X25519+X25519").is_err());
+        assert!(agreement_components("X25519+McEliece").is_err());
+
+        let name = hybrid_agreement_name(&components);
+        let bob = RecipientKeys::generate(&name).unwrap();
+        let (secret, bandwidth) = perform_key_agreement(&name, &bob).unwrap();
+        assert_eq!(secret.len(), 3 * (SECRET_LENGTH_PREFIX_BYTES + 32));
+        assert_eq!(bandwidth.upload, kyber768::ciphertext_bytes() + kyber1024::ciphertext_bytes());
+        assert_eq!(bandwidth.download, 32 + kyber768::public_key_bytes() + kyber1024::public_key_bytes());
+        assert_eq!(SecurityLevel::of_agreement(&name), SecurityLevel::Category5);
+
+        // O Olm-Híbrido é o caso de dois componentes do mesmo combinador
+        assert_eq!(agreement_components("X25519+Kyber768").unwrap(), agreement_components("Olm-Híbrido").unwrap());
+        let key = combine_shared_secrets(&secret);
+        assert_eq!(key, combine_shared_secrets(&secret));
+        assert_ne!(key, combine_shared_secrets(&secret[..2 * (SECRET_LENGTH_PREFIX_BYTES + 32)]));
+    }
+
+    #[test]
+    fn test_combiner_known_vector() {
+        // Layout canônico: tamanho u16 big-endian seguido do segredo, X25519 antes do KEM
+        let mut material = Vec::new();
+        append_shared_secret(&mut material, &[0x11; 32]).unwrap();
+        append_shared_secret(&mut material, &[0x22; 32]).unwrap();
+        assert_eq!(&material[..3], &[0x00, 0x20, 0x11]);
+        assert_eq!(&material[34..37], &[0x00, 0x20, 0x22]);
+        assert!(append_shared_secret(&mut material, &vec![0; 70_000]).is_err());
+
+        // HMAC-SHA256(sal "MATRIX_HYBRID_COMBINER", material), calculado de forma independente
+        assert_eq!(
+            hex::encode(combine_shared_secrets(&material)),
+            "042a786704f70fc446504f1377c6b19cc24eb6441bbf95eebb96abb4cb9ecd88"
+        );
+        assert_eq!(
+            hex::encode(combine_shared_secrets(&material[..34])),
+            "bbff74bd362d835ce1bd77e7e0355596de7f91ce6b54869962d90d0cdb4dd959"
+        );
+    }
+
+    #[test]
+    fn test_classic_session_key_skips_combiner() {
+        // Olm-Clássico: a chave é o segredo X25519 em si; o combinador só entra nos híbridos
+        let mut material = Vec::new();
+        append_shared_secret(&mut material, &[0x11; 32]).unwrap();
+        assert_eq!(session_key(&[KemComponent::X25519], &material), [0x11; 32]);
+
+        append_shared_secret(&mut material, &[0x22; 32]).unwrap();
+        let hybrid = [KemComponent::X25519, KemComponent::Kyber768];
+        assert_eq!(session_key(&hybrid, &material), combine_shared_secrets(&material));
+    }
+
+    #[test]
+    fn test_security_level_of_agreement() {
+        assert_eq!(SecurityLevel::of_agreement("Olm-Clássico"), SecurityLevel::Category1Classical);
+        assert_eq!(SecurityLevel::of_agreement("Olm-Híbrido").category(), 3);
+        assert_eq!(SecurityLevel::of_agreement("Olm-Clássico").name(), "cat1-classico");
+    }
+
+    #[test]
+    fn test_cipher_overhead() {
+        let key = [1u8; 32];
+        let nonce = [2u8; 16];
+        let plaintext = vec![0u8; 100];
+
+        // AEADs: tag de 16 bytes, nonce de 12 bytes
+        for cipher in ["AES-GCM", "ChaCha20"] {
+            let (ciphertext, nonce_len) = encrypt_message(cipher, &key, &nonce, &plaintext, true, CtrWidth::default()).unwrap();
+            assert_eq!((ciphertext.len(), nonce_len), (116, 12));
+        }
+
+        // Megolm-Like: IV de 16 bytes e tag HMAC de 32 bytes apenas quando autenticado
+        let (ciphertext, nonce_len) = encrypt_message("Megolm-Like", &key, &nonce, &plaintext, true, CtrWidth::default()).unwrap();
+        assert_eq!((ciphertext.len(), nonce_len), (132, 16));
+        let (ciphertext, _) = encrypt_message("Megolm-Like", &key, &nonce, &plaintext, false, CtrWidth::default()).unwrap();
+        assert_eq!(ciphertext.len(), 100);
+    }
+
+    #[test]
+    fn test_in_place_matches_allocating_encryption() {
+        let key = [3u8; 32];
+        let nonce = [4u8; 16];
+        let plaintext = vec![0x42u8; 300];
+        let mut buffer = Vec::with_capacity(plaintext.len() + 32);
+
+        for cipher in CIPHERS {
+            for authenticate in [true, false] {
+                let expected = encrypt_message(cipher, &key, &nonce, &plaintext, authenticate, CtrWidth::default()).unwrap();
+                buffer.clear();
+                buffer.extend_from_slice(&plaintext);
+                let capacity = buffer.capacity();
+                let nonce_len = encrypt_message_in_place(cipher, &key, &nonce, &mut buffer, authenticate, CtrWidth::default()).unwrap();
+                assert_eq!((buffer.clone(), nonce_len), expected, "{} {}", cipher, authenticate);
+                // O buffer reutilizado já comporta a tag: nenhuma realocação
+                assert_eq!(buffer.capacity(), capacity);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ctr_widths_roundtrip_and_limits() {
+        // IV com a palavra baixa perto do limite: o contador de 32 bits dá a volta
+        // dentro da própria palavra, sem transportar para o nonce
+        let key = [9u8; 32];
+        let mut iv = [0u8; 16];
+        iv[12..].copy_from_slice(&u32::MAX.to_be_bytes());
+        let plaintext = vec![0x5au8; 1000];
+
+        for width in [CtrWidth::Ctr32, CtrWidth::Ctr64, CtrWidth::Ctr128] {
+            let (ciphertext, _) = encrypt_message("Megolm-Like", &key, &iv, &plaintext, false, width).unwrap();
+            // CTR é involutivo: cifrar de novo com o mesmo IV recupera o texto claro
+            let (decrypted, _) = encrypt_message("Megolm-Like", &key, &iv, &ciphertext, false, width).unwrap();
+            assert_eq!(decrypted, plaintext, "{:?}", width);
+        }
+
+        assert_eq!(CtrWidth::Ctr32.max_message_bytes(), (u32::MAX as u128) * 16);
+        assert_eq!(CtrWidth::Ctr64.max_message_bytes(), (u64::MAX as u128) * 16);
+        assert_eq!(CtrWidth::Ctr128.max_message_bytes(), u128::MAX);
+        // O maior payload gerado (arquivos de 10 MB) cabe folgadamente em qualquer largura
+        assert!(CtrWidth::Ctr32.max_message_bytes() > 10_000_000);
+    }
+
+    #[test]
+    fn test_plaintext_size_limits() {
+        assert_eq!(max_plaintext_bytes("AES-GCM", CtrWidth::Ctr32), 68_719_476_704);
+        assert_eq!(max_plaintext_bytes("ChaCha20", CtrWidth::Ctr32), 274_877_906_880);
+        assert_eq!(max_plaintext_bytes("Megolm-Like", CtrWidth::Ctr32), CtrWidth::Ctr32.max_message_bytes());
+        for cipher in CIPHERS {
+            assert!(check_plaintext_size(cipher, CtrWidth::default(), 10_000_000).is_ok());
+        }
+
+        // Só a cifra cujo limite foi excedido é recusada, e o erro traz limite e tamanho
+        if let Ok(size) = usize::try_from(AES_GCM_MAX_PLAINTEXT + 1) {
+            let error = check_plaintext_size("AES-GCM", CtrWidth::default(), size).unwrap_err().to_string();
+            assert!(error.contains(&size.to_string()) && error.contains(&AES_GCM_MAX_PLAINTEXT.to_string()));
+            assert!(check_plaintext_size("ChaCha20", CtrWidth::default(), size).is_ok());
+        }
+        if let Ok(size) = usize::try_from(CtrWidth::Ctr32.max_message_bytes() + 1) {
+            assert!(check_plaintext_size("Megolm-Like", CtrWidth::Ctr32, size).is_err());
+            assert!(check_plaintext_size("Megolm-Like", CtrWidth::Ctr64, size).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_registries_cover_the_matrix() {
+        // Toda cifra e todo acordo da matriz estão registrados com o próprio nome
+        let ciphers = CipherRegistry::standard(true, CtrWidth::default());
+        let mut names = CIPHERS.to_vec();
+        names.sort_unstable();
+        assert_eq!(ciphers.names(), names);
+        for name in CIPHERS {
+            assert_eq!(ciphers.lookup(name).unwrap().name(), name);
+        }
+        let hybrid = hybrid_agreement_name(&[KemComponent::X25519, KemComponent::Kyber1024]);
+        let agreements = AgreementRegistry::standard(std::slice::from_ref(&hybrid)).unwrap();
+        for name in AGREEMENTS.iter().copied().chain([hybrid.as_str()]) {
+            let agreement = agreements.lookup(name).unwrap();
+            assert_eq!(agreement.name(), name);
+            assert_eq!(agreement.components(), agreement_components(name).unwrap());
+        }
+
+        // Nomes desconhecidos são recusados com a lista do que está registrado
+        let error = ciphers.lookup("Serpent").err().unwrap();
+        assert!(matches!(error, ExperimentError::Config(_)));
+        let error = error.to_string();
+        assert!(error.contains("Serpent") && error.contains("ChaCha20"));
+        assert!(agreements.lookup("Olm-Quântico").is_err());
+        assert!(AgreementRegistry::standard(&["Olm-Quântico".to_string()]).is_err());
+
+        // Uma cifra registrada pelo trait é a mesma usada pelo atalho por nome
+        let (key, nonce) = ([7u8; 32], [9u8; 16]);
+        let megolm = CipherRegistry::standard(false, CtrWidth::Ctr64);
+        assert_eq!(
+            megolm.lookup("Megolm-Like").unwrap().encrypt(&key, &nonce, b"registro").unwrap(),
+            encrypt_message("Megolm-Like", &key, &nonce, b"registro", false, CtrWidth::Ctr64).unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_detached_tag_matches_attached_layout() {
+        // Os dois layouts produzem os mesmos bytes: texto cifrado + tag == saída anexada
+        let (key, nonce) = ([3u8; 32], [5u8; 16]);
+        let plaintext = b"mensagem com tag destacada";
+        for authenticate in [true, false] {
+            let registry = CipherRegistry::standard(authenticate, CtrWidth::default());
+            for name in CIPHERS {
+                let cipher = registry.lookup(name).unwrap();
+                let (attached, attached_nonce) = cipher.encrypt(&key, &nonce, plaintext).unwrap();
+                let (mut detached, tag, detached_nonce) = cipher.encrypt_detached(&key, &nonce, plaintext).unwrap();
+                assert_eq!(detached.len(), plaintext.len(), "{}", name);
+                assert_eq!(attached_nonce, detached_nonce);
+                detached.extend_from_slice(tag.as_slice());
+                assert_eq!(detached, attached, "{} (autenticada: {})", name, authenticate);
+            }
+        }
+        let megolm = MegolmLikeCipher { authenticate: false, ctr_width: CtrWidth::default() };
+        assert_eq!(megolm.encrypt_detached(&key, &nonce, plaintext).unwrap().1, DetachedTag::EMPTY);
+    }
+
+    #[test]
+    fn test_megolm_derives_separate_aes_and_mac_keys() {
+        let key = [6u8; 32];
+        let (aes_key, mac_key) = megolm_keys(&key);
+        assert_ne!(aes_key, mac_key);
+        assert_ne!(aes_key, key);
+        assert_ne!(mac_key, key);
+
+        // O texto cifrado usa a chave AES derivada e a tag, a chave HMAC derivada
+        let (iv, plaintext) = ([2u8; 16], b"chaves separadas".to_vec());
+        let (message, _) = encrypt_message("Megolm-Like", &key, &iv, &plaintext, true, CtrWidth::default()).unwrap();
+        let mut expected = plaintext.clone();
+        megolm_keystream(&aes_key, &iv, &mut expected, CtrWidth::default());
+        assert_eq!(&message[..plaintext.len()], expected.as_slice());
+        assert_eq!(&message[plaintext.len()..], megolm_tag(&mac_key, &iv, &expected).as_slice());
+    }
+
+    #[test]
+    fn test_megolm_tag_verified_in_constant_time() {
+        assert!(tags_equal(&[1, 2, 3], &[1, 2, 3]));
+        assert!(!tags_equal(&[1, 2, 3], &[1, 2, 4]));
+        assert!(!tags_equal(&[1, 2, 3], &[1, 2]));
+
+        let (key, iv) = ([8u8; 32], [1u8; 16]);
+        let plaintext = b"texto autenticado com HMAC".to_vec();
+        for authenticate in [true, false] {
+            let cipher = MegolmLikeCipher { authenticate, ctr_width: CtrWidth::default() };
+            let (mut message, _) = cipher.encrypt(&key, &iv, &plaintext).unwrap();
+            cipher.decrypt(&key, &iv, &mut message).unwrap();
+            assert_eq!(message, plaintext);
+        }
+
+        // Qualquer bit alterado no texto cifrado ou na tag é recusado antes de decifrar
+        let cipher = MegolmLikeCipher { authenticate: true, ctr_width: CtrWidth::default() };
+        let (message, _) = cipher.encrypt(&key, &iv, &plaintext).unwrap();
+        for position in [0, plaintext.len() - 1, plaintext.len(), message.len() - 1] {
+            let mut tampered = message.clone();
+            tampered[position] ^= 0x01;
+            assert!(cipher.decrypt(&key, &iv, &mut tampered).is_err(), "byte {}", position);
+        }
+        assert!(cipher.decrypt(&key, &iv, &mut vec![0u8; MAX_TAG_SIZE - 1]).is_err());
+    }
+
+    #[test]
+    fn test_shared_secret_length_check() {
+        // Todos os componentes atuais devolvem exatamente 32 bytes
+        for agreement in AGREEMENTS {
+            let (secret, _) = perform_key_agreement(agreement, &RecipientKeys::generate(agreement).unwrap()).unwrap();
+            let components = agreement_components(agreement).unwrap().len();
+            assert_eq!(secret.len(), (SECRET_LENGTH_PREFIX_BYTES + MIN_SHARED_SECRET_BYTES) * components);
+        }
+        assert!(check_shared_secret("Olm-Híbrido", KemComponent::Kyber768, 32).is_ok());
+
+        // Um segredo curto é recusado com o acordo, o componente e os tamanhos
+        let error = check_shared_secret("X25519+Kyber512", KemComponent::Kyber512, 16).unwrap_err();
+        assert!(matches!(error, ExperimentError::Crypto(_)));
+        let error = error.to_string();
+        assert!(error.contains("X25519+Kyber512") && error.contains("Kyber512"));
+        assert!(error.contains("16") && error.contains(&MIN_SHARED_SECRET_BYTES.to_string()));
+    }
+
+    #[test]
+    fn test_key_commitment_binds_key_and_nonce() {
+        let commitment = key_commitment(&[1u8; 32], &[2u8; 12]);
+        assert_eq!(commitment.len(), KEY_COMMITMENT_SIZE);
+        assert_eq!(commitment, key_commitment(&[1u8; 32], &[2u8; 12]));
+        assert_ne!(commitment, key_commitment(&[3u8; 32], &[2u8; 12]));
+        assert_ne!(commitment, key_commitment(&[1u8; 32], &[4u8; 12]));
+    }
+
+    #[test]
+    fn test_derive_message_key_is_deterministic_per_counter() {
+        let session_key = [7u8; 32];
+        let (key_a, nonce_a) = derive_message_key(&session_key, 1);
+        let (key_b, nonce_b) = derive_message_key(&session_key, 1);
+        let (key_c, nonce_c) = derive_message_key(&session_key, 2);
+        assert_eq!((key_a, nonce_a), (key_b, nonce_b));
+        assert_ne!(key_a, key_c);
+        assert_ne!(nonce_a, nonce_c);
+        assert_ne!(key_a, session_key);
+    }
+
+    #[test]
+    fn test_primitive_versions_per_combination() {
+        // Versões vêm do Cargo.lock via build.rs
+        assert!(PRIMITIVE_VERSIONS.iter().all(|(_, v)| !v.is_empty() && *v != "desconhecida"));
+
+        let classic = primitive_versions("Olm-Clássico", "AES-GCM", false);
+        assert!(classic.starts_with("x25519-dalek@"));
+        assert!(classic.contains("aes-gcm@") && !classic.contains("pqcrypto-kyber"));
+
+        let hybrid = primitive_versions("Olm-Híbrido", "Megolm-Like", true);
+        for name in ["pqcrypto-kyber@", "aes@", "ctr@", "hmac@", "hkdf@", "sha2@"] {
+            assert!(hybrid.contains(name), "{} ausente em {}", name, hybrid);
+        }
+        assert!(!hybrid.contains(','));
+    }
+}
@@ -0,0 +1,186 @@
+//! Gravação dos resultados em um banco SQLite (`--sqlite`)
+//!
+//! Cada execução vira uma linha da tabela `runs` (semente, arquivo CSV e metadados do
+//! ambiente em JSON) e cada configuração uma linha da tabela `results`, ligada à
+//! execução pela chave estrangeira `run_id`. As demais colunas de `results` são as do
+//! CSV de resultados, na mesma ordem, com o tipo SQLite do tipo declarado em
+//! `RESULT_COLUMNS`; as linhas chegam como células tipadas (`Cell`), sem passar pelo
+//! texto do CSV. Execuções sucessivas acrescentam linhas ao mesmo banco.
+
+use rusqlite::types::Value;
+use rusqlite::{Connection, params, params_from_iter};
+use std::io;
+use std::path::Path;
+
+use crate::error::ExperimentError;
+use crate::results::{Cell, Column, ColumnType};
+
+/// Tipo declarado no `CREATE TABLE` para uma coluna; booleanos são INTEGER 0/1
+pub fn sql_type(kind: ColumnType) -> &'static str {
+    match kind {
+        ColumnType::Integer | ColumnType::Boolean => "INTEGER",
+        ColumnType::Real => "REAL",
+        ColumnType::Text => "TEXT",
+    }
+}
+
+/// Valor SQLite de uma célula; células vazias e números não finitos viram NULL
+fn sql_value(cell: &Cell) -> Value {
+    match cell {
+        Cell::Empty => Value::Null,
+        Cell::Integer(value) => Value::Integer(*value),
+        Cell::Real(value, _) if value.is_finite() => Value::Real(*value),
+        Cell::Real(..) => Value::Null,
+        Cell::Boolean(value) => Value::Integer(i64::from(*value)),
+        Cell::Text(value) => Value::Text(value.clone()),
+    }
+}
+
+/// Falha do SQLite (abertura, esquema, gravação), tratada como erro de E/S
+fn sqlite_error(e: rusqlite::Error) -> ExperimentError {
+    ExperimentError::Io(io::Error::other(e))
+}
+
+/// Uma execução do experimento, gravada na tabela `runs`
+#[derive(Debug, Clone)]
+pub struct RunRecord<'a> {
+    pub timestamp: &'a str,
+    pub seed: Option<u64>,
+    pub schema_version: u32,
+    pub csv_file: &'a str,
+    /// Conteúdo do arquivo de metadados da execução (JSON)
+    pub metadata_json: &'a str,
+}
+
+/// Acrescenta uma execução e suas linhas de resultados ao banco, criando as tabelas se preciso
+///
+/// `columns` é o registro de colunas (`RESULT_COLUMNS`) e cada linha de `rows` tem uma
+/// célula por coluna. Um banco cuja tabela `results` tenha outras colunas (outro
+/// `schema_version`) é recusado, em vez de misturar esquemas. Tudo é gravado em uma
+/// única transação. Retorna o `id` da execução em `runs`.
+/// Esquema ou células incompatíveis são `ExperimentError::Config`; falhas do SQLite, `Io`.
+pub fn write_results(path: &Path, run: &RunRecord, columns: &[Column], rows: &[Vec<Cell>]) -> Result<i64, ExperimentError> {
+    let mut connection = Connection::open(path).map_err(sqlite_error)?;
+    connection.pragma_update(None, "foreign_keys", true).map_err(sqlite_error)?;
+
+    let existing: Vec<String> = connection
+        .prepare("SELECT name FROM pragma_table_info('results')")
+        .and_then(|mut statement| statement.query_map([], |row| row.get(0))?.collect())
+        .map_err(sqlite_error)?;
+    let expected: Vec<&str> = std::iter::once("run_id").chain(columns.iter().map(|column| column.name)).collect();
+    if !existing.is_empty() && existing != expected {
+        return Err(ExperimentError::Config(format!(
+            "a tabela results de {} tem {} colunas de outro esquema (esperadas {}); use outro arquivo",
+            path.display(), existing.len(), expected.len()
+        )));
+    }
+
+    let transaction = connection.transaction().map_err(sqlite_error)?;
+    let definitions: Vec<String> = columns.iter()
+        .map(|column| format!("\"{}\" {}", column.name, sql_type(column.kind)))
+        .collect();
+    transaction.execute_batch(&format!(
+        "CREATE TABLE IF NOT EXISTS runs (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             timestamp TEXT NOT NULL,
+             seed TEXT,
+             schema_version INTEGER NOT NULL,
+             arquivo_resultados TEXT NOT NULL,
+             metadata TEXT NOT NULL
+         );
+         CREATE TABLE IF NOT EXISTS results (
+             run_id INTEGER NOT NULL REFERENCES runs(id),
+             {}
+         );",
+        definitions.join(",\n             ")
+    )).map_err(sqlite_error)?;
+
+    transaction.execute(
+        "INSERT INTO runs (timestamp, seed, schema_version, arquivo_resultados, metadata) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![run.timestamp, run.seed.map(|seed| seed.to_string()), run.schema_version, run.csv_file, run.metadata_json],
+    ).map_err(sqlite_error)?;
+    let run_id = transaction.last_insert_rowid();
+
+    let placeholders = vec!["?"; columns.len() + 1].join(", ");
+    {
+        let mut insert = transaction
+            .prepare(&format!("INSERT INTO results VALUES ({})", placeholders))
+            .map_err(sqlite_error)?;
+        for (line, row) in rows.iter().enumerate() {
+            if row.len() != columns.len() {
+                return Err(ExperimentError::Config(format!(
+                    "linha {} tem {} células, esperadas {}", line + 1, row.len(), columns.len()
+                )));
+            }
+            let mut values = vec![Value::Integer(run_id)];
+            for (cell, column) in row.iter().zip(columns) {
+                if !cell.fits(column.kind) {
+                    return Err(ExperimentError::Config(format!(
+                        "linha {}, coluna {}: valor {:?} incompatível com o tipo {:?}", line + 1, column.name, cell, column.kind
+                    )));
+                }
+                values.push(sql_value(cell));
+            }
+            insert.execute(params_from_iter(values)).map_err(sqlite_error)?;
+        }
+    }
+    transaction.commit().map_err(sqlite_error)?;
+    Ok(run_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::results::RESULT_COLUMNS;
+
+    /// Colunas do registro com os nomes dados, na ordem dada
+    fn registry_columns(names: &[&str]) -> Vec<Column> {
+        names.iter().map(|name| *RESULT_COLUMNS.iter().find(|column| column.name == *name).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_runs_accumulate_and_schema_mismatch_is_refused() {
+        let path = std::env::temp_dir().join(format!("pq_crypto_matrix_sqlite_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let columns = registry_columns(&["schema_version", "cifra", "num_msgs", "kem_ms_mean", "kem_normal", "config_seed"]);
+        let rows = vec![
+            vec![Cell::from(18u32), Cell::from("AES-GCM"), Cell::from(200usize), Cell::Real(0.1234, 4),
+                 Cell::from(true), Cell::from(u64::MAX.to_string())],
+            vec![Cell::from(18u32), Cell::from("ChaCha20"), Cell::from(200usize), Cell::Real(f64::NAN, 4),
+                 Cell::from(false), Cell::from("1")],
+        ];
+        let run = RunRecord { timestamp: "x", seed: Some(u64::MAX), schema_version: 18, csv_file: "r.csv", metadata_json: "{}" };
+        assert_eq!(write_results(&path, &run, &columns, &rows).unwrap(), 1);
+        assert_eq!(write_results(&path, &run, &columns, &rows[..1]).unwrap(), 2);
+
+        let connection = Connection::open(&path).unwrap();
+        let count: i64 = connection.query_row("SELECT COUNT(*) FROM results WHERE run_id = 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 2);
+        let (kem, normal, seed): (Option<f64>, i64, String) = connection.query_row(
+            "SELECT kem_ms_mean, kem_normal, config_seed FROM results WHERE cifra = 'ChaCha20'", [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).unwrap();
+        assert_eq!((kem, normal, seed.as_str()), (None, 0, "1"));
+        let run_seed: String = connection.query_row("SELECT seed FROM runs WHERE id = 2", [], |row| row.get(0)).unwrap();
+        assert_eq!(run_seed, u64::MAX.to_string());
+
+        // Outro conjunto de colunas não é misturado ao existente
+        let other = registry_columns(&["schema_version", "cifra"]);
+        let error = write_results(&path, &run, &other, &[vec![Cell::from(19u32), Cell::from("AES-GCM")]]).unwrap_err();
+        assert!(matches!(&error, ExperimentError::Config(message) if message.contains("outro esquema")), "{}", error);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_cells_must_match_registry_types() {
+        let path = std::env::temp_dir().join(format!("pq_crypto_matrix_sqlite_types_{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let columns = registry_columns(&["num_msgs", "kem_normal"]);
+        let run = RunRecord { timestamp: "x", seed: None, schema_version: 18, csv_file: "r.csv", metadata_json: "{}" };
+        let error = write_results(&path, &run, &columns, &[vec![Cell::Real(1.5, 1), Cell::from(true)]]).unwrap_err();
+        assert!(matches!(&error, ExperimentError::Config(message) if message.contains("num_msgs")), "{}", error);
+        assert_eq!(sql_type(ColumnType::Boolean), "INTEGER");
+        assert_eq!(sql_value(&Cell::Empty), Value::Null);
+        let _ = std::fs::remove_file(&path);
+    }
+}
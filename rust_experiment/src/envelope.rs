@@ -0,0 +1,109 @@
+//! Envelope de evento Matrix em volta de cada mensagem (`--envelope matrix-json`)
+//!
+//! Por padrão, o texto claro cifrado é só o payload da mensagem. Um cliente Matrix cifra
+//! o evento inteiro: um objeto JSON com `type`, `content`, `sender` e `origin_server_ts`,
+//! em que o `content` segue o `msgtype` de cada tipo de mensagem (`m.text`, `m.image`,
+//! `m.file`, `m.notice`, `m.audio`). A mídia binária entra no JSON em base64 sem padding,
+//! a codificação do Matrix para dados binários, o que infla imagens, arquivos e voz em ~4/3.
+//! Um cliente real enviaria a mídia ao repositório de conteúdo e guardaria só a URL
+//! `mxc://` no evento; aqui ela segue embutida, no campo `data`.
+
+use base64::prelude::{BASE64_STANDARD_NO_PAD, Engine as _};
+use serde_json::{Value, json};
+
+use crate::workload::MessageType;
+
+/// Envelope aplicado ao texto claro antes da compressão e da cifragem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum Envelope {
+    /// Só o payload (comportamento original)
+    #[default]
+    None,
+    /// Evento `m.room.message` em JSON, com a mídia em base64
+    MatrixJson,
+}
+
+impl Envelope {
+    /// Nome do envelope como registrado no CSV
+    pub fn name(&self) -> &'static str {
+        match self {
+            Envelope::None => "none",
+            Envelope::MatrixJson => "matrix-json",
+        }
+    }
+}
+
+/// Codifica bytes binários como o Matrix: base64 padrão sem padding
+pub fn encode_base64(data: &[u8]) -> String {
+    BASE64_STANDARD_NO_PAD.encode(data)
+}
+
+/// Conteúdo de uma mídia: nome, tipo MIME, tamanho original e os bytes em base64
+fn media_content(msgtype: &str, body: &str, mimetype: &str, data: &[u8]) -> Value {
+    json!({
+        "msgtype": msgtype,
+        "body": body,
+        "info": { "mimetype": mimetype, "size": data.len() },
+        "data": encode_base64(data),
+    })
+}
+
+/// Instante de referência (ms desde a época Unix) dos `origin_server_ts` do experimento
+///
+/// Os eventos não usam o relógio de parede: o texto claro cifrado seria diferente a cada
+/// execução, inclusive com a mesma `--seed`. O instante de cada evento é esta referência
+/// mais o relógio simulado da sessão.
+pub const EVENT_EPOCH_MS: u64 = 1_700_000_000_000;
+
+/// Serializa a mensagem como um evento Matrix `m.room.message` em JSON
+///
+/// `sender` é o ID do usuário (ex: `@alice:matrix.org`) e `origin_server_ts` o instante
+/// do envio em milissegundos desde a época Unix.
+pub fn matrix_event(message: &MessageType, sender: &str, origin_server_ts: u64) -> Vec<u8> {
+    let content = match message {
+        MessageType::Text(text) => json!({ "msgtype": "m.text", "body": text }),
+        MessageType::System(text) => json!({ "msgtype": "m.notice", "body": text }),
+        MessageType::Image(data) => media_content("m.image", "imagem.jpg", "image/jpeg", data),
+        MessageType::File(data) => media_content("m.file", "arquivo.bin", "application/octet-stream", data),
+        MessageType::Voice(data) => {
+            let mut content = media_content("m.audio", "voz.ogg", "audio/ogg", data);
+            // Marca de mensagem de voz dos clientes Element (MSC3245)
+            content["org.matrix.msc3245.voice"] = json!({});
+            content
+        }
+    };
+    let event = json!({
+        "type": "m.room.message",
+        "content": content,
+        "sender": sender,
+        "origin_server_ts": origin_server_ts,
+    });
+    serde_json::to_vec(&event).expect("Evento JSON sempre serializável")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matrix_event_wraps_payload_and_encodes_media() {
+        let text = matrix_event(&MessageType::Text("olá".to_string()), "@alice:matrix.org", 1_700_000_000_000);
+        let event: Value = serde_json::from_slice(&text).unwrap();
+        assert_eq!(event["type"], "m.room.message");
+        assert_eq!(event["content"]["body"], "olá");
+        assert_eq!(event["sender"], "@alice:matrix.org");
+        assert_eq!(event["origin_server_ts"], EVENT_EPOCH_MS);
+
+        // A mídia vai em base64 sem padding: 3000 bytes viram 4000 caracteres
+        let image = vec![0xABu8; 3000];
+        let event: Value = serde_json::from_slice(&matrix_event(&MessageType::Image(image.clone().into()), "@a:b", 0)).unwrap();
+        let data = event["content"]["data"].as_str().unwrap();
+        assert_eq!(data.len(), 4000);
+        assert_eq!(BASE64_STANDARD_NO_PAD.decode(data).unwrap(), image);
+        assert_eq!(event["content"]["info"]["size"], 3000);
+
+        let voice = matrix_event(&MessageType::Voice(vec![1, 2].into()), "@a:b", 0);
+        let event: Value = serde_json::from_slice(&voice).unwrap();
+        assert_eq!((event["content"]["msgtype"].as_str(), event["content"]["data"].as_str()), (Some("m.audio"), Some("AQI")));
+    }
+}
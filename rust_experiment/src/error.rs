@@ -0,0 +1,97 @@
+//! Erro único da biblioteca do experimento (`ExperimentError`)
+//!
+//! As funções públicas da biblioteca devolvem `Result<_, ExperimentError>` em vez de
+//! entrar em pânico, para que o experimento possa ser embutido em outros programas.
+//! A variante indica a origem da falha; a mensagem (em `Display`) é a mesma que o
+//! binário imprime antes de encerrar com código diferente de zero.
+
+use std::fmt;
+use std::io;
+
+use crate::results::SchemaError;
+
+/// Falha de uma operação do experimento, pela origem
+#[derive(Debug)]
+pub enum ExperimentError {
+    /// Leitura ou gravação de arquivos, banco de dados ou rede
+    Io(io::Error),
+    /// Primitiva criptográfica: cifra recusada, segredo curto, decapsulamento inconsistente
+    Crypto(String),
+    /// Parâmetro inválido: nome de acordo ou cifra, URL, arquivo de entrada incompatível
+    Config(String),
+    /// Dados insuficientes ou inválidos para uma análise estatística
+    Stats(String),
+}
+
+impl ExperimentError {
+    /// Antepõe `context` (ex: o arquivo envolvido) à mensagem, mantendo a variante
+    pub fn context(self, context: impl fmt::Display) -> Self {
+        match self {
+            ExperimentError::Io(e) => ExperimentError::Io(io::Error::new(e.kind(), format!("{}: {}", context, e))),
+            ExperimentError::Crypto(message) => ExperimentError::Crypto(format!("{}: {}", context, message)),
+            ExperimentError::Config(message) => ExperimentError::Config(format!("{}: {}", context, message)),
+            ExperimentError::Stats(message) => ExperimentError::Stats(format!("{}: {}", context, message)),
+        }
+    }
+}
+
+impl fmt::Display for ExperimentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExperimentError::Io(e) => write!(f, "erro de E/S: {}", e),
+            ExperimentError::Crypto(message)
+            | ExperimentError::Config(message)
+            | ExperimentError::Stats(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for ExperimentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ExperimentError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ExperimentError {
+    fn from(e: io::Error) -> Self {
+        ExperimentError::Io(e)
+    }
+}
+
+impl From<SchemaError> for ExperimentError {
+    fn from(e: SchemaError) -> Self {
+        match e {
+            SchemaError::Io(e) => ExperimentError::Io(e),
+            other => ExperimentError::Config(other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+
+    #[test]
+    fn test_display_and_source_by_variant() {
+        let error = ExperimentError::from(io::Error::new(io::ErrorKind::NotFound, "sem arquivo"));
+        assert_eq!(error.to_string(), "erro de E/S: sem arquivo");
+        assert!(error.source().is_some());
+        let error = error.context("não foi possível ler amostras.csv");
+        assert_eq!(error.to_string(), "erro de E/S: não foi possível ler amostras.csv: sem arquivo");
+        assert!(matches!(&error, ExperimentError::Io(e) if e.kind() == io::ErrorKind::NotFound));
+
+        let config = ExperimentError::Config("cifra desconhecida 'Serpent'".to_string());
+        assert_eq!(config.to_string(), "cifra desconhecida 'Serpent'");
+        assert!(config.source().is_none());
+
+        assert!(matches!(ExperimentError::from(SchemaError::NoRows), ExperimentError::Config(_)));
+        assert!(matches!(
+            ExperimentError::from(SchemaError::Io(io::Error::other("falha"))),
+            ExperimentError::Io(_)
+        ));
+    }
+}
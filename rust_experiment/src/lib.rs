@@ -0,0 +1,35 @@
+//! Biblioteca do experimento de avaliação de criptografia pós-quântica (Matrix-like)
+//!
+//! Expõe os módulos compartilhados entre o binário do experimento estatístico
+//! e os microbenchmarks Criterion em `benches/`:
+//! - `cache`: esvaziamento das caches da CPU entre operações medidas (`--flush-cache`)
+//! - `compression`: compressão opcional do texto claro (zstd, gzip) antes da cifragem
+//! - `corpus`: payloads reais lidos de um diretório (`--corpus`)
+//! - `crypto`: acordos de chave (X25519, X25519 + Kyber768) e cifras simétricas
+//! - `database`: gravação dos resultados e das execuções em SQLite (`--sqlite`)
+//! - `envelope`: evento Matrix em JSON em volta de cada mensagem (`--envelope`)
+//! - `error`: erro único das funções da biblioteca (`ExperimentError`)
+//! - `pooling`: combinação de resultados parciais por momentos agrupados (Welford)
+//! - `replay`: gravação e reprodução determinística do workload (`--record`/`--replay`)
+//! - `results`: contrato de formato do CSV de resultados (`schema_version`)
+//! - `seed`: sementes determinísticas por configuração (`--seed`, `config_seed`)
+//! - `stats`: utilitários estatísticos sobre séries (média móvel, histograma)
+//! - `telemetry`: métricas ao vivo para painéis via Pushgateway (`--metrics-endpoint`)
+//! - `throttle`: amostragem da frequência da CPU e detecção de throttling térmico
+//! - `workload`: tipos de mensagem, padrões de tráfego e cenários de uso realistas
+
+pub mod cache;
+pub mod compression;
+pub mod corpus;
+pub mod crypto;
+pub mod database;
+pub mod envelope;
+pub mod error;
+pub mod pooling;
+pub mod replay;
+pub mod results;
+pub mod seed;
+pub mod stats;
+pub mod telemetry;
+pub mod throttle;
+pub mod workload;
@@ -16,7 +16,7 @@ em sistemas de mensagens seguras, comparando especificamente:
 2. **ALGORITMOS DE CIFRAGEM SIMÉTRICA:**
    - AES-GCM: Padrão atual amplamente adotado
    - ChaCha20-Poly1305: Alternativa moderna resistente a ataques de canal lateral
-   - Megolm-Like (AES-CTR): Implementação similar ao protocolo Matrix
+   - Megolm-Like (AES-CTR + HMAC-SHA256): Implementação similar ao protocolo Matrix
    - Comparação de desempenho e adequação para diferentes cenários
 
 3. **CENÁRIOS DE USO REALISTAS:**
@@ -24,6 +24,8 @@ em sistemas de mensagens seguras, comparando especificamente:
    - MediumGroup: Grupos médios (250 mensagens, rotação a cada 50)
    - LargeChannel: Canais grandes (500 mensagens, rotação a cada 25)
    - SystemChannel: Canais de sistema (1000 mensagens, rotação a cada 10)
+   - DirectMessage: Conversa 1:1 com Olm puro (200 mensagens, catraca por mensagem,
+     passo DH a cada 5)
 
 4. **PADRÕES DE TRÁFEGO DIVERSOS:**
    - Constant, Burst, Periodic, Random, Realistic
@@ -59,7 +61,7 @@ Para garantir resultados confiáveis, o experimento implementa:
    - Comparações: t-test, Mann-Whitney U, Welch's t-test
    - Múltiplos grupos: ANOVA, Kruskal-Wallis
    - Testes post-hoc: Tukey HSD
-   - Equivalência: TOST (Two One-Sided Tests)
+   - Equivalência: TOST (Two One-Sided Tests), também disponível no binário via --equivalence-margin
    - Tamanho do efeito: Cohen's d, Cliff's delta, Eta-squared
    - Correlações: Pearson, Spearman, Kendall
 
@@ -88,7 +90,7 @@ PARÂMETROS EXPERIMENTAIS:
   * Olm-Clássico: X25519 ECDH
   * Olm-Híbrido: X25519 ECDH + Kyber768 KEM
 - Algoritmos de cifragem simétrica: AES-GCM, ChaCha20-Poly1305, Megolm-Like
-- Cenários de uso: SmallChat, MediumGroup, LargeChannel, SystemChannel
+- Cenários de uso: SmallChat, MediumGroup, LargeChannel, SystemChannel, DirectMessage
 - Padrões de tráfego: Constant, Burst, Periodic, Random, Realistic
 - Tipos de mensagens: texto, imagem, arquivo, sistema, voz
 
@@ -119,55 +121,177 @@ Autor: Marcos Dantas Ortiz
 Data: Julho de 2025
 =============================================================================================
 */
+mod config;
 
-mod workload;
+// --- PRIMITIVAS CRIPTOGRÁFICAS ---
+// Acordos de chave (X25519, X25519 + Kyber768) e cifras simétricas, compartilhados com os benchmarks
+use pq_crypto_matrix::crypto::{
+    AGREEMENTS, CIPHERS, PRIMITIVE_VERSIONS, RecipientKeys, SecurityLevel,
+    derive_message_key, session_key, key_commitment, KEY_COMMITMENT_SIZE, KEY_CONFIRMATION_TOKEN, RecoveryKey,
+    AgreementRegistry, CipherRegistry, KeyAgreement, primitive_versions, check_plaintext_size, EphemeralMode, KeygenPolicy,
+    SenderKeys, KemComponent, agreement_components, hybrid_agreement_name,
+    AesBackendMode, aes_backend, aes_software_forced, TagLayout,
+};
+
+// Compressão opcional do texto claro antes da cifragem
+use pq_crypto_matrix::cache::CacheFlusher;
+use pq_crypto_matrix::compression::Compression;
+use pq_crypto_matrix::corpus::PayloadCorpus;
+use pq_crypto_matrix::database::{RunRecord, write_results};
+use pq_crypto_matrix::envelope::{EVENT_EPOCH_MS, Envelope, encode_base64, matrix_event};
+use pq_crypto_matrix::error::ExperimentError;
+
+// Sementes determinísticas por configuração e por fluxo
+use pq_crypto_matrix::seed::{SeedStream, config_seed, os_entropy_backend, stream_seed};
+
+// Versão do esquema de colunas do CSV (primeira coluna de cada linha)
+use pq_crypto_matrix::results::{Cell, RESULT_COLUMNS, SCHEMA_COLUMN, SCHEMA_VERSION, csv_row, result_header};
 
-// --- BIBLIOTECAS DE CRIPTOGRAFIA SIMÉTRICA ---
-use aes_gcm::{Aes256Gcm, Key, Nonce};
-use aes_gcm::aead::{Aead, KeyInit};
-use aes::Aes256;
-use ctr::cipher::{KeyIvInit, StreamCipher};
-use chacha20poly1305::{ChaCha20Poly1305, Key as ChaKey, Nonce as ChaNonce};
+// Momentos agrupáveis (média, variância) das estatísticas paramétricas
+use pq_crypto_matrix::pooling::{CONFIDENCE_LEVEL, Moments};
+use pq_crypto_matrix::stats::{DurationSamples, TimeUnit, histogram, moving_average, weighted_mean};
 
-// --- BIBLIOTECAS DE CRIPTOGRAFIA ASSIMÉTRICA (KEMs) ---
-use pqcrypto_kyber::kyber768::*;
-use pqcrypto_traits::kem::{Ciphertext as KemCiphertext, SharedSecret as KemSharedSecret, PublicKey};
+// Monitoramento da frequência da CPU (--monitor-throttle)
+use pq_crypto_matrix::telemetry::{MetricsSnapshot, PUSH_INTERVAL};
+use pq_crypto_matrix::throttle::{FrequencyMonitor, ThrottleCheck, detect_throttle};
 
-// --- CURVAS ELÍPTICAS CLÁSSICAS (X25519) ---
-use x25519_dalek::{EphemeralSecret as StaticSecret, PublicKey as X255PublicKey};
+// Gravação e reprodução determinística do workload
+use pq_crypto_matrix::replay::{MessageDescriptor, WorkloadRecorder, WorkloadReplay};
 
 // --- UTILITÁRIOS DO SISTEMA E TEMPO ---
-use rand::RngCore;
-use std::time::{Duration, Instant};
-use std::fs::{self, OpenOptions};
-use std::io::Write;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore, SeedableRng};
+use rand_core::CryptoRngCore;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::num::NonZeroUsize;
 use std::path::Path;
+use std::sync::{Arc, mpsc};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::process::Command;
-use chrono;
+use rayon::prelude::*;
+use serde_json::json;
+
+// --- CONFIGURAÇÃO DA EXECUÇÃO ---
+use config::{CentralEstimator, DumpEncoding, ExperimentCommand, ExperimentConfig, Pacing, NORMALITY_METRICS};
 
 // --- WORKLOAD REALISTA ---
 // Importa tipos de mensagens, padrões de tráfego e cenários de uso
-use workload::{
-    MessageType, TrafficPattern, UsageScenario,
+use pq_crypto_matrix::workload::{
+    MESSAGE_KINDS, MessageType, TrafficPattern, UsageScenario,
     MessageGenerator, TrafficGenerator,
-    get_rotation_config, get_message_count_config
+    get_rotation_config, get_message_count_config, get_group_size, simulate_inter_arrivals, RotationStrategy
 };
 
 // Número de repetições por configuração experimental
 // Valor balanceado entre robustez estatística e tempo de execução
 const REPETICOES: usize = 50;
 
+// Tamanho mínimo da amostra após a remoção de outliers extremos
+// Abaixo deste piso a remoção é descartada e os dados originais são mantidos
+const MIN_SAMPLE_SIZE: usize = 10;
+
+// Guarda do laço de mensagens: consultas seguidas ao padrão de tráfego sem nenhum envio
+// antes de abandonar a sessão. Um laço apertado faz da ordem de 10^7 consultas por segundo,
+// então o limite cobre com folga a maior pausa prevista (o 1 s entre rajadas do Burst).
+// Por contar iterações, e não tempo, a guarda não depende da carga da máquina e dispara
+// no mesmo ponto com --replay
+const MAX_IDLE_POLLS: u64 = 1_000_000_000;
+
+// Lote da amostragem adaptativa (--target-moe): a margem de erro é reavaliada a cada
+// lote, e uma configuração roda ao menos um lote inteiro
+const ADAPTIVE_BATCH: usize = 10;
+
+// Parâmetros da execução reduzida (--quick): o mínimo para exercitar todo o pipeline
+const QUICK_REPETICOES: usize = MIN_SAMPLE_SIZE;
+const QUICK_MENSAGENS: usize = 4;
+const QUICK_MSGS_POR_ROTACAO: usize = 2;
+
+/// Máximo de pontos por configuração na série de largura de banda acumulada
+/// (--bandwidth-series); séries mais longas são subamostradas uniformemente
+const MAX_SERIES_POINTS: usize = 200;
+
+/// Sinalizado pelo tratador de SIGINT (Ctrl-C) e verificado nas fronteiras de configuração
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Instala o tratador de Ctrl-C (SIGINT no Unix, evento de console no Windows)
+///
+/// O primeiro Ctrl-C apenas sinaliza a interrupção: as configurações em andamento
+/// terminam, o CSV é gravado e fechado, os metadados também, e o processo encerra
+/// com código 130. Um segundo Ctrl-C aborta imediatamente, sem gravar. O tratador roda numa thread própria do `ctrlc`.
+fn install_interrupt_handler() {
+    let installed = ctrlc::set_handler(|| {
+        if INTERRUPTED.swap(true, Ordering::SeqCst) {
+            std::process::exit(130);
+        }
+    });
+    if let Err(error) = installed {
+        println!("  [AVISO] Tratador de Ctrl-C não instalado ({}); uma interrupção perderá os resultados", error);
+    }
+}
+
 // Estrutura para armazenar estatísticas descritivas de cada métrica
 // Suporta tanto estatísticas paramétricas quanto robustas
 #[derive(Debug, Clone)]
 struct Stats {
-    mean: f64,                    // Média (dados normais) ou mediana (dados não-normais)
+    mean: f64,                    // Média (dados normais) ou estimador de `central` (dados não-normais)
     std_dev: f64,                 // Desvio padrão (normal) ou MAD escalado (não-normal)
     ci95: f64,                    // Intervalo de confiança 95%
     is_normal: bool,              // Flag indicando se os dados seguem distribuição normal
     outliers_count: usize,        // Número total de outliers detectados (moderados + extremos)
     extreme_outliers_count: usize, // Número específico de outliers extremos
     sample_size: usize,           // Tamanho da amostra final após remoção de outliers
+    original_size: usize,         // Valores finitos antes da remoção (base das taxas de outliers)
+    skewness: f64,                // Assimetria usada na verificação de normalidade (NaN se não calculada)
+    kurtosis: f64,                // Curtose em excesso usada na verificação (NaN se não calculada)
+    central: CentralEstimator,    // Estimador guardado em `mean` (média, mediana ou moda)
+    sample_floor_hit: bool,       // Remoção de outliers descartada por deixar menos de MIN_SAMPLE_SIZE valores
+    nonfinite_count: usize,       // Valores NaN/Inf descartados antes da análise
+}
+
+impl Stats {
+    /// Estatísticas de uma amostra em que todos os valores eram não finitos
+    ///
+    /// Média, dispersão e IC ficam NaN e `stat_type` é `invalid`, em vez de números
+    /// calculados sobre uma amostra vazia.
+    fn invalid(nonfinite_count: usize) -> Self {
+        Stats {
+            mean: f64::NAN,
+            std_dev: f64::NAN,
+            ci95: f64::NAN,
+            is_normal: false,
+            outliers_count: 0,
+            extreme_outliers_count: 0,
+            sample_size: 0,
+            original_size: 0,
+            skewness: f64::NAN,
+            kurtosis: f64::NAN,
+            central: CentralEstimator::Mean,
+            sample_floor_hit: false,
+            nonfinite_count,
+        }
+    }
+
+    /// Se as estatísticas vieram de uma amostra só de não finitos (`Stats::invalid`)
+    fn is_invalid(&self) -> bool {
+        self.original_size == 0 && self.nonfinite_count > 0
+    }
+
+    /// Tipo de estatística aplicado, como gravado nas colunas `*_stat_type`
+    fn stat_type(&self) -> &'static str {
+        if self.is_invalid() {
+            "invalid"
+        } else if self.is_normal {
+            "parametric"
+        } else {
+            "robust"
+        }
+    }
 }
 
 /// Calcula estatísticas paramétricas para dados que seguem distribuição normal
@@ -195,7 +319,13 @@ fn calculate_parametric_stats(data: &[f64], outliers_count: usize, extreme_outli
             is_normal: true,
             outliers_count,
             extreme_outliers_count,
-            sample_size: n
+            sample_size: n,
+            original_size,
+            skewness: f64::NAN,
+            kurtosis: f64::NAN,
+            central: CentralEstimator::Mean,
+            sample_floor_hit: false,
+            nonfinite_count: 0,
         };
     }
     
@@ -209,21 +339,21 @@ fn calculate_parametric_stats(data: &[f64], outliers_count: usize, extreme_outli
             is_normal: true,
             outliers_count,
             extreme_outliers_count,
-            sample_size: n
+            sample_size: n,
+            original_size,
+            skewness: f64::NAN,
+            kurtosis: f64::NAN,
+            central: CentralEstimator::Mean,
+            sample_floor_hit: false,
+            nonfinite_count: 0,
         };
     }
     
-    // Calcula a variância amostral (correção de Bessel)
-    let variance = data.iter().map(|value| {
-        let diff = mean - value;
-        diff * diff
-    }).sum::<f64>() / (n - 1) as f64;
-    
-    let std_dev = variance.sqrt();
-    
-    // Z-score para 95% de confiança (distribuição normal)
-    let z_score = 1.96;
-    let ci95 = z_score * (std_dev / (n as f64).sqrt());
+    // Desvio padrão amostral (correção de Bessel) e IC95 por z-score (1.96), pelos
+    // mesmos momentos que `pooling` usa para combinar resultados parciais entre execuções
+    let moments = Moments::from_samples(data);
+    let std_dev = moments.std_dev();
+    let ci95 = moments.ci95();
     
     Stats { 
         mean, 
@@ -232,14 +362,21 @@ fn calculate_parametric_stats(data: &[f64], outliers_count: usize, extreme_outli
         is_normal: true,
         outliers_count,
         extreme_outliers_count,
-        sample_size: n
+        sample_size: n,
+        original_size,
+        skewness: f64::NAN,
+        kurtosis: f64::NAN,
+        central: CentralEstimator::Mean,
+        sample_floor_hit: false,
+        nonfinite_count: 0,
     }
 }
 
 /// Calcula estatísticas robustas para dados que não seguem distribuição normal
 /// 
 /// Aplica estatísticas não-paramétricas resistentes a outliers:
-/// - Mediana como medida de tendência central (mais robusta que média)
+/// - Mediana como medida de tendência central (mais robusta que média), ou o
+///   estimador escolhido em `central` (média aritmética ou moda via HSM)
 /// - MAD (Median Absolute Deviation) escalado para dispersão
 /// - Intervalo de confiança baseado em percentis (2.5% e 97.5%)
 ///
@@ -251,11 +388,14 @@ fn calculate_parametric_stats(data: &[f64], outliers_count: usize, extreme_outli
 /// - outliers_count: número total de outliers detectados
 /// - extreme_outliers_count: número específico de outliers extremos
 /// - original_size: tamanho original da amostra antes da limpeza
+/// - central: estimador de tendência central guardado em `Stats.mean`
 ///
 /// Retorna:
 /// - Stats com estatísticas robustas e flag is_normal = false
-fn calculate_robust_stats(data: &[f64], outliers_count: usize, extreme_outliers_count: usize, original_size: usize) -> Stats {
-    let n = data.len();
+fn calculate_robust_stats(data: &[f64], outliers_count: usize, extreme_outliers_count: usize, original_size: usize, central: CentralEstimator) -> Stats {
+    // Ordena os dados finitos para cálculo de percentis
+    let sorted_data = sorted_finite(data, "estatísticas robustas");
+    let n = sorted_data.len();
     if n == 0 {
         return Stats { 
             mean: 0.0, 
@@ -264,51 +404,143 @@ fn calculate_robust_stats(data: &[f64], outliers_count: usize, extreme_outliers_
             is_normal: false,
             outliers_count,
             extreme_outliers_count,
-            sample_size: n
+            sample_size: n,
+            original_size,
+            skewness: f64::NAN,
+            kurtosis: f64::NAN,
+            central,
+            sample_floor_hit: false,
+            nonfinite_count: 0,
         };
     }
     
-    // Ordena os dados para cálculo de percentis
-    let mut sorted_data = data.to_vec();
-    sorted_data.sort_by(|a, b| a.partial_cmp(b).unwrap());
-    
     // Calcula mediana
-    let median = if n % 2 == 0 {
-        (sorted_data[n / 2 - 1] + sorted_data[n / 2]) / 2.0
-    } else {
-        sorted_data[n / 2]
-    };
+    let median = sorted_median(&sorted_data);
     
     // Calcula MAD (Median Absolute Deviation)
-    let mut abs_deviations: Vec<f64> = data.iter()
+    let abs_deviations: Vec<f64> = sorted_data.iter()
         .map(|x| (x - median).abs())
         .collect();
-    abs_deviations.sort_by(|a, b| a.partial_cmp(b).unwrap());
-    
-    let mad = if n % 2 == 0 {
-        (abs_deviations[n / 2 - 1] + abs_deviations[n / 2]) / 2.0
-    } else {
-        abs_deviations[n / 2]
-    };
+    let mad = sorted_median(&sorted_finite(&abs_deviations, "desvios absolutos"));
     
     // Fator de escala para tornar MAD equivalente ao desvio padrão em distribuições normais
     let mad_scaled = mad * 1.4826;
     
     // Intervalo de confiança baseado em percentis (mais robusto)
-    let p2_5_idx = ((n as f64 * 0.025) as usize).min(n - 1);
-    let p97_5_idx = ((n as f64 * 0.975) as usize).min(n - 1);
-    let p2_5 = sorted_data[p2_5_idx];
-    let p97_5 = sorted_data[p97_5_idx];
+    let p2_5 = sorted_quantile(&sorted_data, (1.0 - CONFIDENCE_LEVEL) / 2.0);
+    let p97_5 = sorted_quantile(&sorted_data, (1.0 + CONFIDENCE_LEVEL) / 2.0);
     let ci95_robust = (p97_5 - p2_5) / 2.0;
     
+    // Medida central: mediana por padrão; média ou moda (HSM) quando selecionadas
+    let center = match central {
+        CentralEstimator::Median => median,
+        CentralEstimator::Mean => sorted_data.iter().sum::<f64>() / n as f64,
+        CentralEstimator::Mode => half_sample_mode(&sorted_data),
+    };
+    
     Stats { 
-        mean: median,        // Usa mediana como medida central
+        mean: center,        // Usa o estimador central selecionado (mediana por padrão)
         std_dev: mad_scaled, // Usa MAD escalado como dispersão
         ci95: ci95_robust,   // Usa diferença de percentis
         is_normal: false,
         outliers_count,
         extreme_outliers_count,
-        sample_size: n
+        sample_size: n,
+        original_size,
+        skewness: f64::NAN,
+        kurtosis: f64::NAN,
+        central,
+        sample_floor_hit: false,
+        nonfinite_count: 0,
+    }
+}
+
+/// Estimador half-sample mode (HSM) da moda de uma amostra contínua
+///
+/// Algoritmo de Bickel & Frühwirth (2006): repetidamente mantém a metade da
+/// amostra ordenada com menor amplitude (a "meia amostra" mais densa) até
+/// restarem no máximo três valores, que são resolvidos diretamente.
+/// É robusto a outliers e adequado a distribuições multimodais.
+///
+/// Parâmetros:
+/// - sorted: amostra ordenada de forma crescente
+///
+/// Retorna:
+/// - Estimativa da moda (NaN para amostra vazia)
+fn half_sample_mode(sorted: &[f64]) -> f64 {
+    let mut window = sorted;
+    loop {
+        match window.len() {
+            0 => return f64::NAN,
+            1 => return window[0],
+            2 => return (window[0] + window[1]) / 2.0,
+            3 => {
+                let (left, right) = (window[1] - window[0], window[2] - window[1]);
+                return if left < right {
+                    (window[0] + window[1]) / 2.0
+                } else if left > right {
+                    (window[1] + window[2]) / 2.0
+                } else {
+                    window[1]
+                };
+            }
+            n => {
+                // Meia amostra de menor amplitude (primeira, em caso de empate)
+                let h = n.div_ceil(2);
+                let start = (0..=n - h)
+                    .min_by(|&a, &b| {
+                        (window[a + h - 1] - window[a]).total_cmp(&(window[b + h - 1] - window[b]))
+                    })
+                    .unwrap_or(0);
+                window = &window[start..start + h];
+            }
+        }
+    }
+}
+
+/// Valores finitos de uma amostra, avisando quando NaN ou ±Inf são descartados
+///
+/// Um tempo NaN ou infinito vem de uma falha de medição, não do fenômeno medido;
+/// as estatísticas o ignoram em vez de propagá-lo ou de entrar em pânico na ordenação.
+fn finite_values<'a>(data: &'a [f64], label: &str) -> Cow<'a, [f64]> {
+    let discarded = data.iter().filter(|value| !value.is_finite()).count();
+    if discarded == 0 {
+        return Cow::Borrowed(data);
+    }
+    println!("  [AVISO] {}: {} valores não finitos (NaN/Inf) ignorados", label, discarded);
+    Cow::Owned(data.iter().copied().filter(|value| value.is_finite()).collect())
+}
+
+/// Valores finitos de uma amostra em ordem crescente, para quartis, percentis e mediana
+///
+/// Descarta NaN/±Inf como `finite_values` (com o mesmo aviso) e ordena por
+/// `f64::total_cmp`, uma ordem total: valores empatados são bit a bit iguais e o
+/// resultado não depende da ordem de entrada.
+fn sorted_finite(data: &[f64], label: &str) -> Vec<f64> {
+    let mut sorted = finite_values(data, label).into_owned();
+    sorted.sort_by(f64::total_cmp);
+    sorted
+}
+
+/// Quantil empírico `p` de uma amostra ordenada: o valor de índice ⌊n·p⌋ (limitado a n - 1)
+///
+/// É a convenção dos quartis do IQR e dos percentis do IC robusto. NaN para amostra vazia.
+fn sorted_quantile(sorted: &[f64], p: f64) -> f64 {
+    match sorted.len() {
+        0 => f64::NAN,
+        n => sorted[((n as f64 * p) as usize).min(n - 1)],
+    }
+}
+
+/// Mediana de uma amostra ordenada (média dos dois centrais se n for par); NaN se vazia
+fn sorted_median(sorted: &[f64]) -> f64 {
+    let n = sorted.len();
+    if n == 0 {
+        f64::NAN
+    } else if n.is_multiple_of(2) {
+        (sorted[n / 2 - 1] + sorted[n / 2]) / 2.0
+    } else {
+        sorted[n / 2]
     }
 }
 
@@ -321,6 +553,7 @@ fn calculate_robust_stats(data: &[f64], outliers_count: usize, extreme_outliers_
 /// O método IQR é robusto e amplamente aceito na literatura estatística.
 /// Outliers moderados são identificados mas mantidos na análise.
 /// Outliers extremos são candidatos à remoção da amostra.
+/// Valores não finitos (NaN/Inf) são sempre outliers extremos e ficam fora dos quartis.
 ///
 /// Parâmetros:
 /// - data: slice de valores f64 para análise
@@ -329,21 +562,19 @@ fn calculate_robust_stats(data: &[f64], outliers_count: usize, extreme_outliers_
 /// Retorna:
 /// - Tupla contendo: (índices_outliers_moderados, índices_outliers_extremos, dados_limpos)
 fn detect_outliers(data: &[f64], label: &str) -> (Vec<usize>, Vec<usize>, Vec<f64>) {
-    let n = data.len();
+    // Ordena os dados finitos para calcular quartis
+    let sorted_data = sorted_finite(data, label);
+    let n = sorted_data.len();
     if n < 4 {
         println!("  [OUTLIERS] {}: Amostra muito pequena (n={}), sem detecção de outliers", label, n);
-        return (vec![], vec![], data.to_vec());
+        let non_finite = (0..data.len()).filter(|&i| !data[i].is_finite()).collect();
+        let finite = data.iter().copied().filter(|value| value.is_finite()).collect();
+        return (vec![], non_finite, finite);
     }
     
-    // Ordena os dados para calcular quartis
-    let mut sorted_data = data.to_vec();
-    sorted_data.sort_by(|a, b| a.partial_cmp(b).unwrap());
-    
     // Calcula quartis
-    let q1_idx = (n as f64 * 0.25) as usize;
-    let q3_idx = (n as f64 * 0.75) as usize;
-    let q1 = sorted_data[q1_idx];
-    let q3 = sorted_data[q3_idx];
+    let q1 = sorted_quantile(&sorted_data, 0.25);
+    let q3 = sorted_quantile(&sorted_data, 0.75);
     let iqr = q3 - q1;
     
     // Limites para outliers
@@ -363,7 +594,7 @@ fn detect_outliers(data: &[f64], label: &str) -> (Vec<usize>, Vec<usize>, Vec<f6
     // Itera sobre os dados e classifica os valores
     for (i, &value) in data.iter().enumerate() {
         // Verifica se o valor é um outlier moderado ou extremo
-        if value < extreme_lower || value > extreme_upper {
+        if !value.is_finite() || value < extreme_lower || value > extreme_upper {
             // Adiciona a lista de outliers extremos
             extreme_outliers.push(i);
         } else if value < lower_bound || value > upper_bound {
@@ -411,13 +642,17 @@ fn detect_outliers(data: &[f64], label: &str) -> (Vec<usize>, Vec<usize>, Vec<f6
 /// - data: slice de valores f64 para análise
 /// - label: nome da métrica para logging detalhado
 ///
+/// Valores não finitos (NaN/Inf) são ignorados com aviso.
+///
 /// Retorna:
-/// - bool: true se os dados seguem distribuição normal
-fn check_normality(data: &[f64], label: &str) -> bool {
+/// - Tupla contendo: (true se os dados seguem distribuição normal, assimetria, curtose em excesso);
+///   assimetria e curtose são NaN quando não podem ser calculadas (n < 3 ou variância zero)
+fn check_normality(data: &[f64], label: &str) -> (bool, f64, f64) {
+    let data = &*finite_values(data, label);
     let n = data.len();
     if n < 3 {
         println!("  [NORMALIDADE] {}: Amostra muito pequena (n={}), assumindo normalidade", label, n);
-        return true;
+        return (true, f64::NAN, f64::NAN);
     }
     
     // Calcula estatísticas básicas
@@ -427,7 +662,7 @@ fn check_normality(data: &[f64], label: &str) -> bool {
     
     if std_dev == 0.0 {
         println!("  [NORMALIDADE] {}: Variância zero, assumindo normalidade", label);
-        return true;
+        return (true, f64::NAN, f64::NAN);
     }
     
     // Calcula assimetria (skewness) e curtose (kurtosis)
@@ -448,7 +683,7 @@ fn check_normality(data: &[f64], label: &str) -> bool {
     println!("  [NORMALIDADE] {}: Assimetria={:.3}, Curtose={:.3}, Normal={}", 
              label, skewness, kurtosis, is_normal);
     
-    is_normal
+    (is_normal, skewness, kurtosis)
 }
 
 /// Calcula estatísticas apropriadas baseadas na normalidade dos dados
@@ -463,6 +698,9 @@ fn check_normality(data: &[f64], label: &str) -> bool {
 /// - Outliers moderados: mantidos na análise (podem ser variação natural)
 /// - Outliers extremos: removidos da análise (provavelmente erros de medição)
 /// 
+/// Valores não finitos (NaN/Inf) são descartados com aviso antes do passo 1 e contados
+/// em `nonfinite_count`. Se nenhum valor finito sobrar, o resultado é `Stats::invalid`.
+///
 /// Seleção de estatísticas:
 /// - Dados normais: média, desvio padrão, IC95 via z-score
 /// - Dados não-normais: mediana, MAD, IC95 via percentis
@@ -470,10 +708,23 @@ fn check_normality(data: &[f64], label: &str) -> bool {
 /// Parâmetros:
 /// - data: slice de valores f64 para análise
 /// - label: nome da métrica para logging detalhado
+/// - central: estimador de tendência central para dados não normais
+/// - remove_extreme: remove os outliers extremos (falso com `--no-outlier-removal`;
+///   as contagens de outliers são reportadas de qualquer forma)
 ///
 /// Retorna:
 /// - Stats com estatísticas apropriadas e metadados da análise
-fn calculate_adaptive_stats(data: &[f64], label: &str) -> Stats {
+fn calculate_adaptive_stats(data: &[f64], label: &str, central: CentralEstimator, remove_extreme: bool) -> Stats {
+    // Passo 0: NaN/Inf de falhas de medição (ex: divisão por duração zero) saem antes de
+    // tudo, mesmo com --no-outlier-removal, e são contados para o CSV (nonfinite_count)
+    let finite = finite_values(data, label);
+    let nonfinite_count = data.len() - finite.len();
+    if finite.is_empty() && nonfinite_count > 0 {
+        println!("  [AVISO] {}: todos os {} valores são não finitos; estatísticas marcadas como inválidas",
+                 label, nonfinite_count);
+        return Stats::invalid(nonfinite_count);
+    }
+    let data = &*finite;
     let original_size = data.len();
     
     // Passo 1: Detecta outliers usando método IQR
@@ -481,468 +732,4164 @@ fn calculate_adaptive_stats(data: &[f64], label: &str) -> Stats {
     
     // Passo 2: Decide se usar dados limpos ou originais
     // Estratégia: remove apenas outliers EXTREMOS, mantém outliers moderados
+    // A remoção é descartada se deixar menos de MIN_SAMPLE_SIZE valores (IC sem sentido)
+    // Com --no-outlier-removal, nada é removido, mas as contagens continuam no CSV
+    let sample_floor_hit = remove_extreme && !extreme_outliers.is_empty() && cleaned_data.len() < MIN_SAMPLE_SIZE;
     let data_for_analysis = if extreme_outliers.is_empty() {
         data.to_vec()
+    } else if !remove_extreme {
+        println!("  [DECISÃO] {}: {} outliers extremos detectados e mantidos (--no-outlier-removal)",
+                 label, extreme_outliers.len());
+        data.to_vec()
+    } else if sample_floor_hit {
+        println!("  [AVISO] {}: Remoção de {} outliers extremos deixaria {} valores (mínimo {}); mantendo dados originais",
+                 label, extreme_outliers.len(), cleaned_data.len(), MIN_SAMPLE_SIZE);
+        data.to_vec()
     } else {
         println!("  [DECISÃO] {}: Removendo {} outliers extremos para análise", label, extreme_outliers.len());
         cleaned_data.clone()
     };
     
     // Passo 3: Verifica normalidade nos dados tratados
-    let is_normal = check_normality(&data_for_analysis, label);
+    let (is_normal, skewness, kurtosis) = check_normality(&data_for_analysis, label);
     
     // Log dos outliers detectados
     let total_outliers = outliers.len() + extreme_outliers.len();
     
     // Passo 4: Calcula estatísticas apropriadas baseadas na normalidade
-    if is_normal {
+    let mut stats = if is_normal {
         println!("  [ESTATÍSTICAS] {}: Usando estatísticas paramétricas (média, desvio padrão)", label);
         let mut stats = calculate_parametric_stats(&data_for_analysis, total_outliers, extreme_outliers.len(), original_size);
         stats.is_normal = true;
         stats
     } else {
-        println!("  [ESTATÍSTICAS] {}: Usando estatísticas robustas (mediana, MAD)", label);
-        calculate_robust_stats(&data_for_analysis, total_outliers, extreme_outliers.len(), original_size)
+        println!("  [ESTATÍSTICAS] {}: Usando estatísticas robustas ({}, MAD)", label, central.name());
+        calculate_robust_stats(&data_for_analysis, total_outliers, extreme_outliers.len(), original_size, central)
+    };
+
+    // Guarda os diagnósticos que motivaram a classificação, para auditoria no CSV
+    stats.skewness = skewness;
+    stats.kurtosis = kurtosis;
+    stats.sample_floor_hit = sample_floor_hit;
+    stats.nonfinite_count = nonfinite_count;
+    stats
+}
+
+/// `calculate_adaptive_stats` sobre amostras de tempo, convertidas para `unit` só aqui
+///
+/// As amostras continuam em nanossegundos inteiros fora da análise; as estatísticas
+/// resultantes ficam na unidade pedida.
+fn calculate_duration_stats(
+    samples: &DurationSamples,
+    unit: TimeUnit,
+    label: &str,
+    central: CentralEstimator,
+    remove_extreme: bool,
+) -> Stats {
+    calculate_adaptive_stats(&samples.to_unit(unit), label, central, remove_extreme)
+}
+
+/// Logaritmo natural da função gama (aproximação de Lanczos, g=7, n=9)
+///
+/// Precisão de ~15 dígitos para x > 0, suficiente para as distribuições
+/// t de Student usadas nos testes de hipótese.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // Fórmula de reflexão para valores pequenos
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
     }
+
+    let x = x - 1.0;
+    let mut acc = COEFS[0];
+    for (i, &c) in COEFS.iter().enumerate().skip(1) {
+        acc += c / (x + i as f64);
+    }
+    let t = x + 7.5;
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + acc.ln()
 }
 
-/// Função principal do experimento com verificação de normalidade
-/// 
-/// Esta função executa o experimento completo de desempenho criptográfico,
-/// incluindo detecção de outliers, verificação de normalidade e aplicação
-/// de estatísticas apropriadas para cada tipo de distribuição.
-/// 
-/// Retorna o nome do arquivo CSV com os resultados do experimento.
-fn run_normality_aware_experiment() -> String {
-    println!("=== EXPERIMENTO COM VERIFICAÇÃO DE NORMALIDADE ===");
-    
-    // Gera timestamp único para identificar o experimento
-    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S");
-    let pasta_resultados = "../results";
-    let filename = format!("{}/resultados_normality_check_{}.csv", pasta_resultados, timestamp);
+/// Função beta incompleta regularizada I_x(a, b)
+///
+/// Avaliada pela fração contínua de Lentz (Numerical Recipes, seção 6.4),
+/// usando a simetria I_x(a, b) = 1 - I_{1-x}(b, a) para garantir convergência.
+fn regularized_incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
 
-    // Garante que a pasta de resultados existe
-    if !Path::new(pasta_resultados).exists() {
-        fs::create_dir_all(pasta_resultados).expect("Não foi possível criar a pasta de resultados");
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    if x > (a + 1.0) / (a + b + 2.0) {
+        return 1.0 - regularized_incomplete_beta(1.0 - x, b, a);
     }
 
-    // Abre arquivo CSV para escrita dos resultados
-    let mut writer = OpenOptions::new()
-        .create(true)
-        .write(true)
-        .truncate(true)
-        .open(&filename)
-        .expect("Não foi possível criar o arquivo de resultados");
-
-    // Escreve cabeçalho do CSV com todas as métricas e informações estatísticas
-    writeln!(
-        writer,
-        "cenario,padrao_trafego,acordo,cifra,num_msgs,msgs_por_rotacao,rotacoes,kem_ms_mean,kem_ms_std,kem_ms_ci95,cipher_ms_mean,cipher_ms_std,cipher_ms_ci95,kem_bw_mean,kem_bw_std,kem_bw_ci95,msg_bw_mean,msg_bw_std,msg_bw_ci95,text_msgs,image_msgs,file_msgs,system_msgs,kem_normal,cipher_normal,kem_bw_normal,msg_bw_normal,kem_stat_type,cipher_stat_type,kem_bw_stat_type,msg_bw_stat_type,kem_outliers,cipher_outliers,kem_bw_outliers,msg_bw_outliers,kem_extreme_outliers,cipher_extreme_outliers,kem_bw_extreme_outliers,msg_bw_extreme_outliers,kem_sample_size,cipher_sample_size,kem_bw_sample_size,msg_bw_sample_size"
-    ).unwrap();
+    // Fração contínua (algoritmo de Lentz modificado)
+    const TINY: f64 = 1e-300;
+    const EPS: f64 = 1e-14;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
 
-    // Define configurações experimentais
-    let cenarios = vec![
-        UsageScenario::SmallChat,
-        UsageScenario::MediumGroup,
-        UsageScenario::LargeChannel,
-        UsageScenario::SystemChannel,
-    ];
-    
-    let padroes_trafego = vec![
-        TrafficPattern::Constant,
-        TrafficPattern::Burst,
-        TrafficPattern::Periodic,
-        TrafficPattern::Random,
-        TrafficPattern::Realistic,
-    ];
-    
-    let acordos = vec!["Olm-Clássico", "Olm-Híbrido"];
-    let cifragens = vec!["AES-GCM", "ChaCha20", "Megolm-Like"];
+    for m in 1..=300 {
+        let m = m as f64;
+        // Termo par
+        let numerator = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 + numerator * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + numerator / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        h *= d * c;
 
-    let total_configs = cenarios.len() * padroes_trafego.len() * acordos.len() * cifragens.len();
-    let mut config_count = 0;
+        // Termo ímpar
+        let numerator = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 + numerator * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        c = 1.0 + numerator / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
 
-    // Loop principal: executa todas as combinações experimentais
-    // Itera sobre cenários, padrões de tráfego, acordos e cifragens
-    // total_configs = 4 cenários * 5 padrões de tráfego * 2 acordos * 3 cifragens = 120 combinações
-    for cenario in cenarios.iter() {
-        for padrao in padroes_trafego.iter() {
-            for acordo in acordos.iter() {
-                for cipher_name in cifragens.iter() {
-                    config_count += 1;
-                    println!("\n{}/{}. Configuração: {:?} + {:?} + {} + {}", 
-                             config_count, total_configs, cenario, padrao, acordo, cipher_name);
-                    
-                    // Obtém parâmetros específicos do cenário
-                    // Define número de mensagens por rotação e total de mensagens
-                    // Baseado na configuração do cenário
-                    // Exemplo: SmallChat pode ter 10 mensagens por rotação, 100 no total
-                    // MediumGroup pode ter 20 mensagens por rotação, 200 no total
-                    // LargeChannel pode ter 50 mensagens por rotação, 500 no total
-                    // SystemChannel pode ter 100 mensagens por rotação, 1000 no total
-                    // Estes valores são configuráveis e podem ser ajustados conforme necessário
-                    let msgs_por_rotacao = get_rotation_config(cenario); 
-                    let num_messages = get_message_count_config(cenario);
-
-                    // Inicializa vetores para coleta de métricas
-                    let mut kem_times = Vec::with_capacity(REPETICOES);
-                    let mut cipher_times = Vec::with_capacity(REPETICOES);
-                    let mut kem_bws = Vec::with_capacity(REPETICOES);
-                    let mut msg_bws = Vec::with_capacity(REPETICOES);
-                    let mut total_rotations_per_run = 0;
-                    let mut text_count = 0; 
-                    let mut image_count = 0;
-                    let mut file_count = 0;
-                    let mut system_count = 0;
-
-                    // Executa as repetições do experimento para esta configuração
-                    for rep in 0..REPETICOES {
-                        if rep % 10 == 0 {
-                            println!("  Repetição {}/{}", rep + 1, REPETICOES);
-                        }
-                        
-                        // Inicializa geradores de mensagens e tráfego
-                        let mut message_gen = MessageGenerator::new(cenario.clone());
-                        let mut traffic_gen = TrafficGenerator::new(padrao.clone());
-
-                        // Gera chaves criptográficas baseadas no tipo de acordo
-                        // Olm-Clássico usa apenas X25519, Olm-Híbrido usa Kyber768 + X25519
-                        // Chaves são geradas aleatoriamente usando o gerador de números aleatórios do sistema
-                        // Garante que as chaves sejam únicas e seguras para cada execução
-                            
-                        // Gera chaves Kyber para Bob, se necessário
-                        // Olm-Híbrido usa Kyber768, então gera chaves públicas e secret
-                        let (bob_pk_kyber, bob_sk_kyber) = if *acordo == "Olm-Híbrido" {
-                            let (pk, sk) = keypair();
-                            (Some(pk), Some(sk))
-                        } 
-                        // Olm-Clássico não usa Kyber, então chaves são None
-                        else {
-                            (None, None)
-                        };
-                        
-                        // Gera chaves X25519 para Bob
-                        let bob_x25519_secret = StaticSecret::random_from_rng(&mut rand::thread_rng());
-                        let bob_x25519_public = X255PublicKey::from(&bob_x25519_secret);
-
-                        // Inicializa estado do experimento
-                        let mut current_key: [u8; 32] = [0u8; 32];
-                        let mut last_rotation = Instant::now();
-                        let mut total_kem_time = Duration::ZERO;
-                        let mut total_kem_bandwidth = 0;
-                        let mut total_msg_bandwidth = 0;
-                        let mut total_rotations = 0;
-                        let mut messages_processed = 0;
-
-                        // Início do tempo de cifragem
-                        let start_enc = Instant::now();  
-                        
-                        // Loop principal de processamento de mensagens
-                        while messages_processed < num_messages {
-                            let current_time = Instant::now();
-                            
-                            // Verifica se deve enviar mensagem baseado no padrão de tráfego
-                            if traffic_gen.should_send_message(current_time) {
-                                let time_since_last_rotation = current_time.duration_since(last_rotation);
-                                
-                                // Executa rotação de chave quando necessário
-                                // Rotação ocorre se:
-                                // - Número de mensagens processadas é múltiplo de msgs_por_rotacao
-                                // - Ou se passaram 7 dias desde a última rotação
-                                // Isso garante que as chaves sejam rotacionadas periodicamente
-                                // e também após um número fixo de mensagens, dependendo do padrão de tráfego
-                                if messages_processed % msgs_por_rotacao == 0 || 
-                                    time_since_last_rotation >= Duration::from_secs(7 * 86400) {
-                                    let start_kem = Instant::now();
-                                    
-                                    // Seleciona algoritmo de acordo de chaves
-                                    let (shared_secret, kem_bandwidth) = if *acordo == "Olm-Clássico" {
-                                        // Olm-Clássico: apenas X25519 ECDH
-                                        let alice_secret = StaticSecret::random_from_rng(&mut rand::thread_rng());
-                                        let shared_secret = alice_secret.diffie_hellman(&bob_x25519_public);
-                                        let bandwidth = bob_x25519_public.as_bytes().len();
-                                        (shared_secret.as_bytes().to_vec(), bandwidth)
-                                    } else {
-                                        // Olm-Híbrido: X25519 + Kyber768
-                                        let alice_secret = StaticSecret::random_from_rng(&mut rand::thread_rng());
-                                        let x25519_shared = alice_secret.diffie_hellman(&bob_x25519_public);
-                                        
-                                        let (kyber_shared, kyber_ct) = encapsulate(&bob_pk_kyber.as_ref().unwrap());
-                                        let _kyber_decap = decapsulate(&kyber_ct, &bob_sk_kyber.as_ref().unwrap());
-                                        
-                                        let mut combined_secret = Vec::with_capacity(64);
-                                        combined_secret.extend_from_slice(x25519_shared.as_bytes());
-                                        combined_secret.extend_from_slice(kyber_shared.as_bytes());
-                                        
-                                        let bandwidth = bob_x25519_public.as_bytes().len() + 
-                                                       kyber_ct.as_bytes().len() + 
-                                                       bob_pk_kyber.as_ref().unwrap().as_bytes().len();
-                                        (combined_secret, bandwidth)
-                                    };
-                                    
-                                    // Atualiza chave e métricas
-                                    current_key.copy_from_slice(&shared_secret[..32]);
-                                    let elapsed_kem = start_kem.elapsed();
-                                    total_kem_time += elapsed_kem;          // Tempo gasto na KEM
-                                    total_rotations += 1;                   // Incrementa contador de rotações
-                                    total_kem_bandwidth += kem_bandwidth;   // Atualiza largura de banda KEM
-                                    last_rotation = current_time;           // Atualiza tempo da última rotação
-                                }
-                                
-                                // Gera mensagem e executa cifragem
-                                let message = message_gen.generate_message();
-                                // Conta tipos de mensagens para estatísticas
-                                match &message {
-                                    MessageType::Text(_) => text_count += 1,
-                                    MessageType::Image(_) => image_count += 1,
-                                    MessageType::File(_) => file_count += 1,
-                                    MessageType::System(_) => system_count += 1,
-                                    MessageType::Voice(_) => text_count += 1,
-                                }
-                                
-                                let plaintext = message_gen.get_message_bytes(&message);
-                                // Baseado no nome da cifra, escolhe o algoritmo apropriado
-                                // AES-GCM, ChaCha20 ou Megolm-Like (AES-CTR)
-                                // Cada algoritmo é configurado com nonce/IV aleatório
-                                // e a chave atual gerada pelo KEM
-                                let (ciphertext, nonce_len, _): (Vec<u8>, usize, Vec<u8>) = match *cipher_name {
-                                    "AES-GCM" => {
-                                        let mut nonce = [0u8; 12];
-                                        rand::thread_rng().fill_bytes(&mut nonce);
-                                        let key = Key::<Aes256Gcm>::from_slice(&current_key);
-                                        let cipher = Aes256Gcm::new(key);
-                                        let ciphertext = cipher.encrypt(
-                                            Nonce::from_slice(&nonce),
-                                            aes_gcm::aead::Payload { msg: &plaintext, aad: b"" }
-                                        ).expect("Erro na criptografia AES-GCM");
-                                        (ciphertext, nonce.len(), nonce.to_vec())
-                                    }
-                                    "ChaCha20" => {
-                                        let mut nonce = [0u8; 12];
-                                        rand::thread_rng().fill_bytes(&mut nonce);
-                                        let key = ChaKey::from_slice(&current_key);
-                                        let cipher = ChaCha20Poly1305::new(key);
-                                        let ciphertext = cipher.encrypt(
-                                            ChaNonce::from_slice(&nonce),
-                                            chacha20poly1305::aead::Payload { msg: &plaintext, aad: b"" }
-                                        ).expect("Erro na criptografia ChaCha20");
-                                        (ciphertext, nonce.len(), nonce.to_vec())
-                                    }
-                                    _ => {
-                                        // Megolm-Like: AES-CTR
-                                        let mut iv = [0u8; 16];
-                                        rand::thread_rng().fill_bytes(&mut iv);
-                                        let mut cipher = ctr::Ctr64BE::<Aes256>::new(&current_key.into(), &iv.into());
-                                        let mut buffer = plaintext.clone();
-                                        cipher.apply_keystream(&mut buffer);
-                                        (buffer, iv.len(), iv.to_vec())
-                                    }
-                                };
-                                
-                                // Atualiza métricas de largura de banda
-                                total_msg_bandwidth += ciphertext.len() + nonce_len;
-                                messages_processed += 1;
-                            }
-                            
-                            // Pequena pausa para simular processamento realista
-                            //std::thread::sleep(Duration::from_millis(10));
-                        }
-                        
-                        let total_enc_time = start_enc.elapsed();
-                        
-                        // Armazena resultados desta repetição
-                        // Coleta tempos de KEM e cifragem, largura de banda e contadores de mensagens
-                        kem_times.push(total_kem_time.as_secs_f64() * 1000.0);      // Tempo KEM em milissegundos
-                        cipher_times.push(total_enc_time.as_secs_f64() * 1000.0);   // Tempo de cifragem em milissegundos
-                        kem_bws.push(total_kem_bandwidth as f64);                   // Largura de banda KEM em bytes
-                        msg_bws.push(total_msg_bandwidth as f64);                   // Largura de banda de mensagens em bytes
-                        total_rotations_per_run = total_rotations;                  // Total de rotações nesta sessão
-                    }
-                    
-                    // Executa análise estatística adaptativa nos dados coletados
-                    println!("  Analisando normalidade e calculando estatísticas...");
-                    let kem_time_stats = calculate_adaptive_stats(&kem_times, "KEM Times");
-                    let cipher_time_stats = calculate_adaptive_stats(&cipher_times, "Cipher Times");
-                    let kem_bw_stats = calculate_adaptive_stats(&kem_bws, "KEM Bandwidth");
-                    let msg_bw_stats = calculate_adaptive_stats(&msg_bws, "Message Bandwidth");
-                    
-                    // Calcula médias dos contadores de tipos de mensagens
-                    let total_repetitions = REPETICOES as f64;
-                    let avg_text = text_count as f64 / total_repetitions;
-                    let avg_image = image_count as f64 / total_repetitions;
-                    let avg_file = file_count as f64 / total_repetitions;
-                    let avg_system = system_count as f64 / total_repetitions;
-                    
-                    // Determina o tipo de estatística aplicado para cada métrica
-                    let kem_stat_type = if kem_time_stats.is_normal { "parametric" } else { "robust" };
-                    let cipher_stat_type = if cipher_time_stats.is_normal { "parametric" } else { "robust" };
-                    let kem_bw_stat_type = if kem_bw_stats.is_normal { "parametric" } else { "robust" };
-                    let msg_bw_stat_type = if msg_bw_stats.is_normal { "parametric" } else { "robust" };
-                    
-                    // Grava linha de resultados no arquivo CSV
-                    writeln!(
-                        writer,
-                        "{:?},{:?},{},{},{},{},{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{:.2},{:.2},{:.2},{:.2},{:.2},{:.2},{:.1},{:.1},{:.1},{:.1},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
-                        cenario, padrao, acordo, cipher_name, num_messages, msgs_por_rotacao,
-                        total_rotations_per_run,
-                        kem_time_stats.mean, kem_time_stats.std_dev, kem_time_stats.ci95,
-                        cipher_time_stats.mean, cipher_time_stats.std_dev, cipher_time_stats.ci95,
-                        kem_bw_stats.mean, kem_bw_stats.std_dev, kem_bw_stats.ci95,
-                        msg_bw_stats.mean, msg_bw_stats.std_dev, msg_bw_stats.ci95,
-                        avg_text, avg_image, avg_file, avg_system,
-                        kem_time_stats.is_normal, cipher_time_stats.is_normal, 
-                        kem_bw_stats.is_normal, msg_bw_stats.is_normal,
-                        kem_stat_type, cipher_stat_type, kem_bw_stat_type, msg_bw_stat_type,
-                        kem_time_stats.outliers_count, cipher_time_stats.outliers_count,
-                        kem_bw_stats.outliers_count, msg_bw_stats.outliers_count,
-                        kem_time_stats.extreme_outliers_count, cipher_time_stats.extreme_outliers_count,
-                        kem_bw_stats.extreme_outliers_count, msg_bw_stats.extreme_outliers_count,
-                        kem_time_stats.sample_size, cipher_time_stats.sample_size,
-                        kem_bw_stats.sample_size, msg_bw_stats.sample_size
-                    ).unwrap();
-                }
-            }
+        if (delta - 1.0).abs() < EPS {
+            break;
         }
     }
-    
-    // Finaliza experimento e exibe resumo
-    println!("\n=== EXPERIMENTO COM ANÁLISE DE OUTLIERS E NORMALIDADE CONCLUÍDO ===");
-    println!("Resultados salvos em: {}", filename);
-    println!("Arquivo inclui informações sobre:");
-    println!("  - Detecção de outliers (moderados e extremos)");
-    println!("  - Verificação de normalidade");
-    println!("  - Tipo de estatística aplicada");
-    println!("  - Tamanho das amostras após limpeza");
-    println!("\nSequência de análise aplicada:");
-    println!("  1. Detecção de outliers (método IQR)");
-    println!("  2. Remoção de outliers extremos (opcional)");
-    println!("  3. Verificação de normalidade");
-    println!("  4. Aplicação de estatísticas apropriadas");
-    
-    filename
+
+    ln_front.exp() * h / a
 }
 
-/// Função para executar o script de geração de gráficos
-/// 
-/// Esta função executa o script Python responsável por gerar gráficos
-/// dos resultados experimentais, incluindo análise de normalidade e outliers.
-/// Tenta usar o ambiente virtual primeiro, com fallback para execução direta.
-fn generate_plots() {
-    println!("\nGerando gráficos dos resultados...");
-    
-    let venv_path = "../venv";
-    let venv_python = format!("{}/bin/python", venv_path);
-    let plot_script = "../analysis/gerar_graficos.py";
-    
-    // Verifica se o script de geração de gráficos existe
-    if !Path::new(plot_script).exists() {
-        println!("ERRO: Script de gráficos não encontrado: {}", plot_script);
-        return;
+/// Função de distribuição acumulada da t de Student com `df` graus de liberdade
+fn student_t_cdf(t: f64, df: f64) -> f64 {
+    let x = df / (df + t * t);
+    let tail = 0.5 * regularized_incomplete_beta(x, df / 2.0, 0.5);
+    if t >= 0.0 { 1.0 - tail } else { tail }
+}
+
+/// Teste de equivalência TOST (Two One-Sided Tests) de Schuirmann
+///
+/// Verifica se a diferença entre as médias de `a` e `b` está contida no intervalo
+/// de equivalência (-margin, +margin), realizando dois testes t unilaterais de Welch:
+/// - H0₁: μa - μb <= -margin  (rejeitada quando a diferença é "grande o suficiente")
+/// - H0₂: μa - μb >= +margin  (rejeitada quando a diferença é "pequena o suficiente")
+///
+/// A equivalência é declarada quando ambas as hipóteses nulas são rejeitadas ao
+/// nível de 5%, isto é, quando o maior dos dois p-valores é menor que 0.05.
+/// A versão de Welch não assume variâncias iguais entre as amostras.
+///
+/// Parâmetros:
+/// - a, b: amostras a comparar (ex: tempos de cifragem em ms)
+/// - margin: margem de equivalência, na mesma unidade das amostras
+///
+/// Retorna:
+/// - Tupla contendo: (maior p-valor dos dois testes, veredito de equivalência)
+fn tost(a: &[f64], b: &[f64], margin: f64) -> (f64, bool) {
+    let Some((diff, se, df)) = welch_moments(a, b) else {
+        return (1.0, false);
+    };
+
+    // Sem variabilidade: o veredito depende apenas da diferença observada
+    if se == 0.0 {
+        return if diff.abs() < margin { (0.0, true) } else { (1.0, false) };
     }
-    
-    // Tenta usar o ambiente virtual primeiro
-    if Path::new(&venv_python).exists() {
-        println!("  Usando ambiente virtual Python...");
-        
-        // Instala dependências necessárias para geração de gráficos
-        let venv_pip = format!("{}/bin/pip", venv_path);
-        let install_plot_deps = Command::new(&venv_pip)
-            .arg("install")
-            .arg("--quiet")
-            .arg("matplotlib")
-            .arg("seaborn")
-            .arg("pandas")
-            .arg("numpy")
-            .output();
-        
-        match install_plot_deps {
-            Ok(output) => {
-                if !output.status.success() {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    println!("  AVISO: Problemas na instalação de dependências de gráficos: {}", stderr);
-                }
-            }
-            Err(e) => {
-                println!("  AVISO: Erro ao instalar dependências de gráficos: {}", e);
-            }
-        }
-        
-        // Executa script de gráficos com ambiente virtual
-        let result = Command::new(&venv_python)
-            .arg(plot_script)
-            .current_dir("../analysis")
-            .output();
-        
-        match result {
-            Ok(output) => {
-                if output.status.success() {
-                    println!("  SUCESSO: Gráficos gerados com sucesso!");
-                    println!("  Arquivos salvos em: ../plots/");
-                    
-                    let stdout = String::from_utf8_lossy(&output.stdout);
-                    if !stdout.is_empty() {
-                        println!("  Saída do script:");
-                        for line in stdout.lines() {
-                            println!("    {}", line);
-                        }
-                    }
-                    return;
-                } else {
-                    println!("  AVISO: Erro ao gerar gráficos com venv:");
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    println!("    {}", stderr);
-                }
-            }
-            Err(e) => {
-                println!("  AVISO: Erro ao executar script com venv: {}", e);
-            }
-        }
+
+    let t_lower = (diff + margin) / se;
+    let t_upper = (diff - margin) / se;
+    let p_lower = 1.0 - student_t_cdf(t_lower, df);
+    let p_upper = student_t_cdf(t_upper, df);
+
+    let p_value = p_lower.max(p_upper);
+    (p_value, p_value < 0.05)
+}
+
+/// Diferença entre as médias de `a` e `b`, seu erro padrão de Welch e os graus de
+/// liberdade de Welch-Satterthwaite, base do `tost` e do `welch_t_test`
+///
+/// Retorna `None` com menos de duas amostras em um dos grupos. Sem variabilidade, o erro
+/// padrão é 0 e os graus de liberdade, NaN; cada teste decide o que fazer nesse caso.
+fn welch_moments(a: &[f64], b: &[f64]) -> Option<(f64, f64, f64)> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
     }
-    
-    // Fallback: tenta executar sem ambiente virtual
-    println!("  Tentando executar sem ambiente virtual...");
-    let fallback_result = Command::new("python3")
-        .arg(plot_script)
-        .current_dir("../analysis")
-        .output();
-    
-    match fallback_result {
-        Ok(fallback_output) => {
-            if fallback_output.status.success() {
-                println!("  SUCESSO: Gráficos gerados com sucesso (fallback)!");
-                println!("  Arquivos salvos em: ../plots/");
-                
-                let stdout = String::from_utf8_lossy(&fallback_output.stdout);
-                if !stdout.is_empty() {
-                    println!("  Saída do script:");
-                    for line in stdout.lines() {
-                        println!("    {}", line);
-                    }
-                }
-            } else {
-                println!("  ERRO: Falha no fallback:");
-                let fallback_stderr = String::from_utf8_lossy(&fallback_output.stderr);
-                println!("    {}", fallback_stderr);
-                println!("  INFO: Verifique se as dependências Python estão instaladas:");
-                println!("    pip install matplotlib seaborn pandas numpy");
-            }
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let (mean_a, mean_b) = (average(a), average(b));
+    let se_a = a.iter().map(|x| (x - mean_a).powi(2)).sum::<f64>() / (na - 1.0) / na;
+    let se_b = b.iter().map(|x| (x - mean_b).powi(2)).sum::<f64>() / (nb - 1.0) / nb;
+    let df = (se_a + se_b).powi(2) / (se_a.powi(2) / (na - 1.0) + se_b.powi(2) / (nb - 1.0));
+    Some((mean_a - mean_b, (se_a + se_b).sqrt(), df))
+}
+
+/// Teste de Kolmogorov-Smirnov para duas amostras
+///
+/// Compara as funções de distribuição empíricas de `a` e `b`: a estatística D é a
+/// maior distância vertical entre elas. Por não supor nenhuma distribuição, detecta
+/// diferenças de forma (dispersão, assimetria, multimodalidade), e não só de posição.
+/// O p-valor usa a distribuição assintótica de Kolmogorov com a correção de
+/// Stephens para amostras finitas, `λ = (√nₑ + 0.12 + 0.11/√nₑ)·D`.
+///
+/// Retorna:
+/// - Tupla contendo: (estatística D, p-valor assintótico); (NaN, NaN) se uma amostra estiver vazia
+fn ks_two_sample(a: &[f64], b: &[f64]) -> (f64, f64) {
+    if a.is_empty() || b.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort_by(|x, y| x.total_cmp(y));
+    b.sort_by(|x, y| x.total_cmp(y));
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+
+    // Percorre os valores distintos em ordem; empates avançam as duas amostras juntas
+    let (mut i, mut j, mut d) = (0, 0, 0.0f64);
+    while i < a.len() && j < b.len() {
+        let value = a[i].min(b[j]);
+        while i < a.len() && a[i] <= value {
+            i += 1;
         }
-        Err(e) => {
-            println!("  ERRO: Erro ao executar fallback: {}", e);
+        while j < b.len() && b[j] <= value {
+            j += 1;
         }
+        d = d.max((i as f64 / na - j as f64 / nb).abs());
     }
+
+    let en = (na * nb / (na + nb)).sqrt();
+    (d, kolmogorov_q((en + 0.12 + 0.11 / en) * d))
 }
 
-/// Função main
-/// 
+/// Função de distribuição acumulada da normal padrão
+///
+/// Usa a aproximação de erf de Abramowitz & Stegun (7.1.26), com erro absoluto
+/// abaixo de 1.5e-7, suficiente para p-valores.
+fn normal_cdf(z: f64) -> f64 {
+    let x = z.abs() / std::f64::consts::SQRT_2;
+    let t = 1.0 / (1.0 + 0.327_591_1 * x);
+    let poly = t * (0.254_829_592 + t * (-0.284_496_736 + t * (1.421_413_741 + t * (-1.453_152_027 + t * 1.061_405_429))));
+    let erf = 1.0 - poly * (-x * x).exp();
+    if z >= 0.0 { 0.5 * (1.0 + erf) } else { 0.5 * (1.0 - erf) }
+}
+
+/// Teste t de Welch bilateral para a diferença entre as médias de `a` e `b`
+///
+/// Retorna:
+/// - Tupla contendo: (estatística t, graus de liberdade de Welch-Satterthwaite, p-valor);
+///   NaN com menos de duas amostras em um dos grupos ou sem variabilidade
+fn welch_t_test(a: &[f64], b: &[f64]) -> (f64, f64, f64) {
+    match welch_moments(a, b) {
+        Some((diff, se, df)) if se > 0.0 => {
+            let t = diff / se;
+            (t, df, 2.0 * (1.0 - student_t_cdf(t.abs(), df)))
+        }
+        _ => (f64::NAN, f64::NAN, f64::NAN),
+    }
+}
+
+/// Teste U de Mann-Whitney bilateral (aproximação normal)
+///
+/// U é o de `a`: o número de pares (x de `a`, y de `b`) com x > y, empates contando
+/// meio. A variância tem correção de empates e o z, correção de continuidade, como
+/// no `mannwhitneyu` assintótico do SciPy.
+///
+/// Retorna:
+/// - Tupla contendo: (U de `a`, p-valor); NaN se uma amostra estiver vazia
+fn mann_whitney_u(a: &[f64], b: &[f64]) -> (f64, f64) {
+    if a.is_empty() || b.is_empty() {
+        return (f64::NAN, f64::NAN);
+    }
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let combined: Vec<f64> = a.iter().chain(b).copied().collect();
+    let ranked = ranks(&combined);
+    let u = ranked[..a.len()].iter().sum::<f64>() - na * (na + 1.0) / 2.0;
+
+    // Correção de empates: Σ(t³ - t) sobre os grupos de valores empatados
+    let mut sorted = combined;
+    sorted.sort_by(f64::total_cmp);
+    let ties: f64 = sorted
+        .chunk_by(|x, y| x == y)
+        .map(|group| (group.len() as f64).powi(3) - group.len() as f64)
+        .sum();
+    let n = na + nb;
+    let variance = na * nb / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return (u, 1.0);
+    }
+    let z = ((u - na * nb / 2.0).abs() - 0.5).max(0.0) / variance.sqrt();
+    (u, (2.0 * (1.0 - normal_cdf(z))).min(1.0))
+}
+
+/// d de Cohen: diferença entre as médias sobre o desvio padrão combinado
+///
+/// Retorna NaN com menos de duas amostras em um dos grupos ou sem variabilidade.
+fn cohens_d(a: &[f64], b: &[f64]) -> f64 {
+    if a.len() < 2 || b.len() < 2 {
+        return f64::NAN;
+    }
+    let (na, nb) = (a.len() as f64, b.len() as f64);
+    let (mean_a, mean_b) = (average(a), average(b));
+    let ss_a = a.iter().map(|x| (x - mean_a).powi(2)).sum::<f64>();
+    let ss_b = b.iter().map(|x| (x - mean_b).powi(2)).sum::<f64>();
+    let pooled = ((ss_a + ss_b) / (na + nb - 2.0)).sqrt();
+    if pooled == 0.0 { f64::NAN } else { (mean_a - mean_b) / pooled }
+}
+
+/// Função de sobrevivência da distribuição de Kolmogorov: Q(λ) = 2 Σ (-1)^(j-1) e^(-2j²λ²)
+///
+/// Para λ pequeno a série converge mal, mas Q(λ) é praticamente 1 nessa região.
+fn kolmogorov_q(lambda: f64) -> f64 {
+    if lambda < 0.2 {
+        return 1.0;
+    }
+    let mut sum = 0.0;
+    let mut sign = 1.0;
+    for j in 1..=100 {
+        let term = sign * (-2.0 * (j as f64 * lambda).powi(2)).exp();
+        sum += term;
+        if term.abs() < 1e-12 {
+            break;
+        }
+        sign = -sign;
+    }
+    (2.0 * sum).clamp(0.0, 1.0)
+}
+
+/// Coeficiente de correlação linear de Pearson
+///
+/// Retorna NaN quando há menos de dois pares ou quando uma das variáveis é constante.
+fn pearson(x: &[f64], y: &[f64]) -> f64 {
+    let n = x.len().min(y.len());
+    if n < 2 {
+        return f64::NAN;
+    }
+    let mean_x = x[..n].iter().sum::<f64>() / n as f64;
+    let mean_y = y[..n].iter().sum::<f64>() / n as f64;
+
+    let mut cov = 0.0;
+    let mut var_x = 0.0;
+    let mut var_y = 0.0;
+    for (xi, yi) in x[..n].iter().zip(&y[..n]) {
+        cov += (xi - mean_x) * (yi - mean_y);
+        var_x += (xi - mean_x).powi(2);
+        var_y += (yi - mean_y).powi(2);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return f64::NAN;
+    }
+    cov / (var_x * var_y).sqrt()
+}
+
+/// Postos (ranks) de uma amostra, com empates recebendo o posto médio
+fn ranks(data: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..data.len()).collect();
+    order.sort_by(|&a, &b| data[a].total_cmp(&data[b]));
+
+    let mut ranks = vec![0.0; data.len()];
+    let mut i = 0;
+    while i < order.len() {
+        // Agrupa valores empatados e atribui a todos a média de suas posições
+        let mut j = i;
+        while j + 1 < order.len() && data[order[j + 1]] == data[order[i]] {
+            j += 1;
+        }
+        let average_rank = (i + j) as f64 / 2.0 + 1.0;
+        for &idx in &order[i..=j] {
+            ranks[idx] = average_rank;
+        }
+        i = j + 1;
+    }
+    ranks
+}
+
+/// Coeficiente de correlação de postos de Spearman (Pearson sobre os postos)
+///
+/// Mede associação monotônica, sem supor linearidade; empates são tratados com postos médios.
+fn spearman(x: &[f64], y: &[f64]) -> f64 {
+    let n = x.len().min(y.len());
+    pearson(&ranks(&x[..n]), &ranks(&y[..n]))
+}
+
+/// Uma combinação experimental (célula da matriz fatorial)
+///
+/// O `index` é atribuído na ordem canônica dos laços aninhados
+/// (cenário → padrão de tráfego → acordo → cifra) e determina a posição
+/// da linha no CSV, independentemente da ordem em que a configuração termina.
+#[derive(Debug, Clone)]
+struct ConfigSpec {
+    index: usize,
+    scenario: UsageScenario,
+    pattern: TrafficPattern,
+    agreement: Cow<'static, str>, // Emprestado para os acordos de AGREEMENTS, próprio para os de --hybrid
+    cipher: &'static str,
+    message_count: Option<usize>, // Total de mensagens imposto por --message-counts; None segue o cenário
+}
+
+/// Estatísticas por mensagem de um único tipo (`--per-type-stats`)
+///
+/// Cada distribuição reúne só mensagens do mesmo tipo, sem a mistura de textos de
+/// dezenas de bytes com imagens de centenas de KB que torna a distribuição por
+/// repetição multimodal.
+#[derive(Debug, Clone)]
+struct TypeStats {
+    cipher_us: Stats,   // Tempo de cifragem de cada mensagem, em µs
+    msg_bytes: Stats,   // Bytes enviados por mensagem (texto cifrado + nonce)
+}
+
+/// Prefixos das colunas por tipo de mensagem (tempo, bytes), na ordem de `MESSAGE_KINDS`
+const TYPE_METRICS: [(&str, &str); MESSAGE_KINDS.len()] = [
+    ("cipher_us_texto", "msg_bytes_texto"),
+    ("cipher_us_imagem", "msg_bytes_imagem"),
+    ("cipher_us_arquivo", "msg_bytes_arquivo"),
+    ("cipher_us_sistema", "msg_bytes_sistema"),
+    ("cipher_us_voz", "msg_bytes_voz"),
+];
+
+/// Resultado agregado de uma configuração após a análise estatística
+#[derive(Debug, Clone)]
+struct ConfigResult {
+    spec: ConfigSpec,
+    num_messages: usize,
+    msgs_per_rotation: usize,
+    rotations: usize,
+    rotation_strategy: RotationStrategy, // Estratégia de rotação ativa (--rotation-strategy)
+    ephemeral: EphemeralMode, // Ciclo de vida dos segredos X25519 de Alice e Bob (--ephemeral)
+    keygen_policy: KeygenPolicy, // Geração das chaves de Bob (--keygen-policy)
+    duplicate_payloads: Option<usize>, // Payloads de mídia repetidos na configuração (--detect-duplicates)
+    repetitions: usize, // Repetições executadas (variável com --target-moe)
+    config_wall_ms: f64, // Tempo de parede de todas as repetições da configuração
+    kem_bw_bps: f64,     // Taxa efetiva do acordo de chaves: bits / tempo de parede da repetição
+    msg_bw_bps: f64,     // Taxa efetiva das mensagens cifradas: bits / tempo de parede da repetição
+    kem_time_stats: Stats,
+    cipher_time_stats: Stats,
+    kem_bw_stats: Stats,
+    msg_bw_stats: Stats,
+    avg_text: f64,
+    avg_image: f64,
+    avg_file: f64,
+    avg_system: f64,
+    avg_voice: f64,
+    expansion_ratio: f64,
+    authenticated: bool,    // Se a cifra autentica o texto cifrado (AEAD ou CTR + HMAC)
+    cipher_only_time_stats: Stats, // Tempo apenas das chamadas de cifragem (sem geração de mensagens, RNG e tráfego)
+    // Tempos por repetição em ns inteiros, convertidos para ms só nas análises e na gravação
+    cipher_only_times: DurationSamples, // Apenas cifragem, usado nas comparações entre configurações (ex: TOST)
+    kem_times: DurationSamples,         // Acordo de chaves, usado na comparação entre acordos (KS)
+    cipher_times: DurationSamples,      // Tempo de parede do laço (remetente mais lento)
+    compress_times: DurationSamples,
+    commitment_times: DurationSamples,
+    repetition_starts_ns: Vec<u128>, // Início de cada repetição, em ns desde a época Unix (--drift-log)
+    primitive_versions: String, // Bibliotecas usadas pela configuração, no formato crate@versão
+    fixed_size: Option<usize>,  // Tamanho fixo das mensagens (--fixed-size), se ativo
+    payload_entropy: Option<f64>, // Entropia das mídias em bits por byte (--payload-entropy), se limitada
+    size_time_pairs: Vec<(f64, f64)>, // Pares (bytes, µs) por mensagem; vazio sem --size-time-correlation e --size-weighted
+    type_stats: [Option<TypeStats>; MESSAGE_KINDS.len()], // Por tipo de mensagem, na ordem de MESSAGE_KINDS; None sem --per-type-stats ou sem mensagens do tipo
+    devices_per_user: usize,    // Dispositivos de Bob, cada um com uma sessão (--devices-per-user)
+    group_size: usize,          // Membros no fan-out de cada rotação (2 sem --group-fanout)
+    kem_sender_ms: f64,         // Parte do tempo de KEM fora dos decapsulamentos (remetente), média por repetição
+    kem_receiver_ms: f64,       // Decapsulamentos de todos os receptores somados, média por repetição
+    decapsulations: usize,      // Decapsulamentos por encapsulamento (--decaps-per-encap)
+    compression: Compression,   // Compressão aplicada antes da cifragem (--compress)
+    envelope: Envelope,         // Envelope do texto claro (--envelope)
+    base64_media: bool,         // Mídia codificada em base64 antes da cifragem (--base64-media)
+    compress_time_stats: Stats, // Tempo de compressão por repetição, medido à parte da cifragem
+    compression_ratios: [f64; 5], // Bytes comprimidos / originais por tipo (ordem de MESSAGE_KINDS); NaN sem mensagens
+    senders: usize,             // Remetentes simultâneos (--senders)
+    aggregate_throughput: f64,  // Mensagens/s somando todos os remetentes (média das repetições)
+    per_sender_throughput: f64, // Mensagens/s de cada remetente (média entre remetentes e repetições)
+    message_latency_us: f64,    // Tempo médio de cifragem por mensagem, em µs
+    weighted_latency_us: Option<f64>, // Idem, ponderado pelo tamanho do texto claro (--size-weighted)
+    key_commitment: bool,       // Compromisso de chave SHA-256 anexado a cada mensagem (--key-commitment)
+    commitment_time_stats: Stats, // Tempo dos compromissos de chave por repetição
+    upload_bytes: f64,          // Bytes enviados pelo remetente (mensagens + texto cifrado Kyber), média das repetições
+    download_bytes: f64,        // Bytes recebidos pelo remetente (chaves públicas de Bob), média das repetições
+    kem_failures: f64,          // Textos cifrados Kyber corrompidos por repetição (--inject-kem-failures)
+    decap_ok_us: f64,           // Tempo médio de um decapsulamento bem-sucedido, em µs
+    decap_fail_us: f64,         // Tempo médio de um decapsulamento rejeitado, em µs
+    config_seed: u64,           // Semente da configuração (--seed XOR hash da tupla da configuração)
+    stalled_sessions: usize,    // Sessões encerradas pela guarda de consultas antes de num_msgs
+    storage_bytes: f64,         // Bytes persistidos pelo servidor (mensagens + texto cifrado KEM), média das repetições
+    bandwidth_series: Vec<BandwidthPoint>, // Série acumulada da primeira repetição; vazia sem --bandwidth-series
+    ciphertext_sample: Option<CiphertextSample>, // Primeira mensagem da primeira repetição (--dump-sample)
+    rekey_events: f64,          // Re-keys completos por saída de membro, média por repetição
+    rekey_ms: f64,              // Tempo de acordo de chaves desses re-keys, média por repetição
+    rekey_bw: f64,              // Bytes de acordo de chaves desses re-keys, média por repetição
+    rekey_ms_per_event: f64,    // Custo médio de um re-key completo, em ms (NaN sem eventos)
+    edit_events: f64,           // Edições de mensagens (--edit-rate), média por repetição
+    edit_ms: f64,               // Tempo de recifragem dessas edições, média por repetição
+    edit_bw: f64,               // Bytes cifrados dessas edições, média por repetição
+    confirm_ms: f64,            // Tempo das confirmações de chave (--key-confirmation), média por repetição
+    confirm_bw: f64,            // Bytes dessas confirmações, média por repetição
+    combine_ms: f64,            // Derivação das chaves de sessão pelo combinador, média por repetição
+    backup_ops: f64,            // Chaves de sessão enviadas ao backup (--key-backup), média por repetição
+    backup_ms: f64,             // Tempo dessas cifragens de backup, média por repetição
+    backup_bw: f64,             // Bytes enviados ao backup, média por repetição
+    tag_layout: TagLayout,      // Disposição da tag das cifras (--tag-layout)
+    relative_speed: f64,        // Velocidade frente à cifra de referência da célula; preenchida pelo OrderedResultWriter
+}
+
+impl ConfigResult {
+    /// Cabeçalho do CSV com todas as métricas e informações estatísticas
+    ///
+    /// Vem do registro `RESULT_COLUMNS`, que também gera a legenda de unidades dos
+    /// metadados; a primeira coluna é sempre `schema_version`, e qualquer mudança no
+    /// conjunto de colunas exige incrementar `SCHEMA_VERSION`.
+    fn csv_header() -> String {
+        result_header()
+    }
+
+    /// Métricas com distribuição por repetição, pelos prefixos das colunas do CSV largo
+    fn distributions(&self) -> [(&'static str, &Stats); 7] {
+        [
+            ("kem_ms", &self.kem_time_stats),
+            ("cipher_ms", &self.cipher_time_stats),
+            ("cipher_only_ms", &self.cipher_only_time_stats),
+            ("kem_bw_bytes", &self.kem_bw_stats),
+            ("msg_bw_bytes", &self.msg_bw_stats),
+            ("compress_ms", &self.compress_time_stats),
+            ("commitment_ms", &self.commitment_time_stats),
+        ]
+    }
+
+    /// Estatísticas de uma métrica de `NORMALITY_METRICS`, pelo prefixo da coluna `*_normal`
+    fn normality_stats(&self, metric: &str) -> Option<&Stats> {
+        match metric {
+            "kem" => Some(&self.kem_time_stats),
+            "cipher" => Some(&self.cipher_time_stats),
+            "kem_bw_bytes" => Some(&self.kem_bw_stats),
+            "msg_bw_bytes" => Some(&self.msg_bw_stats),
+            "cipher_only" => Some(&self.cipher_only_time_stats),
+            _ => None,
+        }
+    }
+
+    /// Valores não finitos descartados somando todas as distribuições da configuração
+    fn nonfinite_count(&self) -> usize {
+        self.distributions().iter().map(|(_, stats)| stats.nonfinite_count).sum()
+    }
+
+    /// Triplas (métrica, estatística, valor) da configuração, para a saída longa (`--tidy`)
+    ///
+    /// As métricas com distribuição por repetição trazem todas as estatísticas do
+    /// `Stats`; as grandezas escalares (médias por repetição, razões) trazem `mean`
+    /// ou `value`. Os nomes das métricas são os prefixos das colunas do CSV largo.
+    fn tidy_rows(&self) -> Vec<(&'static str, &'static str, f64)> {
+        let mut rows = Vec::new();
+        for (metric, stats) in self.distributions() {
+            rows.extend([
+                (metric, "mean", stats.mean),
+                (metric, "std", stats.std_dev),
+                (metric, "ci95", stats.ci95),
+                (metric, "normal", f64::from(u8::from(stats.is_normal))),
+                (metric, "outliers", stats.outliers_count as f64),
+                (metric, "extreme_outliers", stats.extreme_outliers_count as f64),
+                (metric, "sample_size", stats.sample_size as f64),
+                (metric, "skewness", stats.skewness),
+                (metric, "kurtosis", stats.kurtosis),
+                (metric, "nonfinite", stats.nonfinite_count as f64),
+            ]);
+        }
+        rows.extend([
+            ("text_msgs", "mean", self.avg_text),
+            ("image_msgs", "mean", self.avg_image),
+            ("file_msgs", "mean", self.avg_file),
+            ("system_msgs", "mean", self.avg_system),
+            ("voice_msgs", "mean", self.avg_voice),
+            ("expansion_ratio", "mean", self.expansion_ratio),
+            ("throughput_agregado_msgs_s", "mean", self.aggregate_throughput),
+            ("throughput_por_remetente_msgs_s", "mean", self.per_sender_throughput),
+            ("latencia_cifragem_msg_us", "mean", self.message_latency_us),
+            ("upload_bytes", "mean", self.upload_bytes),
+            ("download_bytes", "mean", self.download_bytes),
+            ("storage_bytes", "mean", self.storage_bytes),
+            ("kem_bw_bps", "mean", self.kem_bw_bps),
+            ("msg_bw_bps", "mean", self.msg_bw_bps),
+            ("kem_failures", "mean", self.kem_failures),
+            ("kem_sender_ms", "mean", self.kem_sender_ms),
+            ("kem_receiver_ms", "mean", self.kem_receiver_ms),
+            ("decap_ok_us", "mean", self.decap_ok_us),
+            ("decap_fail_us", "mean", self.decap_fail_us),
+            ("rekey_events", "mean", self.rekey_events),
+            ("rekey_ms", "mean", self.rekey_ms),
+            ("rekey_bw_bytes", "mean", self.rekey_bw),
+            ("rekey_ms_per_event", "mean", self.rekey_ms_per_event),
+            ("edit_events", "mean", self.edit_events),
+            ("edit_ms", "mean", self.edit_ms),
+            ("edit_bw_bytes", "mean", self.edit_bw),
+            ("confirm_ms", "mean", self.confirm_ms),
+            ("confirm_bw_bytes", "mean", self.confirm_bw),
+            ("combiner_ms", "mean", self.combine_ms),
+            ("backup_ops", "mean", self.backup_ops),
+            ("backup_ms", "mean", self.backup_ms),
+            ("backup_bw_bytes", "mean", self.backup_bw),
+            ("rotacoes", "value", self.rotations as f64),
+            ("sessoes_travadas", "value", self.stalled_sessions as f64),
+            ("velocidade_relativa", "value", self.relative_speed),
+        ]);
+        for (kind, ratio) in ["texto", "imagem", "arquivo", "sistema", "voz"].iter().zip(self.compression_ratios) {
+            rows.push(("razao_compressao", kind, ratio));
+        }
+        if let Some(latency) = self.weighted_latency_us {
+            rows.push(("latencia_cifragem_msg_ponderada_us", "mean", latency));
+        }
+        for ((cipher_metric, bytes_metric), stats) in TYPE_METRICS.iter().zip(&self.type_stats) {
+            if let Some(stats) = stats {
+                for (metric, stats) in [(*cipher_metric, &stats.cipher_us), (*bytes_metric, &stats.msg_bytes)] {
+                    rows.extend([
+                        (metric, "mean", stats.mean),
+                        (metric, "ci95", stats.ci95),
+                        (metric, "normal", f64::from(u8::from(stats.is_normal))),
+                    ]);
+                }
+            }
+        }
+        rows
+    }
+
+    /// Células das colunas por tipo de mensagem (`--per-type-stats`), na ordem de
+    /// `MESSAGE_KINDS`; vazios sem a opção ou para tipos sem mensagens na configuração
+    fn type_stat_cells(&self) -> Vec<Cell> {
+        self.type_stats.iter()
+            .flat_map(|stats| match stats {
+                Some(TypeStats { cipher_us, msg_bytes }) => [
+                    Cell::Real(cipher_us.mean, 4), Cell::Real(cipher_us.ci95, 4), Cell::from(cipher_us.is_normal),
+                    Cell::Real(msg_bytes.mean, 1), Cell::Real(msg_bytes.ci95, 1), Cell::from(msg_bytes.is_normal),
+                ],
+                None => std::array::from_fn(|_| Cell::Empty),
+            })
+            .collect()
+    }
+
+    /// Células da linha de resultados, na ordem e com os tipos de `RESULT_COLUMNS`
+    fn to_cells(&self) -> Vec<Cell> {
+        // Determina o tipo de estatística aplicado para cada métrica
+        let stat_type = Stats::stat_type;
+        let (kem, cipher, kem_bw, msg_bw) =
+            (&self.kem_time_stats, &self.cipher_time_stats, &self.kem_bw_stats, &self.msg_bw_stats);
+        let cipher_only = &self.cipher_only_time_stats;
+
+        let mut cells = vec![
+            Cell::from(SCHEMA_VERSION),
+            Cell::from(format!("{:?}", self.spec.scenario)),
+            Cell::from(format!("{:?}", self.spec.pattern)),
+            Cell::from(self.spec.agreement.as_ref()),
+            Cell::from(self.spec.cipher),
+            Cell::from(self.num_messages),
+            Cell::from(self.msgs_per_rotation),
+            Cell::from(self.rotations),
+            Cell::Real(kem.mean, 4),
+            Cell::Real(kem.std_dev, 4),
+            Cell::Real(kem.ci95, 4),
+            Cell::Real(cipher.mean, 4),
+            Cell::Real(cipher.std_dev, 4),
+            Cell::Real(cipher.ci95, 4),
+            Cell::Real(kem_bw.mean, 2),
+            Cell::Real(kem_bw.std_dev, 2),
+            Cell::Real(kem_bw.ci95, 2),
+            Cell::Real(msg_bw.mean, 2),
+            Cell::Real(msg_bw.std_dev, 2),
+            Cell::Real(msg_bw.ci95, 2),
+            Cell::Real(self.avg_text, 1),
+            Cell::Real(self.avg_image, 1),
+            Cell::Real(self.avg_file, 1),
+            Cell::Real(self.avg_system, 1),
+            Cell::from(kem.is_normal),
+            Cell::from(cipher.is_normal),
+            Cell::from(kem_bw.is_normal),
+            Cell::from(msg_bw.is_normal),
+            Cell::from(stat_type(kem)),
+            Cell::from(stat_type(cipher)),
+            Cell::from(stat_type(kem_bw)),
+            Cell::from(stat_type(msg_bw)),
+            Cell::from(kem.outliers_count),
+            Cell::from(cipher.outliers_count),
+            Cell::from(kem_bw.outliers_count),
+            Cell::from(msg_bw.outliers_count),
+            Cell::from(kem.extreme_outliers_count),
+            Cell::from(cipher.extreme_outliers_count),
+            Cell::from(kem_bw.extreme_outliers_count),
+            Cell::from(msg_bw.extreme_outliers_count),
+            Cell::from(kem.sample_size),
+            Cell::from(cipher.sample_size),
+            Cell::from(kem_bw.sample_size),
+            Cell::from(msg_bw.sample_size),
+            Cell::Real(self.expansion_ratio, 6),
+            Cell::from(self.authenticated),
+            Cell::from(self.primitive_versions.as_str()),
+            Cell::from(self.fixed_size),
+            Cell::Real(cipher_only.mean, 4),
+            Cell::Real(cipher_only.std_dev, 4),
+            Cell::Real(cipher_only.ci95, 4),
+            Cell::from(cipher_only.is_normal),
+            Cell::from(stat_type(cipher_only)),
+            Cell::from(self.devices_per_user),
+            Cell::Real(kem.skewness, 4),
+            Cell::Real(kem.kurtosis, 4),
+            Cell::Real(cipher.skewness, 4),
+            Cell::Real(cipher.kurtosis, 4),
+            Cell::Real(kem_bw.skewness, 4),
+            Cell::Real(kem_bw.kurtosis, 4),
+            Cell::Real(msg_bw.skewness, 4),
+            Cell::Real(msg_bw.kurtosis, 4),
+            Cell::Real(cipher_only.skewness, 4),
+            Cell::Real(cipher_only.kurtosis, 4),
+            Cell::from(self.compression.name()),
+            Cell::Real(self.compress_time_stats.mean, 4),
+            Cell::Real(self.compress_time_stats.std_dev, 4),
+            Cell::Real(self.compress_time_stats.ci95, 4),
+            Cell::Real(self.compression_ratios[0], 4),
+            Cell::Real(self.compression_ratios[1], 4),
+            Cell::Real(self.compression_ratios[2], 4),
+            Cell::Real(self.compression_ratios[3], 4),
+            Cell::Real(self.compression_ratios[4], 4),
+            Cell::from(kem.central.name()),
+            Cell::from(cipher.central.name()),
+            Cell::from(kem_bw.central.name()),
+            Cell::from(msg_bw.central.name()),
+            Cell::from(cipher_only.central.name()),
+            Cell::from(kem.sample_floor_hit),
+            Cell::from(cipher.sample_floor_hit),
+            Cell::from(kem_bw.sample_floor_hit),
+            Cell::from(msg_bw.sample_floor_hit),
+            Cell::from(cipher_only.sample_floor_hit),
+            Cell::from(self.senders),
+            Cell::Real(self.aggregate_throughput, 2),
+            Cell::Real(self.per_sender_throughput, 2),
+            Cell::Real(self.message_latency_us, 3),
+            Cell::from(self.key_commitment),
+            Cell::Real(self.commitment_time_stats.mean, 4),
+            Cell::Real(self.commitment_time_stats.std_dev, 4),
+            Cell::Real(self.commitment_time_stats.ci95, 4),
+            Cell::Real(self.upload_bytes, 1),
+            Cell::Real(self.download_bytes, 1),
+            Cell::from(SecurityLevel::of_agreement(&self.spec.agreement).name()),
+            Cell::Real(self.kem_failures, 1),
+            Cell::Real(self.decap_ok_us, 3),
+            Cell::Real(self.decap_fail_us, 3),
+            Cell::from(self.config_seed.to_string()),
+            Cell::from(self.stalled_sessions > 0),
+            Cell::from(self.stalled_sessions),
+            Cell::Real(self.storage_bytes, 1),
+            Cell::Real(self.rekey_events, 2),
+            Cell::Real(self.rekey_ms, 4),
+            Cell::Real(self.rekey_bw, 1),
+            Cell::Real(self.rekey_ms_per_event, 4),
+            Cell::Real(self.avg_voice, 1),
+            Cell::from(self.rotation_strategy.name()),
+            Cell::from(self.ephemeral.name()),
+            Cell::from(self.keygen_policy.name()),
+            Cell::from(self.duplicate_payloads),
+            Cell::from(self.repetitions),
+            Cell::Real(self.config_wall_ms, 1),
+            Cell::Real(self.kem_bw_bps, 1),
+            Cell::Real(self.msg_bw_bps, 1),
+            Cell::from(self.group_size),
+            Cell::Real(self.kem_sender_ms, 4),
+            Cell::Real(self.kem_receiver_ms, 4),
+            Cell::from(self.decapsulations),
+            Cell::Real(self.edit_events, 2),
+            Cell::Real(self.edit_ms, 4),
+            Cell::Real(self.edit_bw, 1),
+            Cell::from(self.tag_layout.name()),
+            self.weighted_latency_us.map_or(Cell::Empty, |latency| Cell::Real(latency, 3)),
+            Cell::Real(self.confirm_ms, 4),
+            Cell::Real(self.confirm_bw, 1),
+            Cell::Real(self.backup_ops, 2),
+            Cell::Real(self.backup_ms, 4),
+            Cell::Real(self.backup_bw, 1),
+            Cell::from(self.nonfinite_count()),
+            Cell::from(self.envelope.name()),
+            Cell::from(self.base64_media),
+            self.payload_entropy.map_or(Cell::Empty, |bits| Cell::Real(bits, 2)),
+            Cell::from(self.ephemeral.dh_variant()),
+            Cell::Real(self.combine_ms, 4),
+        ];
+        cells.extend(self.type_stat_cells());
+        cells.push(Cell::Real(self.relative_speed, 4));
+        cells
+    }
+
+    /// Formata a linha de resultados correspondente ao cabeçalho
+    fn to_csv_row(&self) -> String {
+        csv_row(&self.to_cells())
+    }
+}
+
+/// Escritor ordenado de resultados (reorder buffer)
+///
+/// Recebe pares `(índice, ConfigResult)` em qualquer ordem de conclusão e grava
+/// no CSV, o quanto antes, o maior prefixo contíguo de células já disponível.
+/// Assim o arquivo é escrito incrementalmente e tem conteúdo determinístico
+/// mesmo quando as configurações são executadas em paralelo.
+///
+/// As cifras de uma célula (cenário, padrão, acordo) são índices consecutivos; a
+/// célula só é gravada completa, para que a `velocidade_relativa` de cada linha já
+/// conheça o tempo da cifra de referência.
+struct OrderedResultWriter<W: Write> {
+    writer: W,
+    baseline: String, // Cifra de referência da velocidade relativa (--baseline-cipher)
+    next_index: usize,
+    pending: BTreeMap<usize, ConfigResult>,
+    written: Vec<ConfigResult>,
+}
+
+impl<W: Write> OrderedResultWriter<W> {
+    /// Cria o escritor e grava o cabeçalho do CSV
+    fn new(mut writer: W, baseline: &str) -> std::io::Result<Self> {
+        writeln!(writer, "{}", ConfigResult::csv_header())?;
+        Ok(Self { writer, baseline: baseline.to_string(), next_index: 0, pending: BTreeMap::new(), written: Vec::new() })
+    }
+
+    /// Registra um resultado e grava todas as células que passaram a formar um prefixo contíguo
+    fn push(&mut self, index: usize, result: ConfigResult) -> std::io::Result<()> {
+        self.pending.insert(index, result);
+        let cell_size = CIPHERS.len();
+        while (self.next_index..self.next_index + cell_size).all(|index| self.pending.contains_key(&index)) {
+            let cell: Vec<ConfigResult> = (self.next_index..self.next_index + cell_size)
+                .filter_map(|index| self.pending.remove(&index))
+                .collect();
+            self.write_cell(cell)?;
+            self.next_index += cell_size;
+        }
+        // Descarrega a cada prefixo para que uma interrupção não perca linhas completas
+        self.writer.flush()
+    }
+
+    /// Preenche a velocidade relativa das linhas de uma célula e as grava
+    fn write_cell(&mut self, cell: Vec<ConfigResult>) -> std::io::Result<()> {
+        let baseline_ms = cell.iter()
+            .find(|result| result.spec.cipher == self.baseline)
+            .map(|result| result.cipher_time_stats.mean);
+        for mut result in cell {
+            result.relative_speed = relative_speed(baseline_ms, result.cipher_time_stats.mean);
+            writeln!(self.writer, "{}", result.to_csv_row())?;
+            self.written.push(result);
+        }
+        Ok(())
+    }
+
+    /// Finaliza a escrita, retornando os resultados na ordem canônica
+    fn finish(mut self) -> std::io::Result<Vec<ConfigResult>> {
+        if !self.pending.is_empty() {
+            return Err(std::io::Error::other(format!(
+                "{} configurações aguardando índices ausentes (próximo esperado: {})",
+                self.pending.len(), self.next_index
+            )));
+        }
+        self.writer.flush()?;
+        Ok(self.written)
+    }
+
+    /// Finaliza uma execução interrompida (ex: `--max-runtime`)
+    ///
+    /// Grava, ainda em ordem canônica, os resultados que aguardavam índices que
+    /// nunca serão executados; o CSV fica com lacunas, mas sem perder configurações concluídas.
+    fn finish_partial(mut self) -> std::io::Result<Vec<ConfigResult>> {
+        let pending: Vec<ConfigResult> = std::mem::take(&mut self.pending).into_values().collect();
+        let cell_of = |result: &ConfigResult| result.spec.index / CIPHERS.len();
+        for cell in pending.chunk_by(|a, b| cell_of(a) == cell_of(b)) {
+            self.write_cell(cell.to_vec())?;
+        }
+        self.writer.flush()?;
+        Ok(self.written)
+    }
+}
+
+/// Origem das decisões do workload de uma execução
+///
+/// - Live: geradores aleatórios (comportamento padrão)
+/// - Record: geradores aleatórios, com cada decisão gravada (`--record`)
+/// - Replay: decisões lidas de um arquivo gravado (`--replay`)
+enum WorkloadTrace {
+    Live,
+    Record(WorkloadRecorder),
+    Replay(WorkloadReplay),
+}
+
+/// Métricas brutas de uma sessão (um remetente durante uma repetição)
+struct SessionMetrics {
+    kem_time: Duration,           // Tempo total dos acordos de chave
+    combine_time: Duration,       // Derivação das chaves de sessão pelo combinador, fora de kem_time
+    cipher_only_time: Duration,   // Tempo somado das chamadas de cifragem
+    compress_time: Duration,      // Tempo somado da compressão (--compress)
+    commitment_time: Duration,    // Tempo somado dos compromissos de chave (--key-commitment)
+    loop_time: Duration,          // Tempo de parede do laço de mensagens
+    kem_bandwidth: usize,
+    kem_upload: usize,            // Bytes do acordo enviados por Alice (texto cifrado Kyber)
+    kem_download: usize,          // Bytes do acordo recebidos por Alice (chaves públicas de Bob)
+    decap_ok_time: Duration,      // Tempo somado dos decapsulamentos Kyber bem-sucedidos
+    decap_ok_count: usize,
+    decap_fail_time: Duration,    // Tempo somado dos decapsulamentos rejeitados (--inject-kem-failures)
+    decap_fail_count: usize,
+    msg_bandwidth: usize,
+    plaintext_bytes: usize,
+    rotations: usize,
+    messages: usize,
+    text_count: usize,
+    image_count: usize,
+    file_count: usize,
+    system_count: usize,
+    voice_count: usize,
+    compression_bytes: [(usize, usize); MESSAGE_KINDS.len()], // (originais, comprimidos) por tipo
+    size_time_pairs: Vec<(f64, f64)>,  // Pares (bytes, µs) com --size-time-correlation ou --size-weighted
+    kind_samples: [Vec<(f64, f64)>; MESSAGE_KINDS.len()], // (µs de cifragem, bytes) por mensagem e tipo, com --per-type-stats
+    stalled: bool,                // Laço abandonado pela guarda de consultas (MAX_IDLE_POLLS)
+    rekey_events: usize,          // Re-keys completos do grupo disparados por saída de membro
+    rekey_time: Duration,         // Tempo somado dos acordos de chave desses re-keys
+    rekey_bandwidth: usize,       // Bytes de acordo de chaves desses re-keys
+    edit_events: usize,           // Edições de mensagens de texto (--edit-rate)
+    edit_time: Duration,          // Tempo somado das recifragens dessas edições
+    edit_bandwidth: usize,        // Bytes cifrados dessas edições (texto cifrado + nonce)
+    confirm_time: Duration,       // Cifragem dos tokens de confirmação de chave (--key-confirmation)
+    confirm_bandwidth: usize,     // Bytes desses tokens (texto cifrado + nonce)
+    backup_ops: usize,            // Chaves de sessão cifradas para o backup (--key-backup)
+    backup_time: Duration,        // Tempo dessas cifragens de backup
+    backup_bandwidth: usize,      // Bytes enviados ao backup (efêmero, chave cifrada e tag)
+    bandwidth_series: Vec<BandwidthPoint>, // Bytes acumulados após cada mensagem (--bandwidth-series)
+    payload_hashes: Vec<[u8; 32]>, // SHA-256 dos payloads de mídia (--detect-duplicates)
+    ciphertext_sample: Option<CiphertextSample>, // Primeira mensagem cifrada (--dump-sample)
+}
+
+/// Exemplo concreto de cifragem de uma mensagem (`--dump-sample`)
+#[derive(Debug, Clone, PartialEq)]
+struct CiphertextSample {
+    plaintext_bytes: usize,    // Tamanho do texto claro cifrado (após a compressão, se houver)
+    plaintext_sha256: [u8; 32],
+    key: [u8; 32],             // Chave da mensagem; gravada como SHA-256 salvo --dump-keys-insecure
+    nonce: Vec<u8>,            // Nonce/IV transmitido
+    ciphertext: Vec<u8>,       // Texto cifrado com tag (e compromisso de chave, se houver)
+}
+
+/// Largura de banda acumulada após uma mensagem (`--bandwidth-series`)
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BandwidthPoint {
+    message_index: usize, // Mensagens processadas até aqui (1 = após a primeira)
+    kem_bytes: usize,     // Bytes de acordo de chaves acumulados (inclui a rotação inicial)
+    msg_bytes: usize,     // Bytes de mensagens cifradas acumulados
+    smoothed_bytes: f64,  // Bytes desta mensagem (KEM + cifrada), média móvel de --series-window
+}
+
+/// Parâmetros de uma configuração comuns a todas as suas sessões
+#[derive(Clone, Copy)]
+struct SessionPlan<'a> {
+    num_messages: usize,
+    rotation: RotationStrategy, // Estratégia de rotação do cenário
+    seed: u64, // Semente da configuração (config_seed)
+    cached_keys: Option<&'a [RecipientKeys]>, // Chaves de Bob da configuração (--keygen-policy cached)
+    corpus: Option<&'a Arc<PayloadCorpus>>, // Payloads reais das mensagens (--corpus)
+}
+
+/// Membros do grupo considerados no acordo de chaves de cada rotação
+///
+/// Sem `--group-fanout`, apenas Alice e Bob; com a opção, o tamanho do grupo do cenário,
+/// e a sessão é distribuída a cada um dos outros membros.
+fn kem_group_size(scenario: &UsageScenario, config: &ExperimentConfig) -> usize {
+    if config.group_fanout { get_group_size(scenario).max(2) } else { 2 }
+}
+
+/// Envia a chave de sessão nova ao backup do servidor (--key-backup)
+///
+/// Cada rotação e cada re-key produzem uma chave nova, e o cliente a cifra para a chave
+/// de recuperação antes de enviá-la: o custo acompanha a frequência de rotação. Tempo e
+/// bytes ficam fora de kem_ms e kem_bw_bytes.
+fn backup_session_key(recovery: &RecoveryKey, session_key: &[u8; 32], rng: &mut impl CryptoRngCore, metrics: &mut SessionMetrics) {
+    let start_backup = Instant::now();
+    let backup = recovery.backup(session_key, rng);
+    metrics.backup_time += start_backup.elapsed();
+    metrics.backup_bandwidth += backup.len();
+    metrics.backup_ops += 1;
+}
+
+/// Gera as chaves de cada dispositivo de Bob (--devices-per-user) para o acordo
+fn generate_bob_devices(agreement: &dyn KeyAgreement, devices: usize, rng: &mut impl CryptoRngCore) -> Vec<RecipientKeys> {
+    (0..devices).map(|_| agreement.generate_keys(&mut *rng)).collect()
+}
+
+/// Registro dos acordos da execução: os de `AGREEMENTS` e os de --hybrid
+fn agreement_registry(config: &ExperimentConfig) -> Result<AgreementRegistry, ExperimentError> {
+    AgreementRegistry::standard(&config.hybrid)
+}
+
+/// Registro das cifras da execução, com a Megolm-Like configurada por
+/// --megolm-unauthenticated e --ctr-width
+fn cipher_registry(config: &ExperimentConfig) -> CipherRegistry {
+    CipherRegistry::standard(!config.megolm_unauthenticated, config.ctr_width)
+}
+
+/// Esvazia as caches antes da próxima operação medida (--flush-cache)
+///
+/// O tempo da varredura é acumulado em `flush_time`, descontado do tempo do laço.
+fn flush_caches(flusher: &mut Option<CacheFlusher>, flush_time: &mut Duration) {
+    if let Some(flusher) = flusher {
+        let start_flush = Instant::now();
+        flusher.flush();
+        *flush_time += start_flush.elapsed();
+    }
+}
+
+/// Executa o laço de mensagens de um remetente: rotações de chave e cifragem
+///
+/// Cria seus próprios geradores de mensagens e tráfego e as chaves de Bob, de modo
+/// que várias sessões possam rodar em threads distintas (`--senders`).
+/// Com `--replay`, as decisões de envio e as mensagens vêm do arquivo gravado.
+fn run_session(
+    spec: &ConfigSpec,
+    config: &ExperimentConfig,
+    trace: &WorkloadTrace,
+    repetition: usize,
+    sender: usize,
+    plan: SessionPlan,
+) -> Result<SessionMetrics, ExperimentError> {
+    let SessionPlan { num_messages, rotation, seed, cached_keys, corpus } = plan;
+    let session_key = (spec.index, repetition, sender);
+    // As sessões planejadas já foram verificadas antes da execução (check_replay)
+    let replay_stream = match trace {
+        WorkloadTrace::Replay(replay) => {
+            replay.check_session(session_key, num_messages)?;
+            replay.session(session_key)
+        }
+        _ => None,
+    };
+    // Acordo e cifra buscados por nome nos registros, fora de qualquer medição
+    let agreements = agreement_registry(config)?;
+    let acordo = agreements.lookup(&spec.agreement)?;
+    let ciphers = cipher_registry(config);
+    let cipher = ciphers.lookup(spec.cipher)?;
+    let devices_per_user = config.devices_per_user;
+    let failure_rate = config.inject_kem_failures.unwrap_or(0.0);
+    let leave_rate = config.member_leave_rate.unwrap_or(0.0);
+    let edit_rate = config.edit_rate.unwrap_or(0.0);
+    // Membros atuais da sala: cada saída (--member-leave-rate) remove um, até restarem só
+    // o remetente e Bob. Com --group-fanout, as rotações seguintes acompanham o grupo menor
+    let mut group_members = get_group_size(&spec.scenario).max(2);
+
+    // Inicializa geradores de mensagens e tráfego e o gerador criptográfico (chaves
+    // X25519, nonces, injeção de falhas), cada um com o seu fluxo derivado da semente
+    // da configuração para esta repetição e remetente
+    let mut message_gen = MessageGenerator::new(spec.scenario.clone())
+        .with_fixed_size(config.fixed_size)
+        .with_entropy(config.payload_entropy)
+        .with_corpus(corpus.cloned())
+        .with_seed(stream_seed(seed, repetition, sender, SeedStream::Messages));
+    let mut traffic_gen = TrafficGenerator::new(spec.pattern.clone())
+        .with_seed(stream_seed(seed, repetition, sender, SeedStream::Traffic));
+    // Com --os-rng, o gerador criptográfico é o do sistema e a semente do fluxo é ignorada
+    let mut crypto_rng = config.entropy_source()
+        .session_rng(stream_seed(seed, repetition, sender, SeedStream::Crypto));
+    // Saídas de membros fazem parte do workload: fluxo próprio, semeado mesmo com --os-rng
+    let mut members_rng = StdRng::seed_from_u64(stream_seed(seed, repetition, sender, SeedStream::Members));
+
+    // Gera chaves criptográficas baseadas no tipo de acordo
+    // Olm-Clássico usa apenas X25519, Olm-Híbrido usa Kyber768 + X25519
+    // Chaves são geradas aleatoriamente usando o gerador de números aleatórios do sistema
+    // Garante que as chaves sejam únicas e seguras para cada execução
+    // Cada dispositivo de Bob (--devices-per-user) tem seu próprio par de chaves
+    // Com --keygen-policy per-rotation, as chaves são geradas a cada rotação (no tempo
+    // de KEM); com cached, vêm prontas da configuração
+    let keygen_per_rotation = config.keygen_policy == KeygenPolicy::PerRotation;
+    let mut session_devices = if cached_keys.is_none() && !keygen_per_rotation {
+        generate_bob_devices(acordo, devices_per_user, &mut crypto_rng)
+    } else {
+        Vec::new()
+    };
+    // Com --ephemeral static, o segredo X25519 de Alice é gerado aqui, uma vez por sessão,
+    // e o custo da geração fica fora do tempo das rotações
+    let alice_keys = SenderKeys::generate_with_rng(config.ephemeral, &mut crypto_rng);
+    // Com --key-backup, a chave de recuperação do usuário, fora dos tempos medidos
+    let recovery_key = config.key_backup.then(|| RecoveryKey::generate(&mut crypto_rng));
+
+    // Inicializa estado do experimento
+    let mut current_key: [u8; 32] = [0u8; 32];
+    let mut last_rotation = Instant::now();
+    let mut messages_since_rotation = 0;
+    let mut bytes_since_rotation = 0;
+    let mut metrics = SessionMetrics {
+        kem_time: Duration::ZERO,
+        cipher_only_time: Duration::ZERO,
+        compress_time: Duration::ZERO,
+        commitment_time: Duration::ZERO,
+        loop_time: Duration::ZERO,
+        kem_bandwidth: 0,
+        kem_upload: 0,
+        kem_download: 0,
+        decap_ok_time: Duration::ZERO,
+        decap_ok_count: 0,
+        decap_fail_time: Duration::ZERO,
+        decap_fail_count: 0,
+        msg_bandwidth: 0,
+        plaintext_bytes: 0,
+        rotations: 0,
+        messages: 0,
+        text_count: 0,
+        image_count: 0,
+        file_count: 0,
+        system_count: 0,
+        voice_count: 0,
+        compression_bytes: [(0, 0); MESSAGE_KINDS.len()],
+        size_time_pairs: Vec::new(),
+        kind_samples: Default::default(),
+        payload_hashes: Vec::new(),
+        stalled: false,
+        rekey_events: 0,
+        rekey_time: Duration::ZERO,
+        rekey_bandwidth: 0,
+        edit_events: 0,
+        edit_time: Duration::ZERO,
+        edit_bandwidth: 0,
+        confirm_time: Duration::ZERO,
+        confirm_bandwidth: 0,
+        combine_time: Duration::ZERO,
+        backup_ops: 0,
+        backup_time: Duration::ZERO,
+        backup_bandwidth: 0,
+        bandwidth_series: Vec::new(),
+        ciphertext_sample: None,
+    };
+    // Série temporal apenas da primeira repetição do primeiro remetente (representativa)
+    let record_series = config.bandwidth_series && repetition == 0 && sender == 0;
+    let record_sample = config.dump_sample.is_some() && repetition == 0 && sender == 0;
+
+    // Consultas ao padrão de tráfego recusadas desde o último envio (gravadas/reproduzidas)
+    let mut polls: u64 = 0;
+    // Buffer reutilizado entre as mensagens da sessão com --in-place
+    let mut in_place_buffer: Vec<u8> = Vec::new();
+    // Buffer de rascunho de --flush-cache, alocado uma vez por thread e reaproveitado
+    // entre as sessões; as varreduras ficam fora dos tempos medidos e são descontadas
+    // do tempo de parede do laço
+    let mut cache_flusher = config.flush_cache_mib().map(CacheFlusher::acquire);
+    let mut flush_time = Duration::ZERO;
+    // Hashes de --detect-duplicates, também descontados do tempo de parede do laço
+    let mut hash_time = Duration::ZERO;
+    // Índice da catraca de --per-message-kdf: avança a cada evento cifrado (mensagens e edições)
+    let mut ratchet_index: u64 = 0;
+    // O DirectMessage deriva uma chave por mensagem mesmo sem --per-message-kdf (catraca do Olm)
+    let per_message_kdf = config.per_message_kdf || spec.scenario.ratchets_per_message();
+    // Remetente dos eventos de --envelope matrix-json, um usuário Matrix por remetente
+    let matrix_sender = format!("@remetente{}:matrix.org", sender);
+    // Texto claro da última mensagem de texto enviada, alvo das edições (--edit-rate)
+    let mut last_text: Option<Vec<u8>> = None;
+
+    // Cadência entre mensagens: com --pacing simulated, o atraso é somado a este
+    // deslocamento do relógio visto pelo padrão de tráfego e pela rotação
+    let inter_message_delay = Duration::from_secs_f64(config.inter_message_delay / 1000.0);
+    let mut clock_offset = Duration::ZERO;
+
+    // Início do tempo de parede do laço de mensagens (inclui geração, RNG e tráfego)
+    let start_enc = Instant::now();
+
+    // Loop principal de processamento de mensagens
+    while metrics.messages < num_messages {
+        let now = Instant::now();
+        let current_time = now + clock_offset;
+
+        // Verifica se deve enviar mensagem baseado no padrão de tráfego
+        // (no replay, após o mesmo número de consultas recusadas da gravação)
+        let should_send = match replay_stream {
+            Some(stream) => polls >= stream[metrics.messages].polls,
+            None => traffic_gen.should_send_message(current_time),
+        };
+        if !should_send {
+            polls += 1;
+            // Guarda contra padrões que nunca liberam o envio (ex: fase de probabilidade
+            // zero do Periodic): a sessão é encerrada com as mensagens já processadas
+            if polls >= MAX_IDLE_POLLS {
+                println!("  [AVISO] {:?} + {:?}: {} consultas seguidas sem envio (repetição {}, remetente {}); \
+                          sessão encerrada com {}/{} mensagens",
+                         spec.scenario, spec.pattern, polls, repetition, sender,
+                         metrics.messages, num_messages);
+                metrics.stalled = true;
+                break;
+            }
+        } else {
+            let time_since_last_rotation = current_time.duration_since(last_rotation);
+
+            // Executa rotação de chave quando necessário
+            // A primeira mensagem sempre estabelece a sessão; depois, a estratégia do
+            // cenário (--rotation-strategy) decide pelo número de mensagens, pela idade
+            // da sessão, pelo volume cifrado ou (padrão) por mensagens ou 7 dias
+            if metrics.rotations == 0 || rotation.should_rotate(
+                messages_since_rotation, time_since_last_rotation, bytes_since_rotation,
+            ) {
+                flush_caches(&mut cache_flusher, &mut flush_time);
+                let start_kem = Instant::now();
+                if keygen_per_rotation {
+                    session_devices = generate_bob_devices(acordo, devices_per_user, &mut crypto_rng);
+                }
+                let bob_devices = cached_keys.unwrap_or(&session_devices);
+                // Segredos das sessões novas: o primeiro define a chave de sessão; todos são
+                // guardados para a confirmação de chave
+                let mut session_secrets = Vec::new();
+
+                // Executa o acordo de chaves selecionado (X25519 ou X25519 + Kyber768)
+                // com cada dispositivo de cada destinatário: uma sessão (e um encapsulamento)
+                // por dispositivo. Os membros do fan-out reutilizam as chaves de Bob, o que
+                // preserva o custo por sessão sem gerar chaves extras
+                let mut kem_bandwidth = 0;
+                // Destinatários: todos os membros menos o remetente (apenas Bob sem --group-fanout)
+                let kem_recipients = if config.group_fanout { group_members - 1 } else { 1 };
+                for (recipient, (device, bob_keys)) in (0..kem_recipients)
+                    .flat_map(|recipient| bob_devices.iter().enumerate().map(move |entry| (recipient, entry)))
+                {
+                    // Com --inject-kem-failures, parte dos textos cifrados Kyber é corrompida
+                    let corrupt = failure_rate > 0.0 && crypto_rng.gen_bool(failure_rate);
+                    // Com --decaps-per-encap, o texto cifrado é decapsulado por M receptores
+                    let (shared_secret, device_bandwidth, decapsulation) = acordo.agree(
+                        bob_keys, &alice_keys, corrupt, config.decaps_per_encap, &mut crypto_rng,
+                    )?;
+                    kem_bandwidth += device_bandwidth.total();
+                    metrics.kem_upload += device_bandwidth.upload;
+                    metrics.kem_download += device_bandwidth.download;
+                    match decapsulation {
+                        Some(decap) if decap.rejected => {
+                            metrics.decap_fail_time += decap.time;
+                            metrics.decap_fail_count += 1;
+                        }
+                        Some(decap) => {
+                            metrics.decap_ok_time += decap.time;
+                            metrics.decap_ok_count += 1;
+                        }
+                        None => {}
+                    }
+                    // A sessão do primeiro dispositivo de Bob define a chave de cifragem das mensagens
+                    if (recipient == 0 && device == 0) || config.key_confirmation {
+                        session_secrets.push(shared_secret);
+                    }
+                }
+
+                // Atualiza métricas
+                let elapsed_kem = start_kem.elapsed();
+                metrics.kem_time += elapsed_kem;          // Tempo gasto na KEM (todos os dispositivos)
+
+                // Chave de sessão: o segredo X25519 no Olm-Clássico, o combinador HKDF sobre
+                // todos os componentes nos híbridos; cronometrada à parte de kem_ms
+                if let Some(secret) = session_secrets.first() {
+                    let start_combine = Instant::now();
+                    current_key = session_key(acordo.components(), secret);
+                    metrics.combine_time += start_combine.elapsed();
+                }
+                metrics.rotations += 1;                   // Incrementa contador de rotações
+                metrics.kem_bandwidth += kem_bandwidth;   // Atualiza largura de banda KEM
+                last_rotation = current_time;             // Atualiza tempo da última rotação
+                messages_since_rotation = 0;
+                bytes_since_rotation = 0;
+
+                // Confirmação de chave (--key-confirmation): cada sessão nova devolve ao
+                // remetente o token fixo cifrado com a chave derivada, a ida e volta que
+                // completa o handshake. Só a cifragem é cronometrada; tempo e bytes ficam
+                // fora de kem_ms e kem_bw_bytes, como os re-keys
+                for secret in session_secrets.iter().filter(|_| config.key_confirmation) {
+                    let confirm_key = session_key(acordo.components(), secret);
+                    let mut nonce_material = [0u8; 16];
+                    crypto_rng.fill_bytes(&mut nonce_material);
+                    cipher.check_plaintext_size(KEY_CONFIRMATION_TOKEN.len())?;
+                    flush_caches(&mut cache_flusher, &mut flush_time);
+                    let start_confirm = Instant::now();
+                    let (confirmation, nonce_len) = cipher.encrypt(&confirm_key, &nonce_material, KEY_CONFIRMATION_TOKEN)?;
+                    metrics.confirm_time += start_confirm.elapsed();
+                    metrics.confirm_bandwidth += confirmation.len() + nonce_len;
+                }
+
+                if let Some(recovery) = &recovery_key {
+                    flush_caches(&mut cache_flusher, &mut flush_time);
+                    backup_session_key(recovery, &current_key, &mut crypto_rng, &mut metrics);
+                }
+            }
+
+            // Saída de membro (--member-leave-rate): a sessão Megolm deixa de ser segura e
+            // uma nova é distribuída a cada membro restante (e a cada um de seus dispositivos),
+            // em uma rajada de acordos de chave proporcional ao tamanho do grupo. O grupo
+            // encolhe a cada saída; com só o remetente e Bob na sala, ninguém mais sai
+            if leave_rate > 0.0 && group_members > 2 && members_rng.gen_bool(leave_rate) {
+                group_members -= 1;
+                flush_caches(&mut cache_flusher, &mut flush_time);
+                let start_rekey = Instant::now();
+                let bob_devices = cached_keys.unwrap_or(&session_devices);
+                let mut session_secret = None;
+                for member in 0..group_members - 1 {
+                    for (device, bob_keys) in bob_devices.iter().enumerate() {
+                        let (shared_secret, bandwidth, _) =
+                            acordo.agree(bob_keys, &alice_keys, false, 1, &mut crypto_rng)?;
+                        metrics.rekey_bandwidth += bandwidth.total();
+                        if member == 0 && device == 0 {
+                            session_secret = Some(shared_secret);
+                        }
+                    }
+                }
+                metrics.rekey_time += start_rekey.elapsed();
+                if let Some(secret) = session_secret {
+                    let start_combine = Instant::now();
+                    current_key = session_key(acordo.components(), &secret);
+                    metrics.combine_time += start_combine.elapsed();
+                }
+                metrics.rekey_events += 1;
+                last_rotation = current_time;
+                messages_since_rotation = 0;
+                bytes_since_rotation = 0;
+
+                if let Some(recovery) = &recovery_key {
+                    flush_caches(&mut cache_flusher, &mut flush_time);
+                    backup_session_key(recovery, &current_key, &mut crypto_rng, &mut metrics);
+                }
+            }
+
+            // Gera mensagem (ou reproduz a gravada) e executa cifragem
+            let message = match replay_stream {
+                Some(stream) => stream[metrics.messages].to_message(&mut message_gen)?,
+                None => message_gen.generate_message(),
+            };
+            if let WorkloadTrace::Record(recorder) = trace {
+                let descriptor = MessageDescriptor::from_message(session_key, metrics.messages, polls, &message);
+                recorder.record(&descriptor)?;
+            }
+            polls = 0;
+            // Conta tipos de mensagens para estatísticas
+            match &message {
+                MessageType::Text(_) => metrics.text_count += 1,
+                MessageType::Image(_) => metrics.image_count += 1,
+                MessageType::File(_) => metrics.file_count += 1,
+                MessageType::System(_) => metrics.system_count += 1,
+                MessageType::Voice(_) => metrics.voice_count += 1,
+            }
+
+            let original = message_gen.get_message_bytes(&message);
+            // Com --detect-duplicates, guarda o hash dos payloads aleatórios (imagem, arquivo,
+            // voz); textos e mensagens de sistema vêm de vocabulários fixos e se repetem.
+            // O tempo do hash é descontado de cipher_ms
+            if config.detect_duplicates && !matches!(message, MessageType::Text(_) | MessageType::System(_)) {
+                let start_hash = Instant::now();
+                metrics.payload_hashes.push(Sha256::digest(&original).into());
+                hash_time += start_hash.elapsed();
+            }
+            // Com --envelope matrix-json, o texto claro passa a ser o evento Matrix inteiro,
+            // montado fora dos tempos medidos; os duplicados continuam comparando o payload.
+            // O instante do evento vem do relógio simulado (um milissegundo por mensagem,
+            // mais a cadência de --pacing simulated), para que a mesma semente cifre os
+            // mesmos eventos
+            let original = match config.envelope {
+                Envelope::None => original,
+                Envelope::MatrixJson => {
+                    let origin_server_ts = EVENT_EPOCH_MS + clock_offset.as_millis() as u64 + metrics.messages as u64;
+                    matrix_event(&message, &matrix_sender, origin_server_ts)
+                }
+            };
+            // Com --base64-media, a mídia binária segue codificada em base64, também fora dos
+            // tempos medidos (a opção não combina com --envelope)
+            let media = !matches!(message, MessageType::Text(_) | MessageType::System(_));
+            let original = if config.base64_media && media {
+                encode_base64(&original).into_bytes()
+            } else {
+                original
+            };
+
+            // Compressão opcional antes da cifragem (--compress), com tempo medido à parte
+            let start_compress = Instant::now();
+            let plaintext = config.compress.compress(&original)?;
+            metrics.compress_time += start_compress.elapsed();
+            let kind_bytes = &mut metrics.compression_bytes[message.kind_index()];
+            kind_bytes.0 += original.len();
+            kind_bytes.1 += plaintext.len();
+
+            // Com --flush-cache, a derivação da chave e a cifragem começam com as caches frias
+            flush_caches(&mut cache_flusher, &mut flush_time);
+
+            // Chave e nonce/IV desta mensagem: por padrão a chave de sessão com nonce
+            // aleatório; com --per-message-kdf (ou no DirectMessage), ambos derivados via HKDF-Expand
+            let (message_key, nonce_material) = if per_message_kdf {
+                derive_message_key(&current_key, ratchet_index)
+            } else {
+                let mut nonce_material = [0u8; 16];
+                crypto_rng.fill_bytes(&mut nonce_material);
+                (current_key, nonce_material)
+            };
+            ratchet_index += 1;
+
+            // Baseado no nome da cifra, escolhe o algoritmo apropriado
+            // AES-GCM, ChaCha20 ou Megolm-Like (AES-CTR + HMAC)
+            // Cada algoritmo é configurado com o nonce/IV e a chave da mensagem
+            // Mede apenas a chamada de cifragem desta mensagem (granularidade fina)
+            // Os tamanhos já foram validados contra os limites das cifras em main
+            // (check_fixed_size_limits); a verificação por mensagem fica fora do tempo medido
+            cipher.check_plaintext_size(plaintext.len())?;
+            let mut allocated;
+            let (ciphertext, nonce_len, elapsed_msg) = if config.in_place {
+                // --in-place: o texto claro é copiado, fora do tempo medido, para o buffer
+                // reutilizado, com folga para a tag e o compromisso de chave; a cifra o
+                // sobrescreve sem alocar
+                in_place_buffer.clear();
+                in_place_buffer.reserve(plaintext.len() + 32 + KEY_COMMITMENT_SIZE);
+                in_place_buffer.extend_from_slice(&plaintext);
+                let start_msg = Instant::now();
+                let encrypted = cipher.encrypt_in_place(&message_key, &nonce_material, &mut in_place_buffer);
+                let elapsed_msg = start_msg.elapsed();
+                let nonce_len = encrypted?;
+                (&mut in_place_buffer, nonce_len, elapsed_msg)
+            } else if config.tag_layout == TagLayout::Detached {
+                // --tag-layout detached: a tag sai da cifra em um campo à parte; fora do
+                // tempo medido, ela é reunida ao texto cifrado para a contagem de bytes,
+                // que é a mesma do layout anexado
+                let start_msg = Instant::now();
+                let encrypted = cipher.encrypt_detached(&message_key, &nonce_material, &plaintext);
+                let elapsed_msg = start_msg.elapsed();
+                let (tag, nonce_len);
+                (allocated, tag, nonce_len) = encrypted?;
+                allocated.extend_from_slice(tag.as_slice());
+                (&mut allocated, nonce_len, elapsed_msg)
+            } else {
+                let start_msg = Instant::now();
+                let encrypted = cipher.encrypt(&message_key, &nonce_material, &plaintext);
+                let elapsed_msg = start_msg.elapsed();
+                let nonce_len;
+                (allocated, nonce_len) = encrypted?;
+                (&mut allocated, nonce_len, elapsed_msg)
+            };
+            metrics.cipher_only_time += elapsed_msg;
+            if config.size_time_correlation || config.size_weighted {
+                metrics.size_time_pairs.push((plaintext.len() as f64, elapsed_msg.as_secs_f64() * 1e6));
+            }
+
+            // Compromisso de chave (--key-commitment): SHA-256 da chave e do nonce anexado ao
+            // texto cifrado, com tempo medido à parte; independe da cifra escolhida
+            if config.key_commitment {
+                let start_commit = Instant::now();
+                let commitment = key_commitment(&message_key, &nonce_material[..nonce_len]);
+                ciphertext.extend_from_slice(&commitment);
+                metrics.commitment_time += start_commit.elapsed();
+            }
+
+            // Exemplo de cifragem da primeira mensagem (--dump-sample), fora dos tempos medidos
+            if record_sample && metrics.messages == 0 {
+                metrics.ciphertext_sample = Some(CiphertextSample {
+                    plaintext_bytes: plaintext.len(),
+                    plaintext_sha256: Sha256::digest(&plaintext).into(),
+                    key: message_key,
+                    nonce: nonce_material[..nonce_len].to_vec(),
+                    ciphertext: ciphertext.clone(),
+                });
+            }
+
+            // Amostras por tipo de mensagem (--per-type-stats): tempo desta cifragem e bytes enviados
+            if config.per_type_stats {
+                metrics.kind_samples[message.kind_index()]
+                    .push((elapsed_msg.as_secs_f64() * 1e6, (ciphertext.len() + nonce_len) as f64));
+            }
+
+            // Atualiza métricas de largura de banda
+            metrics.msg_bandwidth += ciphertext.len() + nonce_len;
+            metrics.plaintext_bytes += message_gen.get_message_size(&message);
+            metrics.messages += 1;
+            messages_since_rotation += 1;
+            bytes_since_rotation += ciphertext.len() + nonce_len;
+            if record_series {
+                metrics.bandwidth_series.push(BandwidthPoint {
+                    message_index: metrics.messages,
+                    kem_bytes: metrics.kem_bandwidth,
+                    msg_bytes: metrics.msg_bandwidth,
+                    smoothed_bytes: f64::NAN,
+                });
+            }
+
+            // Edição de mensagem (--edit-rate): o Matrix reenvia o conteúdo inteiro como um
+            // novo evento, cifrado com a chave de sessão atual e um nonce novo. Só textos são
+            // editáveis; o alvo é o último texto enviado. Tempo e bytes ficam à parte de
+            // cipher_only_ms e msg_bw_bytes, como os re-keys ficam à parte da rotação
+            if matches!(message, MessageType::Text(_)) {
+                last_text = Some(plaintext);
+            }
+            if let Some(text) = last_text.as_ref().filter(|_| edit_rate > 0.0 && crypto_rng.gen_bool(edit_rate)) {
+                let (edit_key, edit_nonce) = if per_message_kdf {
+                    derive_message_key(&current_key, ratchet_index)
+                } else {
+                    let mut nonce_material = [0u8; 16];
+                    crypto_rng.fill_bytes(&mut nonce_material);
+                    (current_key, nonce_material)
+                };
+                ratchet_index += 1;
+                cipher.check_plaintext_size(text.len())?;
+                flush_caches(&mut cache_flusher, &mut flush_time);
+                let start_edit = Instant::now();
+                let (mut edited, nonce_len) = cipher.encrypt(&edit_key, &edit_nonce, text)?;
+                if config.key_commitment {
+                    edited.extend_from_slice(&key_commitment(&edit_key, &edit_nonce[..nonce_len]));
+                }
+                metrics.edit_time += start_edit.elapsed();
+                metrics.edit_bandwidth += edited.len() + nonce_len;
+                metrics.edit_events += 1;
+            }
+
+            // Cadência entre mensagens (--inter-message-delay): avança o relógio simulado
+            // ou dorme de fato; a espera não conta como consulta ao padrão de tráfego
+            if !inter_message_delay.is_zero() {
+                match config.pacing {
+                    Pacing::Simulated => clock_offset += inter_message_delay,
+                    Pacing::Sleep => std::thread::sleep(inter_message_delay),
+                }
+            }
+        }
+    }
+
+    metrics.loop_time = start_enc.elapsed().saturating_sub(flush_time + hash_time);
+    if let Some(flusher) = cache_flusher {
+        flusher.release();
+    }
+    Ok(metrics)
+}
+
+/// Executa todas as repetições de uma configuração e calcula suas estatísticas
+fn run_configuration(
+    spec: &ConfigSpec,
+    config: &ExperimentConfig,
+    trace: &WorkloadTrace,
+    corpus: Option<&Arc<PayloadCorpus>>,
+    total_configs: usize,
+) -> Result<ConfigResult, ExperimentError> {
+    print_config_header(spec, total_configs);
+    let mut run = ConfigRun::new(spec, config, trace, corpus)?;
+    for rep in 0..run.repeticoes {
+        if rep % 10 == 0 {
+            println!("  Repetição {}/{}", rep + 1, run.repeticoes);
+        }
+        if !run.run_repetition(trace, rep)? {
+            break;
+        }
+    }
+    Ok(run.finish())
+}
+
+/// Pool de workers de `--jobs`; com um único job não há pool e tudo roda na thread atual
+fn worker_pool(jobs: usize) -> Result<Option<rayon::ThreadPool>, ExperimentError> {
+    match jobs {
+        1 => Ok(None),
+        _ => Ok(Some(rayon::ThreadPoolBuilder::new().num_threads(jobs).build().map_err(std::io::Error::other)?)),
+    }
+}
+
+/// Executa as configurações de `order` no pool (ou em sequência, sem ele) e envia cada
+/// (índice, resultado) a `sender` assim que fica pronto
+///
+/// `should_stop` é consultado antes de cada configuração; depois do primeiro erro, ou de
+/// um envio recusado, nenhuma configuração nova é iniciada.
+fn run_specs(
+    order: &[&ConfigSpec],
+    pool: Option<&rayon::ThreadPool>,
+    config: &ExperimentConfig,
+    trace: &WorkloadTrace,
+    corpus: Option<&Arc<PayloadCorpus>>,
+    should_stop: &(impl Fn() -> bool + Sync),
+    sender: mpsc::Sender<(usize, Result<ConfigResult, ExperimentError>)>,
+) {
+    let total_configs = order.len();
+    let stopped = AtomicBool::new(false);
+    let run = |sender: &mut mpsc::Sender<_>, spec: &&ConfigSpec| {
+        if stopped.load(Ordering::Relaxed) || should_stop() {
+            return;
+        }
+        let result = run_configuration(spec, config, trace, corpus, total_configs);
+        let failed = result.is_err();
+        if sender.send((spec.index, result)).is_err() || failed {
+            stopped.store(true, Ordering::Relaxed);
+        }
+    };
+    match pool {
+        Some(pool) => pool.install(|| order.par_iter().for_each_with(sender, run)),
+        None => {
+            let mut sender = sender;
+            order.iter().for_each(|spec| run(&mut sender, spec));
+        }
+    }
+}
+
+/// Métricas classificadas como não normais, para o modo estrito (`--fail-on-nonnormal`)
+///
+/// Usa o `is_normal` já calculado de cada métrica; `selected` vazio considera todas as
+/// de `NORMALITY_METRICS`. Retorna uma descrição "configuração: métrica" por violação.
+fn nonnormal_metrics(results: &[ConfigResult], selected: &[String]) -> Vec<String> {
+    let mut violations = Vec::new();
+    for result in results {
+        for name in NORMALITY_METRICS {
+            let checked = selected.is_empty() || selected.iter().any(|metric| metric == name);
+            if checked && result.normality_stats(name).is_some_and(|stats| !stats.is_normal) {
+                violations.push(format!("{:?} + {:?} + {} + {}: {}",
+                                        result.spec.scenario, result.spec.pattern,
+                                        result.spec.agreement, result.spec.cipher, name));
+            }
+        }
+    }
+    violations
+}
+
+/// Taxa global de outliers, em uma métrica de tempo, acima da qual a execução é dada como ruidosa
+const OUTLIER_RATE_WARNING: f64 = 0.10;
+
+/// Outliers de uma métrica somados sobre todas as configurações da execução
+#[derive(Debug, Clone, PartialEq)]
+struct OutlierRate {
+    metric: &'static str,
+    samples: usize,  // Valores finitos antes da remoção de outliers
+    outliers: usize, // Outliers moderados + extremos
+    extreme: usize,  // Apenas os extremos
+    nonfinite: usize, // Valores NaN/Inf descartados antes da análise
+    invalid: usize,  // Configurações em que todos os valores eram não finitos
+}
+
+impl OutlierRate {
+    /// Fração das amostras marcada como outlier (moderado ou extremo)
+    fn rate(&self) -> f64 {
+        if self.samples == 0 { 0.0 } else { self.outliers as f64 / self.samples as f64 }
+    }
+
+    /// Fração das amostras marcada como outlier extremo
+    fn extreme_rate(&self) -> f64 {
+        if self.samples == 0 { 0.0 } else { self.extreme as f64 / self.samples as f64 }
+    }
+}
+
+/// Resumo das taxas de outliers de cada métrica sobre toda a execução
+///
+/// Soma as contagens por configuração (`outliers_count`, `extreme_outliers_count`,
+/// `nonfinite_count`) e as amostras antes da remoção; uma taxa global alta indica máquina
+/// ruidosa ou erro de medição. Métricas sem amostras (ex: compressão desligada) ficam de fora.
+fn outlier_summary(results: &[ConfigResult]) -> Vec<OutlierRate> {
+    let mut summary: Vec<OutlierRate> = Vec::new();
+    for result in results {
+        for (metric, stats) in result.distributions() {
+            let entry = match summary.iter_mut().position(|entry| entry.metric == metric) {
+                Some(position) => &mut summary[position],
+                None => {
+                    summary.push(OutlierRate { metric, samples: 0, outliers: 0, extreme: 0, nonfinite: 0, invalid: 0 });
+                    let last = summary.len() - 1;
+                    &mut summary[last]
+                }
+            };
+            entry.samples += stats.original_size;
+            entry.outliers += stats.outliers_count;
+            entry.extreme += stats.extreme_outliers_count;
+            entry.nonfinite += stats.nonfinite_count;
+            entry.invalid += usize::from(stats.is_invalid());
+        }
+    }
+    summary.retain(|entry| entry.samples > 0 || entry.nonfinite > 0);
+    summary
+}
+
+/// Imprime o resumo de outliers da execução, com aviso para as métricas ruidosas
+///
+/// Só as métricas de tempo (`*_ms`) entram no aviso: nas de banda, os outliers vêm da
+/// variação natural dos tamanhos de mensagem do workload, não da máquina.
+fn print_outlier_summary(summary: &[OutlierRate]) {
+    println!("\n=== RESUMO DE OUTLIERS (todas as configurações) ===");
+    for entry in summary {
+        println!("  {}: {}/{} amostras outliers ({:.1}%), {} extremos ({:.1}%)",
+                 entry.metric, entry.outliers, entry.samples, entry.rate() * 100.0,
+                 entry.extreme, entry.extreme_rate() * 100.0);
+    }
+    // Qualidade dos dados: valores não finitos nunca deveriam aparecer em uma medição válida
+    let nonfinite: Vec<String> = summary.iter()
+        .filter(|entry| entry.nonfinite > 0)
+        .map(|entry| format!("{} ({} valores, {} configurações inválidas)", entry.metric, entry.nonfinite, entry.invalid))
+        .collect();
+    if !nonfinite.is_empty() {
+        println!("[AVISO] Valores não finitos (NaN/Inf) descartados em {}; ver a coluna nonfinite_count",
+                 nonfinite.join(", "));
+    }
+    let noisy: Vec<&str> = summary.iter()
+        .filter(|entry| entry.metric.ends_with("_ms") && entry.rate() > OUTLIER_RATE_WARNING)
+        .map(|entry| entry.metric)
+        .collect();
+    if !noisy.is_empty() {
+        println!("[AVISO] Taxa de outliers acima de {:.0}% em {}: máquina ruidosa ou erro de medição?",
+                 OUTLIER_RATE_WARNING * 100.0, noisy.join(", "));
+    }
+}
+
+/// Permuta a ordem de execução das configurações (`--shuffle-configs`)
+///
+/// A permutação vem da semente base e da rodada (0 fora do `--interleave`), de modo
+/// que a mesma `--seed` reproduz a mesma ordem. Sem a opção, a ordem canônica é mantida.
+fn shuffle_execution_order<T>(items: &mut [T], config: &ExperimentConfig, round: usize) {
+    if config.shuffle_configs {
+        let seed = stream_seed(config.seed.unwrap_or_default(), round, 0, SeedStream::Order);
+        items.shuffle(&mut StdRng::seed_from_u64(seed));
+    }
+}
+
+/// Imprime o cabeçalho de progresso de uma configuração
+fn print_config_header(spec: &ConfigSpec, total_configs: usize) {
+    println!("\n{}/{}. Configuração: {:?} + {:?} + {} + {}{}",
+             spec.index + 1, total_configs, spec.scenario, spec.pattern, spec.agreement, spec.cipher,
+             spec.message_count.map(|count| format!(" ({} mensagens)", count)).unwrap_or_default());
+}
+
+/// Estado de uma configuração em execução: parâmetros e métricas acumuladas
+///
+/// Separa cada repetição (`run_repetition`) da análise final (`finish`), de modo que
+/// as repetições de várias configurações possam ser intercaladas (`--interleave`).
+struct ConfigRun<'a> {
+    spec: &'a ConfigSpec,
+    config: &'a ExperimentConfig,
+    num_messages: usize,
+    msgs_por_rotacao: usize,
+    repeticoes: usize,
+    seed: u64,
+    rotation: RotationStrategy,
+    cached_keys: Option<Vec<RecipientKeys>>,
+    corpus: Option<&'a Arc<PayloadCorpus>>,
+    devices_per_user: usize,
+    senders: usize,
+    // Tempo de parede acumulado da configuração: preparação e todas as repetições
+    wall_time: Duration,
+    // Instante de início de cada repetição no relógio do sistema (ns desde a época Unix)
+    repetition_starts_ns: Vec<u128>,
+    kem_times: DurationSamples,
+    // Parte de kem_times gasta nos decapsulamentos (receptores), por repetição
+    kem_receiver_times: DurationSamples,
+    cipher_times: DurationSamples,
+    cipher_only_times: DurationSamples,
+    compress_times: DurationSamples,
+    commitment_times: DurationSamples,
+    // Bytes (originais, comprimidos) acumulados por tipo de mensagem, na ordem de MESSAGE_KINDS
+    compression_bytes: [(usize, usize); MESSAGE_KINDS.len()],
+    kem_bws: Vec<f64>,
+    msg_bws: Vec<f64>,
+    kem_rates: Vec<f64>,
+    msg_rates: Vec<f64>,
+    upload_bytes: Vec<f64>,
+    download_bytes: Vec<f64>,
+    storage_bytes: Vec<f64>,
+    expansion_ratios: Vec<f64>,
+    aggregate_throughputs: Vec<f64>,
+    per_sender_throughputs: Vec<f64>,
+    message_latencies: Vec<f64>,
+    total_rotations_per_run: usize,
+    stalled_sessions: usize,
+    text_count: usize,
+    image_count: usize,
+    file_count: usize,
+    system_count: usize,
+    voice_count: usize,
+    size_time_pairs: Vec<(f64, f64)>,
+    kind_samples: [Vec<(f64, f64)>; MESSAGE_KINDS.len()],
+    // Hashes dos payloads de mídia de toda a configuração (repetições e remetentes)
+    payload_hashes: HashSet<[u8; 32]>,
+    duplicate_payloads: usize,
+    bandwidth_series: Vec<BandwidthPoint>,
+    ciphertext_sample: Option<CiphertextSample>,
+    rekey_events: usize,
+    rekey_time: Duration,
+    rekey_bandwidth: usize,
+    edit_events: usize,
+    edit_time: Duration,
+    edit_bandwidth: usize,
+    confirm_time: Duration,
+    confirm_bandwidth: usize,
+    combine_time: Duration,
+    backup_ops: usize,
+    backup_time: Duration,
+    backup_bandwidth: usize,
+    // Decapsulamentos Kyber (sucesso e rejeição implícita) somados em todas as repetições
+    decap_ok_time: Duration,
+    decap_ok_count: usize,
+    decap_fail_time: Duration,
+    decap_fail_count: usize,
+}
+
+impl<'a> ConfigRun<'a> {
+    fn new(
+        spec: &'a ConfigSpec,
+        config: &'a ExperimentConfig,
+        trace: &WorkloadTrace,
+        corpus: Option<&'a Arc<PayloadCorpus>>,
+    ) -> Result<Self, ExperimentError> {
+        let start = Instant::now();
+        let cenario = &spec.scenario;
+        let padrao = &spec.pattern;
+        let acordo: &str = &spec.agreement;
+
+        // Obtém parâmetros específicos do cenário
+        // Define número de mensagens por rotação e total de mensagens
+        // Baseado na configuração do cenário
+        // Exemplo: SmallChat pode ter 10 mensagens por rotação, 100 no total
+        // MediumGroup pode ter 20 mensagens por rotação, 200 no total
+        // LargeChannel pode ter 50 mensagens por rotação, 500 no total
+        // SystemChannel pode ter 100 mensagens por rotação, 1000 no total
+        // Estes valores são configuráveis e podem ser ajustados conforme necessário
+        // Com --quick, repetições e mensagens são reduzidas ao mínimo (teste de fumaça)
+        let (msgs_por_rotacao, num_messages) = message_plan(spec, config);
+        let repeticoes = planned_repetitions(spec, config, trace);
+
+        // Semente própria da configuração: independe da ordem de execução e de --jobs
+        let seed = config_seed(
+            config.seed.unwrap_or_default(),
+            &format!("{:?}", cenario), &format!("{:?}", padrao), acordo, spec.cipher,
+        );
+        let rotation = config.rotation_policy(cenario).strategy(cenario, msgs_por_rotacao);
+        // Com --keygen-policy cached, as chaves de Bob são geradas uma única vez aqui, fora
+        // de qualquer medição, e compartilhadas por todas as repetições e remetentes
+        let cached_keys = if config.keygen_policy == KeygenPolicy::Cached {
+            let mut keys_rng = config.entropy_source().session_rng(stream_seed(seed, 0, 0, SeedStream::Keys));
+            let agreements = agreement_registry(config)?;
+            let agreement = agreements.lookup(acordo)?;
+            Some(generate_bob_devices(agreement, config.devices_per_user, &mut keys_rng))
+        } else {
+            None
+        };
+
+        // Inicializa vetores para coleta de métricas
+        Ok(ConfigRun {
+            spec,
+            config,
+            num_messages,
+            msgs_por_rotacao,
+            repeticoes,
+            seed,
+            rotation,
+            cached_keys,
+            corpus,
+            devices_per_user: config.devices_per_user,
+            senders: config.senders,
+            wall_time: start.elapsed(),
+            repetition_starts_ns: Vec::with_capacity(repeticoes),
+            kem_times: DurationSamples::with_capacity(repeticoes),
+            kem_receiver_times: DurationSamples::with_capacity(repeticoes),
+            cipher_times: DurationSamples::with_capacity(repeticoes),
+            cipher_only_times: DurationSamples::with_capacity(repeticoes),
+            compress_times: DurationSamples::with_capacity(repeticoes),
+            commitment_times: DurationSamples::with_capacity(repeticoes),
+            compression_bytes: [(0, 0); MESSAGE_KINDS.len()],
+            kem_bws: Vec::with_capacity(repeticoes),
+            msg_bws: Vec::with_capacity(repeticoes),
+            kem_rates: Vec::with_capacity(repeticoes),
+            msg_rates: Vec::with_capacity(repeticoes),
+            upload_bytes: Vec::with_capacity(repeticoes),
+            download_bytes: Vec::with_capacity(repeticoes),
+            storage_bytes: Vec::with_capacity(repeticoes),
+            expansion_ratios: Vec::with_capacity(repeticoes),
+            aggregate_throughputs: Vec::with_capacity(repeticoes),
+            per_sender_throughputs: Vec::with_capacity(repeticoes),
+            message_latencies: Vec::with_capacity(repeticoes),
+            total_rotations_per_run: 0,
+            stalled_sessions: 0,
+            text_count: 0,
+            image_count: 0,
+            file_count: 0,
+            system_count: 0,
+            voice_count: 0,
+            size_time_pairs: Vec::new(),
+            kind_samples: Default::default(),
+            payload_hashes: HashSet::new(),
+            duplicate_payloads: 0,
+            bandwidth_series: Vec::new(),
+            ciphertext_sample: None,
+            rekey_events: 0,
+            rekey_time: Duration::ZERO,
+            rekey_bandwidth: 0,
+            edit_events: 0,
+            edit_time: Duration::ZERO,
+            edit_bandwidth: 0,
+            confirm_time: Duration::ZERO,
+            confirm_bandwidth: 0,
+            combine_time: Duration::ZERO,
+            backup_ops: 0,
+            backup_time: Duration::ZERO,
+            backup_bandwidth: 0,
+            decap_ok_time: Duration::ZERO,
+            decap_ok_count: 0,
+            decap_fail_time: Duration::ZERO,
+            decap_fail_count: 0,
+        })
+    }
+
+    /// Número de repetições já executadas
+    fn completed(&self) -> usize {
+        self.kem_times.len()
+    }
+
+    /// Executa uma repetição e acumula suas métricas
+    ///
+    /// Retorna `Ok(false)` quando a configuração não precisa de mais repetições, ou seja,
+    /// quando a amostragem adaptativa (`--target-moe`) atingiu a margem alvo. A falha
+    /// de qualquer sessão (E/S do workload, primitiva criptográfica) é devolvida.
+    fn run_repetition(&mut self, trace: &WorkloadTrace, rep: usize) -> Result<bool, ExperimentError> {
+        let start = Instant::now();
+        self.repetition_starts_ns.push(unix_nanos());
+        let (spec, config) = (self.spec, self.config);
+        let plan = SessionPlan {
+            num_messages: self.num_messages,
+            rotation: self.rotation,
+            seed: self.seed,
+            cached_keys: self.cached_keys.as_deref(),
+            corpus: self.corpus,
+        };
+        let senders = self.senders;
+
+        // Uma sessão por remetente; com --senders N > 1, as N sessões rodam em threads
+        // simultâneas e disputam a CPU, como os remetentes de um canal grande
+        let sessions: Vec<SessionMetrics> = if senders == 1 {
+            vec![run_session(spec, config, trace, rep, 0, plan)?]
+        } else {
+            std::thread::scope(|scope| {
+                let handles: Vec<_> = (0..senders)
+                    .map(|sender| scope.spawn(move || {
+                        run_session(spec, config, trace, rep, sender, plan)
+                    }))
+                    .collect();
+                // O pânico de um remetente é repassado à thread da configuração
+                handles.into_iter()
+                    .map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+                    .collect::<Result<Vec<_>, _>>()
+            })?
+        };
+
+        // Agrega as sessões desta repetição: custos e bytes somados entre remetentes,
+        // tempo de parede do laço dado pelo remetente mais lento
+        let mut total_kem_time = Duration::ZERO;
+        let mut total_decap_time = Duration::ZERO;
+        let mut total_cipher_only_time = Duration::ZERO;
+        let mut total_compress_time = Duration::ZERO;
+        let mut total_commitment_time = Duration::ZERO;
+        let mut total_loop_time = Duration::ZERO;
+        let mut total_kem_bandwidth = 0;
+        let mut total_kem_upload = 0;
+        let mut total_kem_download = 0;
+        let mut total_msg_bandwidth = 0;
+        let mut total_plaintext_bytes = 0;
+        let mut total_messages = 0;
+        let mut sender_throughput_sum = 0.0;
+        for session in sessions {
+            total_kem_time += session.kem_time;
+            total_decap_time += session.decap_ok_time + session.decap_fail_time;
+            total_cipher_only_time += session.cipher_only_time;
+            total_compress_time += session.compress_time;
+            total_commitment_time += session.commitment_time;
+            total_loop_time = total_loop_time.max(session.loop_time);
+            total_kem_bandwidth += session.kem_bandwidth;
+            total_kem_upload += session.kem_upload;
+            total_kem_download += session.kem_download;
+            total_msg_bandwidth += session.msg_bandwidth;
+            total_plaintext_bytes += session.plaintext_bytes;
+            total_messages += session.messages;
+            // 0 para um laço sem duração mensurável, como nas taxas em bps abaixo
+            let loop_secs = session.loop_time.as_secs_f64();
+            sender_throughput_sum += if loop_secs > 0.0 { session.messages as f64 / loop_secs } else { 0.0 };
+            self.text_count += session.text_count;
+            self.image_count += session.image_count;
+            self.file_count += session.file_count;
+            self.system_count += session.system_count;
+            self.voice_count += session.voice_count;
+            for (total, kind) in self.compression_bytes.iter_mut().zip(session.compression_bytes) {
+                total.0 += kind.0;
+                total.1 += kind.1;
+            }
+            self.size_time_pairs.extend(session.size_time_pairs);
+            for (total, samples) in self.kind_samples.iter_mut().zip(session.kind_samples) {
+                total.extend(samples);
+            }
+            for hash in session.payload_hashes {
+                if !self.payload_hashes.insert(hash) {
+                    self.duplicate_payloads += 1;
+                }
+            }
+            self.decap_ok_time += session.decap_ok_time;
+            self.decap_ok_count += session.decap_ok_count;
+            self.decap_fail_time += session.decap_fail_time;
+            self.decap_fail_count += session.decap_fail_count;
+            self.stalled_sessions += usize::from(session.stalled);
+            self.rekey_events += session.rekey_events;
+            self.rekey_time += session.rekey_time;
+            self.rekey_bandwidth += session.rekey_bandwidth;
+            self.edit_events += session.edit_events;
+            self.edit_time += session.edit_time;
+            self.edit_bandwidth += session.edit_bandwidth;
+            self.confirm_time += session.confirm_time;
+            self.confirm_bandwidth += session.confirm_bandwidth;
+            self.combine_time += session.combine_time;
+            self.backup_ops += session.backup_ops;
+            self.backup_time += session.backup_time;
+            self.backup_bandwidth += session.backup_bandwidth;
+            if !session.bandwidth_series.is_empty() {
+                let series = smooth_series(&session.bandwidth_series, config.series_window);
+                self.bandwidth_series = downsample(&series, MAX_SERIES_POINTS);
+            }
+            if session.ciphertext_sample.is_some() {
+                self.ciphertext_sample = session.ciphertext_sample;
+            }
+            self.total_rotations_per_run = session.rotations;           // Total de rotações nesta sessão
+        }
+
+        // Armazena resultados desta repetição
+        // Coleta tempos de KEM e cifragem (exatos, em ns), largura de banda e contadores de mensagens
+        self.kem_times.push(total_kem_time);
+        self.kem_receiver_times.push(total_decap_time);
+        self.cipher_times.push(total_loop_time);  // Tempo de parede do laço
+        self.cipher_only_times.push(total_cipher_only_time); // Apenas cifragem
+        self.compress_times.push(total_compress_time);
+        self.commitment_times.push(total_commitment_time);
+        self.kem_bws.push(total_kem_bandwidth as f64);                   // Largura de banda KEM em bytes
+        self.msg_bws.push(total_msg_bandwidth as f64);                   // Largura de banda de mensagens em bytes
+        // Taxas efetivas em bits por segundo: os totais acima sobre o tempo de parede da
+        // repetição inteira (sessões, acordos de chave e laço); 0 se nada foi medido
+        let rep_secs = start.elapsed().as_secs_f64();
+        let rate = |bytes: usize| if rep_secs > 0.0 { bytes as f64 * 8.0 / rep_secs } else { 0.0 };
+        self.kem_rates.push(rate(total_kem_bandwidth));
+        self.msg_rates.push(rate(total_msg_bandwidth));
+        // Bytes por direção, do ponto de vista do remetente: as mensagens cifradas e o texto
+        // cifrado Kyber sobem; as chaves públicas de Bob descem
+        self.upload_bytes.push((total_kem_upload + total_msg_bandwidth) as f64);
+        self.download_bytes.push(total_kem_download as f64);
+        // Armazenamento no servidor: o homeserver persiste cada evento que retransmite, ou seja,
+        // as mensagens cifradas e o texto cifrado KEM enviado ao destinatário. As chaves
+        // públicas que Alice baixa já estavam guardadas no servidor antes da sessão: contá-las
+        // a cada download somaria o mesmo material uma vez por busca
+        self.storage_bytes.push((total_kem_upload + total_msg_bandwidth) as f64);
+        // Razão de expansão: bytes transmitidos (cifrado + nonce/IV) por byte de texto claro
+        self.expansion_ratios.push(total_msg_bandwidth as f64 / total_plaintext_bytes.max(1) as f64);
+        // Throughput sob contenção: agregado (todos os remetentes) e médio por remetente
+        let loop_secs = total_loop_time.as_secs_f64();
+        self.aggregate_throughputs.push(if loop_secs > 0.0 { total_messages as f64 / loop_secs } else { 0.0 });
+        self.per_sender_throughputs.push(sender_throughput_sum / senders as f64);
+        // Latência média de cifragem por mensagem, em microssegundos
+        self.message_latencies.push(total_cipher_only_time.as_secs_f64() * 1e6 / total_messages.max(1) as f64);
+
+        self.wall_time += start.elapsed();
+
+        // Amostragem adaptativa (--target-moe): ao fim de cada lote de ADAPTIVE_BATCH
+        // repetições, recalcula as estatísticas dos tempos de KEM e do laço e para quando
+        // as duas margens de erro relativas ficam abaixo do alvo
+        let done = self.completed();
+        if let Some(target) = config.target_moe
+            && (done.is_multiple_of(ADAPTIVE_BATCH) || done == self.repeticoes)
+        {
+            let moe = |samples: &DurationSamples, label: &str| relative_moe(&calculate_duration_stats(
+                samples, TimeUnit::Millis, label, config.central, !config.no_outlier_removal,
+            ));
+            let kem_moe = moe(&self.kem_times, "KEM Times (parcial)");
+            let cipher_moe = moe(&self.cipher_times, "Cipher Times (parcial)");
+            println!("  [AMOSTRAGEM] {} repetições: margem relativa KEM {:.4}, laço {:.4} (alvo {})",
+                     done, kem_moe, cipher_moe, target);
+            if kem_moe < target && cipher_moe < target {
+                println!("  [AMOSTRAGEM] Margem alvo atingida com {} repetições", done);
+                return Ok(false);
+            }
+            if done == self.repeticoes {
+                println!("  [AVISO] Margem alvo não atingida em {} repetições (--max-repetitions)", done);
+            }
+        }
+        Ok(done < self.repeticoes)
+    }
+
+    /// Executa a análise estatística das repetições acumuladas
+    fn finish(self) -> ConfigResult {
+        let (spec, config) = (self.spec, self.config);
+        let repetitions_run = self.completed();
+
+        // Executa análise estatística adaptativa nos dados coletados
+        if config.detect_duplicates {
+            let report = if self.duplicate_payloads > 0 { "[AVISO]" } else { "[QUALIDADE]" };
+            println!("  {} Payloads de mídia: {} distintos, {} duplicados",
+                     report, self.payload_hashes.len(), self.duplicate_payloads);
+        }
+
+        println!("  Analisando normalidade e calculando estatísticas...");
+        let adaptive_stats = |data: &[f64], label: &str| {
+            calculate_adaptive_stats(data, label, config.central, !config.no_outlier_removal)
+        };
+        let time_stats = |samples: &DurationSamples, label: &str| {
+            calculate_duration_stats(samples, TimeUnit::Millis, label, config.central, !config.no_outlier_removal)
+        };
+        let kem_time_stats = time_stats(&self.kem_times, "KEM Times");
+        let cipher_time_stats = time_stats(&self.cipher_times, "Cipher Times");
+        let cipher_only_time_stats = time_stats(&self.cipher_only_times, "Cipher-Only Times");
+        let compress_time_stats = time_stats(&self.compress_times, "Compression Times");
+        let commitment_time_stats = time_stats(&self.commitment_times, "Key Commitment Times");
+        let kem_bw_stats = adaptive_stats(&self.kem_bws, "KEM Bandwidth");
+        let msg_bw_stats = adaptive_stats(&self.msg_bws, "Message Bandwidth");
+        // Estatísticas por tipo de mensagem (--per-type-stats), apenas dos tipos presentes
+        let type_stats = std::array::from_fn(|index| {
+            let samples = &self.kind_samples[index];
+            let kind = MESSAGE_KINDS[index];
+            (!samples.is_empty()).then(|| {
+                let (times, bytes): (Vec<f64>, Vec<f64>) = samples.iter().copied().unzip();
+                TypeStats {
+                    cipher_us: adaptive_stats(&times, &format!("Cipher Times ({})", kind)),
+                    msg_bytes: adaptive_stats(&bytes, &format!("Message Bytes ({})", kind)),
+                }
+            })
+        });
+
+        // Calcula médias dos contadores de tipos de mensagens
+        let total_repetitions = repetitions_run as f64;
+
+        // Razão de expansão média: quantifica o overhead de tag e nonce como fração do payload
+        let expansion_ratio = average(&self.expansion_ratios);
+        // Tempo médio por decapsulamento, em µs; NaN quando o caminho não ocorreu
+        // (count conta textos cifrados, cada um decapsulado por --decaps-per-encap receptores)
+        let per_decap_us = |time: Duration, count: usize| {
+            let decapsulations = count * config.decaps_per_encap;
+            if decapsulations == 0 { f64::NAN } else { time.as_secs_f64() * 1e6 / decapsulations as f64 }
+        };
+        // Divisão do tempo de KEM: receptores (decapsulamentos) e remetente (o restante:
+        // encapsulamento, ECDH e geração de chaves de Bob quando por rotação)
+        let mean_ms = |samples: &DurationSamples| average(&samples.to_unit(TimeUnit::Millis));
+        let kem_receiver_ms = mean_ms(&self.kem_receiver_times);
+        let kem_sender_ms = mean_ms(&self.kem_times) - kem_receiver_ms;
+        // Latência por mensagem ponderada pelo tamanho (--size-weighted), sobre as mensagens
+        // de todas as repetições: as grandes dominam a média como dominam o tempo total
+        let weighted_latency_us = config.size_weighted.then(|| {
+            let (sizes, times): (Vec<f64>, Vec<f64>) = self.size_time_pairs.iter().copied().unzip();
+            weighted_mean(&times, &sizes)
+        });
+
+        ConfigResult {
+            spec: spec.clone(),
+            num_messages: self.num_messages,
+            msgs_per_rotation: self.msgs_por_rotacao,
+            rotations: self.total_rotations_per_run,
+            rotation_strategy: self.rotation,
+            ephemeral: config.ephemeral,
+            keygen_policy: config.keygen_policy,
+            duplicate_payloads: config.detect_duplicates.then_some(self.duplicate_payloads),
+            repetitions: repetitions_run,
+            config_wall_ms: self.wall_time.as_secs_f64() * 1000.0,
+            kem_bw_bps: average(&self.kem_rates),
+            msg_bw_bps: average(&self.msg_rates),
+            kem_time_stats,
+            cipher_time_stats,
+            kem_bw_stats,
+            msg_bw_stats,
+            avg_text: self.text_count as f64 / total_repetitions,
+            avg_image: self.image_count as f64 / total_repetitions,
+            avg_file: self.file_count as f64 / total_repetitions,
+            avg_system: self.system_count as f64 / total_repetitions,
+            avg_voice: self.voice_count as f64 / total_repetitions,
+            expansion_ratio,
+            authenticated: spec.cipher != "Megolm-Like" || !config.megolm_unauthenticated,
+            cipher_only_time_stats,
+            cipher_only_times: self.cipher_only_times,
+            kem_times: self.kem_times,
+            cipher_times: self.cipher_times,
+            compress_times: self.compress_times,
+            commitment_times: self.commitment_times,
+            repetition_starts_ns: self.repetition_starts_ns,
+            primitive_versions: primitive_versions(
+                &spec.agreement, spec.cipher, config.per_message_kdf || spec.scenario.ratchets_per_message(),
+            ),
+            fixed_size: config.fixed_size,
+            payload_entropy: config.payload_entropy,
+            size_time_pairs: self.size_time_pairs,
+            type_stats,
+            devices_per_user: self.devices_per_user,
+            group_size: kem_group_size(&spec.scenario, config),
+            kem_sender_ms,
+            kem_receiver_ms,
+            decapsulations: config.decaps_per_encap,
+            compression: config.compress,
+            envelope: config.envelope,
+            base64_media: config.base64_media,
+            compress_time_stats,
+            compression_ratios: self.compression_bytes.map(|(original, compressed)| {
+                if original == 0 { f64::NAN } else { compressed as f64 / original as f64 }
+            }),
+            senders: self.senders,
+            aggregate_throughput: average(&self.aggregate_throughputs),
+            per_sender_throughput: average(&self.per_sender_throughputs),
+            message_latency_us: average(&self.message_latencies),
+            weighted_latency_us,
+            key_commitment: config.key_commitment,
+            commitment_time_stats,
+            upload_bytes: average(&self.upload_bytes),
+            download_bytes: average(&self.download_bytes),
+            kem_failures: self.decap_fail_count as f64 / total_repetitions,
+            decap_ok_us: per_decap_us(self.decap_ok_time, self.decap_ok_count),
+            decap_fail_us: per_decap_us(self.decap_fail_time, self.decap_fail_count),
+            config_seed: self.seed,
+            stalled_sessions: self.stalled_sessions,
+            storage_bytes: average(&self.storage_bytes),
+            bandwidth_series: self.bandwidth_series,
+            ciphertext_sample: self.ciphertext_sample,
+            rekey_events: self.rekey_events as f64 / total_repetitions,
+            rekey_ms: self.rekey_time.as_secs_f64() * 1000.0 / total_repetitions,
+            rekey_bw: self.rekey_bandwidth as f64 / total_repetitions,
+            rekey_ms_per_event: if self.rekey_events == 0 {
+                f64::NAN
+            } else {
+                self.rekey_time.as_secs_f64() * 1000.0 / self.rekey_events as f64
+            },
+            edit_events: self.edit_events as f64 / total_repetitions,
+            edit_ms: self.edit_time.as_secs_f64() * 1000.0 / total_repetitions,
+            edit_bw: self.edit_bandwidth as f64 / total_repetitions,
+            confirm_ms: self.confirm_time.as_secs_f64() * 1000.0 / total_repetitions,
+            confirm_bw: self.confirm_bandwidth as f64 / total_repetitions,
+            combine_ms: self.combine_time.as_secs_f64() * 1000.0 / total_repetitions,
+            backup_ops: self.backup_ops as f64 / total_repetitions,
+            backup_ms: self.backup_time.as_secs_f64() * 1000.0 / total_repetitions,
+            backup_bw: self.backup_bandwidth as f64 / total_repetitions,
+            tag_layout: config.tag_layout,
+            relative_speed: f64::NAN,
+        }
+    }
+}
+
+/// Número de repetições por configuração (reduzido com `--quick`)
+///
+/// Com `--target-moe` (incompatível com `--quick`), é o teto da amostragem adaptativa,
+/// que pode parar antes.
+fn repetitions(config: &ExperimentConfig) -> usize {
+    if config.target_moe.is_some() {
+        config.max_repetitions.max(ADAPTIVE_BATCH)
+    } else if config.quick {
+        QUICK_REPETICOES
+    } else {
+        REPETICOES
+    }
+}
+
+/// Mensagens por rotação e total de mensagens por sessão (reduzidos com `--quick`)
+///
+/// O total imposto pela configuração (`--message-counts`) prevalece sobre o do cenário.
+/// Com `--rotation-ratio R`, o intervalo é o total dividido por R, arredondado para
+/// cima (ao menos 1), de modo que toda sessão tenha cerca de R rotações.
+fn message_plan(spec: &ConfigSpec, config: &ExperimentConfig) -> (usize, usize) {
+    let (msgs_por_rotacao, num_messages) = if config.quick {
+        (QUICK_MSGS_POR_ROTACAO, QUICK_MENSAGENS)
+    } else {
+        (get_rotation_config(&spec.scenario), get_message_count_config(&spec.scenario))
+    };
+    let num_messages = spec.message_count.unwrap_or(num_messages);
+    let msgs_por_rotacao = match config.rotation_ratio {
+        Some(ratio) => ((num_messages as f64 / ratio).ceil() as usize).max(1),
+        None => msgs_por_rotacao,
+    };
+    (msgs_por_rotacao, num_messages)
+}
+
+/// Repetições planejadas de uma configuração
+///
+/// No replay com `--target-moe`, o teto é o número de repetições gravadas: a
+/// amostragem adaptativa não pode ir além do que o arquivo reproduz.
+fn planned_repetitions(spec: &ConfigSpec, config: &ExperimentConfig, trace: &WorkloadTrace) -> usize {
+    match trace {
+        WorkloadTrace::Replay(replay) if config.target_moe.is_some() => {
+            repetitions(config).min(replay.recorded_repetitions(spec.index))
+        }
+        _ => repetitions(config),
+    }
+}
+
+/// Verifica, antes da execução, se o arquivo de replay cobre todas as sessões planejadas
+///
+/// Cada repetição de cada configuração precisa de uma sessão por remetente com pelo menos
+/// o número de mensagens da configuração; com `--target-moe`, ao menos um lote
+/// (ADAPTIVE_BATCH) de repetições. O erro traz a primeira sessão faltante e o total de problemas.
+fn check_replay(config: &ExperimentConfig, specs: &[ConfigSpec], trace: &WorkloadTrace) -> Result<(), ExperimentError> {
+    let WorkloadTrace::Replay(replay) = trace else { return Ok(()) };
+    let mut problems: Vec<String> = Vec::new();
+    for spec in specs {
+        let (_, num_messages) = message_plan(spec, config);
+        let repetitions = planned_repetitions(spec, config, trace);
+        if config.target_moe.is_some() && repetitions < ADAPTIVE_BATCH {
+            problems.push(format!(
+                "tem {} repetições gravadas para a configuração {}, mínimo {} com --target-moe",
+                repetitions, spec.index, ADAPTIVE_BATCH
+            ));
+            continue;
+        }
+        for repetition in 0..repetitions {
+            for sender in 0..config.senders {
+                if let Err(problem) = replay.check_session((spec.index, repetition, sender), num_messages) {
+                    problems.push(problem.to_string());
+                }
+            }
+        }
+    }
+    match problems.first() {
+        None => Ok(()),
+        Some(first) => Err(ExperimentError::Config(format!(
+            "o arquivo de replay {} ({} sessão(ões) com problema; execute o --record com as mesmas opções)",
+            first, problems.len()
+        ))),
+    }
+}
+
+/// Margem de erro relativa de uma estatística: semiamplitude do IC95 sobre a média
+fn relative_moe(stats: &Stats) -> f64 {
+    stats.ci95 / stats.mean.abs()
+}
+
+/// Configurações executadas nesta execução, na ordem canônica
+///
+/// Com `--quick`, a matriz fica restrita ao cenário SmallChat e os índices são
+/// renumerados para continuarem contíguos.
+fn experiment_specs(config: &ExperimentConfig) -> Result<Vec<ConfigSpec>, ExperimentError> {
+    if !config.message_counts.is_empty() {
+        return sweep_specs(config);
+    }
+    let mut specs = build_config_specs(&experiment_agreements(config));
+    if config.quick {
+        specs.retain(|spec| spec.scenario == UsageScenario::SmallChat);
+        for (index, spec) in specs.iter_mut().enumerate() {
+            spec.index = index;
+        }
+    }
+    Ok(specs)
+}
+
+/// Acordos executados: os padrão seguidos dos híbridos extras (`--hybrid`)
+fn experiment_agreements(config: &ExperimentConfig) -> Vec<Cow<'static, str>> {
+    let mut agreements: Vec<Cow<'static, str>> = AGREEMENTS.map(Cow::Borrowed).to_vec();
+    for name in &config.hybrid {
+        if !agreements.iter().any(|agreement| *agreement == *name) {
+            agreements.push(Cow::Owned(name.clone()));
+        }
+    }
+    agreements
+}
+
+/// Configurações do modo de amortização (`--message-counts`)
+///
+/// Uma única célula (--sweep-scenario, --sweep-pattern, --sweep-cipher), com cada
+/// acordo para cada total de mensagens, na ordem total → acordo. Uma cifra fora de
+/// `CIPHERS` é recusada, em vez de trocada em silêncio por outra.
+fn sweep_specs(config: &ExperimentConfig) -> Result<Vec<ConfigSpec>, ExperimentError> {
+    let cipher = CIPHERS.into_iter().find(|name| *name == config.sweep_cipher).ok_or_else(|| {
+        ExperimentError::Config(format!(
+            "--sweep-cipher: cifra desconhecida '{}' (opções: {})", config.sweep_cipher, CIPHERS.join(", ")
+        ))
+    })?;
+    let agreements = experiment_agreements(config);
+    let mut specs = Vec::new();
+    for &count in &config.message_counts {
+        for agreement in &agreements {
+            specs.push(ConfigSpec {
+                index: specs.len(),
+                scenario: config.sweep_scenario.clone(),
+                pattern: config.sweep_pattern.clone(),
+                agreement: agreement.clone(),
+                cipher,
+                message_count: Some(count),
+            });
+        }
+    }
+    Ok(specs)
+}
+
+/// Monta a matriz fatorial de configurações na ordem canônica, com os acordos informados
+fn build_config_specs<A: Clone + Into<Cow<'static, str>>>(agreements: &[A]) -> Vec<ConfigSpec> {
+    // Define configurações experimentais
+    let cenarios = UsageScenario::ALL;
+    let padroes_trafego = TrafficPattern::ALL;
+
+    let acordos = agreements;
+    let cifragens = CIPHERS;
+
+    // Itera sobre cenários, padrões de tráfego, acordos e cifragens
+    // total = 5 cenários * 5 padrões de tráfego * 2 acordos * 3 cifragens = 150 combinações
+    // (cada acordo extra de --hybrid acrescenta 75)
+    let mut specs = Vec::new();
+    for cenario in cenarios.iter() {
+        for padrao in padroes_trafego.iter() {
+            for acordo in acordos.iter() {
+                for cipher_name in cifragens.iter() {
+                    specs.push(ConfigSpec {
+                        index: specs.len(),
+                        scenario: cenario.clone(),
+                        pattern: padrao.clone(),
+                        agreement: acordo.clone().into(),
+                        cipher: cipher_name,
+                        message_count: None,
+                    });
+                }
+            }
+        }
+    }
+    specs
+}
+
+/// Cria (ou trunca) um arquivo de saída com buffer; a falha cita o arquivo
+fn create_output(path: &Path) -> Result<BufWriter<fs::File>, ExperimentError> {
+    let file = fs::File::create(path)
+        .map_err(|e| ExperimentError::from(e).context(format!("não foi possível criar {}", path.display())))?;
+    Ok(BufWriter::new(file))
+}
+
+/// Executa os testes de equivalência (TOST) entre AES-GCM e ChaCha20 em cada célula
+///
+/// Os resultados já coletados são agrupados por (cenário, padrão, acordo), de modo
+/// que a comparação independe da ordem em que as configurações foram executadas.
+/// Compara os tempos apenas de cifragem (`cipher_only`), livres do custo do laço.
+fn write_equivalence_tests(results: &[ConfigResult], margin: f64, path: &Path) -> Result<(), ExperimentError> {
+    let mut writer = create_output(path)?;
+    writeln!(writer, "cenario,padrao_trafego,acordo,cifra_a,cifra_b,margem_ms,diff_ms,p_value,equivalente")?;
+
+    for aes in results.iter().filter(|r| r.spec.cipher == "AES-GCM") {
+        let chacha = results.iter().find(|r| {
+            r.spec.cipher == "ChaCha20"
+                && r.spec.scenario == aes.spec.scenario
+                && r.spec.pattern == aes.spec.pattern
+                && r.spec.agreement == aes.spec.agreement
+        });
+        let Some(chacha) = chacha else { continue };
+
+        let (a, b) = (aes.cipher_only_times.to_unit(TimeUnit::Millis), chacha.cipher_only_times.to_unit(TimeUnit::Millis));
+        let (p_value, equivalent) = tost(&a, &b, margin);
+        let diff = a.iter().sum::<f64>() / a.len() as f64 - b.iter().sum::<f64>() / b.len() as f64;
+        println!("  [TOST] {:?} + {:?} + {}: AES-GCM vs ChaCha20: diff={:.4} ms, p={:.4}, Equivalentes={}",
+                 aes.spec.scenario, aes.spec.pattern, aes.spec.agreement, diff, p_value, equivalent);
+        writeln!(
+            writer,
+            "{:?},{:?},{},AES-GCM,ChaCha20,{},{:.4},{:.6},{}",
+            aes.spec.scenario, aes.spec.pattern, aes.spec.agreement, margin, diff, p_value, equivalent
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Compara as distribuições do acordo clássico e do híbrido em cada célula e cifra
+///
+/// Aplica o teste de Kolmogorov-Smirnov às amostras por repetição do tempo de KEM
+/// e do tempo apenas de cifragem, emparelhando as configurações que diferem só no
+/// acordo. Um D alto com p < 0.05 indica que as distribuições diferem em forma
+/// ou posição, sem supor normalidade.
+fn write_agreement_comparisons(results: &[ConfigResult], path: &Path) -> Result<(), ExperimentError> {
+    let mut writer = create_output(path)?;
+    writeln!(writer, "cenario,padrao_trafego,cifra,acordo_a,acordo_b,metrica,n_a,n_b,ks_d,ks_p_value,distribuicoes_diferentes")?;
+
+    for (classic, hybrid) in agreement_pairs(results) {
+        let metrics = [
+            ("kem_ms", &classic.kem_times, &hybrid.kem_times),
+            ("cipher_only_ms", &classic.cipher_only_times, &hybrid.cipher_only_times),
+        ];
+        for (metric, a, b) in metrics {
+            let (a, b) = (a.to_unit(TimeUnit::Millis), b.to_unit(TimeUnit::Millis));
+            let (d, p_value) = ks_two_sample(&a, &b);
+            writeln!(
+                writer,
+                "{:?},{:?},{},{},{},{},{},{},{:.4},{:.6},{}",
+                classic.spec.scenario, classic.spec.pattern, classic.spec.cipher,
+                classic.spec.agreement, hybrid.spec.agreement, metric,
+                a.len(), b.len(), d, p_value, p_value < 0.05
+            )?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Velocidade relativa de uma cifra frente à referência da sua célula (cenário, padrão, acordo)
+///
+/// É o `cipher_ms_mean` da referência dividido pelo da cifra: 1.1 significa 1.1× mais
+/// rápida que a referência. Sem a referência na célula (ex: execução interrompida por
+/// `--max-runtime`) ou com uma média não positiva, o valor é NaN, e nunca infinito.
+fn relative_speed(baseline_ms: Option<f64>, cipher_ms: f64) -> f64 {
+    match baseline_ms {
+        Some(baseline_ms) if cipher_ms > 0.0 => baseline_ms / cipher_ms,
+        _ => f64::NAN,
+    }
+}
+
+/// Matriz cenário × (acordo, cifra) do custo total de KEM e cifragem, normalizado por linha
+///
+/// O custo de uma configuração é `kem_ms_mean + cipher_only_ms_mean` (ms por repetição),
+/// com a média sobre os padrões de tráfego de cada cenário. Como o total de mensagens
+/// muda de um cenário para outro, cada linha é dividida pela sua combinação mais
+/// barata (1.0 = a melhor do cenário). Cenários e colunas seguem a ordem dos
+/// resultados; combinações ausentes em um cenário ficam NaN.
+fn cost_heatmap(results: &[ConfigResult]) -> (Vec<(&str, &'static str)>, Vec<(UsageScenario, Vec<f64>)>) {
+    let mut columns: Vec<(&str, &'static str)> = Vec::new();
+    let mut scenarios: Vec<UsageScenario> = Vec::new();
+    for result in results {
+        let column = (&*result.spec.agreement, result.spec.cipher);
+        if !columns.contains(&column) {
+            columns.push(column);
+        }
+        if !scenarios.contains(&result.spec.scenario) {
+            scenarios.push(result.spec.scenario.clone());
+        }
+    }
+
+    let rows = scenarios.into_iter().map(|scenario| {
+        let costs: Vec<f64> = columns.iter().map(|&(agreement, cipher)| {
+            let cell: Vec<f64> = results.iter()
+                .filter(|r| r.spec.scenario == scenario && r.spec.agreement == agreement && r.spec.cipher == cipher)
+                .map(|r| r.kem_time_stats.mean + r.cipher_only_time_stats.mean)
+                .collect();
+            average(&cell)
+        }).collect();
+        let cheapest = costs.iter().copied().filter(|cost| cost.is_finite()).fold(f64::INFINITY, f64::min);
+        (scenario, costs.iter().map(|cost| cost / cheapest).collect())
+    }).collect();
+    (columns, rows)
+}
+
+/// Grava a matriz de `cost_heatmap` em formato largo, pronta para um heatmap
+///
+/// Uma linha por cenário e uma coluna `acordo/cifra` por combinação.
+fn write_cost_heatmap(results: &[ConfigResult], path: &Path) -> Result<(), ExperimentError> {
+    let mut writer = create_output(path)?;
+    let (columns, rows) = cost_heatmap(results);
+    let header: Vec<String> = columns.iter().map(|(agreement, cipher)| format!("{}/{}", agreement, cipher)).collect();
+    writeln!(writer, "cenario,{}", header.join(","))?;
+
+    for (scenario, costs) in rows {
+        let cells: Vec<String> = costs.iter().map(|cost| format!("{:.4}", cost)).collect();
+        writeln!(writer, "{:?},{}", scenario, cells.join(","))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Calcula e grava a correlação entre tamanho da mensagem e tempo de cifragem, por cifra
+///
+/// Agrupa os pares (bytes, µs) de todas as configurações de cada cifra e calcula
+/// os coeficientes de Pearson (relação linear) e Spearman (relação monotônica).
+/// Valores próximos de 1 confirmam empiricamente o modelo de custo linear por byte.
+fn write_size_time_correlations(results: &[ConfigResult], path: &Path) -> Result<(), ExperimentError> {
+    let mut writer = create_output(path)?;
+    writeln!(writer, "cifra,num_pares,pearson,spearman")?;
+
+    for cipher in CIPHERS {
+        let (sizes, times): (Vec<f64>, Vec<f64>) = results
+            .iter()
+            .filter(|r| r.spec.cipher == cipher)
+            .flat_map(|r| r.size_time_pairs.iter().copied())
+            .unzip();
+        let (r_pearson, r_spearman) = (pearson(&sizes, &times), spearman(&sizes, &times));
+        println!("  [CORRELAÇÃO] {}: {} pares, Pearson={:.4}, Spearman={:.4}",
+                 cipher, sizes.len(), r_pearson, r_spearman);
+        writeln!(writer, "{},{},{:.6},{:.6}", cipher, sizes.len(), r_pearson, r_spearman)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Compara a fração de distribuições normais por tipo de mensagem (`--per-type-stats`)
+/// com a das distribuições por repetição (`cipher_normal`, `msg_bw_bytes_normal`) das
+/// mesmas configurações
+fn print_type_normality(results: &[ConfigResult]) {
+    let normal_count = |flags: Vec<bool>| format!("{}/{}", flags.iter().filter(|normal| **normal).count(), flags.len());
+    let per_type = || results.iter().flat_map(|result| result.type_stats.iter().flatten());
+    println!("  Distribuições normais — cifragem: {} por repetição, {} por tipo; bytes: {} por repetição, {} por tipo",
+             normal_count(results.iter().map(|result| result.cipher_time_stats.is_normal).collect()),
+             normal_count(per_type().map(|stats| stats.cipher_us.is_normal).collect()),
+             normal_count(results.iter().map(|result| result.msg_bw_stats.is_normal).collect()),
+             normal_count(per_type().map(|stats| stats.msg_bytes.is_normal).collect()));
+}
+
+/// Grava os tempos brutos de cada repetição de cada configuração, em nanossegundos inteiros
+fn write_raw_samples(results: &[ConfigResult], path: &Path) -> Result<(), ExperimentError> {
+    let mut writer = create_output(path)?;
+    writeln!(writer, "cenario,padrao_trafego,acordo,cifra,repeticao,kem_ns,cipher_ns,cipher_only_ns,compress_ns,commitment_ns")?;
+
+    for result in results {
+        for repetition in 0..result.kem_times.len() {
+            let ns = |samples: &DurationSamples| samples.nanos()[repetition];
+            writeln!(
+                writer,
+                "{:?},{:?},{},{},{},{},{},{},{},{}",
+                result.spec.scenario, result.spec.pattern, result.spec.agreement, result.spec.cipher, repetition,
+                ns(&result.kem_times), ns(&result.cipher_times), ns(&result.cipher_only_times),
+                ns(&result.compress_times), ns(&result.commitment_times)
+            )?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Instante atual do relógio do sistema, em ns desde a época Unix
+fn unix_nanos() -> u128 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_nanos()).unwrap_or_default()
+}
+
+/// Grava uma linha por repetição, em ordem cronológica de início (`--drift-log`)
+///
+/// Ao contrário das amostras brutas, agrupadas por configuração, aqui as repetições de
+/// todas as configurações ficam na ordem em que rodaram (intercaladas com `--interleave`
+/// ou `--shuffle-configs`): plotar os tempos contra `unix_timestamp_ns` mostra deriva
+/// térmica ou do escalonador que, por configuração, pareceria outlier aleatório.
+fn write_drift_log(results: &[ConfigResult], path: &Path) -> Result<(), ExperimentError> {
+    let mut writer = create_output(path)?;
+    writeln!(writer, "cenario,padrao_trafego,acordo,cifra,repeticao,unix_timestamp_ns,kem_ms,cipher_ms")?;
+
+    let mut rows: Vec<(u128, &ConfigResult, usize)> = results.iter()
+        .flat_map(|result| result.repetition_starts_ns.iter().enumerate().map(move |(repetition, &start)| (start, result, repetition)))
+        .collect();
+    rows.sort_by_key(|&(start, result, repetition)| (start, result.spec.index, repetition));
+    for (start, result, repetition) in rows {
+        let ms = |samples: &DurationSamples| samples.nanos()[repetition] as f64 / 1e6;
+        writeln!(
+            writer,
+            "{:?},{:?},{},{},{},{},{:.6},{:.6}",
+            result.spec.scenario, result.spec.pattern, result.spec.agreement, result.spec.cipher, repetition,
+            start, ms(&result.kem_times), ms(&result.cipher_times)
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Grava os resultados em formato longo (tidy): uma linha por (configuração, métrica, estatística)
+///
+/// Evita o `melt` do CSV largo em ggplot/seaborn. A primeira coluna é `schema_version`,
+/// como no CSV largo, e os nomes das métricas seguem os prefixos das colunas dele.
+fn write_tidy_results(results: &[ConfigResult], path: &Path) -> Result<(), ExperimentError> {
+    let mut writer = create_output(path)?;
+    writeln!(writer, "{},cenario,padrao_trafego,acordo,cifra,metric_name,stat_name,value", SCHEMA_COLUMN)?;
+
+    for result in results {
+        for (metric, stat, value) in result.tidy_rows() {
+            writeln!(
+                writer,
+                "{},{:?},{:?},{},{},{},{},{}",
+                SCHEMA_VERSION, result.spec.scenario, result.spec.pattern, result.spec.agreement,
+                result.spec.cipher, metric, stat, value
+            )?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Preenche a média móvel dos bytes por mensagem de uma série acumulada completa
+///
+/// Os bytes de cada mensagem são a diferença entre pontos consecutivos (o primeiro
+/// inclui a rotação inicial); a suavização é feita antes da subamostragem, sobre
+/// todas as mensagens.
+fn smooth_series(series: &[BandwidthPoint], window: NonZeroUsize) -> Vec<BandwidthPoint> {
+    let totals: Vec<usize> = series.iter().map(|point| point.kem_bytes + point.msg_bytes).collect();
+    let per_message: Vec<f64> = totals.iter()
+        .zip(std::iter::once(&0).chain(&totals))
+        .map(|(total, previous)| (total - previous) as f64)
+        .collect();
+    series.iter()
+        .zip(moving_average(&per_message, window))
+        .map(|(point, smoothed_bytes)| BandwidthPoint { smoothed_bytes, ..*point })
+        .collect()
+}
+
+/// Subamostra uniformemente uma série para no máximo `max_points` pontos
+///
+/// Mantém sempre o primeiro e o último ponto, de modo que o total final da série
+/// acumulada é preservado; séries já curtas são devolvidas inteiras.
+fn downsample<T: Copy>(series: &[T], max_points: usize) -> Vec<T> {
+    if series.len() <= max_points || max_points < 2 {
+        return series.to_vec();
+    }
+    let last = series.len() - 1;
+    (0..max_points)
+        .map(|i| series[(i * last + (max_points - 1) / 2) / (max_points - 1)])
+        .collect()
+}
+
+/// Grava a largura de banda acumulada por índice de mensagem, em formato longo
+fn write_bandwidth_series(results: &[ConfigResult], path: &Path) -> Result<(), ExperimentError> {
+    let mut writer = create_output(path)?;
+    writeln!(writer, "cenario,padrao_trafego,acordo,cifra,indice_mensagem,kem_bytes_acumulados,msg_bytes_acumulados,bytes_acumulados,bytes_mensagem_media_movel")?;
+
+    for result in results {
+        for point in &result.bandwidth_series {
+            writeln!(
+                writer,
+                "{:?},{:?},{},{},{},{},{},{},{:.1}",
+                result.spec.scenario, result.spec.pattern, result.spec.agreement, result.spec.cipher,
+                point.message_index, point.kem_bytes, point.msg_bytes, point.kem_bytes + point.msg_bytes,
+                point.smoothed_bytes
+            )?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Ponto da curva de amortização de uma configuração do modo `--message-counts`
+#[derive(Debug, Clone, Copy)]
+struct AmortizationPoint {
+    kem_ms_per_message: f64,          // Tempo de acordo de chaves da repetição / mensagens
+    kem_bandwidth_fraction: f64,      // Bytes de acordo / bytes totais (acordo + mensagens)
+    kem_ms_overhead_per_message: f64, // Tempo de acordo por mensagem acima do clássico no mesmo total
+    bandwidth_overhead_fraction: f64, // Bytes de acordo acima do clássico / bytes totais
+}
+
+/// Custo de acordo de chaves por mensagem e overhead sobre o clássico no mesmo total de mensagens
+///
+/// O overhead de banda considera só os bytes de acordo: cada acordo tem semente e, portanto,
+/// mensagens próprias. Sem o resultado clássico correspondente, os overheads ficam NaN.
+fn amortization_point(result: &ConfigResult, classic: Option<&ConfigResult>) -> AmortizationPoint {
+    let messages = result.num_messages.max(1) as f64;
+    let total = result.kem_bw_stats.mean + result.msg_bw_stats.mean;
+    AmortizationPoint {
+        kem_ms_per_message: result.kem_time_stats.mean / messages,
+        kem_bandwidth_fraction: result.kem_bw_stats.mean / total,
+        kem_ms_overhead_per_message: classic
+            .map_or(f64::NAN, |classic| (result.kem_time_stats.mean - classic.kem_time_stats.mean) / messages),
+        bandwidth_overhead_fraction: classic
+            .map_or(f64::NAN, |classic| (result.kem_bw_stats.mean - classic.kem_bw_stats.mean) / total),
+    }
+}
+
+/// Pontos de amortização de cada resultado, comparado ao clássico com o mesmo total de mensagens
+fn amortization_curve(results: &[ConfigResult]) -> Vec<AmortizationPoint> {
+    let [classic_name, _] = AGREEMENTS;
+    results.iter()
+        .map(|result| {
+            let classic = results.iter().find(|candidate| {
+                candidate.spec.agreement == classic_name && candidate.spec.message_count == result.spec.message_count
+            });
+            amortization_point(result, classic)
+        })
+        .collect()
+}
+
+/// Grava a curva de amortização do modo `--message-counts`, uma linha por configuração
+fn write_amortization(results: &[ConfigResult], path: &Path) -> Result<(), ExperimentError> {
+    let mut writer = create_output(path)?;
+    writeln!(writer, "cenario,padrao_trafego,acordo,cifra,num_msgs,rotacoes,repeticoes,kem_ms_mean,kem_ms_por_mensagem,kem_bw_bytes_mean,msg_bw_bytes_mean,fracao_banda_kem,overhead_kem_ms_por_mensagem,fracao_overhead_banda")?;
+
+    for (result, point) in results.iter().zip(amortization_curve(results)) {
+        writeln!(
+            writer,
+            "{:?},{:?},{},{},{},{},{},{:.6},{:.6},{:.1},{:.1},{:.6},{:.6},{:.6}",
+            result.spec.scenario, result.spec.pattern, result.spec.agreement, result.spec.cipher,
+            result.num_messages, result.rotations, result.repetitions, result.kem_time_stats.mean,
+            point.kem_ms_per_message, result.kem_bw_stats.mean, result.msg_bw_stats.mean,
+            point.kem_bandwidth_fraction, point.kem_ms_overhead_per_message, point.bandwidth_overhead_fraction
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Grava o exemplo de cifragem de cada configuração (`--dump-sample`)
+///
+/// A chave sai como `sha256:<hex>` do seu SHA-256, salvo com `--dump-keys-insecure`,
+/// quando sai em claro na codificação escolhida.
+fn write_ciphertext_samples(
+    results: &[ConfigResult],
+    encoding: DumpEncoding,
+    include_keys: bool,
+    path: &Path,
+) -> Result<(), ExperimentError> {
+    let mut writer = create_output(path)?;
+    writeln!(writer, "cenario,padrao_trafego,acordo,cifra,codificacao,plaintext_bytes,plaintext_sha256,chave,nonce,texto_cifrado")?;
+
+    for result in results {
+        let Some(sample) = &result.ciphertext_sample else { continue };
+        let key = if include_keys {
+            encoding.encode(&sample.key)
+        } else {
+            format!("sha256:{}", hex::encode(Sha256::digest(sample.key)))
+        };
+        writeln!(
+            writer,
+            "{:?},{:?},{},{},{},{},{},{},{},{}",
+            result.spec.scenario, result.spec.pattern, result.spec.agreement, result.spec.cipher,
+            encoding.name(), sample.plaintext_bytes, hex::encode(sample.plaintext_sha256), key,
+            encoding.encode(&sample.nonce), encoding.encode(&sample.ciphertext)
+        )?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Média aritmética; NaN para lista vazia
+fn average(values: &[f64]) -> f64 {
+    if values.is_empty() { f64::NAN } else { values.iter().sum::<f64>() / values.len() as f64 }
+}
+
+/// Divide as amostras em duas metades aleatórias (embaralhamento semeado)
+///
+/// Com número ímpar de amostras, a primeira metade fica com a amostra extra.
+fn split_half(samples: &[f64], seed: u64) -> (Vec<f64>, Vec<f64>) {
+    let mut shuffled = samples.to_vec();
+    shuffled.shuffle(&mut StdRng::seed_from_u64(seed));
+    let second = shuffled.split_off(shuffled.len().div_ceil(2));
+    (shuffled, second)
+}
+
+/// Verifica a estabilidade das estatísticas dividindo as amostras em duas metades
+///
+/// Para os tempos de KEM e apenas de cifragem de cada configuração, as amostras por
+/// repetição são divididas em duas metades aleatórias (semente derivada de
+/// `config_seed`) e cada metade passa por `calculate_adaptive_stats`. Grava a
+/// diferença entre as tendências centrais e se os IC95 das metades se sobrepõem;
+/// ICs disjuntos indicam uma métrica instável, que precisa de mais repetições.
+fn write_split_half_stability(results: &[ConfigResult], config: &ExperimentConfig, path: &Path) -> Result<(), ExperimentError> {
+    let mut writer = create_output(path)?;
+    writeln!(writer, "cenario,padrao_trafego,acordo,cifra,metrica,n_a,n_b,central_a,central_b,ci95_a,ci95_b,diferenca,diferenca_relativa,ic_sobrepostos")?;
+
+    let mut unstable = 0;
+    for result in results {
+        let seed = stream_seed(result.config_seed, 0, 0, SeedStream::Split);
+        let metrics = [
+            ("kem_ms", &result.kem_times),
+            ("cipher_only_ms", &result.cipher_only_times),
+        ];
+        for (metric, samples) in metrics {
+            let (a, b) = split_half(&samples.to_unit(TimeUnit::Millis), seed);
+            let stats = |half: &[f64], fold: &str| calculate_adaptive_stats(
+                half, &format!("{} ({})", metric, fold), config.central, !config.no_outlier_removal,
+            );
+            let (stats_a, stats_b) = (stats(&a, "metade A"), stats(&b, "metade B"));
+            let diff = stats_a.mean - stats_b.mean;
+            let relative = diff.abs() / ((stats_a.mean + stats_b.mean) / 2.0).abs();
+            let overlap = diff.abs() <= stats_a.ci95 + stats_b.ci95;
+            unstable += usize::from(!overlap);
+            writeln!(
+                writer,
+                "{:?},{:?},{},{},{},{},{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4},{}",
+                result.spec.scenario, result.spec.pattern, result.spec.agreement, result.spec.cipher, metric,
+                a.len(), b.len(), stats_a.mean, stats_b.mean, stats_a.ci95, stats_b.ci95, diff, relative, overlap
+            )?;
+        }
+    }
+    if unstable > 0 {
+        println!("  [AVISO] {} métricas com IC95 disjuntos entre as metades; considere mais repetições", unstable);
+    } else {
+        println!("  [QUALIDADE] IC95 das metades se sobrepõem em todas as métricas");
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Amostras por repetição de uma configuração, entrada das comparações entre acordos
+///
+/// Vem dos resultados de uma execução ou de um arquivo `amostras_ns_<timestamp>.csv`
+/// (subcomando `analyze`), de modo que as comparações independem da medição.
+#[derive(Debug, Clone, PartialEq)]
+struct ConfigSamples {
+    scenario: String,
+    pattern: String,
+    agreement: String,
+    cipher: String,
+    kem_ms: Vec<f64>,
+    cipher_only_ms: Vec<f64>,
+}
+
+impl ConfigSamples {
+    fn from_result(result: &ConfigResult) -> Self {
+        ConfigSamples {
+            scenario: format!("{:?}", result.spec.scenario),
+            pattern: format!("{:?}", result.spec.pattern),
+            agreement: result.spec.agreement.to_string(),
+            cipher: result.spec.cipher.to_string(),
+            kem_ms: result.kem_times.to_unit(TimeUnit::Millis),
+            cipher_only_ms: result.cipher_only_times.to_unit(TimeUnit::Millis),
+        }
+    }
+}
+
+/// Lê um arquivo de amostras brutas (`--raw-samples-ns`), agrupando as repetições
+///
+/// As colunas são localizadas pelo nome no cabeçalho; os tempos são convertidos de
+/// nanossegundos para milissegundos. As configurações mantêm a ordem do arquivo.
+fn read_raw_samples(path: &Path) -> Result<Vec<ConfigSamples>, ExperimentError> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| ExperimentError::from(e).context(format!("não foi possível ler {}", path.display())))?;
+    let mut lines = content.lines();
+    let header: Vec<&str> = lines.next()
+        .ok_or_else(|| ExperimentError::Stats("arquivo vazio".to_string()))?
+        .split(',')
+        .collect();
+    let column = |name: &str| header.iter().position(|column| *column == name)
+        .ok_or_else(|| ExperimentError::Stats(format!("coluna '{}' ausente no cabeçalho", name)));
+    let keys = [column("cenario")?, column("padrao_trafego")?, column("acordo")?, column("cifra")?];
+    let (kem, cipher_only) = (column("kem_ns")?, column("cipher_only_ns")?);
+
+    let mut samples: Vec<ConfigSamples> = Vec::new();
+    for (number, line) in lines.enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+        let fields: Vec<&str> = line.split(',').collect();
+        let field = |index: usize| fields.get(index).copied()
+            .ok_or_else(|| ExperimentError::Stats(format!("linha {}: colunas insuficientes", number + 2)));
+        let nanos = |index: usize| -> Result<f64, ExperimentError> {
+            let value = field(index)?;
+            value.parse::<u128>()
+                .map(|ns| ns as f64 / 1e6)
+                .map_err(|_| ExperimentError::Stats(format!("linha {}: '{}' não é um tempo em ns", number + 2, value)))
+        };
+        let key = [field(keys[0])?, field(keys[1])?, field(keys[2])?, field(keys[3])?];
+        let position = samples.iter().position(|s| {
+            [s.scenario.as_str(), &s.pattern, &s.agreement, &s.cipher] == key
+        });
+        let entry = match position {
+            Some(index) => &mut samples[index],
+            None => {
+                samples.push(ConfigSamples {
+                    scenario: key[0].to_string(),
+                    pattern: key[1].to_string(),
+                    agreement: key[2].to_string(),
+                    cipher: key[3].to_string(),
+                    kem_ms: Vec::new(),
+                    cipher_only_ms: Vec::new(),
+                });
+                let last = samples.len() - 1;
+                &mut samples[last]
+            }
+        };
+        entry.kem_ms.push(nanos(kem)?);
+        entry.cipher_only_ms.push(nanos(cipher_only)?);
+    }
+    Ok(samples)
+}
+
+/// Compara cada acordo com o Olm-Clássico da mesma célula e cifra
+///
+/// Para `kem_ms` e `cipher_only_ms`, grava o teste t de Welch, o U de Mann-Whitney
+/// (sem supor normalidade) e o d de Cohen (tamanho do efeito), todos bilaterais e
+/// orientados como acordo_b - acordo_a. Cobre também os acordos extras de `--hybrid`.
+fn write_pairwise_comparisons(samples: &[ConfigSamples], path: &Path) -> Result<(), ExperimentError> {
+    let mut writer = create_output(path)?;
+    writeln!(writer, "cenario,padrao_trafego,cifra,acordo_a,acordo_b,metrica,n_a,n_b,mean_a,mean_b,welch_t,welch_df,welch_p_value,mann_whitney_u,mann_whitney_p_value,cohens_d")?;
+
+    for other in samples.iter().filter(|s| s.agreement != "Olm-Clássico") {
+        let classic = samples.iter().find(|s| {
+            s.agreement == "Olm-Clássico"
+                && s.scenario == other.scenario
+                && s.pattern == other.pattern
+                && s.cipher == other.cipher
+        });
+        let Some(classic) = classic else { continue };
+
+        let metrics: [(&str, &[f64], &[f64]); 2] = [
+            ("kem_ms", &classic.kem_ms, &other.kem_ms),
+            ("cipher_only_ms", &classic.cipher_only_ms, &other.cipher_only_ms),
+        ];
+        for (metric, a, b) in metrics {
+            let (t, df, welch_p) = welch_t_test(b, a);
+            let (u, mw_p) = mann_whitney_u(b, a);
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{},{},{:.6},{:.6},{:.4},{:.2},{:.6},{:.1},{:.6},{:.4}",
+                other.scenario, other.pattern, other.cipher, classic.agreement, other.agreement, metric,
+                a.len(), b.len(), average(a), average(b), t, df, welch_p, u, mw_p, cohens_d(b, a)
+            )?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Refaz apenas as comparações entre acordos a partir de amostras brutas já gravadas
+///
+/// Nenhuma medição é executada: lê o arquivo de `--raw-samples-ns` e grava
+/// `comparacoes_<timestamp>.csv` em `output_dir`. Arquivos ilegíveis ou malformados
+/// resultam em erro, sem gravar nada.
+fn run_analyze(raw_path: &Path, output_dir: &Path) -> Result<(), ExperimentError> {
+    println!("=== COMPARAÇÕES A PARTIR DE AMOSTRAS GRAVADAS ===");
+    let samples = read_raw_samples(raw_path).map_err(|e| e.context("arquivo de amostras inválido"))?;
+    if samples.is_empty() {
+        return Err(ExperimentError::Stats(format!("{} não contém amostras", raw_path.display())));
+    }
+    println!("{} configurações lidas de: {}", samples.len(), raw_path.display());
+
+    fs::create_dir_all(output_dir)
+        .map_err(|e| ExperimentError::from(e).context(format!("não foi possível criar {}", output_dir.display())))?;
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
+    let path = output_dir.join(format!("comparacoes_{}.csv", timestamp));
+    write_pairwise_comparisons(&samples, &path)?;
+    println!("Comparações (Welch, Mann-Whitney, Cohen's d) em: {}", path.display());
+    Ok(())
+}
+
+/// Histograma dos intervalos entre envios de cada padrão de tráfego (subcomando `inter-arrival`)
+///
+/// Cada padrão é simulado com `simulate_inter_arrivals`, sob relógio simulado e sem
+/// criptografia. Grava `intervalos_chegada_<timestamp>.csv` em `output_dir` e imprime,
+/// por padrão, a média e o coeficiente de variação (≈ 1 para chegadas exponenciais).
+fn run_inter_arrival(sends: usize, poll_ms: f64, bins: usize, seed: u64, output_dir: &Path) -> Result<(), ExperimentError> {
+    println!("=== INTERVALOS ENTRE ENVIOS POR PADRÃO DE TRÁFEGO (relógio simulado) ===");
+    fs::create_dir_all(output_dir)
+        .map_err(|e| ExperimentError::from(e).context(format!("não foi possível criar {}", output_dir.display())))?;
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
+    let path = output_dir.join(format!("intervalos_chegada_{}.csv", timestamp));
+    let mut writer = create_output(&path)?;
+    writeln!(writer, "padrao_trafego,envios,bin_inicio_ms,bin_fim_ms,contagem,fracao")?;
+
+    let poll_interval = Duration::from_secs_f64(poll_ms / 1000.0);
+    for pattern in TrafficPattern::ALL {
+        let gaps: Vec<f64> = simulate_inter_arrivals(pattern.clone(), seed, sends, poll_interval)
+            .iter()
+            .map(|gap| gap.as_secs_f64() * 1000.0)
+            .collect();
+        if gaps.len() < sends {
+            println!("  [AVISO] {:?}: apenas {} de {} envios antes do limite de inatividade", pattern, gaps.len(), sends);
+        }
+        let moments = Moments::from_samples(&gaps);
+        println!("  {:<10} {} intervalos, média {:.2} ms, CV {:.2}",
+                 format!("{:?}", pattern), gaps.len(), moments.mean, moments.std_dev() / moments.mean);
+        for bin in histogram(&gaps, bins)? {
+            writeln!(writer, "{:?},{},{:.3},{:.3},{},{:.6}", pattern, gaps.len(), bin.start, bin.end, bin.count,
+                     bin.count as f64 / gaps.len() as f64)?;
+        }
+    }
+    writer.flush()?;
+    println!("Histogramas em: {}", path.display());
+    Ok(())
+}
+
+/// Lista os valores aceitos pela matriz (subcomando `list`)
+///
+/// Cifras, acordos, componentes de `--hybrid`, cenários (com os parâmetros que cada um
+/// define) e padrões de tráfego, lidos das mesmas constantes que montam a matriz.
+fn list_options() -> String {
+    let mut out = String::new();
+    out.push_str("Cifras:\n");
+    for cipher in CIPHERS {
+        out.push_str(&format!("  {}\n", cipher));
+    }
+    out.push_str("Acordos de chave:\n");
+    for agreement in AGREEMENTS {
+        let name = agreement_components(agreement)
+            .map_or_else(|_| agreement.to_string(), |components| hybrid_agreement_name(&components));
+        out.push_str(&format!("  {:<14} {} ({})\n", agreement, name,
+                              SecurityLevel::of_agreement(agreement).name()));
+    }
+    out.push_str("Componentes de --hybrid (ex: X25519+Kyber768+Kyber1024):\n");
+    for component in KemComponent::ALL {
+        let category = component.nist_category()
+            .map_or("sem segurança pós-quântica".to_string(), |category| format!("categoria NIST {}", category));
+        out.push_str(&format!("  {:<14} {}\n", component.name(), category));
+    }
+    out.push_str("Cenários:\n");
+    for scenario in UsageScenario::ALL {
+        out.push_str(&format!("  {:<14} {} membros, {} mensagens, rotação a cada {} mensagens\n",
+                              format!("{:?}", scenario), get_group_size(&scenario),
+                              get_message_count_config(&scenario), get_rotation_config(&scenario)));
+    }
+    out.push_str("Padrões de tráfego:\n");
+    for pattern in TrafficPattern::ALL {
+        let description = match pattern {
+            TrafficPattern::Constant => "uma mensagem a cada 100 ms",
+            TrafficPattern::Burst => "rajadas de 5 a 10 mensagens com pausas de 1 s",
+            TrafficPattern::Periodic => "probabilidade senoidal de envio (período de 10 s)",
+            TrafficPattern::Random => "30% de chance de envio a cada consulta",
+            TrafficPattern::Realistic => "probabilidade de 10% a 30% variando no tempo",
+        };
+        out.push_str(&format!("  {:<14} {}\n", format!("{:?}", pattern), description));
+    }
+    out
+}
+
+/// Pares (clássico, híbrido) da mesma célula e cifra, entre os dois acordos padrão
+fn agreement_pairs(results: &[ConfigResult]) -> Vec<(&ConfigResult, &ConfigResult)> {
+    let [classic, hybrid] = AGREEMENTS;
+    results
+        .iter()
+        .filter(|r| r.spec.agreement == classic)
+        .filter_map(|c| {
+            results
+                .iter()
+                .find(|h| {
+                    h.spec.agreement == hybrid
+                        && h.spec.scenario == c.spec.scenario
+                        && h.spec.pattern == c.spec.pattern
+                        && h.spec.cipher == c.spec.cipher
+                })
+                .map(|h| (c, h))
+        })
+        .collect()
+}
+
+/// Monta o relatório Markdown da execução a partir dos resultados coletados
+///
+/// Para cada cenário: o tempo médio de cada cifra (apenas cifragem e laço completo),
+/// com a melhor e a pior cifra, e o overhead do acordo híbrido sobre o clássico por
+/// padrão de tráfego (razão entre as médias das cifras de tempo de KEM e de bytes totais).
+/// Os números de destaque são as médias das razões de cada par (célula, cifra).
+fn markdown_report(results: &[ConfigResult], timestamp: &str, base_seed: Option<u64>) -> String {
+    let mut report = String::new();
+    let pairs = agreement_pairs(results);
+    let kem_ratio = |(c, h): &(&ConfigResult, &ConfigResult)| h.kem_time_stats.mean / c.kem_time_stats.mean;
+    let kem_bw_ratio = |(c, h): &(&ConfigResult, &ConfigResult)| h.kem_bw_stats.mean / c.kem_bw_stats.mean;
+    let total_bw_ratio = |(c, h): &(&ConfigResult, &ConfigResult)| {
+        (h.kem_bw_stats.mean + h.msg_bw_stats.mean) / (c.kem_bw_stats.mean + c.msg_bw_stats.mean)
+    };
+
+    report.push_str(&format!("# Resumo do experimento {}\n\n", timestamp));
+    report.push_str(&format!("- Configurações: {}\n", results.len()));
+    if let Some(seed) = base_seed {
+        report.push_str(&format!("- Semente base: {}\n", seed));
+    }
+
+    report.push_str(&format!("\n## Destaques ({} vs {})\n\n", AGREEMENTS[1], AGREEMENTS[0]));
+    report.push_str(&format!("- Overhead médio de tempo de KEM: {:.2}×\n",
+                             average(&pairs.iter().map(kem_ratio).collect::<Vec<_>>())));
+    report.push_str(&format!("- Overhead médio de largura de banda de KEM: {:.2}×\n",
+                             average(&pairs.iter().map(kem_bw_ratio).collect::<Vec<_>>())));
+    report.push_str(&format!("- Overhead médio de largura de banda total (KEM + mensagens): {:.3}×\n",
+                             average(&pairs.iter().map(total_bw_ratio).collect::<Vec<_>>())));
+
+    // Cenários na ordem em que aparecem nos resultados
+    let mut scenarios: Vec<&UsageScenario> = Vec::new();
+    for result in results {
+        if !scenarios.contains(&&result.spec.scenario) {
+            scenarios.push(&result.spec.scenario);
+        }
+    }
+
+    for scenario in scenarios {
+        report.push_str(&format!("\n## {:?}\n\n", scenario));
+        report.push_str("| Cifra | cipher_only_ms (média) | cipher_ms (média) |\n|---|---:|---:|\n");
+        let mut cipher_means: Vec<(&str, f64)> = Vec::new();
+        for cipher in CIPHERS {
+            let cell: Vec<&ConfigResult> = results
+                .iter()
+                .filter(|r| &r.spec.scenario == scenario && r.spec.cipher == cipher)
+                .collect();
+            if cell.is_empty() {
+                continue;
+            }
+            let only = average(&cell.iter().map(|r| r.cipher_only_time_stats.mean).collect::<Vec<_>>());
+            let full = average(&cell.iter().map(|r| r.cipher_time_stats.mean).collect::<Vec<_>>());
+            report.push_str(&format!("| {} | {:.4} | {:.4} |\n", cipher, only, full));
+            cipher_means.push((cipher, only));
+        }
+        let best = cipher_means.iter().min_by(|a, b| a.1.total_cmp(&b.1));
+        let worst = cipher_means.iter().max_by(|a, b| a.1.total_cmp(&b.1));
+        if let (Some(best), Some(worst)) = (best, worst) {
+            report.push_str(&format!("\nMelhor cifra: **{}** ({:.4} ms) · Pior cifra: **{}** ({:.4} ms)\n",
+                                     best.0, best.1, worst.0, worst.1));
+        }
+
+        let scenario_pairs: Vec<_> = pairs.iter().filter(|(c, _)| &c.spec.scenario == scenario).collect();
+        if scenario_pairs.is_empty() {
+            continue;
+        }
+        report.push_str("\n| Padrão | KEM clássico (ms) | KEM híbrido (ms) | Overhead KEM | Bytes clássico | Bytes híbrido | Overhead bytes |\n");
+        report.push_str("|---|---:|---:|---:|---:|---:|---:|\n");
+        let mut patterns: Vec<&TrafficPattern> = Vec::new();
+        for (classic, _) in &scenario_pairs {
+            if !patterns.contains(&&classic.spec.pattern) {
+                patterns.push(&classic.spec.pattern);
+            }
+        }
+        for pattern in patterns {
+            let cell: Vec<_> = scenario_pairs.iter().filter(|(c, _)| &c.spec.pattern == pattern).collect();
+            let column = |f: &dyn Fn(&(&ConfigResult, &ConfigResult)) -> f64| {
+                average(&cell.iter().map(|pair| f(pair)).collect::<Vec<_>>())
+            };
+            let bytes = |r: &ConfigResult| r.kem_bw_stats.mean + r.msg_bw_stats.mean;
+            let (kem_classic, kem_hybrid) = (column(&|(c, _)| c.kem_time_stats.mean), column(&|(_, h)| h.kem_time_stats.mean));
+            let (bytes_classic, bytes_hybrid) = (column(&|(c, _)| bytes(c)), column(&|(_, h)| bytes(h)));
+            report.push_str(&format!(
+                "| {:?} | {:.4} | {:.4} | {:.2}× | {:.0} | {:.0} | {:.3}× |\n",
+                pattern, kem_classic, kem_hybrid, kem_hybrid / kem_classic,
+                bytes_classic, bytes_hybrid, bytes_hybrid / bytes_classic,
+            ));
+        }
+    }
+    report
+}
+
+/// Monta uma tabela LaTeX (`tabular`) do tempo e da largura de banda de KEM por cenário
+///
+/// Uma linha por cenário, com o Olm-Clássico e o Olm-Híbrido lado a lado: média, entre as
+/// células pareadas (padrão e cifra), do valor central de cada célula ± semiamplitude do
+/// IC95. O valor central é a mediana (ou o estimador de `--central`) quando a amostra não
+/// é normal, que é o caso típico dos tempos. O IC da média de k células independentes é
+/// sqrt(Σ IC²) / k. Os números saem em `\num{}` (pacote siunitx).
+fn latex_table(results: &[ConfigResult], timestamp: &str) -> String {
+    let [classic_name, hybrid_name] = AGREEMENTS;
+    let pairs = agreement_pairs(results);
+    let num = |value: f64, decimals: usize| format!("\\num{{{:.*}}}", decimals, value);
+    // Valor central ± IC95 de uma estatística sobre as células pareadas
+    let cell = |stats: Vec<&Stats>, decimals: usize| {
+        let k = stats.len() as f64;
+        let mean = stats.iter().map(|s| s.mean).sum::<f64>() / k;
+        let ci95 = stats.iter().map(|s| s.ci95 * s.ci95).sum::<f64>().sqrt() / k;
+        (mean, format!("{} $\\pm$ {}", num(mean, decimals), num(ci95, decimals)))
+    };
+
+    let mut table = String::new();
+    table.push_str(&format!("% Gerado por pq_crypto_matrix ({}): {} vs {} por cenário\n",
+                            timestamp, hybrid_name, classic_name));
+    // Rótulo do valor central: amostras normais guardam a média; as demais, o estimador robusto
+    let estimators: Vec<CentralEstimator> = results.iter()
+        .flat_map(|r| [r.kem_time_stats.central, r.kem_bw_stats.central])
+        .collect();
+    let central = match estimators.iter().find(|e| **e != CentralEstimator::Mean) {
+        None => "média".to_string(),
+        Some(robust) => {
+            let name = if *robust == CentralEstimator::Mode { "moda" } else { "mediana" };
+            if estimators.contains(&CentralEstimator::Mean) {
+                format!("{} (média se a amostra for normal)", name)
+            } else {
+                name.to_string()
+            }
+        }
+    };
+    table.push_str(&format!("% Requer \\usepackage{{siunitx}}; valores: {} ± semiamplitude do IC95\n",
+                            central));
+    table.push_str("\\begin{tabular}{lcccccc}\n\\hline\n");
+    table.push_str(&format!(
+        "Cenário & KEM {c} (ms) & KEM {h} (ms) & Overhead & KEM {c} (bytes) & KEM {h} (bytes) & Overhead \\\\\n\\hline\n",
+        c = classic_name, h = hybrid_name,
+    ));
+
+    // Cenários na ordem em que aparecem nos resultados
+    let mut scenarios: Vec<&UsageScenario> = Vec::new();
+    for (classic, _) in &pairs {
+        if !scenarios.contains(&&classic.spec.scenario) {
+            scenarios.push(&classic.spec.scenario);
+        }
+    }
+    for scenario in scenarios {
+        let cell_pairs: Vec<_> = pairs.iter().filter(|(c, _)| &c.spec.scenario == scenario).collect();
+        let (kem_c, kem_c_text) = cell(cell_pairs.iter().map(|(c, _)| &c.kem_time_stats).collect(), 4);
+        let (kem_h, kem_h_text) = cell(cell_pairs.iter().map(|(_, h)| &h.kem_time_stats).collect(), 4);
+        let (bw_c, bw_c_text) = cell(cell_pairs.iter().map(|(c, _)| &c.kem_bw_stats).collect(), 0);
+        let (bw_h, bw_h_text) = cell(cell_pairs.iter().map(|(_, h)| &h.kem_bw_stats).collect(), 0);
+        table.push_str(&format!(
+            "{:?} & {} & {} & {}$\\times$ & {} & {} & {}$\\times$ \\\\\n",
+            scenario, kem_c_text, kem_h_text, num(kem_h / kem_c, 2),
+            bw_c_text, bw_h_text, num(bw_h / bw_c, 2),
+        ));
+    }
+    table.push_str("\\hline\n\\end{tabular}\n");
+    table
+}
+
+/// Grava o arquivo de metadados (JSON) que acompanha o CSV de resultados
+///
+/// Registra a procedência da execução: versão do experimento, plataforma,
+/// parâmetros da linha de comando e as versões exatas das bibliotecas
+/// criptográficas, capturadas do Cargo.lock em tempo de compilação.
+///
+/// Com `--monitor-throttle`, o arquivo é regravado ao final da execução com o
+/// resultado da análise de frequência (`throttle`); antes disso, o campo é nulo.
+fn write_metadata_sidecar(
+    config: &ExperimentConfig,
+    csv_filename: &str,
+    timestamp: &str,
+    path: &Path,
+    throttle: Option<&ThrottleCheck>,
+    outliers: Option<&[OutlierRate]>,
+) -> Result<(), ExperimentError> {
+    let bibliotecas: serde_json::Map<String, serde_json::Value> = PRIMITIVE_VERSIONS
+        .iter()
+        .map(|(name, version)| (name.to_string(), json!(version)))
+        .collect();
+    let csv_name = Path::new(csv_filename).file_name().unwrap_or_default().to_string_lossy();
+    let rotation_strategy: Vec<String> = config.rotation_strategy
+        .iter()
+        .map(|selection| match &selection.scenario {
+            Some(scenario) => format!("{:?}={:?}", scenario, selection.policy),
+            None => format!("{:?}", selection.policy),
+        })
+        .collect();
+    // Componentes pós-quânticos dos acordos da execução: os pares e encapsulamentos de
+    // cada um usam o randombytes do PQClean, qualquer que seja --os-rng
+    let agreements = experiment_agreements(config);
+    let kem_entropy: serde_json::Map<String, serde_json::Value> = KemComponent::ALL
+        .into_iter()
+        .filter(|component| component.nist_category().is_some())
+        .filter(|component| agreements.iter().any(|agreement| {
+            agreement_components(agreement).is_ok_and(|components| components.contains(component))
+        }))
+        .map(|component| (component.name().to_string(), json!("randombytes do PQClean (getrandom)")))
+        .collect();
+
+    let metadata = merge_json_objects([
+        json!({
+            "experimento": env!("CARGO_PKG_NAME"),
+            "schema_version": SCHEMA_VERSION,
+            "versao_experimento": env!("CARGO_PKG_VERSION"),
+            "timestamp": timestamp,
+            "arquivo_resultados": csv_name,
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+            "repeticoes": repetitions(config),
+            "quick": config.quick,
+            "jobs": config.jobs,
+            "megolm_unauthenticated": config.megolm_unauthenticated,
+            "per_message_kdf": config.per_message_kdf,
+            "ctr_width": config.ctr_width.bits(),
+            "ephemeral": config.ephemeral.name(),
+            "dh_variant": config.ephemeral.dh_variant(),
+            "keygen_policy": config.keygen_policy.name(),
+            "detect_duplicates": config.detect_duplicates,
+            "target_moe": config.target_moe,
+            "max_repetitions": config.target_moe.map(|_| config.max_repetitions),
+            "interleave": config.interleave,
+            "shuffle_configs": config.shuffle_configs,
+            // Legenda do CSV de resultados, gerada do mesmo registro que o cabeçalho
+            "colunas": RESULT_COLUMNS.iter().map(|column| json!({
+                "nome": column.name,
+                "unidade": column.unit,
+                "descricao": column.description,
+            })).collect::<Vec<_>>(),
+        }),
+        json!({
+            "inter_message_delay_ms": config.inter_message_delay,
+            "pacing": config.pacing.name(),
+            "metrics_endpoint": config.metrics_endpoint.as_ref().map(|exporter| exporter.url()),
+            "split_half": config.split_half,
+            "pairwise_comparisons": config.pairwise_comparisons,
+            "cost_heatmap": config.cost_heatmap,
+            "corpus": config.corpus.as_ref().map(|dir| dir.display().to_string()),
+            "fixed_size": config.fixed_size,
+            "payload_entropy": config.payload_entropy,
+            "size_time_correlation": config.size_time_correlation,
+            "size_weighted": config.size_weighted,
+            "devices_per_user": config.devices_per_user,
+            "max_runtime_s": config.max_runtime,
+            "compress": config.compress.name(),
+            "envelope": config.envelope.name(),
+            "base64_media": config.base64_media,
+            "central": config.central.name(),
+            "no_outlier_removal": config.no_outlier_removal,
+            "senders": config.senders,
+            "record": config.record.as_ref().map(|path| path.display().to_string()),
+            "replay": config.replay.as_ref().map(|path| path.display().to_string()),
+        }),
+        json!({
+            "key_commitment": config.key_commitment,
+            "in_place": config.in_place,
+            "tag_layout": config.tag_layout.name(),
+            // Tempos de cache fria (--flush-cache): não comparáveis aos de execuções sem a opção
+            "flush_cache": config.flush_cache.is_some(),
+            "flush_cache_mib": config.flush_cache_mib(),
+            "baseline_cipher": config.baseline_cipher,
+            "inject_kem_failures": config.inject_kem_failures,
+            "decaps_per_encap": config.decaps_per_encap,
+            "member_leave_rate": config.member_leave_rate,
+            "edit_rate": config.edit_rate,
+            "key_confirmation": config.key_confirmation,
+            "key_backup": config.key_backup,
+            "group_fanout": config.group_fanout,
+            "rotation_strategy": rotation_strategy,
+            "rotation_ratio": config.rotation_ratio,
+            "hybrid": config.hybrid,
+            // Argumentos que reproduzem esta execução (--from-config <este arquivo>) e a
+            // matriz efetiva que eles produzem
+            "reproducao": reproduction_metadata(config)?,
+            "seed": config.seed,
+            "entropia": {
+                "cripto": config.entropy_source().name(),
+                "kem": kem_entropy,
+                "backend_sistema": os_entropy_backend(),
+            },
+            "aes": {
+                "modo": config.aes_backend.name(),
+                "backend": aes_backend(),
+                "software_forcado": aes_software_forced(),
+            },
+        }),
+        json!({
+            "raw_samples_ns": config.raw_samples_ns,
+            "drift_log": config.drift_log,
+            "bandwidth_series": config.bandwidth_series,
+            "series_window": config.bandwidth_series.then_some(config.series_window),
+            "message_counts": config.message_counts,
+            "sweep": (!config.message_counts.is_empty()).then(|| json!({
+                "cenario": format!("{:?}", config.sweep_scenario),
+                "padrao_trafego": format!("{:?}", config.sweep_pattern),
+                "cifra": config.sweep_cipher,
+            })),
+            "dump_sample": config.dump_sample.map(|encoding| encoding.name()),
+            "dump_keys_insecure": config.dump_keys_insecure,
+            "tidy": config.tidy,
+            "per_type_stats": config.per_type_stats,
+            "sqlite": config.sqlite.as_ref().map(|path| path.display().to_string()),
+            "fail_on_nonnormal": config.fail_on_nonnormal,
+            "report": config.report.as_ref().map(|path| path.display().to_string()),
+            "latex": config.latex.as_ref().map(|path| path.display().to_string()),
+            "monitor_throttle": config.monitor_throttle,
+            "throttle_threshold": config.monitor_throttle.then_some(config.throttle_threshold),
+            "throttle_suspected": throttle.map(|check| check.suspected),
+            // Taxas globais de outliers por métrica, gravadas ao fim da execução
+            "outliers": outliers.map(|summary| summary.iter().map(|entry| (entry.metric.to_string(), json!({
+                "amostras": entry.samples,
+                "outliers": entry.outliers,
+                "extremos": entry.extreme,
+                "taxa": entry.rate(),
+                "taxa_extremos": entry.extreme_rate(),
+                "nao_finitos": entry.nonfinite,
+                "configuracoes_invalidas": entry.invalid,
+            }))).collect::<serde_json::Map<_, _>>()),
+            "frequencia_cpu": throttle.map(|check| json!({
+                "amostras": check.samples,
+                "mhz_inicial": check.initial_mhz,
+                "mhz_final": check.final_mhz,
+                "queda_relativa": check.drop_fraction,
+            })),
+            "bibliotecas": bibliotecas,
+        }),
+    ]);
+    let json = serde_json::to_string_pretty(&metadata).map_err(std::io::Error::other)?;
+    fs::write(path, json + "\n")
+        .map_err(|e| ExperimentError::from(e).context(format!("não foi possível gravar {}", path.display())))
+}
+
+/// Junta os objetos JSON de `parts` em um só, preservando a ordem das chaves
+///
+/// Os metadados são montados em blocos porque um único `json!` com todas as chaves
+/// passaria do limite padrão de recursão da expansão de macros.
+fn merge_json_objects<const N: usize>(parts: [serde_json::Value; N]) -> serde_json::Value {
+    let mut merged = serde_json::Map::new();
+    for part in parts {
+        if let serde_json::Value::Object(object) = part {
+            merged.extend(object);
+        }
+    }
+    serde_json::Value::Object(merged)
+}
+
+/// Bloco `reproducao` dos metadados: argumentos efetivos e a matriz que eles executam
+///
+/// Os argumentos incluem a semente base sorteada, quando `--seed` não foi dado;
+/// caminhos relativos valem a partir do diretório de onde a execução é repetida.
+fn reproduction_metadata(config: &ExperimentConfig) -> Result<serde_json::Value, ExperimentError> {
+    let specs = experiment_specs(config)?;
+    let distinct = |values: Vec<String>| {
+        let mut seen: Vec<String> = Vec::new();
+        for value in values {
+            if !seen.contains(&value) {
+                seen.push(value);
+            }
+        }
+        seen
+    };
+    let arguments = config.reproduction_arguments();
+    Ok(json!({
+        "argumentos": arguments,
+        "comando": std::iter::once(env!("CARGO_PKG_NAME").to_string()).chain(arguments.iter().cloned())
+            .collect::<Vec<_>>().join(" "),
+        "cenarios": distinct(specs.iter().map(|spec| format!("{:?}", spec.scenario)).collect()),
+        "padroes_trafego": distinct(specs.iter().map(|spec| format!("{:?}", spec.pattern)).collect()),
+        "acordos": distinct(specs.iter().map(|spec| spec.agreement.to_string()).collect()),
+        "cifras": distinct(specs.iter().map(|spec| spec.cipher.to_string()).collect()),
+        "configuracoes": specs.len(),
+        "nivel_confianca": CONFIDENCE_LEVEL,
+    }))
+}
+
+/// Recusa `--aes-backend software` em um binário que escolhe o AES em tempo de execução
+///
+/// O backend em software só pode ser forçado na compilação; medir com AES-NI achando
+/// que é software invalidaria a comparação, então a execução nem começa.
+fn check_aes_backend(config: &ExperimentConfig) -> Result<(), ExperimentError> {
+    if config.aes_backend == AesBackendMode::Software && !aes_software_forced() {
+        return Err(ExperimentError::Config(format!(
+            "--aes-backend software requer um binário compilado com o AES em software \
+             (backend atual: {}); recompile com RUSTFLAGS=\"--cfg aes_force_soft\" cargo build --release",
+            aes_backend()
+        )));
+    }
+    Ok(())
+}
+
+/// Recusa, antes de executar, um `--fixed-size` acima do limite seguro de alguma cifra
+///
+/// As distribuições realistas não passam de 10 MB, bem abaixo de qualquer limite; só
+/// o tamanho fixo pode excedê-lo. O erro lista cada cifra afetada com o seu limite.
+fn check_fixed_size_limits(config: &ExperimentConfig, specs: &[ConfigSpec]) -> Result<(), ExperimentError> {
+    let Some(size) = config.fixed_size else { return Ok(()) };
+    let mut ciphers: Vec<&str> = specs.iter().map(|spec| spec.cipher).collect();
+    ciphers.sort_unstable();
+    ciphers.dedup();
+    let errors: Vec<String> = ciphers
+        .into_iter()
+        .filter_map(|cipher| check_plaintext_size(cipher, config.ctr_width, size).err())
+        .map(|error| error.to_string())
+        .collect();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(ExperimentError::Config(format!(
+        "--fixed-size {} excede o limite de {} cifra(s):\n  {}",
+        size, errors.len(), errors.join("\n  ")
+    )))
+}
+
+/// Função principal do experimento com verificação de normalidade
+///
+/// Esta função executa o experimento completo de desempenho criptográfico,
+/// incluindo detecção de outliers, verificação de normalidade e aplicação
+/// de estatísticas apropriadas para cada tipo de distribuição.
+///
+/// As configurações são produzidas por um ou mais workers (`--jobs`) e enviadas
+/// a um coordenador que grava o CSV em ordem canônica à medida que os prefixos
+/// ficam completos. Com `--jobs 1` (padrão) a execução é sequencial, como antes.
+///
+/// Retorna o nome do arquivo CSV com os resultados do experimento e as métricas não
+/// normais selecionadas por `--fail-on-nonnormal` (vazio sem a opção). Uma falha de
+/// E/S ou de uma primitiva encerra a execução com o erro; as configurações concluídas
+/// antes dela ficam gravadas no CSV.
+fn run_normality_aware_experiment(config: &ExperimentConfig) -> Result<(String, Vec<String>), ExperimentError> {
+    println!("=== EXPERIMENTO COM VERIFICAÇÃO DE NORMALIDADE ===");
+
+    // Workload gravado (--record) ou reproduzido (--replay); um replay ilegível ou que
+    // não cubra as sessões planejadas encerra a execução antes de criar qualquer arquivo
+    let specs = experiment_specs(config)?;
+    let trace = if let Some(path) = &config.replay {
+        println!("Reproduzindo workload gravado em: {}", path.display());
+        let trace = WorkloadReplay::load(path)
+            .map(WorkloadTrace::Replay)
+            .map_err(|e| e.context(format!("não foi possível ler o arquivo de replay {}", path.display())))?;
+        check_replay(config, &specs, &trace)?;
+        trace
+    } else if let Some(path) = &config.record {
+        println!("Gravando decisões do workload em: {}", path.display());
+        let recorder = WorkloadRecorder::create(path)
+            .map_err(|e| e.context(format!("não foi possível criar {}", path.display())))?;
+        WorkloadTrace::Record(recorder)
+    } else {
+        WorkloadTrace::Live
+    };
+
+    // Gera timestamp único para identificar o experimento
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
+    let pasta_resultados = &config.output_dir;
+
+    // Garante que a pasta de resultados existe
+    fs::create_dir_all(pasta_resultados)
+        .map_err(|e| ExperimentError::from(e).context(format!("não foi possível criar {}", pasta_resultados.display())))?;
+    let filename = pasta_resultados
+        .join(format!("resultados_normality_check_{}.csv", timestamp))
+        .display()
+        .to_string();
+
+    // Abre arquivo CSV para escrita dos resultados
+    let mut ordered_writer = OrderedResultWriter::new(create_output(Path::new(&filename))?, &config.baseline_cipher)?;
+
+    // Metadados de procedência (versões das bibliotecas, parâmetros) ao lado do CSV
+    let metadata_filename = pasta_resultados.join(format!("resultados_normality_check_{}_metadata.json", timestamp));
+    write_metadata_sidecar(config, &filename, &timestamp, &metadata_filename, None, None)?;
+    println!("Metadados da execução em: {}", metadata_filename.display());
+
+    let total_configs = specs.len();
+    let jobs = config.jobs;
+    if jobs > 1 {
+        println!("Executando configurações em paralelo com {} workers", jobs);
+    }
+    if let Some(mib) = config.flush_cache_mib() {
+        println!("Caches esvaziadas antes de cada operação ({} MiB por varredura): tempos de cache fria", mib);
+    }
+
+    // Corpus de payloads reais (--corpus), lido uma vez e compartilhado por todas as sessões
+    let corpus = match &config.corpus {
+        Some(dir) => {
+            let corpus = PayloadCorpus::load(dir)?;
+            println!("Payloads do corpus em: {} ({} arquivos, {} linhas de conversa)",
+                     dir.display(), corpus.file_count(), corpus.chat_line_count());
+            Some(Arc::new(corpus))
+        }
+        None => None,
+    };
+
+    // Exportador de métricas ao vivo (--metrics-endpoint); sem ele, nada é publicado
+    let metrics_installed = config.metrics_endpoint.as_ref().is_some_and(|exporter| {
+        match exporter.install() {
+            Ok(()) => {
+                println!("Métricas ao vivo enviadas a cada {} s para: {}", PUSH_INTERVAL.as_secs(), exporter.url());
+                true
+            }
+            Err(e) => {
+                println!("[AVISO] {}; a execução segue sem métricas ao vivo", e);
+                false
+            }
+        }
+    });
+
+    // Orçamento de tempo (--max-runtime) e Ctrl-C: verificados no início de cada configuração
+    let experiment_start = Instant::now();
+    let budget = config.max_runtime.map(Duration::from_secs_f64);
+    let budget_exhausted = AtomicBool::new(false);
+    // Falha de uma configuração ou da gravação: nenhuma configuração nova é iniciada
+    let failed = AtomicBool::new(false);
+    install_interrupt_handler();
+    let should_stop = || {
+        if budget.is_some_and(|budget| experiment_start.elapsed() >= budget) {
+            budget_exhausted.store(true, Ordering::Relaxed);
+        }
+        budget_exhausted.load(Ordering::Relaxed) || failed.load(Ordering::Relaxed) || INTERRUPTED.load(Ordering::SeqCst)
+    };
+
+    // Frequência da CPU amostrada antes da primeira configuração e ao fim de cada uma
+    let mut frequency_monitor = config.monitor_throttle.then(FrequencyMonitor::new);
+    if let Some(monitor) = frequency_monitor.as_mut() {
+        match monitor.sample() {
+            Some(mhz) => println!("Monitorando a frequência da CPU (inicial: {:.0} MHz)", mhz),
+            None => println!("[AVISO] Frequência da CPU indisponível neste sistema; throttling não será detectado"),
+        }
+    }
+
+    // Ordem de execução: canônica, ou permutada com --shuffle-configs; o coordenador
+    // grava sempre na ordem canônica
+    let mut order: Vec<&ConfigSpec> = specs.iter().collect();
+    shuffle_execution_order(&mut order, config, 0);
+    if config.shuffle_configs {
+        println!("Ordem de execução aleatória (--shuffle-configs); o CSV segue a ordem canônica");
+    }
+
+    let pool = worker_pool(jobs)?;
+
+    // Workers enviam (índice, resultado) ao coordenador, que reordena antes de gravar.
+    // Um envio recusado significa que o coordenador parou por um erro: o worker encerra
+    let (sender, receiver) = mpsc::channel::<(usize, Result<ConfigResult, ExperimentError>)>();
+    let mut failure = None;
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            if config.interleave {
+                // Rodadas intercaladas: uma repetição de cada configuração ainda ativa por
+                // rodada, de modo que derivas lentas (temperatura, frequência, carga do
+                // sistema) atinjam todas as configurações por igual
+                let rounds = repetitions(config);
+                let runs = specs.iter()
+                    .map(|spec| ConfigRun::new(spec, config, &trace, corpus.as_ref()).map(|run| (run, Ok(true))))
+                    .collect::<Result<Vec<(ConfigRun, Result<bool, ExperimentError>)>, _>>();
+                let mut runs = match runs {
+                    Ok(runs) => runs,
+                    Err(e) => {
+                        let _ = sender.send((0, Err(e)));
+                        return;
+                    }
+                };
+                for rep in 0..rounds {
+                    if should_stop() {
+                        break;
+                    }
+                    if rep % 10 == 0 {
+                        println!("\nRodada {}/{} (--interleave)", rep + 1, rounds);
+                    }
+                    shuffle_execution_order(&mut runs, config, rep);
+                    let step = |(run, state): &mut (ConfigRun, Result<bool, ExperimentError>)| {
+                        if matches!(state, Ok(true)) {
+                            *state = run.run_repetition(&trace, rep);
+                        }
+                    };
+                    match &pool {
+                        Some(pool) => pool.install(|| runs.par_iter_mut().for_each(step)),
+                        None => runs.iter_mut().for_each(step),
+                    }
+                    // Sem configurações ativas, ou depois de uma falha, as rodadas terminam
+                    if runs.iter().all(|(_, state)| !matches!(state, Ok(true))) || runs.iter().any(|(_, state)| state.is_err()) {
+                        break;
+                    }
+                }
+                runs.sort_by_key(|(run, _)| run.spec.index);
+                for (run, state) in runs {
+                    let index = run.spec.index;
+                    if let Err(e) = state {
+                        let _ = sender.send((index, Err(e)));
+                        return;
+                    }
+                    if run.completed() == 0 {
+                        continue;
+                    }
+                    print_config_header(run.spec, total_configs);
+                    if sender.send((index, Ok(run.finish()))).is_err() {
+                        return;
+                    }
+                }
+            } else {
+                run_specs(&order, pool.as_ref(), config, &trace, corpus.as_ref(), &should_stop, sender);
+            }
+        });
+
+        // Coordenador: grava no CSV cada prefixo contíguo assim que fica pronto; o primeiro
+        // erro interrompe a execução e descarta os resultados que ainda chegariam
+        for (completed, (index, result)) in receiver.into_iter().enumerate() {
+            let result = match result {
+                Ok(result) => result,
+                Err(e) => {
+                    failed.store(true, Ordering::Relaxed);
+                    failure = Some(e.context(format!("configuração {}/{}", index + 1, total_configs)));
+                    break;
+                }
+            };
+            // Métricas ao vivo (--metrics-endpoint), enviadas em segundo plano pelo exportador
+            if metrics_installed {
+                MetricsSnapshot {
+                    configs_completed: completed + 1,
+                    configs_total: total_configs,
+                    elapsed_s: experiment_start.elapsed().as_secs_f64(),
+                    throughput_msgs_s: result.aggregate_throughput,
+                    kem_ms_mean: result.kem_time_stats.mean,
+                }
+                .record();
+            }
+            if let Err(e) = ordered_writer.push(index, result) {
+                failed.store(true, Ordering::Relaxed);
+                failure = Some(ExperimentError::from(e).context(format!("não foi possível gravar {}", filename)));
+                break;
+            }
+            if let Some(monitor) = frequency_monitor.as_mut() {
+                monitor.sample();
+            }
+        }
+    });
+    if let Some(error) = failure {
+        let results = ordered_writer.finish_partial()?;
+        eprintln!("\n[ERRO] Execução interrompida: {}/{} configurações concluídas e gravadas em {}",
+                  results.len(), total_configs, filename);
+        return Err(error);
+    }
+    if let WorkloadTrace::Record(recorder) = &trace {
+        recorder.flush()?;
+    }
+    // Dá tempo ao exportador de enviar o último instantâneo antes de o processo terminar
+    if metrics_installed {
+        std::thread::sleep(PUSH_INTERVAL);
+    }
+    // Com Ctrl-C, o CSV parcial e os metadados são gravados, e o retorno é antecipado: as
+    // análises finais ficam de fora, e main encerra com código 130
+    let interrupted = INTERRUPTED.load(Ordering::SeqCst);
+    let results = if interrupted {
+        let results = ordered_writer.finish_partial()?;
+        println!("\n[INTERROMPIDO] Ctrl-C recebido: {}/{} configurações concluídas e gravadas em {}",
+                 results.len(), total_configs, filename);
+        results
+    } else if budget_exhausted.load(Ordering::Relaxed) {
+        let results = ordered_writer.finish_partial()?;
+        println!("\n[TEMPO] Orçamento de {:.0} s esgotado: {}/{} configurações concluídas e gravadas",
+                 config.max_runtime.unwrap_or_default(), results.len(), total_configs);
+        results
+    } else {
+        ordered_writer.finish()?
+    };
+
+    // Queda sustentada de frequência ao longo da execução (throttling térmico)
+    let throttle = frequency_monitor.as_ref().and_then(|monitor| {
+        let check = detect_throttle(monitor.samples(), config.throttle_threshold);
+        match &check {
+            Some(check) if check.suspected => println!(
+                "\n[AVISO] Suspeita de throttling térmico: frequência média caiu {:.1}% ({:.0} → {:.0} MHz) \
+                 ao longo da execução; tempos das últimas configurações podem estar inflados",
+                check.drop_fraction * 100.0, check.initial_mhz, check.final_mhz),
+            Some(check) => println!("\nFrequência da CPU estável: {:.0} → {:.0} MHz em {} amostras",
+                                    check.initial_mhz, check.final_mhz, check.samples),
+            None => println!("\nAmostras de frequência insuficientes para avaliar throttling ({})",
+                             monitor.samples().len()),
+        }
+        check
+    });
+
+    // Taxas globais de outliers, como indicador de qualidade dos dados da execução
+    let outliers = outlier_summary(&results);
+    print_outlier_summary(&outliers);
+    write_metadata_sidecar(config, &filename, &timestamp, &metadata_filename, throttle.as_ref(), Some(&outliers))?;
+    if interrupted {
+        return Ok((filename, Vec::new()));
+    }
+
+    // Teste de equivalência entre AES-GCM e ChaCha20 em cada célula
+    if let Some(margin) = config.equivalence_margin {
+        let tost_filename = pasta_resultados.join(format!("equivalencia_tost_{}.csv", timestamp));
+        println!("\nTestes de equivalência (TOST, margem {} ms) em: {}", margin, tost_filename.display());
+        write_equivalence_tests(&results, margin, &tost_filename)?;
+    }
+
+    // Custo total (KEM + cifragem) em grade cenário × acordo/cifra, para o mapa de calor
+    if config.cost_heatmap {
+        let heatmap_filename = pasta_resultados.join(format!("mapa_calor_custo_{}.csv", timestamp));
+        println!("\nMapa de calor do custo KEM + cifragem em: {}", heatmap_filename.display());
+        write_cost_heatmap(&results, &heatmap_filename)?;
+    }
+
+    // Forma das distribuições do acordo clássico vs híbrido (Kolmogorov-Smirnov), por célula
+    let comparison_filename = pasta_resultados.join(format!("comparacao_acordos_{}.csv", timestamp));
+    println!("\nComparação entre acordos (Kolmogorov-Smirnov) em: {}", comparison_filename.display());
+    write_agreement_comparisons(&results, &comparison_filename)?;
+
+    // Welch, Mann-Whitney e d de Cohen de cada acordo frente ao clássico (também via o subcomando analyze)
+    if config.pairwise_comparisons {
+        let pairwise_filename = pasta_resultados.join(format!("comparacoes_{}.csv", timestamp));
+        println!("\nComparações (Welch, Mann-Whitney, Cohen's d) em: {}", pairwise_filename.display());
+        let samples: Vec<ConfigSamples> = results.iter().map(ConfigSamples::from_result).collect();
+        write_pairwise_comparisons(&samples, &pairwise_filename)?;
+    }
+
+    // Estabilidade das estatísticas em duas metades aleatórias das amostras
+    if config.split_half {
+        let split_filename = pasta_resultados.join(format!("estabilidade_metades_{}.csv", timestamp));
+        println!("\nEstabilidade em duas metades (split-half) em: {}", split_filename.display());
+        write_split_half_stability(&results, config, &split_filename)?;
+    }
+
+    // Relatório legível em Markdown, montado a partir dos resultados coletados
+    if let Some(report_path) = &config.report {
+        println!("\nRelatório em Markdown em: {}", report_path.display());
+        fs::write(report_path, markdown_report(&results, &timestamp, config.seed))
+            .map_err(|e| ExperimentError::from(e).context(format!("não foi possível gravar {}", report_path.display())))?;
+    }
+
+    // Tabela LaTeX do comparativo clássico vs híbrido, pronta para \input no artigo
+    if let Some(latex_path) = &config.latex {
+        println!("Tabela LaTeX em: {}", latex_path.display());
+        fs::write(latex_path, latex_table(&results, &timestamp))
+            .map_err(|e| ExperimentError::from(e).context(format!("não foi possível gravar {}", latex_path.display())))?;
+    }
+
+    // Tempos brutos por repetição em nanossegundos inteiros
+    if config.raw_samples_ns {
+        let raw_filename = pasta_resultados.join(format!("amostras_ns_{}.csv", timestamp));
+        println!("\nAmostras brutas (ns) em: {}", raw_filename.display());
+        write_raw_samples(&results, &raw_filename)?;
+    }
+
+    // Repetições em ordem cronológica, para diagnosticar deriva ao longo da execução
+    if config.drift_log {
+        let drift_filename = pasta_resultados.join(format!("deriva_{}.csv", timestamp));
+        println!("\nTempos por instante de início (deriva) em: {}", drift_filename.display());
+        write_drift_log(&results, &drift_filename)?;
+    }
+
+    // Mesmos resultados em formato longo, uma linha por (configuração, métrica, estatística)
+    if config.tidy {
+        let tidy_filename = pasta_resultados.join(format!("resultados_tidy_{}.csv", timestamp));
+        println!("\nResultados em formato longo (tidy) em: {}", tidy_filename.display());
+        write_tidy_results(&results, &tidy_filename)?;
+    }
+
+    // Execução e resultados acrescentados ao banco SQLite, para consultas entre execuções
+    if let Some(db_path) = &config.sqlite {
+        let metadata = fs::read_to_string(&metadata_filename)
+            .map_err(|e| ExperimentError::from(e).context(format!("não foi possível ler {}", metadata_filename.display())))?;
+        let run = RunRecord {
+            timestamp: &timestamp,
+            seed: config.seed,
+            schema_version: SCHEMA_VERSION,
+            csv_file: &filename,
+            metadata_json: &metadata,
+        };
+        let rows: Vec<Vec<Cell>> = results.iter().map(ConfigResult::to_cells).collect();
+        match write_results(db_path, &run, &RESULT_COLUMNS, &rows) {
+            Ok(run_id) => println!("\nResultados no SQLite em: {} (execução {})", db_path.display(), run_id),
+            Err(e) => println!("\n[AVISO] Não foi possível gravar no SQLite {}: {}", db_path.display(), e),
+        }
+    }
+
+    // Largura de banda acumulada por mensagem (uma repetição representativa por configuração)
+    if config.bandwidth_series {
+        let series_filename = pasta_resultados.join(format!("largura_banda_acumulada_{}.csv", timestamp));
+        println!("\nLargura de banda acumulada em: {}", series_filename.display());
+        write_bandwidth_series(&results, &series_filename)?;
+    }
+
+    // Um exemplo concreto de cifragem por configuração, para apêndices de vetores de teste
+    if let Some(encoding) = config.dump_sample {
+        let sample_filename = pasta_resultados.join(format!("amostras_cifradas_{}.csv", timestamp));
+        println!("\nAmostras de texto cifrado ({}) em: {}", encoding.name(), sample_filename.display());
+        if config.dump_keys_insecure {
+            println!("  [AVISO] --dump-keys-insecure: as chaves das mensagens estão gravadas em claro");
+        }
+        write_ciphertext_samples(&results, encoding, config.dump_keys_insecure, &sample_filename)?;
+    }
+
+    // Estatísticas de cifragem e bytes separadas por tipo de mensagem
+    if config.per_type_stats {
+        println!("\nEstatísticas por tipo de mensagem nas colunas cipher_us_<tipo>_* e msg_bytes_<tipo>_* do CSV");
+        print_type_normality(&results);
+    }
+
+    // Correlação entre tamanho da mensagem e tempo de cifragem, por cifra
+    if config.size_time_correlation {
+        let corr_filename = pasta_resultados.join(format!("correlacao_tamanho_tempo_{}.csv", timestamp));
+        println!("\nCorrelação tamanho x tempo de cifragem em: {}", corr_filename.display());
+        write_size_time_correlations(&results, &corr_filename)?;
+    }
+
+    // Finaliza experimento e exibe resumo
+    println!("\n=== EXPERIMENTO COM ANÁLISE DE OUTLIERS E NORMALIDADE CONCLUÍDO ===");
+    println!("Resultados salvos em: {}", filename);
+    println!("Arquivo inclui informações sobre:");
+    println!("  - Detecção de outliers (moderados e extremos)");
+    println!("  - Verificação de normalidade");
+    println!("  - Tipo de estatística aplicada");
+    println!("  - Tamanho das amostras após limpeza");
+    println!("\nSequência de análise aplicada:");
+    println!("  1. Detecção de outliers (método IQR)");
+    println!("  2. Remoção de outliers extremos (opcional)");
+    println!("  3. Verificação de normalidade");
+    println!("  4. Aplicação de estatísticas apropriadas");
+
+    let nonnormal = config.fail_on_nonnormal.as_deref()
+        .map(|selected| nonnormal_metrics(&results, selected))
+        .unwrap_or_default();
+    Ok((filename, nonnormal))
+}
+
+/// Função para executar o script de geração de gráficos
+/// 
+/// Esta função executa o script Python responsável por gerar gráficos
+/// dos resultados experimentais, incluindo análise de normalidade e outliers.
+/// Tenta usar o ambiente virtual primeiro, com fallback para execução direta.
+///
+/// As pastas de resultados e de gráficos são repassadas ao script pelas variáveis
+/// de ambiente `PQC_RESULTS_DIR` e `PQC_PLOTS_DIR`; o CSV de resultados desta execução
+/// vai como primeiro argumento, seguido dos de `--analysis-args`. O script é o de
+/// `--analysis-script` ou, sem a opção, `gerar_graficos.py` em `--analysis-dir`.
+fn generate_plots(config: &ExperimentConfig, results_filename: &str) {
+    println!("\nGerando gráficos dos resultados...");
+    
+    // O ambiente virtual fica na raiz do projeto, ao lado da pasta de análise
+    let analysis_dir = &config.analysis_dir;
+    let venv_path = analysis_dir.join("../venv");
+    let venv_python = venv_path.join("bin/python");
+    // Script padrão ou o de --analysis-script, que recebe o CSV de resultados em argv
+    let plot_script = config.analysis_script_path();
+    let script_args = config.analysis_arguments(results_filename);
+    let plots_dir = config.plots_dir.display();
+    
+    // Verifica se o script de geração de gráficos existe
+    if !plot_script.exists() {
+        println!("ERRO: Script de gráficos não encontrado: {}", plot_script.display());
+        return;
+    }
+    
+    // Tenta usar o ambiente virtual primeiro
+    if venv_python.exists() {
+        println!("  Usando ambiente virtual Python...");
+        
+        // Instala dependências necessárias para geração de gráficos
+        let venv_pip = venv_path.join("bin/pip");
+        let install_plot_deps = Command::new(&venv_pip)
+            .arg("install")
+            .arg("--quiet")
+            .arg("matplotlib")
+            .arg("seaborn")
+            .arg("pandas")
+            .arg("numpy")
+            .output();
+        
+        match install_plot_deps {
+            Ok(output) => {
+                if !output.status.success() {
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    println!("  AVISO: Problemas na instalação de dependências de gráficos: {}", stderr);
+                }
+            }
+            Err(e) => {
+                println!("  AVISO: Erro ao instalar dependências de gráficos: {}", e);
+            }
+        }
+        
+        // Executa script de gráficos com ambiente virtual
+        let result = Command::new(&venv_python)
+            .arg(&plot_script)
+            .args(&script_args)
+            .current_dir(analysis_dir)
+            .env("PQC_RESULTS_DIR", &config.output_dir)
+            .env("PQC_PLOTS_DIR", &config.plots_dir)
+            .output();
+        
+        match result {
+            Ok(output) => {
+                if output.status.success() {
+                    println!("  SUCESSO: Gráficos gerados com sucesso!");
+                    println!("  Arquivos salvos em: {}", plots_dir);
+                    
+                    let stdout = String::from_utf8_lossy(&output.stdout);
+                    if !stdout.is_empty() {
+                        println!("  Saída do script:");
+                        for line in stdout.lines() {
+                            println!("    {}", line);
+                        }
+                    }
+                    return;
+                } else {
+                    println!("  AVISO: Erro ao gerar gráficos com venv:");
+                    let stderr = String::from_utf8_lossy(&output.stderr);
+                    println!("    {}", stderr);
+                }
+            }
+            Err(e) => {
+                println!("  AVISO: Erro ao executar script com venv: {}", e);
+            }
+        }
+    }
+    
+    // Fallback: tenta executar sem ambiente virtual
+    println!("  Tentando executar sem ambiente virtual...");
+    let fallback_result = Command::new("python3")
+        .arg(&plot_script)
+        .args(&script_args)
+        .current_dir(analysis_dir)
+        .env("PQC_RESULTS_DIR", &config.output_dir)
+        .env("PQC_PLOTS_DIR", &config.plots_dir)
+        .output();
+    
+    match fallback_result {
+        Ok(fallback_output) => {
+            if fallback_output.status.success() {
+                println!("  SUCESSO: Gráficos gerados com sucesso (fallback)!");
+                println!("  Arquivos salvos em: {}", plots_dir);
+                
+                let stdout = String::from_utf8_lossy(&fallback_output.stdout);
+                if !stdout.is_empty() {
+                    println!("  Saída do script:");
+                    for line in stdout.lines() {
+                        println!("    {}", line);
+                    }
+                }
+            } else {
+                println!("  ERRO: Falha no fallback:");
+                let fallback_stderr = String::from_utf8_lossy(&fallback_output.stderr);
+                println!("    {}", fallback_stderr);
+                println!("  INFO: Verifique se as dependências Python estão instaladas:");
+                println!("    pip install matplotlib seaborn pandas numpy");
+            }
+        }
+        Err(e) => {
+            println!("  ERRO: Erro ao executar fallback: {}", e);
+        }
+    }
+}
+
+/// Modo de amortização (`--message-counts`): executa a célula escolhida com cada acordo
+/// para cada total de mensagens e grava a curva em amortizacao_<timestamp>.csv
+///
+/// O CSV principal não é gravado: as configurações diferem apenas no total de mensagens,
+/// que as comparações entre células do pós-processamento não distinguem.
+fn run_message_count_sweep(config: &ExperimentConfig) -> Result<String, ExperimentError> {
+    println!("=== MODO DE AMORTIZAÇÃO: {:?} + {:?} + {} ===",
+             config.sweep_scenario, config.sweep_pattern, config.sweep_cipher);
+    let specs = experiment_specs(config)?;
+
+    let timestamp = chrono::Local::now().format("%Y%m%d_%H%M%S").to_string();
+    fs::create_dir_all(&config.output_dir)
+        .map_err(|e| ExperimentError::from(e).context(format!("não foi possível criar {}", config.output_dir.display())))?;
+    let filename = config.output_dir
+        .join(format!("amortizacao_{}.csv", timestamp))
+        .display()
+        .to_string();
+    let metadata_filename = config.output_dir.join(format!("amortizacao_{}_metadata.json", timestamp));
+    write_metadata_sidecar(config, &filename, &timestamp, &metadata_filename, None, None)?;
+
+    let corpus = match &config.corpus {
+        Some(dir) => Some(Arc::new(PayloadCorpus::load(dir)?)),
+        None => None,
+    };
+    // Mesmo pool e mesma execução da matriz principal; o primeiro erro encerra a curva
+    let (sender, receiver) = mpsc::channel();
+    let order: Vec<&ConfigSpec> = specs.iter().collect();
+    run_specs(&order, worker_pool(config.jobs)?.as_ref(), config, &WorkloadTrace::Live, corpus.as_ref(), &|| false, sender);
+    let mut indexed = Vec::with_capacity(specs.len());
+    for (index, result) in receiver {
+        let result = result.map_err(|e| e.context(format!("configuração {}/{}", index + 1, specs.len())))?;
+        indexed.push((index, result));
+    }
+    indexed.sort_by_key(|(index, _)| *index);
+    let results: Vec<ConfigResult> = indexed.into_iter().map(|(_, result)| result).collect();
+    write_amortization(&results, Path::new(&filename))?;
+
+    println!("\nCurva de amortização (custo de acordo de chaves por mensagem):");
+    for (result, point) in results.iter().zip(amortization_curve(&results)) {
+        println!("  {:>6} msgs | {:<24} | {:.4} ms/msg | banda do acordo: {:.2}%",
+                 result.num_messages, result.spec.agreement, point.kem_ms_per_message,
+                 point.kem_bandwidth_fraction * 100.0);
+    }
+    let outliers = outlier_summary(&results);
+    print_outlier_summary(&outliers);
+    write_metadata_sidecar(config, &filename, &timestamp, &metadata_filename, None, Some(&outliers))?;
+    Ok(filename)
+}
+
+/// Função main
+/// 
 /// Função principal que coordena todo o experimento de desempenho criptográfico.
 /// Executa o experimento, análise estatística e geração de gráficos em sequência.
 fn main() {
+    // Lê as opções de linha de comando (ou as registradas, com --from-config) e resolve
+    // os caminhos para absolutos
+    let args: Vec<String> = std::env::args_os().map(|arg| arg.to_string_lossy().into_owned()).collect();
+    let mut config = ExperimentConfig::from_arguments(&args[0], &args[1..]).unwrap_or_else(|error| {
+        eprintln!("Erro: {}", error);
+        std::process::exit(2);
+    });
+    if let Some(path) = &config.from_config {
+        println!("Reexecutando a configuração de {}: {}", path.display(), config.arguments.join(" "));
+    }
+    if let Err(error) = config.resolve_paths() {
+        eprintln!("Erro: {}", error);
+        std::process::exit(1);
+    }
+    if let Some(ExperimentCommand::Analyze { raw_samples, output_dir }) = &config.command {
+        if let Err(error) = run_analyze(raw_samples, output_dir) {
+            eprintln!("Erro: {}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(ExperimentCommand::List) = config.command {
+        print!("{}", list_options());
+        return;
+    }
+    if let Some(ExperimentCommand::InterArrival { sends, poll_ms, bins, seed, output_dir }) = &config.command {
+        if let Err(error) = run_inter_arrival(*sends, *poll_ms, *bins, *seed, output_dir) {
+            eprintln!("Erro: {}", error);
+            std::process::exit(1);
+        }
+        return;
+    }
+    // Opções inválidas encerram a execução antes de qualquer medição
+    if let Err(error) = check_aes_backend(&config)
+        .and_then(|()| experiment_specs(&config))
+        .and_then(|specs| check_fixed_size_limits(&config, &specs))
+    {
+        eprintln!("Erro: {}", error);
+        std::process::exit(2);
+    }
+    // Sem --seed, sorteia a semente base; ela fica registrada nos metadados para reexecução
+    let base_seed = *config.seed.get_or_insert_with(rand::random);
+    if !config.message_counts.is_empty() {
+        println!("Semente base: {} (reexecute com --seed {} para reproduzir)", base_seed, base_seed);
+        let filename = run_message_count_sweep(&config).unwrap_or_else(|error| {
+            eprintln!("Erro: {}", error);
+            std::process::exit(1);
+        });
+        println!("\nArquivos gerados:");
+        println!("  - CSV de amortização: {}", filename);
+        println!("  - Metadados (JSON): {}", filename.replace(".csv", "_metadata.json"));
+        return;
+    }
+
     println!("=== EXPERIMENTO DE DESEMPENHO CRIPTOGRÁFICO COM ANÁLISE ESTATÍSTICA ===");
     println!("Inicializando experimento");
+    if config.os_rng {
+        println!("Semente base do workload: {} (chaves e nonces vêm do sistema e não são reproduzíveis)", base_seed);
+    } else {
+        println!("Semente base: {} (reexecute com --seed {} para reproduzir)", base_seed, base_seed);
+    }
+    println!("Entropia criptográfica: {} [{}]", config.entropy_source().name(), os_entropy_backend());
+    println!("Backend de AES: {} (--aes-backend {})", aes_backend(), config.aes_backend.name());
     
     // Executa o experimento principal e obtém o nome do arquivo de resultados
-    let results_filename = run_normality_aware_experiment();
+    let (results_filename, nonnormal) = run_normality_aware_experiment(&config).unwrap_or_else(|error| {
+        eprintln!("Erro: {}", error);
+        std::process::exit(1);
+    });
+
+    // Ctrl-C: o CSV parcial e os metadados já estão gravados; análises e gráficos ficam de fora
+    if INTERRUPTED.load(Ordering::SeqCst) {
+        println!("\nArquivos gerados (execução interrompida):");
+        println!("  - CSV de resultados: {}", results_filename);
+        println!("  - Metadados (JSON): {}", results_filename.replace(".csv", "_metadata.json"));
+        std::process::exit(130);
+    }
     
     println!("\nExperimento concluído com sucesso!");
     println!("Análise estatística aplicada:");
@@ -955,7 +4902,1228 @@ fn main() {
     // Lista arquivos gerados
     println!("\nArquivos gerados:");
     println!("  - CSV de resultados: {}", results_filename);
+    println!("  - Metadados (JSON): {}", results_filename.replace(".csv", "_metadata.json"));
     
     // Executa geração de gráficos
-    generate_plots();
-}
\ No newline at end of file
+    generate_plots(&config, &results_filename);
+
+    // Modo estrito: a normalidade vira uma condição de sucesso da execução
+    if !nonnormal.is_empty() {
+        eprintln!("\nErro: {} métrica(s) não normal(is) com --fail-on-nonnormal:", nonnormal.len());
+        for violation in &nonnormal {
+            eprintln!("  - {}", violation);
+        }
+        std::process::exit(3);
+    }
+}
+#[cfg(test)]
+/// Testes unitários das funções estatísticas do experimento
+/// Verifica a distribuição t de Student contra valores tabelados
+/// e o comportamento do teste de equivalência TOST
+mod tests {
+    use super::*;
+    use clap::Parser;
+    use pq_crypto_matrix::crypto::BACKUP_MAC_SIZE;
+
+    #[test]
+    fn test_student_t_cdf_known_values() {
+        // Valores críticos bicaudais de 95% (tabela t)
+        assert!((student_t_cdf(2.228139, 10.0) - 0.975).abs() < 1e-5);
+        assert!((student_t_cdf(-2.228139, 10.0) - 0.025).abs() < 1e-5);
+        assert!((student_t_cdf(12.706205, 1.0) - 0.975).abs() < 1e-5);
+        // Com muitos graus de liberdade, converge para a normal padrão
+        assert!((student_t_cdf(1.959964, 1e6) - 0.975).abs() < 1e-5);
+        assert!((student_t_cdf(0.0, 5.0) - 0.5).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_pearson_and_spearman() {
+        let x = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let linear: Vec<f64> = x.iter().map(|v| 3.0 * v + 1.0).collect();
+        let cubic: Vec<f64> = x.iter().map(|v: &f64| v.powi(3)).collect();
+        let reversed: Vec<f64> = x.iter().rev().copied().collect();
+
+        assert!((pearson(&x, &linear) - 1.0).abs() < 1e-12);
+        assert!(pearson(&x, &cubic) < 1.0);
+        // Relação monotônica não linear: Spearman continua igual a 1
+        assert!((spearman(&x, &cubic) - 1.0).abs() < 1e-12);
+        assert!((spearman(&x, &reversed) + 1.0).abs() < 1e-12);
+        // Casos degenerados
+        assert!(pearson(&[1.0], &[2.0]).is_nan());
+        assert!(pearson(&x, &[2.0; 5]).is_nan());
+    }
+
+    #[test]
+    fn test_ranks_with_ties() {
+        assert_eq!(ranks(&[10.0, 20.0, 20.0, 30.0]), vec![1.0, 2.5, 2.5, 4.0]);
+        assert_eq!(ranks(&[3.0, 1.0, 2.0]), vec![3.0, 1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_tost_equivalent_samples() {
+        let a: Vec<f64> = (0..50).map(|i| 10.0 + (i % 5) as f64 * 0.01).collect();
+        let b: Vec<f64> = (0..50).map(|i| 10.01 + (i % 5) as f64 * 0.01).collect();
+        let (p_value, equivalent) = tost(&a, &b, 0.5);
+        assert!(equivalent);
+        assert!(p_value < 0.05);
+    }
+
+    #[test]
+    fn test_tost_different_samples() {
+        let a: Vec<f64> = (0..50).map(|i| 10.0 + (i % 5) as f64 * 0.01).collect();
+        let b: Vec<f64> = (0..50).map(|i| 12.0 + (i % 5) as f64 * 0.01).collect();
+        let (p_value, equivalent) = tost(&a, &b, 0.5);
+        assert!(!equivalent);
+        assert!(p_value > 0.05);
+    }
+
+    #[test]
+    fn test_message_type_counts_sum_to_total() {
+        let session = session_with(UsageScenario::SmallChat, "AES-GCM", &["--seed", "1"], 300, 100, 1);
+
+        let counted = session.text_count + session.image_count + session.file_count
+            + session.system_count + session.voice_count;
+        assert_eq!(session.messages, 300);
+        assert_eq!(counted, session.messages);
+        // Com 3% de voz no SmallChat, 300 mensagens incluem mensagens de voz
+        assert!(session.voice_count > 0);
+    }
+
+    #[test]
+    fn test_detect_duplicates_hashes_media_payloads() {
+        let session = session_with(UsageScenario::SmallChat, "AES-GCM", &["--seed", "1", "--detect-duplicates"], 100, 100, 1);
+
+        // Um hash por payload de mídia, todos distintos; textos e sistema ficam de fora
+        let media = session.image_count + session.file_count + session.voice_count;
+        assert!(media > 0);
+        assert_eq!(session.payload_hashes.len(), media);
+        assert_eq!(session.payload_hashes.iter().collect::<HashSet<_>>().len(), media);
+
+        // O corpus repete arquivos por construção, então as opções são incompatíveis
+        assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--detect-duplicates", "--corpus", "/tmp"]).is_err());
+    }
+
+    #[test]
+    fn test_analysis_script_receives_results_file_and_extra_args() {
+        let config = ExperimentConfig::parse_from(["pq_crypto_matrix", "--analysis-dir", "/opt/analise"]);
+        assert_eq!(config.analysis_script_path(), Path::new("/opt/analise/gerar_graficos.py"));
+        assert_eq!(config.analysis_arguments("/r/resultados.csv"), ["/r/resultados.csv"]);
+
+        let config = ExperimentConfig::parse_from([
+            "pq_crypto_matrix", "--analysis-script", "/tmp/minha_analise.py", "--analysis-args", "--formato pdf  --dpi 300",
+        ]);
+        assert_eq!(config.analysis_script_path(), Path::new("/tmp/minha_analise.py"));
+        assert_eq!(
+            config.analysis_arguments("/r/resultados.csv"),
+            ["/r/resultados.csv", "--formato", "pdf", "--dpi", "300"],
+        );
+    }
+
+    #[test]
+    fn test_simulated_pacing_drives_time_based_rotation() {
+        // Rotação por idade de 1 s e padrão Random (sem esperas): 20 mensagens com 100 ms
+        // simulados cada cobrem ~2 s de relógio simulado e rotacionam, sem dormir
+        let spec = random_spec(UsageScenario::SmallChat, "AES-GCM");
+        let plan = SessionPlan {
+            num_messages: 20, rotation: RotationStrategy::ByTime(Duration::from_secs(1)), seed: 1, cached_keys: None, corpus: None,
+        };
+        let config = ExperimentConfig::parse_from(["pq_crypto_matrix", "--seed", "1", "--inter-message-delay", "100"]);
+        assert_eq!(config.pacing, Pacing::Simulated);
+        let session = spec_session(&spec, &["--seed", "1", "--inter-message-delay", "100"], plan);
+        assert_eq!(session.messages, 20);
+        assert!(session.rotations >= 2, "{} rotações", session.rotations);
+        assert!(session.loop_time < Duration::from_secs(1), "{:?}", session.loop_time);
+
+        // Sem atraso, o laço termina bem antes de a sessão completar 1 s
+        assert_eq!(spec_session(&spec, &["--seed", "1"], plan).rotations, 1);
+
+        for invalid in ["-1", "NaN", "inf", "1e300"] {
+            assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--inter-message-delay", invalid]).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_target_moe_runs_at_least_one_batch_and_rejects_quick() {
+        let parse = |args: &[&str]| ExperimentConfig::try_parse_from([&["pq_crypto_matrix"][..], args].concat());
+        let config = parse(&["--target-moe", "0.05", "--max-repetitions", "3"]).unwrap();
+        assert_eq!(repetitions(&config), ADAPTIVE_BATCH);
+        let config = parse(&["--target-moe", "0.05"]).unwrap();
+        assert_eq!(repetitions(&config), 500);
+        // --quick fixa as repetições; a amostragem adaptativa não combina com ele
+        assert!(parse(&["--target-moe", "0.05", "--quick"]).is_err());
+    }
+
+    #[test]
+    fn test_counts_reject_zero() {
+        for option in ["--jobs", "--devices-per-user", "--senders"] {
+            assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", option, "0"]).is_err(), "{}", option);
+            assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", option, "2"]).is_ok(), "{}", option);
+        }
+    }
+
+    #[test]
+    fn test_hybrid_rejects_standard_and_single_component_agreements() {
+        for invalid in ["X25519+Kyber768", "kyber768+x25519", "Olm-Híbrido", "Olm-Clássico", "X25519", "Kyber1024"] {
+            assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--hybrid", invalid]).is_err(), "{}", invalid);
+        }
+        let config = ExperimentConfig::parse_from(["pq_crypto_matrix", "--hybrid", "kyber1024+X25519"]);
+        assert_eq!(experiment_agreements(&config), ["Olm-Clássico", "Olm-Híbrido", "X25519+Kyber1024"]);
+    }
+
+    #[test]
+    fn test_max_runtime_rejects_invalid_budgets() {
+        let config = ExperimentConfig::parse_from(["pq_crypto_matrix", "--max-runtime", "0.5"]);
+        assert_eq!(config.max_runtime, Some(0.5));
+        for invalid in ["0", "-1", "nan", "inf", "1e300", "x"] {
+            assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--max-runtime", invalid]).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_equivalence_margin_rejects_invalid_margins() {
+        let config = ExperimentConfig::parse_from(["pq_crypto_matrix", "--equivalence-margin", "0.05"]);
+        assert_eq!(config.equivalence_margin, Some(0.05));
+        for invalid in ["0", "-0.05", "nan", "inf", "x"] {
+            assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--equivalence-margin", invalid]).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_rotation_ratio_normalizes_rotations_per_session() {
+        let specs = build_config_specs(&AGREEMENTS);
+        let default = ExperimentConfig::parse_from(["pq_crypto_matrix"]);
+        let config = ExperimentConfig::parse_from(["pq_crypto_matrix", "--rotation-ratio", "10"]);
+        for spec in &specs {
+            let (interval, messages) = message_plan(spec, &config);
+            assert_eq!(messages, message_plan(spec, &default).1);
+            assert_eq!(interval, messages.div_ceil(10));
+        }
+
+        // As rotações por contagem seguem a razão em qualquer cenário
+        let session = session_with(UsageScenario::SmallChat, "AES-GCM", &["--rotation-ratio", "10"], 105, 105usize.div_ceil(10), 2);
+        assert_eq!(session.rotations, 10);
+
+        // Razões maiores que o total deixam o intervalo em 1 (uma rotação por mensagem)
+        let quick = ExperimentConfig::parse_from(["pq_crypto_matrix", "--quick", "--rotation-ratio", "1e9"]);
+        assert_eq!(message_plan(&specs[0], &quick).0, 1);
+        for invalid in ["0", "-2", "inf", "x"] {
+            assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--rotation-ratio", invalid]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_message_count_sweep_amortizes_kem_overhead() {
+        let config = ExperimentConfig::parse_from([
+            "pq_crypto_matrix", "--message-counts", "50,500", "--sweep-pattern", "burst",
+        ]);
+        let specs = experiment_specs(&config).unwrap();
+        assert_eq!(specs.len(), 2 * AGREEMENTS.len());
+        assert!(specs.iter().all(|spec| {
+            spec.scenario == UsageScenario::MediumGroup && spec.pattern == TrafficPattern::Burst && spec.cipher == "AES-GCM"
+        }));
+        assert_eq!(message_plan(&specs[2], &config).1, 500);
+        assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--message-counts", "0"]).is_err());
+        assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--sweep-cipher", "AES-GCM"]).is_err());
+        // Uma cifra fora de CIPHERS é recusada, em vez de trocada pela primeira
+        let mut unknown = config.clone();
+        unknown.sweep_cipher = "ROT13".to_string();
+        assert!(matches!(experiment_specs(&unknown), Err(ExperimentError::Config(_))));
+
+        // O mesmo handshake diluído em 10x mais mensagens
+        let curve = |messages: usize| {
+            let mut results: Vec<ConfigResult> = specs[..2].iter().cloned().map(dummy_result).collect();
+            for (result, (kem_ms, kem_bytes)) in results.iter_mut().zip([(1.0, 64.0), (3.0, 1_200.0)]) {
+                result.num_messages = messages;
+                result.kem_time_stats.mean = kem_ms;
+                result.kem_bw_stats.mean = kem_bytes;
+                result.msg_bw_stats.mean = 100.0 * messages as f64;
+            }
+            amortization_curve(&results)
+        };
+        let (short, long) = (curve(50), curve(500));
+        assert_eq!(short[0].kem_ms_overhead_per_message, 0.0);
+        assert!((short[1].kem_ms_overhead_per_message - 2.0 / 50.0).abs() < 1e-12);
+        assert!(long[1].kem_ms_per_message < short[1].kem_ms_per_message);
+        assert!(long[1].kem_bandwidth_fraction < short[1].kem_bandwidth_fraction);
+        assert!(long[1].bandwidth_overhead_fraction < short[1].bandwidth_overhead_fraction);
+    }
+
+    #[test]
+    fn test_per_type_samples_follow_message_counts() {
+        let session = session_with(UsageScenario::MediumGroup, "AES-GCM", &["--seed", "3", "--per-type-stats"], 30, 50, 3);
+        let counts = [session.text_count, session.image_count, session.file_count, session.system_count, session.voice_count];
+        assert_eq!(session.kind_samples.each_ref().map(Vec::len), counts);
+
+        // Cada tipo tem sua própria escala de tamanho: textos ficam muito abaixo das imagens
+        let mean_bytes = |samples: &[(f64, f64)]| samples.iter().map(|sample| sample.1).sum::<f64>() / samples.len() as f64;
+        let [text, image, ..] = &session.kind_samples;
+        assert!(mean_bytes(text) * 100.0 < mean_bytes(image));
+
+        let session = session_with(UsageScenario::MediumGroup, "AES-GCM", &["--seed", "3"], 30, 50, 3);
+        assert!(session.kind_samples.iter().all(Vec::is_empty));
+    }
+
+    /// Primeira configuração com o cenário e a cifra dados no padrão Random, que não espera
+    /// entre envios: as sessões dos testes ficam rápidas e reprodutíveis
+    fn random_spec(scenario: UsageScenario, cipher: &str) -> ConfigSpec {
+        build_config_specs(&AGREEMENTS)
+            .into_iter()
+            .find(|spec| spec.scenario == scenario && spec.pattern == TrafficPattern::Random && spec.cipher == cipher)
+            .unwrap()
+    }
+
+    /// Sessão ao vivo (repetição 0) de `spec`, executada com `args` (sem o nome do programa)
+    fn spec_session(spec: &ConfigSpec, args: &[&str], plan: SessionPlan) -> SessionMetrics {
+        let config = ExperimentConfig::parse_from([&["pq_crypto_matrix"][..], args].concat());
+        run_session(spec, &config, &WorkloadTrace::Live, 0, 0, plan).unwrap()
+    }
+
+    /// Sessão ao vivo de `random_spec(scenario, cipher)`, executada com `args` (sem o nome
+    /// do programa) e mensagens rotacionadas a cada `interval`
+    fn session_with(scenario: UsageScenario, cipher: &str, args: &[&str], messages: usize, interval: usize, seed: u64) -> SessionMetrics {
+        let plan = SessionPlan { num_messages: messages, rotation: RotationStrategy::ByCount(interval), seed, cached_keys: None, corpus: None };
+        spec_session(&random_spec(scenario, cipher), args, plan)
+    }
+
+    /// Sessão de 30 mensagens do SmallChat com AES-GCM, rotação a cada 10 e semente 4
+    fn small_chat_session(args: &[&str]) -> SessionMetrics {
+        session_with(UsageScenario::SmallChat, "AES-GCM", &[&["--seed", "4"][..], args].concat(), 30, 10, 4)
+    }
+
+    #[test]
+    fn test_edit_rate_reencrypts_last_text() {
+        let session = small_chat_session(&[]);
+        assert_eq!((session.edit_events, session.edit_bandwidth), (0, 0));
+
+        // Com taxa 1, toda mensagem a partir do primeiro texto é seguida de uma edição
+        let edited = small_chat_session(&["--edit-rate", "1"]);
+        assert!(edited.text_count > 0);
+        assert!(edited.edit_events >= edited.text_count && edited.edit_events <= edited.messages);
+        // Cada edição carrega ao menos o nonce de 12 bytes e a tag de 16 do AES-GCM
+        assert!(edited.edit_bandwidth >= edited.edit_events * 28);
+        // As mensagens em si não mudam: as edições ficam fora de msg_bw_bytes
+        assert_eq!(edited.text_count, session.text_count);
+    }
+
+    #[test]
+    fn test_key_confirmation_adds_one_token_per_session() {
+        let session = small_chat_session(&[]);
+        assert_eq!((session.confirm_time, session.confirm_bandwidth), (Duration::ZERO, 0));
+
+        // Uma confirmação por rotação (um dispositivo de Bob): nonce de 12 bytes, token e tag de 16
+        let confirmed = small_chat_session(&["--key-confirmation"]);
+        assert_eq!(confirmed.rotations, 3);
+        assert_eq!(confirmed.confirm_bandwidth, confirmed.rotations * (12 + KEY_CONFIRMATION_TOKEN.len() + 16));
+        // A confirmação fica fora dos bytes de acordo
+        assert_eq!(confirmed.kem_bandwidth, session.kem_bandwidth);
+    }
+
+    #[test]
+    fn test_group_fanout_agrees_once_per_recipient() {
+        // SmallChat tem 5 membros: cada rotação faz o acordo com os 4 que não são o remetente
+        let single = small_chat_session(&[]);
+        let fanout = small_chat_session(&["--group-fanout"]);
+        let recipients = get_group_size(&UsageScenario::SmallChat) - 1;
+        assert_eq!(fanout.rotations, single.rotations);
+        assert_eq!(fanout.kem_bandwidth, single.kem_bandwidth * recipients);
+        assert_eq!(fanout.kem_upload, single.kem_upload * recipients);
+        assert_eq!(fanout.kem_download, single.kem_download * recipients);
+    }
+
+    #[test]
+    fn test_matrix_envelope_is_reproducible_with_the_same_seed() {
+        // origin_server_ts vem do relógio simulado: a mesma semente cifra o mesmo evento
+        let args = ["--envelope", "matrix-json", "--dump-sample", "hex"];
+        let first = small_chat_session(&args).ciphertext_sample.unwrap();
+        std::thread::sleep(Duration::from_millis(5));
+        let second = small_chat_session(&args).ciphertext_sample.unwrap();
+        assert_eq!(first.plaintext_sha256, second.plaintext_sha256);
+        assert_eq!(first.plaintext_bytes, second.plaintext_bytes);
+    }
+
+    #[test]
+    fn test_base64_media_inflates_only_binary_messages() {
+        let run = |extra: &[&str]| {
+            let args = [&["--seed", "9", "--fixed-size", "300"][..], extra].concat();
+            session_with(UsageScenario::MediumGroup, "AES-GCM", &args, 40, 10, 9)
+        };
+        let raw = run(&[]);
+        let encoded = run(&["--base64-media"]);
+        assert!(raw.image_count > 0 && raw.text_count > 0);
+        // Com --fixed-size, a mídia é imagem: 300 bytes viram 400 caracteres base64; textos não mudam
+        assert_eq!(encoded.msg_bandwidth, raw.msg_bandwidth + raw.image_count * 100);
+        assert_eq!(encoded.plaintext_bytes, raw.plaintext_bytes);
+        // O envelope matrix-json já leva a mídia em base64: a combinação é recusada
+        assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--base64-media", "--envelope", "matrix-json"]).is_err());
+    }
+
+    #[test]
+    fn test_member_leave_rekeys_the_shrinking_group() {
+        let session = small_chat_session(&[]);
+        let per_agreement = session.kem_bandwidth / session.rotations;
+
+        // Com taxa 1, um membro sai a cada mensagem até restarem o remetente e Bob: no
+        // SmallChat (5 membros), 3 saídas com re-key para 3, 2 e 1 destinatários
+        let leaving = small_chat_session(&["--member-leave-rate", "1"]);
+        assert_eq!(leaving.rekey_events, 3);
+        assert_eq!(leaving.rekey_bandwidth, per_agreement * (3 + 2 + 1));
+
+        // Com --group-fanout, só a primeira rotação vê o grupo inteiro; as seguintes vão
+        // apenas para Bob
+        let fanout = small_chat_session(&["--member-leave-rate", "1", "--group-fanout"]);
+        assert_eq!(fanout.rekey_events, 3);
+        assert_eq!(fanout.kem_bandwidth, per_agreement * (4 + fanout.rotations - 1));
+    }
+
+    #[test]
+    fn test_key_backup_tracks_rotations_and_rekeys() {
+        let session = small_chat_session(&[]);
+        assert_eq!((session.backup_ops, session.backup_bandwidth), (0, 0));
+
+        // Uma chave enviada ao backup por chave de sessão nova: rotações e re-keys
+        let backed_up = small_chat_session(&["--key-backup", "--member-leave-rate", "0.2"]);
+        assert_eq!(backed_up.backup_ops, backed_up.rotations + backed_up.rekey_events);
+        assert_eq!(backed_up.backup_bandwidth, backed_up.backup_ops * (32 + 32 + BACKUP_MAC_SIZE));
+    }
+
+    #[test]
+    fn test_direct_message_ratchets_every_message() {
+        let spec = random_spec(UsageScenario::DirectMessage, "AES-GCM");
+        let (interval, messages) = message_plan(&spec, &ExperimentConfig::parse_from(["pq_crypto_matrix", "--seed", "9"]));
+        assert_eq!((interval, messages), (5, 200));
+        let session = session_with(UsageScenario::DirectMessage, "AES-GCM", &["--seed", "9"], 20, interval, 9);
+        // Um passo DH a cada 5 mensagens; sem arquivos nem mensagens de sistema
+        assert_eq!((session.messages, session.rotations), (20, 4));
+        assert_eq!(session.file_count + session.system_count, 0);
+    }
+
+    #[test]
+    fn test_detached_tag_layout_keeps_bandwidth() {
+        let detached = ExperimentConfig::parse_from(["pq_crypto_matrix", "--seed", "6", "--tag-layout", "detached"]);
+        assert_eq!(detached.tag_layout, TagLayout::Detached);
+        for cipher in CIPHERS {
+            let a = session_with(UsageScenario::SmallChat, cipher, &["--seed", "6"], 20, 10, 6);
+            let d = session_with(UsageScenario::SmallChat, cipher, &["--seed", "6", "--tag-layout", "detached"], 20, 10, 6);
+            assert_eq!((a.messages, a.msg_bandwidth), (d.messages, d.msg_bandwidth), "{}", cipher);
+        }
+        // A API destacada já é a do --in-place
+        assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--in-place", "--tag-layout", "detached"]).is_err());
+    }
+
+    #[test]
+    fn test_dump_sample_records_decryptable_first_message() {
+        use aes_gcm::aead::{Aead, KeyInit};
+        use aes_gcm::{Aes256Gcm, Nonce};
+
+        let config = ExperimentConfig::parse_from(["pq_crypto_matrix", "--seed", "1", "--dump-sample"]);
+        assert_eq!(config.dump_sample, Some(DumpEncoding::Hex));
+        let spec = random_spec(UsageScenario::SmallChat, "AES-GCM");
+        let plan = SessionPlan { num_messages: 5, rotation: RotationStrategy::ByCount(100), seed: 1, cached_keys: None, corpus: None };
+        let session = spec_session(&spec, &["--seed", "1", "--dump-sample"], plan);
+        let sample = session.ciphertext_sample.unwrap();
+        let plaintext = Aes256Gcm::new(&sample.key.into())
+            .decrypt(Nonce::from_slice(&sample.nonce), sample.ciphertext.as_slice())
+            .unwrap();
+        assert_eq!(plaintext.len(), sample.plaintext_bytes);
+        assert_eq!(<[u8; 32]>::from(Sha256::digest(&plaintext)), sample.plaintext_sha256);
+        // Apenas a primeira repetição guarda o exemplo
+        assert!(run_session(&spec, &config, &WorkloadTrace::Live, 1, 0, plan).unwrap().ciphertext_sample.is_none());
+
+        // Sem --dump-keys-insecure, a chave sai apenas como hash
+        let mut result = dummy_result(spec);
+        result.ciphertext_sample = Some(sample.clone());
+        let path = std::env::temp_dir().join(format!("amostras_cifradas_teste_{}.csv", std::process::id()));
+        for (include_keys, encoding) in [(false, DumpEncoding::Hex), (true, DumpEncoding::Base64)] {
+            write_ciphertext_samples(std::slice::from_ref(&result), encoding, include_keys, &path).unwrap();
+            let dump = fs::read_to_string(&path).unwrap();
+            let row: Vec<&str> = dump.lines().nth(1).unwrap().split(',').collect();
+            assert_eq!(row.len(), 10);
+            assert_eq!(row[7].starts_with("sha256:"), !include_keys);
+            assert_eq!(row[7] == encoding.encode(&sample.key), include_keys);
+            assert_eq!(row[9], encoding.encode(&sample.ciphertext));
+        }
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_list_options_names_every_value() {
+        let listing = list_options();
+        for name in CIPHERS.into_iter().chain(AGREEMENTS) {
+            assert!(listing.contains(name), "{}", name);
+        }
+        for component in KemComponent::ALL {
+            assert!(listing.contains(component.name()));
+        }
+        for scenario in UsageScenario::ALL {
+            assert!(listing.contains(&format!("{:?}", scenario)));
+        }
+        for pattern in TrafficPattern::ALL {
+            assert!(listing.contains(&format!("{:?}", pattern)));
+        }
+        let config = ExperimentConfig::parse_from(["pq_crypto_matrix", "list"]);
+        assert_eq!(config.command, Some(ExperimentCommand::List));
+    }
+
+    #[test]
+    fn test_tidy_rows_match_wide_columns() {
+        let spec = build_config_specs(&AGREEMENTS).remove(0);
+        let result = dummy_result(spec);
+        let rows = result.tidy_rows();
+
+        // Sem pares (métrica, estatística) repetidos
+        let mut keys: Vec<_> = rows.iter().map(|(metric, stat, _)| (*metric, *stat)).collect();
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), rows.len());
+
+        // Os valores coincidem com os do CSV largo
+        let value = |metric: &str, stat: &str| {
+            rows.iter().find(|(m, s, _)| *m == metric && *s == stat).map(|row| row.2).unwrap()
+        };
+        assert_eq!(value("kem_ms", "mean"), result.kem_time_stats.mean);
+        assert_eq!(value("msg_bw_bytes", "sample_size"), result.msg_bw_stats.sample_size as f64);
+        assert_eq!(value("storage_bytes", "mean"), result.storage_bytes);
+        // Toda métrica nomeada existe como prefixo de alguma coluna do CSV largo
+        let columns = ConfigResult::csv_header();
+        assert!(rows.iter().all(|(metric, _, _)| columns.contains(metric)));
+    }
+
+    #[test]
+    fn test_sqlite_columns_accept_result_cells() {
+        let path = std::env::temp_dir().join(format!("pq_crypto_matrix_results_{}.db", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let result = dummy_result(build_config_specs(&AGREEMENTS).remove(0));
+        let run = RunRecord { timestamp: "x", seed: Some(7), schema_version: SCHEMA_VERSION, csv_file: "r.csv", metadata_json: "{}" };
+        write_results(&path, &run, &RESULT_COLUMNS, &[result.to_cells()]).unwrap();
+
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        let (cipher, messages, normal): (String, i64, i64) = connection.query_row(
+            "SELECT cifra, num_msgs, kem_normal FROM results", [], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        ).unwrap();
+        assert_eq!((cipher.as_str(), messages), (result.spec.cipher, result.num_messages as i64));
+        assert_eq!(normal, result.kem_time_stats.is_normal as i64);
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_downsample_keeps_endpoints() {
+        let series: Vec<usize> = (1..=1000).collect();
+        let sampled = downsample(&series, 200);
+        assert_eq!(sampled.len(), 200);
+        assert_eq!((sampled[0], sampled[199]), (1, 1000));
+        assert!(sampled.windows(2).all(|pair| pair[0] < pair[1]));
+        // Séries curtas não são alteradas
+        assert_eq!(downsample(&series[..50], 200), series[..50].to_vec());
+    }
+
+    #[test]
+    fn test_smooth_series_averages_per_message_bytes() {
+        // Rotação inicial de 100 bytes e mensagens de 10, 30, 20 bytes
+        let point = |message_index, kem_bytes, msg_bytes| BandwidthPoint {
+            message_index, kem_bytes, msg_bytes, smoothed_bytes: f64::NAN,
+        };
+        let series = [point(1, 100, 10), point(2, 100, 40), point(3, 100, 60)];
+        let smoothed: Vec<f64> = smooth_series(&series, NonZeroUsize::new(2).unwrap()).iter().map(|p| p.smoothed_bytes).collect();
+        assert_eq!(smoothed, [110.0, 70.0, 25.0]);
+        let raw: Vec<f64> = smooth_series(&series, NonZeroUsize::MIN).iter().map(|p| p.smoothed_bytes).collect();
+        assert_eq!(raw, [110.0, 30.0, 20.0]);
+        // Uma janela vazia é recusada na leitura das opções
+        assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--bandwidth-series", "--series-window", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_pairwise_comparison_statistics() {
+        // Valores de referência calculados à parte (Welch-Satterthwaite; U com empates em 3, 4 e 5)
+        let a = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let b = [3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let (t, df, p) = welch_t_test(&a, &b);
+        assert!((t - -2.4019).abs() < 1e-3 && (df - 8.9894).abs() < 1e-3 && (p - 0.0398).abs() < 1e-3);
+        let (u, p) = mann_whitney_u(&a, &b);
+        assert_eq!(u, 4.5);
+        assert!((p - 0.0660).abs() < 1e-3);
+        assert!((cohens_d(&a, &b) - -1.4302).abs() < 1e-3);
+        assert!((normal_cdf(1.96) - 0.975).abs() < 1e-4);
+        // O z-score dos IC95 corresponde ao nível de confiança registrado nos metadados
+        assert!((normal_cdf(pq_crypto_matrix::pooling::CONFIDENCE_Z) - (1.0 + CONFIDENCE_LEVEL) / 2.0).abs() < 1e-4);
+        assert!(cohens_d(&[1.0, 1.0], &[1.0, 1.0]).is_nan());
+    }
+
+    #[test]
+    fn test_read_raw_samples_groups_configurations() {
+        let path = std::env::temp_dir().join(format!("amostras_ns_teste_{}.csv", std::process::id()));
+        fs::write(&path, "cenario,padrao_trafego,acordo,cifra,repeticao,kem_ns,cipher_ns,cipher_only_ns,compress_ns,commitment_ns\n\
+            SmallChat,Random,Olm-Clássico,AES-GCM,0,1000000,9,2000000,0,0\n\
+            SmallChat,Random,Olm-Híbrido,AES-GCM,0,3000000,9,2000000,0,0\n\
+            SmallChat,Random,Olm-Clássico,AES-GCM,1,1500000,9,2500000,0,0\n").unwrap();
+        let samples = read_raw_samples(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].agreement, "Olm-Clássico");
+        assert_eq!(samples[0].kem_ms, [1.0, 1.5]);
+        assert_eq!(samples[0].cipher_only_ms, [2.0, 2.5]);
+        assert_eq!(samples[1].kem_ms, [3.0]);
+    }
+
+    #[test]
+    fn test_drift_log_orders_repetitions_by_start_time() {
+        let specs = build_config_specs(&AGREEMENTS);
+        let mut results: Vec<ConfigResult> = specs[..2].iter().cloned().map(dummy_result).collect();
+        // Repetições intercaladas entre as duas configurações
+        results[0].repetition_starts_ns = vec![100, 300, 500];
+        results[1].repetition_starts_ns = vec![200, 400, 600];
+        let path = std::env::temp_dir().join(format!("deriva_teste_{}.csv", std::process::id()));
+        write_drift_log(&results, &path).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[0], "cenario,padrao_trafego,acordo,cifra,repeticao,unix_timestamp_ns,kem_ms,cipher_ms");
+        let starts: Vec<&str> = lines[1..].iter().map(|line| line.split(',').nth(5).unwrap()).collect();
+        assert_eq!(starts, ["100", "200", "300", "400", "500", "600"]);
+        assert!(lines[2].starts_with(&format!("{:?},{:?},{},{},0,200,1.000000,", specs[1].scenario, specs[1].pattern, specs[1].agreement, specs[1].cipher)));
+        assert!(lines[6].ends_with(",2,600,3.000000,3.000000"));
+    }
+
+    #[test]
+    fn test_ks_two_sample() {
+        // Amostras idênticas: D = 0 e p = 1
+        let a: Vec<f64> = (0..40).map(|i| i as f64).collect();
+        assert_eq!(ks_two_sample(&a, &a), (0.0, 1.0));
+
+        // Amostras disjuntas: D = 1 e p desprezível
+        let shifted: Vec<f64> = a.iter().map(|v| v + 100.0).collect();
+        let (d, p_value) = ks_two_sample(&a, &shifted);
+        assert_eq!(d, 1.0);
+        assert!(p_value < 1e-10);
+
+        // Mesma média e mediana, formas diferentes: concentrada vs bimodal
+        let narrow = vec![50.0; 40];
+        let bimodal: Vec<f64> = (0..40).map(|i| if i % 2 == 0 { 0.0 } else { 100.0 }).collect();
+        let (d, p_value) = ks_two_sample(&narrow, &bimodal);
+        assert_eq!(d, 0.5);
+        assert!(p_value < 0.01);
+
+        // Valor de referência: n = m = 10, D = 0.5 -> λ = (√5 + 0.12 + 0.11/√5)·0.5 ≈ 1.2026
+        // e Q(λ) = 2(e^(-2λ²) - e^(-8λ²) + ...) ≈ 0.1108
+        let x: Vec<f64> = (0..10).map(|i| i as f64).collect();
+        let y: Vec<f64> = (5..15).map(|i| i as f64).collect();
+        let (d, p_value) = ks_two_sample(&x, &y);
+        assert_eq!(d, 0.5);
+        assert!((p_value - 0.1108).abs() < 1e-3, "p = {}", p_value);
+
+        assert!(ks_two_sample(&[], &a).0.is_nan());
+    }
+
+    #[test]
+    fn test_tost_small_sample() {
+        assert_eq!(tost(&[1.0], &[1.0, 2.0], 1.0), (1.0, false));
+    }
+
+    fn dummy_result(spec: ConfigSpec) -> ConfigResult {
+        let stats = calculate_parametric_stats(&[1.0, 2.0, 3.0], 0, 0, 3);
+        ConfigResult {
+            spec,
+            num_messages: 10,
+            msgs_per_rotation: 5,
+            rotations: 2,
+            rotation_strategy: RotationStrategy::ByCount(5),
+            ephemeral: EphemeralMode::Fresh,
+            keygen_policy: KeygenPolicy::PerSession,
+            duplicate_payloads: None,
+            repetitions: 10,
+            config_wall_ms: 1.0,
+            kem_bw_bps: 1.0,
+            msg_bw_bps: 1.0,
+            kem_time_stats: stats.clone(),
+            cipher_time_stats: stats.clone(),
+            kem_bw_stats: stats.clone(),
+            msg_bw_stats: stats.clone(),
+            avg_text: 10.0,
+            avg_image: 0.0,
+            avg_file: 0.0,
+            avg_system: 0.0,
+            avg_voice: 0.0,
+            expansion_ratio: 1.0,
+            authenticated: true,
+            cipher_only_time_stats: stats.clone(),
+            cipher_only_times: DurationSamples::from_nanos(vec![1_000_000, 2_000_000, 3_000_000]),
+            kem_times: DurationSamples::from_nanos(vec![1_000_000, 2_000_000, 3_000_000]),
+            cipher_times: DurationSamples::from_nanos(vec![1_000_000, 2_000_000, 3_000_000]),
+            compress_times: DurationSamples::default(),
+            commitment_times: DurationSamples::default(),
+            repetition_starts_ns: vec![10, 20, 30],
+            primitive_versions: primitive_versions("Olm-Clássico", "AES-GCM", false),
+            fixed_size: None,
+            payload_entropy: None,
+            size_time_pairs: Vec::new(),
+            type_stats: Default::default(),
+            devices_per_user: 1,
+            group_size: 2,
+            kem_sender_ms: 0.5,
+            kem_receiver_ms: 1.5,
+            decapsulations: 1,
+            compression: Compression::None,
+            envelope: Envelope::None,
+            base64_media: false,
+            compress_time_stats: stats.clone(),
+            compression_ratios: [1.0; 5],
+            senders: 1,
+            aggregate_throughput: 10.0,
+            per_sender_throughput: 10.0,
+            message_latency_us: 1.0,
+            weighted_latency_us: None,
+            key_commitment: false,
+            commitment_time_stats: stats,
+            upload_bytes: 0.0,
+            download_bytes: 0.0,
+            kem_failures: 0.0,
+            decap_ok_us: f64::NAN,
+            decap_fail_us: f64::NAN,
+            config_seed: 0,
+            stalled_sessions: 0,
+            storage_bytes: 0.0,
+            bandwidth_series: Vec::new(),
+            ciphertext_sample: None,
+            rekey_events: 0.0,
+            rekey_ms: 0.0,
+            rekey_bw: 0.0,
+            rekey_ms_per_event: f64::NAN,
+            edit_events: 2.0,
+            edit_ms: 0.01,
+            edit_bw: 120.0,
+            confirm_ms: 0.0,
+            combine_ms: 0.0,
+            relative_speed: f64::NAN,
+            confirm_bw: 0.0,
+            backup_ops: 0.0,
+            backup_ms: 0.0,
+            backup_bw: 0.0,
+            tag_layout: TagLayout::Attached,
+        }
+    }
+
+    #[test]
+    fn test_ordered_writer_reorders_results() {
+        let specs = build_config_specs(&AGREEMENTS);
+        let mut writer = OrderedResultWriter::new(Vec::new(), "AES-GCM").unwrap();
+
+        // Resultados chegam fora de ordem; nada é gravado até o índice 0 chegar
+        writer.push(2, dummy_result(specs[2].clone())).unwrap();
+        writer.push(1, dummy_result(specs[1].clone())).unwrap();
+        assert_eq!(writer.written.len(), 0);
+        writer.push(0, dummy_result(specs[0].clone())).unwrap();
+        assert_eq!(writer.written.len(), 3);
+
+        let csv = String::from_utf8(writer.writer.clone()).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], ConfigResult::csv_header());
+        assert!(lines[1].contains("AES-GCM"));
+        assert!(lines[2].contains("ChaCha20"));
+        assert!(lines[3].contains("Megolm-Like"));
+
+        let results = writer.finish().unwrap();
+        let indices: Vec<usize> = results.iter().map(|r| r.spec.index).collect();
+        assert_eq!(indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn test_ordered_writer_detects_gaps() {
+        let specs = build_config_specs(&AGREEMENTS);
+        let mut writer = OrderedResultWriter::new(Vec::new(), "AES-GCM").unwrap();
+        writer.push(1, dummy_result(specs[1].clone())).unwrap();
+        assert!(writer.finish().is_err());
+    }
+
+    #[test]
+    fn test_adaptive_stats_keep_normality_diagnostics() {
+        // Amostra simétrica: assimetria nula e curtose calculada
+        let stats = calculate_adaptive_stats(&[1.0, 2.0, 3.0, 4.0, 5.0], "simetrica", CentralEstimator::Median, true);
+        assert!(stats.is_normal);
+        assert!(stats.skewness.abs() < 1e-12);
+        assert!(stats.kurtosis.is_finite());
+
+        // Cauda longa sem outliers extremos removíveis: classificada como não normal
+        let mut skewed = vec![1.0; 40];
+        skewed.extend([1.5, 2.0, 3.0, 40.0]);
+        let (is_normal, skewness, kurtosis) = check_normality(&skewed, "assimetrica");
+        assert!(!is_normal);
+        assert!(skewness > 2.0 || kurtosis > 7.0);
+
+        // Diagnósticos indisponíveis para amostras pequenas
+        let stats = calculate_adaptive_stats(&[1.0, 2.0], "pequena", CentralEstimator::Median, true);
+        assert!(stats.skewness.is_nan() && stats.kurtosis.is_nan());
+    }
+
+    #[test]
+    fn test_ordered_writer_partial_finish_keeps_canonical_order() {
+        let specs = build_config_specs(&AGREEMENTS);
+        let mut writer = OrderedResultWriter::new(Vec::new(), "AES-GCM").unwrap();
+        writer.push(3, dummy_result(specs[3].clone())).unwrap();
+        writer.push(0, dummy_result(specs[0].clone())).unwrap();
+        writer.push(2, dummy_result(specs[2].clone())).unwrap();
+
+        // O índice 1 nunca chega (orçamento esgotado): os demais são gravados em ordem
+        let results = writer.finish_partial().unwrap();
+        let indices: Vec<usize> = results.iter().map(|r| r.spec.index).collect();
+        assert_eq!(indices, vec![0, 2, 3]);
+    }
+
+    #[test]
+    fn test_stats_fuzz_with_non_finite_values() {
+        // Amostras aleatórias (semeadas) com NaN, ±Inf, valores enormes e empates: nenhuma
+        // função pode entrar em pânico, e nenhum valor não finito chega às estatísticas
+        let mut rng = StdRng::seed_from_u64(396);
+        for _ in 0..2000 {
+            let len = rng.gen_range(0..40);
+            let data: Vec<f64> = (0..len)
+                .map(|_| match rng.gen_range(0..10) {
+                    0 => f64::NAN,
+                    1 => f64::INFINITY,
+                    2 => f64::NEG_INFINITY,
+                    3 => 1.0,
+                    4 => rng.gen_range(-1e6..1e6),
+                    _ => rng.gen_range(0.0..10.0),
+                })
+                .collect();
+            let finite = data.iter().filter(|value| value.is_finite()).count();
+
+            let (outliers, extreme, cleaned) = detect_outliers(&data, "fuzz");
+            assert!(cleaned.iter().all(|value| value.is_finite()));
+            assert_eq!(outliers.len() + extreme.len() + cleaned.len(), data.len());
+            assert!((0..len).filter(|&i| !data[i].is_finite()).all(|i| extreme.contains(&i)));
+
+            let (_, skewness, kurtosis) = check_normality(&data, "fuzz");
+            assert!(!skewness.is_infinite() && !kurtosis.is_infinite());
+
+            for central in [CentralEstimator::Mean, CentralEstimator::Median, CentralEstimator::Mode] {
+                for remove_extreme in [true, false] {
+                    let stats = calculate_adaptive_stats(&data, "fuzz", central, remove_extreme);
+                    assert!(stats.sample_size <= finite);
+                    assert_eq!(stats.nonfinite_count, len - finite);
+                    // Só uma amostra sem nenhum valor finito produz estatísticas inválidas (NaN)
+                    assert_eq!(stats.is_invalid(), finite == 0 && len > 0);
+                    if !stats.is_invalid() {
+                        assert!(stats.mean.is_finite() && stats.std_dev.is_finite() && stats.ci95.is_finite(),
+                                "{:?} -> {:?}", data, stats);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample_floor_keeps_original_data() {
+        // 8 valores: a remoção dos extremos deixaria menos que MIN_SAMPLE_SIZE
+        let small = [1.0, 1.0, 1.0, 1.0, 1.1, 1.1, 1.2, 100.0];
+        let stats = calculate_adaptive_stats(&small, "pequena", CentralEstimator::Median, true);
+        assert!(stats.sample_floor_hit);
+        assert_eq!(stats.sample_size, small.len());
+
+        // Amostra grande: a remoção prossegue normalmente
+        let mut large = vec![1.0; 30];
+        large.extend([1.1, 1.2, 100.0]);
+        let stats = calculate_adaptive_stats(&large, "grande", CentralEstimator::Median, true);
+        assert!(!stats.sample_floor_hit);
+        assert!(stats.sample_size < large.len());
+    }
+
+    #[test]
+    fn test_no_outlier_removal_keeps_full_sample() {
+        let mut data = vec![1.0; 30];
+        data.extend([1.1, 1.2, 100.0]);
+        let removed = calculate_adaptive_stats(&data, "com_remocao", CentralEstimator::Mean, true);
+        let kept = calculate_adaptive_stats(&data, "sem_remocao", CentralEstimator::Mean, false);
+
+        // As contagens de outliers são as mesmas; só a amostra analisada muda
+        assert_eq!(kept.extreme_outliers_count, removed.extreme_outliers_count);
+        assert_eq!(kept.outliers_count, removed.outliers_count);
+        assert!(kept.extreme_outliers_count > 0);
+        assert_eq!(kept.sample_size, data.len());
+        assert!(kept.mean > removed.mean);
+    }
+
+    #[test]
+    fn test_split_half_is_seeded_partition() {
+        let samples: Vec<f64> = (0..11).map(f64::from).collect();
+        let (a, b) = split_half(&samples, 7);
+        assert_eq!((a.len(), b.len()), (6, 5));
+        assert_eq!((a.clone(), b.clone()), split_half(&samples, 7));
+        let mut all: Vec<f64> = a.into_iter().chain(b).collect();
+        all.sort_by(f64::total_cmp);
+        assert_eq!(all, samples);
+    }
+
+    #[test]
+    fn test_half_sample_mode() {
+        assert!(half_sample_mode(&[]).is_nan());
+        assert_eq!(half_sample_mode(&[4.0]), 4.0);
+        assert_eq!(half_sample_mode(&[1.0, 2.0, 10.0]), 1.5);
+        // Amostra bimodal: o grupo mais denso define a moda, ignorando a média e a mediana
+        let sorted = [1.0, 1.1, 1.2, 5.0, 5.0, 5.01, 5.02, 5.03, 9.0];
+        let mode = half_sample_mode(&sorted);
+        assert!((5.0..=5.03).contains(&mode), "moda = {}", mode);
+
+        let stats = calculate_robust_stats(&sorted, 0, 0, sorted.len(), CentralEstimator::Mode);
+        assert_eq!((stats.mean, stats.central), (mode, CentralEstimator::Mode));
+        let stats = calculate_robust_stats(&sorted, 0, 0, sorted.len(), CentralEstimator::Median);
+        assert_eq!(stats.mean, 5.0);
+    }
+
+    #[test]
+    fn test_adaptive_stats_count_and_flag_non_finite_values() {
+        let mut data = vec![2.0; 12];
+        data.extend([f64::NAN, f64::INFINITY]);
+        let stats = calculate_adaptive_stats(&data, "teste", CentralEstimator::Median, true);
+        assert_eq!((stats.mean, stats.nonfinite_count, stats.original_size), (2.0, 2, 12));
+        assert!(!stats.is_invalid());
+
+        let stats = calculate_adaptive_stats(&[f64::NAN, f64::NEG_INFINITY], "teste", CentralEstimator::Median, true);
+        assert!(stats.is_invalid() && stats.mean.is_nan() && stats.ci95.is_nan());
+        assert_eq!((stats.nonfinite_count, stats.sample_size), (2, 0));
+
+        // A coluna soma as distribuições, e o tipo de estatística marca a inválida
+        let mut result = dummy_result(build_config_specs(&AGREEMENTS).remove(0));
+        result.kem_time_stats = stats;
+        result.cipher_time_stats.nonfinite_count = 1;
+        assert_eq!(result.nonfinite_count(), 3);
+        let row = result.to_csv_row();
+        assert!(row.contains(",invalid,") && row.ends_with(",3"));
+        let kem = outlier_summary(&[result]).into_iter().find(|entry| entry.metric == "kem_ms").unwrap();
+        assert_eq!((kem.samples, kem.nonfinite, kem.invalid), (0, 2, 1));
+    }
+
+    #[test]
+    fn test_sorted_finite_with_ties_and_non_finite_values() {
+        // Não finitos saem; empates e zeros com sinal ficam em ordem total, qualquer que seja a entrada
+        let data = [3.0, f64::NAN, 1.0, f64::INFINITY, 1.0, 0.0, -0.0, f64::NEG_INFINITY, 3.0];
+        let sorted = sorted_finite(&data, "teste");
+        assert_eq!(sorted.iter().map(|value| value.to_bits()).collect::<Vec<_>>(),
+                   [-0.0f64, 0.0, 1.0, 1.0, 3.0, 3.0].iter().map(|value| value.to_bits()).collect::<Vec<_>>());
+        let mut reversed = data;
+        reversed.reverse();
+        assert_eq!(sorted_finite(&reversed, "teste").iter().map(|value| value.to_bits()).collect::<Vec<_>>(),
+                   sorted.iter().map(|value| value.to_bits()).collect::<Vec<_>>());
+        assert!(sorted_finite(&[f64::NAN, f64::INFINITY], "teste").is_empty());
+
+        // Quantis e mediana sobre empates
+        let ties = [2.0, 2.0, 2.0, 2.0, 7.0];
+        assert_eq!((sorted_quantile(&ties, 0.25), sorted_quantile(&ties, 0.75), sorted_quantile(&ties, 1.0)), (2.0, 2.0, 7.0));
+        assert_eq!((sorted_median(&ties), sorted_median(&[1.0, 2.0, 2.0, 4.0])), (2.0, 2.0));
+        assert!(sorted_quantile(&[], 0.5).is_nan() && sorted_median(&[]).is_nan());
+
+        // IQR nulo (todos empatados): só o valor distinto e os não finitos são outliers
+        let data = [5.0, 5.0, f64::NAN, 5.0, 5.0, 5.0, 6.0];
+        let (moderate, extreme, cleaned) = detect_outliers(&data, "teste");
+        assert!(moderate.is_empty());
+        assert_eq!(extreme, vec![2, 6]);
+        assert_eq!(cleaned, vec![5.0; 5]);
+
+        // As estatísticas robustas ignoram não finitos em vez de entrar em pânico
+        let stats = calculate_robust_stats(&[1.0, 1.0, f64::NAN, 1.0, 9.0], 0, 0, 4, CentralEstimator::Median);
+        assert_eq!((stats.mean, stats.std_dev, stats.sample_size), (1.0, 0.0, 4));
+    }
+
+    #[test]
+    fn test_shuffled_order_is_seeded_permutation() {
+        let specs = build_config_specs(&AGREEMENTS);
+        let order = |args: &[&str], round: usize| {
+            let config = ExperimentConfig::parse_from(args);
+            let mut order: Vec<usize> = specs.iter().map(|spec| spec.index).collect();
+            shuffle_execution_order(&mut order, &config, round);
+            order
+        };
+        let canonical: Vec<usize> = (0..specs.len()).collect();
+        assert_eq!(order(&["pq_crypto_matrix", "--seed", "7"], 0), canonical);
+
+        let shuffled = order(&["pq_crypto_matrix", "--seed", "7", "--shuffle-configs"], 0);
+        assert_ne!(shuffled, canonical);
+        assert_eq!(shuffled, order(&["pq_crypto_matrix", "--seed", "7", "--shuffle-configs"], 0));
+        assert_ne!(shuffled, order(&["pq_crypto_matrix", "--seed", "8", "--shuffle-configs"], 0));
+        // Cada rodada do --interleave tem a sua permutação
+        assert_ne!(shuffled, order(&["pq_crypto_matrix", "--seed", "7", "--shuffle-configs"], 1));
+        let mut sorted = shuffled;
+        sorted.sort_unstable();
+        assert_eq!(sorted, canonical);
+    }
+
+    #[test]
+    fn test_nonnormal_metrics_respect_selection() {
+        let specs = build_config_specs(&AGREEMENTS);
+        let mut results: Vec<ConfigResult> = specs[..2].iter().cloned().map(dummy_result).collect();
+        assert!(results.iter().all(|result| result.kem_time_stats.is_normal && result.cipher_only_time_stats.is_normal));
+        assert!(nonnormal_metrics(&results, &[]).is_empty());
+        // Cada métrica verificável tem uma coluna *_normal e estatísticas no resultado
+        for metric in NORMALITY_METRICS {
+            assert!(RESULT_COLUMNS.iter().any(|column| column.name == format!("{}_normal", metric)), "{}", metric);
+            assert!(results[0].normality_stats(metric).is_some(), "{}", metric);
+        }
+
+        results[1].cipher_only_time_stats.is_normal = false;
+        let all = nonnormal_metrics(&results, &[]);
+        assert_eq!(all.len(), 1);
+        assert!(all[0].ends_with(": cipher_only"), "{}", all[0]);
+        assert!(nonnormal_metrics(&results, &["kem".to_string()]).is_empty());
+        assert_eq!(nonnormal_metrics(&results, &["kem".to_string(), "cipher_only".to_string()]).len(), 1);
+
+        // Sem valores, a opção vale para todas as métricas; nomes desconhecidos são recusados
+        let config = ExperimentConfig::parse_from(["pq_crypto_matrix", "--fail-on-nonnormal"]);
+        assert_eq!(config.fail_on_nonnormal, Some(vec![]));
+        let config = ExperimentConfig::parse_from(["pq_crypto_matrix", "--fail-on-nonnormal", "kem,cipher"]);
+        assert_eq!(config.fail_on_nonnormal, Some(vec!["kem".to_string(), "cipher".to_string()]));
+        assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--fail-on-nonnormal", "latencia"]).is_err());
+        assert_eq!(ExperimentConfig::parse_from(["pq_crypto_matrix"]).fail_on_nonnormal, None);
+        // A varredura de --message-counts não classifica normalidade
+        assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--fail-on-nonnormal", "--message-counts", "50"]).is_err());
+    }
+
+    #[test]
+    fn test_outlier_summary_sums_configurations() {
+        let specs = build_config_specs(&AGREEMENTS);
+        let mut results: Vec<ConfigResult> = specs[..2].iter().cloned().map(dummy_result).collect();
+        results[0].kem_time_stats = calculate_parametric_stats(&[1.0; 20], 4, 1, 20);
+        results[1].kem_time_stats = calculate_parametric_stats(&[1.0; 30], 2, 0, 30);
+
+        let summary = outlier_summary(&results);
+        let kem = summary.iter().find(|entry| entry.metric == "kem_ms").unwrap();
+        assert_eq!((kem.samples, kem.outliers, kem.extreme), (50, 6, 1));
+        assert!((kem.rate() - 0.12).abs() < 1e-12 && (kem.extreme_rate() - 0.02).abs() < 1e-12);
+        let cipher = summary.iter().find(|entry| entry.metric == "cipher_ms").unwrap();
+        assert_eq!((cipher.samples, cipher.outliers, cipher.rate()), (6, 0, 0.0));
+
+        // Sem configurações não há amostras, e o resumo fica vazio
+        assert!(outlier_summary(&[]).is_empty());
+        let empty = OutlierRate { metric: "kem_ms", samples: 0, outliers: 0, extreme: 0, nonfinite: 0, invalid: 0 };
+        assert_eq!(empty.rate(), 0.0);
+    }
+
+    #[test]
+    fn test_csv_row_matches_header() {
+        let spec = build_config_specs(&AGREEMENTS).remove(0);
+        let columns = ConfigResult::csv_header().split(',').count();
+        assert_eq!(dummy_result(spec.clone()).to_csv_row().split(',').count(), columns);
+        // Cada célula tem o tipo declarado da sua coluna no registro
+        let cells = dummy_result(spec.clone()).to_cells();
+        assert_eq!(cells.len(), RESULT_COLUMNS.len());
+        for (cell, column) in cells.iter().zip(&RESULT_COLUMNS) {
+            assert!(cell.fits(column.kind), "{}: {:?}", column.name, cell);
+        }
+
+        let header = ConfigResult::csv_header();
+        let field = |result: &ConfigResult, column: &str| {
+            let index = header.split(',').position(|name| name == column).unwrap();
+            result.to_csv_row().split(',').nth(index).unwrap().to_string()
+        };
+
+        // A latência ponderada (--size-weighted) fica vazia sem a opção
+        let mut weighted = dummy_result(spec);
+        assert_eq!(field(&weighted, "latencia_cifragem_msg_ponderada_us"), "");
+        weighted.weighted_latency_us = Some(12.5);
+        assert_eq!(field(&weighted, "latencia_cifragem_msg_ponderada_us"), "12.500");
+        assert!(weighted.tidy_rows().contains(&("latencia_cifragem_msg_ponderada_us", "mean", 12.5)));
+
+        // Colunas por tipo (--per-type-stats): preenchidas só para os tipos com mensagens
+        assert_eq!(field(&weighted, "cipher_us_imagem_mean"), "");
+        weighted.type_stats[1] = Some(TypeStats {
+            cipher_us: calculate_parametric_stats(&[10.0, 20.0, 30.0], 0, 0, 3),
+            msg_bytes: calculate_parametric_stats(&[100.0, 200.0, 300.0], 0, 0, 3),
+        });
+        assert_eq!(field(&weighted, "cipher_us_imagem_mean"), "20.0000");
+        assert_eq!(field(&weighted, "msg_bytes_imagem_mean"), "200.0");
+        assert_eq!(field(&weighted, "cipher_us_texto_mean"), "");
+        assert!(weighted.tidy_rows().contains(&("msg_bytes_imagem", "mean", 200.0)));
+    }
+
+    #[test]
+    fn test_relative_speed_joins_baseline_per_cell() {
+        // Índices 0..3: primeira célula (AES-GCM, ChaCha20, Megolm-Like); 3..6: a célula seguinte
+        let specs = build_config_specs(&AGREEMENTS);
+        let mut results: Vec<ConfigResult> = specs[..6].iter().cloned().map(dummy_result).collect();
+        for (result, mean) in results.iter_mut().zip([2.0, 1.0, 4.0, 3.0, 0.0, 1.0]) {
+            result.cipher_time_stats.mean = mean;
+        }
+
+        // A célula só é gravada completa, e a junção independe da ordem de chegada
+        let mut writer = OrderedResultWriter::new(Vec::new(), "AES-GCM").unwrap();
+        for index in [2, 0, 4, 5] {
+            writer.push(index, results[index].clone()).unwrap();
+        }
+        assert!(writer.written.is_empty());
+        writer.push(1, results[1].clone()).unwrap();
+        let speeds: Vec<f64> = writer.written.iter().map(|result| result.relative_speed).collect();
+        assert_eq!(speeds, [1.0, 2.0, 0.5]);
+
+        // Média nula da cifra: NaN em vez de infinito. Sem a referência (índice 3 nunca
+        // chega), a célula incompleta sai com NaN em finish_partial
+        let written = writer.finish_partial().unwrap();
+        assert_eq!(written.len(), 5);
+        assert!(written[3..].iter().all(|result| result.relative_speed.is_nan()));
+        assert!(relative_speed(Some(3.0), 0.0).is_nan());
+        assert_eq!(relative_speed(Some(3.0), 1.5), 2.0);
+    }
+
+    #[test]
+    fn test_cost_heatmap_normalizes_each_scenario() {
+        // Primeira célula do SmallChat (índices 0..6) e a mesma célula do MediumGroup
+        let specs = build_config_specs(&AGREEMENTS);
+        let offset = TrafficPattern::ALL.len() * AGREEMENTS.len() * CIPHERS.len();
+        let mut results: Vec<ConfigResult> = specs[..6].iter().chain(&specs[offset..offset + 6]).cloned().map(dummy_result).collect();
+        for (i, result) in results.iter_mut().enumerate() {
+            result.kem_time_stats.mean = if i % 6 >= 3 { 3.0 } else { 1.0 };
+            result.cipher_only_time_stats.mean = [1.0, 0.5, 2.0][i % 3] * if i >= 6 { 10.0 } else { 1.0 };
+        }
+
+        let (columns, rows) = cost_heatmap(&results);
+        assert_eq!(columns.len(), 6);
+        assert_eq!(columns[4], ("Olm-Híbrido", "ChaCha20"));
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].0, UsageScenario::SmallChat);
+        // SmallChat: custos 2, 1.5, 3, 4, 3.5, 5 sobre o mínimo de 1.5
+        let expected = [2.0, 1.5, 3.0, 4.0, 3.5, 5.0].map(|cost| cost / 1.5);
+        assert!(rows[0].1.iter().zip(expected).all(|(cell, cost)| (cell - cost).abs() < 1e-12));
+        // MediumGroup: a combinação mais barata continua sendo 1.0
+        assert_eq!(rows[1].1[1], 1.0);
+
+        let path = std::env::temp_dir().join(format!("mapa_calor_teste_{}.csv", std::process::id()));
+        write_cost_heatmap(&results, &path).unwrap();
+        let csv = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("cenario,Olm-Clássico/AES-GCM,Olm-Clássico/ChaCha20"));
+        assert_eq!(lines[1].split(',').count(), 7);
+        assert!(lines[2].starts_with("MediumGroup,"));
+
+        // A grade só é gravada com --cost-heatmap
+        assert!(!ExperimentConfig::parse_from(["pq_crypto_matrix"]).cost_heatmap);
+        assert!(ExperimentConfig::parse_from(["pq_crypto_matrix", "--cost-heatmap"]).cost_heatmap);
+    }
+
+    #[test]
+    fn test_markdown_report_summarizes_overheads() {
+        // Primeira célula: 3 cifras do Olm-Clássico (índices 0..3) e do Olm-Híbrido (3..6)
+        let specs = build_config_specs(&AGREEMENTS);
+        let mut results: Vec<ConfigResult> = specs[..6].iter().cloned().map(dummy_result).collect();
+        for (i, result) in results.iter_mut().enumerate() {
+            let hybrid = i >= 3;
+            result.kem_time_stats.mean = if hybrid { 3.0 } else { 1.5 };
+            result.kem_bw_stats.mean = if hybrid { 2304.0 } else { 32.0 };
+            result.msg_bw_stats.mean = 968.0;
+            result.cipher_only_time_stats.mean = [1.0, 0.5, 2.0][i % 3];
+        }
+
+        let report = markdown_report(&results, "20260101_000000", Some(7));
+        assert!(report.contains("- Semente base: 7"));
+        assert!(report.contains("Overhead médio de tempo de KEM: 2.00×"));
+        assert!(report.contains("Overhead médio de largura de banda de KEM: 72.00×"));
+        assert!(report.contains("(KEM + mensagens): 3.272×"));
+        assert!(report.contains("## SmallChat"));
+        assert!(report.contains("Melhor cifra: **ChaCha20** (0.5000 ms) · Pior cifra: **Megolm-Like** (2.0000 ms)"));
+        assert!(report.contains("| Constant | 1.5000 | 3.0000 | 2.00× | 1000 | 3272 | 3.272× |"));
+    }
+
+    #[test]
+    fn test_latex_table_pairs_agreements_per_scenario() {
+        let specs = build_config_specs(&AGREEMENTS);
+        let mut results: Vec<ConfigResult> = specs[..6].iter().cloned().map(dummy_result).collect();
+        for (i, result) in results.iter_mut().enumerate() {
+            let hybrid = i >= 3;
+            result.kem_time_stats.mean = if hybrid { 3.0 } else { 1.5 };
+            result.kem_time_stats.ci95 = 0.3;
+            result.kem_time_stats.central = CentralEstimator::Median;
+            result.kem_bw_stats.mean = if hybrid { 2304.0 } else { 32.0 };
+            result.kem_bw_stats.ci95 = 0.0;
+        }
+
+        let table = latex_table(&results, "20260101_000000");
+        assert!(table.starts_with("% Gerado por pq_crypto_matrix (20260101_000000)"));
+        // Tempos robustos (mediana) e larguras de banda normais (média)
+        assert!(table.contains("valores: mediana (média se a amostra for normal) ± semiamplitude do IC95"));
+        assert!(table.contains("\\begin{tabular}{lcccccc}"));
+        // Três cifras: IC combinado = sqrt(3 × 0.3²) / 3
+        assert!(table.contains(
+            "SmallChat & \\num{1.5000} $\\pm$ \\num{0.1732} & \\num{3.0000} $\\pm$ \\num{0.1732} & \\num{2.00}$\\times$ \
+             & \\num{32} $\\pm$ \\num{0} & \\num{2304} $\\pm$ \\num{0} & \\num{72.00}$\\times$ \\\\\n"
+        ));
+        assert!(table.ends_with("\\end{tabular}\n"));
+    }
+
+    #[test]
+    fn test_csv_schema_version_is_readable() {
+        let spec = build_config_specs(&AGREEMENTS).remove(0);
+        let csv = format!("{}\n{}\n", ConfigResult::csv_header(), dummy_result(spec).to_csv_row());
+        let version = pq_crypto_matrix::results::read_schema_version(csv.as_bytes()).unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_from_config_reproduces_recorded_arguments() {
+        let args = |list: &[&str]| list.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+        let mut original = ExperimentConfig::from_arguments(
+            "pq_crypto_matrix", &args(&["--quick", "--hybrid", "X25519+Kyber512", "--central", "mean"]),
+        ).unwrap();
+        // Argumentos efetivos em forma canônica: ordem de declaração, nome longo
+        assert_eq!(original.arguments, args(&["--central", "mean", "--quick", "--hybrid", "X25519+Kyber512"]));
+        // A semente sorteada entra nos argumentos de reprodução
+        original.seed = Some(99);
+        let path = std::env::temp_dir().join(format!("pqc_from_config_test_{}.json", std::process::id()));
+        write_metadata_sidecar(&original, "/tmp/resultados_normality_check_x.csv", "x", &path, None, None).unwrap();
+        let metadata: serde_json::Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(metadata["reproducao"]["argumentos"].as_array().unwrap().last().unwrap(), "99");
+        assert_eq!(metadata["reproducao"]["acordos"].as_array().unwrap().len(), AGREEMENTS.len() + 1);
+        assert_eq!(metadata["reproducao"]["configuracoes"], experiment_specs(&original).unwrap().len());
+
+        // A reexecução parte dos argumentos registrados; os dados agora os substituem
+        let from = path.display().to_string();
+        let replayed = ExperimentConfig::from_arguments("pq_crypto_matrix", &args(&["--from-config", &from])).unwrap();
+        assert_eq!((replayed.quick, replayed.seed, replayed.central), (true, Some(99), CentralEstimator::Mean));
+        assert_eq!(replayed.hybrid, original.hybrid);
+        assert_eq!(replayed.reproduction_arguments(), original.reproduction_arguments());
+        let overridden = ExperimentConfig::from_arguments(
+            "pq_crypto_matrix", &args(&["--seed", "7", &format!("--from-config={}", from)]),
+        ).unwrap();
+        assert_eq!((overridden.seed, overridden.central), (Some(7), CentralEstimator::Mean));
+        // A semente registrada e a substituta não se acumulam: só a que prevaleceu fica
+        assert_eq!(overridden.arguments.iter().filter(|arg| *arg == "--seed").count(), 1);
+        let seed = overridden.arguments.iter().position(|arg| arg == "--seed").unwrap();
+        assert_eq!(overridden.arguments[seed + 1], "7");
+        let dump = ExperimentConfig::from_arguments("pq_crypto_matrix", &args(&["--dump-sample", "--jobs=2"])).unwrap();
+        assert_eq!(dump.arguments, args(&["--jobs", "2", "--dump-sample", "hex"]));
+        fs::remove_file(&path).unwrap();
+
+        assert!(ExperimentConfig::from_arguments("pq_crypto_matrix", &args(&["--from-config", "/nao/existe.json"])).is_err());
+    }
+
+    #[test]
+    fn test_metadata_sidecar_records_library_versions() {
+        let config = ExperimentConfig::parse_from(["pq_crypto_matrix"]);
+        let path = std::env::temp_dir().join(format!("pqc_metadata_test_{}.json", std::process::id()));
+        let check = detect_throttle(&[3000.0, 3000.0, 2700.0, 2700.0, 2400.0, 2400.0], 0.1);
+        write_metadata_sidecar(&config, "/tmp/resultados_normality_check_x.csv", "x", &path, check.as_ref(), None).unwrap();
+        let json = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let metadata: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(metadata["arquivo_resultados"], "resultados_normality_check_x.csv");
+        for (name, version) in PRIMITIVE_VERSIONS {
+            assert_eq!(metadata["bibliotecas"][name], version);
+        }
+        assert_eq!(metadata["throttle_suspected"], true);
+        assert_eq!(metadata["frequencia_cpu"]["mhz_final"], 2400.0);
+        assert_eq!((metadata["flush_cache"].as_bool(), metadata["flush_cache_mib"].as_u64()), (Some(false), None));
+    }
+
+    #[test]
+    fn test_flush_cache_keeps_session_results() {
+        use pq_crypto_matrix::cache::DEFAULT_FLUSH_MIB;
+
+        let warm = session_with(UsageScenario::SmallChat, "AES-GCM", &["--seed", "8"], 10, 5, 8);
+        let config = ExperimentConfig::parse_from(["pq_crypto_matrix", "--seed", "8", "--flush-cache", "1"]);
+        assert_eq!(config.flush_cache_mib(), Some(1));
+        // As varreduras não consomem os geradores: mesmas mensagens, rotações e bytes
+        let cold = session_with(UsageScenario::SmallChat, "AES-GCM", &["--seed", "8", "--flush-cache", "1"], 10, 5, 8);
+        assert_eq!((cold.messages, cold.rotations, cold.msg_bandwidth), (warm.messages, warm.rotations, warm.msg_bandwidth));
+
+        assert_eq!(ExperimentConfig::parse_from(["pq_crypto_matrix", "--flush-cache"]).flush_cache_mib(), Some(DEFAULT_FLUSH_MIB));
+        assert!(ExperimentConfig::try_parse_from(["pq_crypto_matrix", "--flush-cache", "0"]).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+//! Combinação de resultados parciais (média e variância agrupadas)
+//!
+//! Uma execução dividida entre máquinas produz, para cada métrica, vários resumos
+//! parciais (n, média, desvio padrão). Tirar a média dos desvios padrão ou das
+//! médias sem ponderar dá o resultado errado; o correto é agrupar os momentos com
+//! a fórmula paralela de Welford (Chan, Golub e LeVeque, 1979), que reproduz
+//! exatamente a média e a variância da amostra completa.
+//!
+//! Só a média e a variância são agrupáveis a partir de resumos. Mediana, MAD e
+//! percentis (o caminho robusto, `*_stat_type = robust`) exigem as amostras brutas:
+//! concatene-as (ex: `--raw-samples-ns`) e recalcule as estatísticas.
+
+/// Nível de confiança dos intervalos gravados no CSV (colunas `*_ci95`)
+pub const CONFIDENCE_LEVEL: f64 = 0.95;
+
+/// Quantil bilateral da normal padrão para `CONFIDENCE_LEVEL`
+pub const CONFIDENCE_Z: f64 = 1.96;
+
+/// Momentos de uma amostra: tamanho, média e soma dos quadrados dos desvios (M2)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Moments {
+    pub count: usize,
+    pub mean: f64,
+    pub m2: f64,
+}
+
+impl Moments {
+    /// Calcula os momentos de uma amostra em uma passada (algoritmo de Welford)
+    pub fn from_samples(samples: &[f64]) -> Self {
+        samples.iter().fold(Moments::default(), |mut moments, &value| {
+            moments.count += 1;
+            let delta = value - moments.mean;
+            moments.mean += delta / moments.count as f64;
+            moments.m2 += delta * (value - moments.mean);
+            moments
+        })
+    }
+
+    /// Reconstrói os momentos a partir de um resumo (n, média, desvio padrão amostral)
+    ///
+    /// É o caminho para colunas do CSV: `*_sample_size`, `*_mean` e `*_std` de uma
+    /// métrica paramétrica. O desvio padrão deve usar a correção de Bessel (n - 1).
+    pub fn from_summary(count: usize, mean: f64, std_dev: f64) -> Self {
+        let m2 = if count > 1 { std_dev * std_dev * (count - 1) as f64 } else { 0.0 };
+        Moments { count, mean, m2 }
+    }
+
+    /// Combina os momentos de duas amostras disjuntas
+    ///
+    /// O resultado é idêntico (a menos de arredondamento) ao de `from_samples`
+    /// sobre a concatenação das duas amostras, em qualquer ordem.
+    pub fn merge(&self, other: &Moments) -> Moments {
+        if self.count == 0 {
+            return *other;
+        }
+        if other.count == 0 {
+            return *self;
+        }
+        let count = self.count + other.count;
+        let (n_a, n_b, n) = (self.count as f64, other.count as f64, count as f64);
+        let delta = other.mean - self.mean;
+        Moments {
+            count,
+            mean: self.mean + delta * n_b / n,
+            m2: self.m2 + other.m2 + delta * delta * n_a * n_b / n,
+        }
+    }
+
+    /// Variância amostral (correção de Bessel); zero com menos de dois valores
+    pub fn variance(&self) -> f64 {
+        if self.count < 2 { 0.0 } else { self.m2 / (self.count - 1) as f64 }
+    }
+
+    /// Desvio padrão amostral
+    pub fn std_dev(&self) -> f64 {
+        self.variance().sqrt()
+    }
+
+    /// Semiamplitude do IC95 pelo z-score (`CONFIDENCE_Z`), como nas estatísticas paramétricas do CSV
+    pub fn ci95(&self) -> f64 {
+        if self.count < 2 { 0.0 } else { CONFIDENCE_Z * self.std_dev() / (self.count as f64).sqrt() }
+    }
+}
+
+#[cfg(test)]
+/// Testes do agrupamento contra a amostra completa como referência
+mod tests {
+    use super::*;
+
+    fn two_pass(samples: &[f64]) -> (f64, f64) {
+        let n = samples.len() as f64;
+        let mean = samples.iter().sum::<f64>() / n;
+        let variance = samples.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+        (mean, variance)
+    }
+
+    #[test]
+    fn test_merge_matches_single_run() {
+        // "Máquinas" com tamanhos e escalas diferentes, como tempos de KEM parciais
+        let a: Vec<f64> = (0..37).map(|i| 1.0 + (i as f64 * 0.7).sin()).collect();
+        let b: Vec<f64> = (0..13).map(|i| 5.0 + (i as f64 * 1.3).cos() * 3.0).collect();
+        let full: Vec<f64> = a.iter().chain(&b).copied().collect();
+        let (mean, variance) = two_pass(&full);
+
+        let merged = Moments::from_samples(&a).merge(&Moments::from_samples(&b));
+        assert_eq!(merged.count, 50);
+        assert!((merged.mean - mean).abs() < 1e-12);
+        assert!((merged.variance() - variance).abs() < 1e-12);
+        // Ordem indiferente
+        let reversed = Moments::from_samples(&b).merge(&Moments::from_samples(&a));
+        assert!((reversed.variance() - variance).abs() < 1e-12);
+
+        // A partir dos resumos do CSV (n, média, desvio padrão)
+        let summary = |s: &[f64]| {
+            let (m, v) = two_pass(s);
+            Moments::from_summary(s.len(), m, v.sqrt())
+        };
+        let folded = summary(&a).merge(&summary(&b));
+        assert!((folded.mean - mean).abs() < 1e-12);
+        assert!((folded.std_dev() - variance.sqrt()).abs() < 1e-12);
+        assert!((folded.ci95() - 1.96 * variance.sqrt() / 50f64.sqrt()).abs() < 1e-12);
+
+        // A média ingênua dos desvios padrão não reproduz a amostra completa
+        let naive = (summary(&a).std_dev() + summary(&b).std_dev()) / 2.0;
+        assert!((naive - variance.sqrt()).abs() > 0.1);
+    }
+
+    #[test]
+    fn test_merge_edge_cases() {
+        let a = Moments::from_samples(&[2.0, 4.0]);
+        assert_eq!(a.merge(&Moments::default()), a);
+        assert_eq!(Moments::default().merge(&a), a);
+
+        let single = Moments::from_samples(&[3.0]);
+        assert_eq!((single.variance(), single.ci95()), (0.0, 0.0));
+        let merged = single.merge(&Moments::from_summary(1, 5.0, 0.0));
+        assert_eq!((merged.count, merged.mean, merged.variance()), (2, 4.0, 2.0));
+    }
+}
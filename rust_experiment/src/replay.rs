@@ -0,0 +1,212 @@
+//! Gravação e reprodução determinística do workload
+//!
+//! Com `--record`, cada decisão do workload (quantas consultas ao padrão de tráfego
+//! até o envio, tipo e tamanho da mensagem) é gravada como uma linha JSON. Com
+//! `--replay`, o laço de mensagens é conduzido pelo arquivo em vez dos geradores
+//! aleatórios, isolando o tempo criptográfico da aleatoriedade do workload.
+//!
+//! Cada linha identifica a mensagem por (configuração, repetição, remetente, sequência),
+//! de modo que execuções paralelas (`--jobs`, `--senders`) gravam e reproduzem
+//! exatamente os mesmos fluxos.
+
+use crate::error::ExperimentError;
+use crate::workload::{MESSAGE_KINDS, MessageGenerator, MessageType};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Mutex, MutexGuard};
+
+/// Descritor de uma mensagem do workload (uma linha do arquivo JSON-lines)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageDescriptor {
+    pub config: usize,      // Índice canônico da configuração
+    pub repetition: usize,  // Repetição dentro da configuração
+    pub sender: usize,      // Remetente (--senders)
+    pub seq: usize,         // Posição da mensagem na sessão
+    pub polls: u64,         // Consultas ao padrão de tráfego recusadas antes do envio
+    pub kind: String,       // Tipo da mensagem (rótulo de MESSAGE_KINDS)
+    pub size: usize,        // Tamanho do texto claro em bytes
+    /// Conteúdo de mensagens textuais (texto e sistema), relevante para a compressão;
+    /// mídias são bytes aleatórios e são regeneradas com o mesmo tamanho
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+/// Identificação de uma sessão: (configuração, repetição, remetente)
+pub type SessionKey = (usize, usize, usize);
+
+impl MessageDescriptor {
+    /// Descreve uma mensagem gerada, para gravação
+    pub fn from_message(key: SessionKey, seq: usize, polls: u64, message: &MessageType) -> Self {
+        let (size, text) = match message {
+            MessageType::Text(text) | MessageType::System(text) => (text.len(), Some(text.clone())),
+            MessageType::Image(data) | MessageType::File(data) | MessageType::Voice(data) => (data.len(), None),
+        };
+        Self {
+            config: key.0,
+            repetition: key.1,
+            sender: key.2,
+            seq,
+            polls,
+            kind: MESSAGE_KINDS[message.kind_index()].to_string(),
+            size,
+            text,
+        }
+    }
+
+    /// Reconstrói a mensagem descrita (mídias do mesmo tamanho, aleatórias ou do corpus)
+    ///
+    /// Um tipo fora de `MESSAGE_KINDS` é `ExperimentError::Config`; `WorkloadReplay::load`
+    /// já recusa esses descritores, mas um descritor montado à mão não passa por ele.
+    pub fn to_message(&self, generator: &mut MessageGenerator) -> Result<MessageType, ExperimentError> {
+        let text = || self.text.clone().unwrap_or_else(|| "x".repeat(self.size));
+        Ok(match self.kind.as_str() {
+            "texto" => MessageType::Text(text()),
+            "sistema" => MessageType::System(text()),
+            "imagem" => MessageType::Image(generator.payload(self.size).into()),
+            "arquivo" => MessageType::File(generator.payload(self.size).into()),
+            "voz" => MessageType::Voice(generator.payload(self.size).into()),
+            other => return Err(ExperimentError::Config(format!("tipo de mensagem desconhecido: {}", other))),
+        })
+    }
+}
+
+/// Gravador do workload, compartilhável entre threads
+pub struct WorkloadRecorder {
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl WorkloadRecorder {
+    /// Cria (ou trunca) o arquivo de gravação
+    pub fn create(path: &Path) -> Result<Self, ExperimentError> {
+        Ok(Self { writer: Mutex::new(BufWriter::new(File::create(path)?)) })
+    }
+
+    /// Grava o descritor de uma mensagem como uma linha JSON
+    pub fn record(&self, descriptor: &MessageDescriptor) -> Result<(), ExperimentError> {
+        let line = serde_json::to_string(descriptor).map_err(io::Error::other)?;
+        writeln!(self.writer()?, "{}", line)?;
+        Ok(())
+    }
+
+    /// Descarrega o buffer no arquivo
+    pub fn flush(&self) -> Result<(), ExperimentError> {
+        Ok(self.writer()?.flush()?)
+    }
+
+    /// Acesso exclusivo ao arquivo; uma thread que entrou em pânico gravando
+    /// deixa o gravador inutilizável, o que é informado como erro de E/S
+    fn writer(&self) -> Result<MutexGuard<'_, BufWriter<File>>, ExperimentError> {
+        self.writer.lock().map_err(|_| ExperimentError::Io(io::Error::other("gravador de workload envenenado")))
+    }
+}
+
+/// Workload gravado, indexado por sessão e ordenado pela sequência das mensagens
+pub struct WorkloadReplay {
+    sessions: HashMap<SessionKey, Vec<MessageDescriptor>>,
+}
+
+impl WorkloadReplay {
+    /// Carrega um arquivo gravado com `--record`
+    ///
+    /// Linhas que não descrevem uma mensagem válida são `ExperimentError::Config`.
+    pub fn load(path: &Path) -> Result<Self, ExperimentError> {
+        let mut sessions: HashMap<SessionKey, Vec<MessageDescriptor>> = HashMap::new();
+        for line in BufReader::new(File::open(path)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let descriptor: MessageDescriptor = serde_json::from_str(&line)
+                .map_err(|e| ExperimentError::Config(format!("linha de workload inválida: {}", e)))?;
+            if !MESSAGE_KINDS.contains(&descriptor.kind.as_str()) {
+                return Err(ExperimentError::Config(format!("tipo de mensagem desconhecido: {}", descriptor.kind)));
+            }
+            sessions
+                .entry((descriptor.config, descriptor.repetition, descriptor.sender))
+                .or_default()
+                .push(descriptor);
+        }
+        for stream in sessions.values_mut() {
+            stream.sort_by_key(|descriptor| descriptor.seq);
+        }
+        Ok(Self { sessions })
+    }
+
+    /// Fluxo de mensagens gravado para uma sessão, se existir
+    pub fn session(&self, key: SessionKey) -> Option<&[MessageDescriptor]> {
+        self.sessions.get(&key).map(Vec::as_slice)
+    }
+
+    /// Repetições gravadas de uma configuração, contadas a partir da 0 sem lacunas
+    /// (pelo primeiro remetente)
+    pub fn recorded_repetitions(&self, config: usize) -> usize {
+        (0..).take_while(|&repetition| self.sessions.contains_key(&(config, repetition, 0))).count()
+    }
+
+    /// Verifica se a sessão foi gravada com pelo menos `num_messages` mensagens
+    pub fn check_session(&self, key: SessionKey, num_messages: usize) -> Result<(), ExperimentError> {
+        let (config, repetition, sender) = key;
+        match self.session(key) {
+            None => Err(ExperimentError::Config(format!(
+                "não contém a sessão (configuração {}, repetição {}, remetente {})",
+                config, repetition, sender
+            ))),
+            Some(stream) if stream.len() < num_messages => Err(ExperimentError::Config(format!(
+                "tem {} mensagens para a sessão (configuração {}, repetição {}, remetente {}), esperado {}",
+                stream.len(), config, repetition, sender, num_messages
+            ))),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+/// Testes de ida e volta entre gravação e reprodução
+mod tests {
+    use super::*;
+    use crate::workload::UsageScenario;
+
+    #[test]
+    fn test_record_and_replay_roundtrip() {
+        let path = std::env::temp_dir().join(format!("pqc_replay_test_{}.jsonl", std::process::id()));
+        let messages = [
+            MessageType::Text("hello team".to_string()),
+            MessageType::Image(vec![7u8; 300].into()),
+            MessageType::System("Sync completed".to_string()),
+        ];
+
+        let recorder = WorkloadRecorder::create(&path).unwrap();
+        // Grava fora de ordem para verificar a ordenação por sequência
+        for (seq, message) in messages.iter().enumerate().rev() {
+            recorder.record(&MessageDescriptor::from_message((4, 1, 0), seq, seq as u64 * 3, message)).unwrap();
+        }
+        recorder.flush().unwrap();
+
+        let replay = WorkloadReplay::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let stream = replay.session((4, 1, 0)).unwrap();
+        assert!(replay.session((4, 1, 1)).is_none());
+        assert_eq!(stream.len(), 3);
+        // Só a repetição 1 foi gravada: nenhuma repetição contígua a partir da 0
+        assert_eq!(replay.recorded_repetitions(4), 0);
+        assert!(replay.check_session((4, 1, 0), 3).is_ok());
+        assert!(replay.check_session((4, 1, 0), 4).unwrap_err().to_string().contains("esperado 4"));
+        assert!(replay.check_session((4, 0, 0), 1).unwrap_err().to_string().contains("não contém"));
+
+        let mut generator = MessageGenerator::new(UsageScenario::SmallChat);
+        for (seq, (descriptor, original)) in stream.iter().zip(&messages).enumerate() {
+            assert_eq!((descriptor.seq, descriptor.polls), (seq, seq as u64 * 3));
+            let replayed = descriptor.to_message(&mut generator).unwrap();
+            assert_eq!(replayed.kind_index(), original.kind_index());
+            assert_eq!(generator.get_message_size(&replayed), generator.get_message_size(original));
+        }
+        // Conteúdo textual é reproduzido exatamente
+        assert_eq!(stream[0].to_message(&mut generator).unwrap(), messages[0]);
+
+        let unknown = MessageDescriptor { kind: "video".to_string(), ..stream[0].clone() };
+        assert!(matches!(unknown.to_message(&mut generator), Err(ExperimentError::Config(_))));
+    }
+}
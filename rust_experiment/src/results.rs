@@ -0,0 +1,437 @@
+//! Contrato de formato do CSV de resultados
+//!
+//! A primeira coluna de todo CSV de resultados é `schema_version`. O número é
+//! incrementado sempre que o conjunto de colunas muda, e leitores devem recusar
+//! versões que não conhecem em vez de interpretar colunas deslocadas em silêncio.
+//!
+//! `RESULT_COLUMNS` é o registro único das colunas: o cabeçalho do CSV é gerado a
+//! partir dele, e a legenda com unidade e significado de cada coluna vai para os
+//! metadados da execução, de modo que as duas coisas não podem divergir. O registro
+//! também fixa o tipo de cada coluna, e as linhas são montadas como células tipadas
+//! (`Cell`), que o CSV formata e o banco SQLite (`--sqlite`) grava sem reinterpretar texto.
+
+use std::fmt;
+use std::io::BufRead;
+
+/// Versão atual do esquema de colunas do CSV de resultados
+///
+/// Deve ser incrementada a cada coluna adicionada, removida ou reordenada.
+pub const SCHEMA_VERSION: u32 = 33;
+
+/// Nome da coluna que carrega a versão do esquema (sempre a primeira)
+pub const SCHEMA_COLUMN: &str = "schema_version";
+
+/// Tipo dos valores de uma coluna do CSV de resultados
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Integer,
+    Real,
+    Boolean,
+    Text,
+}
+
+/// Definição de uma coluna do CSV de resultados: nome, tipo, unidade e significado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    pub name: &'static str,
+    pub kind: ColumnType,
+    /// Unidade dos valores (`ms`, `µs`, `bytes`, `bits/s`, ...) ou o tipo, para colunas
+    /// sem grandeza física (`texto`, `bool`, `contagem`, `razão`)
+    pub unit: &'static str,
+    pub description: &'static str,
+}
+
+impl Column {
+    const fn new(name: &'static str, kind: ColumnType, unit: &'static str, description: &'static str) -> Self {
+        Column { name, kind, unit, description }
+    }
+}
+
+/// Valor de uma célula de uma linha de resultados
+///
+/// O `Display` é o campo do CSV; campos vazios (opção desligada, tipo sem mensagens)
+/// são `Empty` e cabem em colunas de qualquer tipo.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Empty,
+    Integer(i64),
+    /// Valor e número de casas decimais no CSV
+    Real(f64, usize),
+    Boolean(bool),
+    Text(String),
+}
+
+impl Cell {
+    /// Indica se a célula pode ocupar uma coluna do tipo `kind`
+    pub fn fits(&self, kind: ColumnType) -> bool {
+        match self {
+            Cell::Empty => true,
+            Cell::Integer(_) => kind == ColumnType::Integer,
+            Cell::Real(..) => kind == ColumnType::Real,
+            Cell::Boolean(_) => kind == ColumnType::Boolean,
+            Cell::Text(_) => kind == ColumnType::Text,
+        }
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Cell::Empty => Ok(()),
+            Cell::Integer(value) => write!(f, "{}", value),
+            Cell::Real(value, decimals) => write!(f, "{:.*}", decimals, value),
+            Cell::Boolean(value) => write!(f, "{}", value),
+            Cell::Text(value) => f.write_str(value),
+        }
+    }
+}
+
+impl From<usize> for Cell {
+    fn from(value: usize) -> Self {
+        Cell::Integer(value as i64)
+    }
+}
+
+impl From<u32> for Cell {
+    fn from(value: u32) -> Self {
+        Cell::Integer(i64::from(value))
+    }
+}
+
+impl From<bool> for Cell {
+    fn from(value: bool) -> Self {
+        Cell::Boolean(value)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(value: &str) -> Self {
+        Cell::Text(value.to_string())
+    }
+}
+
+impl From<String> for Cell {
+    fn from(value: String) -> Self {
+        Cell::Text(value)
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Cell::Empty, Into::into)
+    }
+}
+
+/// Linha do CSV de resultados a partir das células, na ordem de `RESULT_COLUMNS`
+pub fn csv_row(cells: &[Cell]) -> String {
+    cells.iter().map(Cell::to_string).collect::<Vec<_>>().join(",")
+}
+
+/// Colunas do CSV de resultados, na ordem em que são gravadas
+///
+/// Os valores `_mean` das métricas por repetição trazem o estimador indicado em
+/// `<métrica>_central`; `_std` é o desvio padrão (paramétrico) ou o MAD (robusto).
+pub const RESULT_COLUMNS: [Column; 165] = [
+    Column::new("schema_version", ColumnType::Integer, "versão", "Versão do conjunto de colunas (sempre a primeira)"),
+    Column::new("cenario", ColumnType::Text, "texto", "Cenário de uso simulado"),
+    Column::new("padrao_trafego", ColumnType::Text, "texto", "Padrão de tráfego"),
+    Column::new("acordo", ColumnType::Text, "texto", "Acordo de chaves"),
+    Column::new("cifra", ColumnType::Text, "texto", "Cifra simétrica"),
+    Column::new("num_msgs", ColumnType::Integer, "mensagens", "Mensagens por sessão"),
+    Column::new("msgs_por_rotacao", ColumnType::Integer, "mensagens", "Mensagens entre rotações da sessão"),
+    Column::new("rotacoes", ColumnType::Integer, "contagem", "Rotações de chave por sessão"),
+    Column::new("kem_ms_mean", ColumnType::Real, "ms", "Tempo de acordo de chaves por repetição (estimador em kem_central)"),
+    Column::new("kem_ms_std", ColumnType::Real, "ms", "Dispersão do tempo de KEM (desvio padrão ou MAD)"),
+    Column::new("kem_ms_ci95", ColumnType::Real, "ms", "Meia largura do IC95 do tempo de KEM"),
+    Column::new("cipher_ms_mean", ColumnType::Real, "ms", "Tempo de parede do laço de mensagens por repetição (estimador em cipher_central)"),
+    Column::new("cipher_ms_std", ColumnType::Real, "ms", "Dispersão do tempo do laço (desvio padrão ou MAD)"),
+    Column::new("cipher_ms_ci95", ColumnType::Real, "ms", "Meia largura do IC95 do tempo do laço"),
+    Column::new("kem_bw_bytes_mean", ColumnType::Real, "bytes", "Bytes de acordo de chaves por repetição (estimador em kem_bw_bytes_central)"),
+    Column::new("kem_bw_bytes_std", ColumnType::Real, "bytes", "Dispersão dos bytes de acordo"),
+    Column::new("kem_bw_bytes_ci95", ColumnType::Real, "bytes", "Meia largura do IC95 dos bytes de acordo"),
+    Column::new("msg_bw_bytes_mean", ColumnType::Real, "bytes", "Bytes de mensagens cifradas por repetição (estimador em msg_bw_bytes_central)"),
+    Column::new("msg_bw_bytes_std", ColumnType::Real, "bytes", "Dispersão dos bytes de mensagens"),
+    Column::new("msg_bw_bytes_ci95", ColumnType::Real, "bytes", "Meia largura do IC95 dos bytes de mensagens"),
+    Column::new("text_msgs", ColumnType::Real, "mensagens", "Mensagens de texto por repetição (média)"),
+    Column::new("image_msgs", ColumnType::Real, "mensagens", "Mensagens de imagem por repetição (média)"),
+    Column::new("file_msgs", ColumnType::Real, "mensagens", "Mensagens de arquivo por repetição (média)"),
+    Column::new("system_msgs", ColumnType::Real, "mensagens", "Mensagens de sistema por repetição (média)"),
+    Column::new("kem_normal", ColumnType::Boolean, "bool", "Se o tempo de KEM (kem_ms) passou na verificação de normalidade"),
+    Column::new("cipher_normal", ColumnType::Boolean, "bool", "Se o tempo do laço (cipher_ms) passou na verificação de normalidade"),
+    Column::new("kem_bw_bytes_normal", ColumnType::Boolean, "bool", "Se o bytes de acordo (kem_bw_bytes) passou na verificação de normalidade"),
+    Column::new("msg_bw_bytes_normal", ColumnType::Boolean, "bool", "Se o bytes de mensagens (msg_bw_bytes) passou na verificação de normalidade"),
+    Column::new("kem_stat_type", ColumnType::Text, "texto", "Estatísticas aplicadas ao tempo de KEM (kem_ms): parametric, robust ou invalid"),
+    Column::new("cipher_stat_type", ColumnType::Text, "texto", "Estatísticas aplicadas ao tempo do laço (cipher_ms): parametric, robust ou invalid"),
+    Column::new("kem_bw_bytes_stat_type", ColumnType::Text, "texto", "Estatísticas aplicadas ao bytes de acordo (kem_bw_bytes): parametric, robust ou invalid"),
+    Column::new("msg_bw_bytes_stat_type", ColumnType::Text, "texto", "Estatísticas aplicadas ao bytes de mensagens (msg_bw_bytes): parametric, robust ou invalid"),
+    Column::new("kem_outliers", ColumnType::Integer, "contagem", "Outliers moderados (IQR 1,5x) do tempo de KEM (kem_ms)"),
+    Column::new("cipher_outliers", ColumnType::Integer, "contagem", "Outliers moderados (IQR 1,5x) do tempo do laço (cipher_ms)"),
+    Column::new("kem_bw_bytes_outliers", ColumnType::Integer, "contagem", "Outliers moderados (IQR 1,5x) do bytes de acordo (kem_bw_bytes)"),
+    Column::new("msg_bw_bytes_outliers", ColumnType::Integer, "contagem", "Outliers moderados (IQR 1,5x) do bytes de mensagens (msg_bw_bytes)"),
+    Column::new("kem_extreme_outliers", ColumnType::Integer, "contagem", "Outliers extremos (IQR 3,0x) do tempo de KEM (kem_ms)"),
+    Column::new("cipher_extreme_outliers", ColumnType::Integer, "contagem", "Outliers extremos (IQR 3,0x) do tempo do laço (cipher_ms)"),
+    Column::new("kem_bw_bytes_extreme_outliers", ColumnType::Integer, "contagem", "Outliers extremos (IQR 3,0x) do bytes de acordo (kem_bw_bytes)"),
+    Column::new("msg_bw_bytes_extreme_outliers", ColumnType::Integer, "contagem", "Outliers extremos (IQR 3,0x) do bytes de mensagens (msg_bw_bytes)"),
+    Column::new("kem_sample_size", ColumnType::Integer, "contagem", "Repetições usadas nas estatísticas do tempo de KEM (kem_ms)"),
+    Column::new("cipher_sample_size", ColumnType::Integer, "contagem", "Repetições usadas nas estatísticas do tempo do laço (cipher_ms)"),
+    Column::new("kem_bw_bytes_sample_size", ColumnType::Integer, "contagem", "Repetições usadas nas estatísticas do bytes de acordo (kem_bw_bytes)"),
+    Column::new("msg_bw_bytes_sample_size", ColumnType::Integer, "contagem", "Repetições usadas nas estatísticas do bytes de mensagens (msg_bw_bytes)"),
+    Column::new("expansion_ratio", ColumnType::Real, "razão", "Bytes transmitidos / bytes de texto claro"),
+    Column::new("cifra_autenticada", ColumnType::Boolean, "bool", "Se o texto cifrado é autenticado"),
+    Column::new("versoes_primitivas", ColumnType::Text, "texto", "Bibliotecas criptográficas no formato crate@versão, separadas por ;"),
+    Column::new("tamanho_fixo_bytes", ColumnType::Integer, "bytes", "Tamanho fixo das mensagens (--fixed-size); vazio no workload realista"),
+    Column::new("cipher_only_ms_mean", ColumnType::Real, "ms", "Tempo somado só das chamadas de cifragem por repetição (estimador em cipher_only_central)"),
+    Column::new("cipher_only_ms_std", ColumnType::Real, "ms", "Dispersão do tempo só de cifragem"),
+    Column::new("cipher_only_ms_ci95", ColumnType::Real, "ms", "Meia largura do IC95 do tempo só de cifragem"),
+    Column::new("cipher_only_normal", ColumnType::Boolean, "bool", "Se o tempo só de cifragem (cipher_only_ms) passou na verificação de normalidade"),
+    Column::new("cipher_only_stat_type", ColumnType::Text, "texto", "Estatísticas aplicadas ao tempo só de cifragem (cipher_only_ms): parametric, robust ou invalid"),
+    Column::new("dispositivos_por_usuario", ColumnType::Integer, "contagem", "Dispositivos de Bob (--devices-per-user)"),
+    Column::new("kem_skewness", ColumnType::Real, "adimensional", "Assimetria do tempo de KEM (kem_ms)"),
+    Column::new("kem_kurtosis", ColumnType::Real, "adimensional", "Curtose em excesso do tempo de KEM (kem_ms)"),
+    Column::new("cipher_skewness", ColumnType::Real, "adimensional", "Assimetria do tempo do laço (cipher_ms)"),
+    Column::new("cipher_kurtosis", ColumnType::Real, "adimensional", "Curtose em excesso do tempo do laço (cipher_ms)"),
+    Column::new("kem_bw_bytes_skewness", ColumnType::Real, "adimensional", "Assimetria do bytes de acordo (kem_bw_bytes)"),
+    Column::new("kem_bw_bytes_kurtosis", ColumnType::Real, "adimensional", "Curtose em excesso do bytes de acordo (kem_bw_bytes)"),
+    Column::new("msg_bw_bytes_skewness", ColumnType::Real, "adimensional", "Assimetria do bytes de mensagens (msg_bw_bytes)"),
+    Column::new("msg_bw_bytes_kurtosis", ColumnType::Real, "adimensional", "Curtose em excesso do bytes de mensagens (msg_bw_bytes)"),
+    Column::new("cipher_only_skewness", ColumnType::Real, "adimensional", "Assimetria do tempo só de cifragem (cipher_only_ms)"),
+    Column::new("cipher_only_kurtosis", ColumnType::Real, "adimensional", "Curtose em excesso do tempo só de cifragem (cipher_only_ms)"),
+    Column::new("compressao", ColumnType::Text, "texto", "Compressão aplicada antes da cifragem (none, zstd, gzip)"),
+    Column::new("compress_ms_mean", ColumnType::Real, "ms", "Tempo de compressão por repetição"),
+    Column::new("compress_ms_std", ColumnType::Real, "ms", "Dispersão do tempo de compressão"),
+    Column::new("compress_ms_ci95", ColumnType::Real, "ms", "Meia largura do IC95 do tempo de compressão"),
+    Column::new("razao_compressao_texto", ColumnType::Real, "razão", "Bytes comprimidos / originais das mensagens de texto; NaN sem mensagens do tipo"),
+    Column::new("razao_compressao_imagem", ColumnType::Real, "razão", "Bytes comprimidos / originais das mensagens de imagem; NaN sem mensagens do tipo"),
+    Column::new("razao_compressao_arquivo", ColumnType::Real, "razão", "Bytes comprimidos / originais das mensagens de arquivo; NaN sem mensagens do tipo"),
+    Column::new("razao_compressao_sistema", ColumnType::Real, "razão", "Bytes comprimidos / originais das mensagens de sistema; NaN sem mensagens do tipo"),
+    Column::new("razao_compressao_voz", ColumnType::Real, "razão", "Bytes comprimidos / originais das mensagens de voz; NaN sem mensagens do tipo"),
+    Column::new("kem_central", ColumnType::Text, "texto", "Estimador guardado na coluna _mean do tempo de KEM (kem_ms) (mean, median, mode)"),
+    Column::new("cipher_central", ColumnType::Text, "texto", "Estimador guardado na coluna _mean do tempo do laço (cipher_ms) (mean, median, mode)"),
+    Column::new("kem_bw_bytes_central", ColumnType::Text, "texto", "Estimador guardado na coluna _mean do bytes de acordo (kem_bw_bytes) (mean, median, mode)"),
+    Column::new("msg_bw_bytes_central", ColumnType::Text, "texto", "Estimador guardado na coluna _mean do bytes de mensagens (msg_bw_bytes) (mean, median, mode)"),
+    Column::new("cipher_only_central", ColumnType::Text, "texto", "Estimador guardado na coluna _mean do tempo só de cifragem (cipher_only_ms) (mean, median, mode)"),
+    Column::new("kem_sample_floor_hit", ColumnType::Boolean, "bool", "Se a remoção de outliers do tempo de KEM (kem_ms) foi descartada pelo piso de amostra"),
+    Column::new("cipher_sample_floor_hit", ColumnType::Boolean, "bool", "Se a remoção de outliers do tempo do laço (cipher_ms) foi descartada pelo piso de amostra"),
+    Column::new("kem_bw_bytes_sample_floor_hit", ColumnType::Boolean, "bool", "Se a remoção de outliers do bytes de acordo (kem_bw_bytes) foi descartada pelo piso de amostra"),
+    Column::new("msg_bw_bytes_sample_floor_hit", ColumnType::Boolean, "bool", "Se a remoção de outliers do bytes de mensagens (msg_bw_bytes) foi descartada pelo piso de amostra"),
+    Column::new("cipher_only_sample_floor_hit", ColumnType::Boolean, "bool", "Se a remoção de outliers do tempo só de cifragem (cipher_only_ms) foi descartada pelo piso de amostra"),
+    Column::new("remetentes", ColumnType::Integer, "contagem", "Remetentes simultâneos (--senders)"),
+    Column::new("throughput_agregado_msgs_s", ColumnType::Real, "msgs/s", "Mensagens por segundo somando todos os remetentes"),
+    Column::new("throughput_por_remetente_msgs_s", ColumnType::Real, "msgs/s", "Mensagens por segundo de cada remetente"),
+    Column::new("latencia_cifragem_msg_us", ColumnType::Real, "µs", "Tempo médio de cifragem por mensagem"),
+    Column::new("compromisso_chave", ColumnType::Boolean, "bool", "Compromisso de chave SHA-256 por mensagem (--key-commitment)"),
+    Column::new("commitment_ms_mean", ColumnType::Real, "ms", "Tempo dos compromissos de chave por repetição"),
+    Column::new("commitment_ms_std", ColumnType::Real, "ms", "Dispersão do tempo dos compromissos"),
+    Column::new("commitment_ms_ci95", ColumnType::Real, "ms", "Meia largura do IC95 do tempo dos compromissos"),
+    Column::new("upload_bytes", ColumnType::Real, "bytes", "Bytes enviados pelo remetente por repetição (média)"),
+    Column::new("download_bytes", ColumnType::Real, "bytes", "Bytes recebidos pelo remetente por repetição (média)"),
+    Column::new("security_level", ColumnType::Text, "texto", "Categoria de segurança NIST do acordo"),
+    Column::new("kem_failures", ColumnType::Real, "contagem", "Textos cifrados Kyber corrompidos por repetição (média)"),
+    Column::new("decap_ok_us_mean", ColumnType::Real, "µs", "Tempo médio de um decapsulamento bem-sucedido; NaN sem ocorrências"),
+    Column::new("decap_fail_us_mean", ColumnType::Real, "µs", "Tempo médio de um decapsulamento rejeitado; NaN sem ocorrências"),
+    Column::new("config_seed", ColumnType::Text, "texto", "Semente da configuração (u64 decimal)"),
+    Column::new("stalled", ColumnType::Boolean, "bool", "Alguma sessão da configuração foi encerrada pela guarda do laço de mensagens"),
+    Column::new("sessoes_travadas", ColumnType::Integer, "contagem", "Sessões encerradas pela guarda de consultas"),
+    Column::new("storage_bytes", ColumnType::Real, "bytes", "Bytes persistidos pelo servidor por repetição (média)"),
+    Column::new("rekey_events", ColumnType::Real, "contagem", "Re-keys por saída de membro por repetição (média)"),
+    Column::new("rekey_ms_mean", ColumnType::Real, "ms", "Tempo dos re-keys por repetição (média)"),
+    Column::new("rekey_bw_bytes_mean", ColumnType::Real, "bytes", "Bytes de acordo dos re-keys por repetição (média)"),
+    Column::new("rekey_ms_per_event", ColumnType::Real, "ms", "Tempo médio de um re-key; NaN sem eventos"),
+    Column::new("voice_msgs", ColumnType::Real, "mensagens", "Mensagens de voz por repetição (média)"),
+    Column::new("estrategia_rotacao", ColumnType::Text, "texto", "Estratégia de rotação com parâmetros"),
+    Column::new("x25519_efemero", ColumnType::Text, "texto", "Ciclo de vida dos segredos X25519 (fresh, static, both)"),
+    Column::new("keygen_bob", ColumnType::Text, "texto", "Política de geração das chaves de Bob"),
+    Column::new("payloads_duplicados", ColumnType::Integer, "contagem", "Payloads de mídia repetidos (--detect-duplicates); vazio sem a opção"),
+    Column::new("repeticoes", ColumnType::Integer, "contagem", "Repetições executadas"),
+    Column::new("config_wall_ms", ColumnType::Real, "ms", "Tempo de parede total da configuração"),
+    Column::new("kem_bw_bps", ColumnType::Real, "bits/s", "Taxa efetiva dos bytes de acordo"),
+    Column::new("msg_bw_bps", ColumnType::Real, "bits/s", "Taxa efetiva dos bytes de mensagens"),
+    Column::new("tamanho_grupo", ColumnType::Integer, "contagem", "Membros considerados no acordo de cada rotação"),
+    Column::new("kem_sender_ms_mean", ColumnType::Real, "ms", "Parte do tempo de KEM do remetente por repetição (média aritmética)"),
+    Column::new("kem_receiver_ms_mean", ColumnType::Real, "ms", "Decapsulamentos de todos os receptores por repetição (média aritmética)"),
+    Column::new("decaps_por_encaps", ColumnType::Integer, "contagem", "Decapsulamentos por encapsulamento (--decaps-per-encap)"),
+    Column::new("edit_events", ColumnType::Real, "contagem", "Edições de mensagens de texto por repetição (média, --edit-rate)"),
+    Column::new("edit_ms_mean", ColumnType::Real, "ms", "Tempo de recifragem das edições por repetição (média)"),
+    Column::new("edit_bw_bytes_mean", ColumnType::Real, "bytes", "Bytes cifrados das edições por repetição (média)"),
+    Column::new("layout_tag", ColumnType::Text, "texto", "Disposição da tag das cifras (attached, detached)"),
+    Column::new("latencia_cifragem_msg_ponderada_us", ColumnType::Real, "µs", "Tempo médio de cifragem por mensagem ponderado pelo tamanho do texto claro (--size-weighted); vazio sem a opção"),
+    Column::new("confirm_ms_mean", ColumnType::Real, "ms", "Tempo das confirmações de chave por repetição (média, --key-confirmation)"),
+    Column::new("confirm_bw_bytes_mean", ColumnType::Real, "bytes", "Bytes das confirmações de chave por repetição (média)"),
+    Column::new("backup_ops", ColumnType::Real, "contagem", "Chaves de sessão enviadas ao backup por repetição (média, --key-backup)"),
+    Column::new("backup_ms_mean", ColumnType::Real, "ms", "Tempo das cifragens de backup por repetição (média)"),
+    Column::new("backup_bw_bytes_mean", ColumnType::Real, "bytes", "Bytes enviados ao backup por repetição (média)"),
+    Column::new("nonfinite_count", ColumnType::Integer, "contagem", "Valores NaN/Inf descartados antes da análise, somando as métricas da configuração"),
+    Column::new("envelope", ColumnType::Text, "texto", "Envelope do texto claro (none, matrix-json)"),
+    Column::new("base64_midia", ColumnType::Boolean, "bool", "Mídia codificada em base64 antes da cifragem (--base64-media)"),
+    Column::new("entropia_payload_bits", ColumnType::Real, "bits/byte", "Entropia das mídias geradas (--payload-entropy); vazio com bytes uniformes (8 bits)"),
+    Column::new("variante_dh", ColumnType::Text, "texto", "Variante do Diffie-Hellman X25519 (ephemeral-static, static-static, ephemeral-ephemeral)"),
+    Column::new("combiner_ms_mean", ColumnType::Real, "ms", "Derivação das chaves de sessão pelo combinador HKDF por repetição (média, fora de kem_ms)"),
+    Column::new("cipher_us_texto_mean", ColumnType::Real, "µs", "Tempo de cifragem por mensagem de texto (estimador adaptativo, --per-type-stats); vazio sem a opção ou sem mensagens do tipo"),
+    Column::new("cipher_us_texto_ci95", ColumnType::Real, "µs", "Meia largura do IC95 do tempo de cifragem por mensagem de texto"),
+    Column::new("cipher_us_texto_normal", ColumnType::Boolean, "bool", "Se o tempo de cifragem por mensagem de texto passou na verificação de normalidade"),
+    Column::new("msg_bytes_texto_mean", ColumnType::Real, "bytes", "Bytes enviados por mensagem de texto (texto cifrado + nonce, --per-type-stats)"),
+    Column::new("msg_bytes_texto_ci95", ColumnType::Real, "bytes", "Meia largura do IC95 dos bytes por mensagem de texto"),
+    Column::new("msg_bytes_texto_normal", ColumnType::Boolean, "bool", "Se os bytes por mensagem de texto passaram na verificação de normalidade"),
+    Column::new("cipher_us_imagem_mean", ColumnType::Real, "µs", "Tempo de cifragem por mensagem de imagem (estimador adaptativo, --per-type-stats); vazio sem a opção ou sem mensagens do tipo"),
+    Column::new("cipher_us_imagem_ci95", ColumnType::Real, "µs", "Meia largura do IC95 do tempo de cifragem por mensagem de imagem"),
+    Column::new("cipher_us_imagem_normal", ColumnType::Boolean, "bool", "Se o tempo de cifragem por mensagem de imagem passou na verificação de normalidade"),
+    Column::new("msg_bytes_imagem_mean", ColumnType::Real, "bytes", "Bytes enviados por mensagem de imagem (texto cifrado + nonce, --per-type-stats)"),
+    Column::new("msg_bytes_imagem_ci95", ColumnType::Real, "bytes", "Meia largura do IC95 dos bytes por mensagem de imagem"),
+    Column::new("msg_bytes_imagem_normal", ColumnType::Boolean, "bool", "Se os bytes por mensagem de imagem passaram na verificação de normalidade"),
+    Column::new("cipher_us_arquivo_mean", ColumnType::Real, "µs", "Tempo de cifragem por mensagem de arquivo (estimador adaptativo, --per-type-stats); vazio sem a opção ou sem mensagens do tipo"),
+    Column::new("cipher_us_arquivo_ci95", ColumnType::Real, "µs", "Meia largura do IC95 do tempo de cifragem por mensagem de arquivo"),
+    Column::new("cipher_us_arquivo_normal", ColumnType::Boolean, "bool", "Se o tempo de cifragem por mensagem de arquivo passou na verificação de normalidade"),
+    Column::new("msg_bytes_arquivo_mean", ColumnType::Real, "bytes", "Bytes enviados por mensagem de arquivo (texto cifrado + nonce, --per-type-stats)"),
+    Column::new("msg_bytes_arquivo_ci95", ColumnType::Real, "bytes", "Meia largura do IC95 dos bytes por mensagem de arquivo"),
+    Column::new("msg_bytes_arquivo_normal", ColumnType::Boolean, "bool", "Se os bytes por mensagem de arquivo passaram na verificação de normalidade"),
+    Column::new("cipher_us_sistema_mean", ColumnType::Real, "µs", "Tempo de cifragem por mensagem de sistema (estimador adaptativo, --per-type-stats); vazio sem a opção ou sem mensagens do tipo"),
+    Column::new("cipher_us_sistema_ci95", ColumnType::Real, "µs", "Meia largura do IC95 do tempo de cifragem por mensagem de sistema"),
+    Column::new("cipher_us_sistema_normal", ColumnType::Boolean, "bool", "Se o tempo de cifragem por mensagem de sistema passou na verificação de normalidade"),
+    Column::new("msg_bytes_sistema_mean", ColumnType::Real, "bytes", "Bytes enviados por mensagem de sistema (texto cifrado + nonce, --per-type-stats)"),
+    Column::new("msg_bytes_sistema_ci95", ColumnType::Real, "bytes", "Meia largura do IC95 dos bytes por mensagem de sistema"),
+    Column::new("msg_bytes_sistema_normal", ColumnType::Boolean, "bool", "Se os bytes por mensagem de sistema passaram na verificação de normalidade"),
+    Column::new("cipher_us_voz_mean", ColumnType::Real, "µs", "Tempo de cifragem por mensagem de voz (estimador adaptativo, --per-type-stats); vazio sem a opção ou sem mensagens do tipo"),
+    Column::new("cipher_us_voz_ci95", ColumnType::Real, "µs", "Meia largura do IC95 do tempo de cifragem por mensagem de voz"),
+    Column::new("cipher_us_voz_normal", ColumnType::Boolean, "bool", "Se o tempo de cifragem por mensagem de voz passou na verificação de normalidade"),
+    Column::new("msg_bytes_voz_mean", ColumnType::Real, "bytes", "Bytes enviados por mensagem de voz (texto cifrado + nonce, --per-type-stats)"),
+    Column::new("msg_bytes_voz_ci95", ColumnType::Real, "bytes", "Meia largura do IC95 dos bytes por mensagem de voz"),
+    Column::new("msg_bytes_voz_normal", ColumnType::Boolean, "bool", "Se os bytes por mensagem de voz passaram na verificação de normalidade"),
+    Column::new("velocidade_relativa", ColumnType::Real, "razão", "cipher_ms_mean da cifra de referência da célula (--baseline-cipher) dividido pelo desta cifra; NaN sem a referência ou com média nula"),
+];
+
+/// Cabeçalho do CSV de resultados, gerado a partir de `RESULT_COLUMNS`
+pub fn result_header() -> String {
+    RESULT_COLUMNS.iter().map(|column| column.name).collect::<Vec<_>>().join(",")
+}
+
+/// Erros de validação do esquema de um CSV de resultados
+#[derive(Debug)]
+pub enum SchemaError {
+    /// Falha de leitura do arquivo
+    Io(std::io::Error),
+    /// O cabeçalho não começa com `schema_version` (arquivo anterior ao versionamento)
+    MissingVersionColumn,
+    /// O arquivo não tem linhas de dados, então a versão não pode ser lida
+    NoRows,
+    /// O valor da coluna de versão não é um número
+    InvalidVersion(String),
+    /// Versão diferente da suportada por este leitor
+    UnsupportedVersion { found: u32, supported: u32 },
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::Io(e) => write!(f, "erro de leitura: {}", e),
+            SchemaError::MissingVersionColumn => write!(
+                f, "cabeçalho sem a coluna '{}' (arquivo anterior ao versionamento do esquema)", SCHEMA_COLUMN
+            ),
+            SchemaError::NoRows => write!(f, "arquivo sem linhas de resultados; versão do esquema indisponível"),
+            SchemaError::InvalidVersion(value) => write!(f, "versão de esquema inválida: '{}'", value),
+            SchemaError::UnsupportedVersion { found, supported } => write!(
+                f, "versão de esquema {} não suportada (este leitor entende a versão {})", found, supported
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl From<std::io::Error> for SchemaError {
+    fn from(e: std::io::Error) -> Self {
+        SchemaError::Io(e)
+    }
+}
+
+/// Lê e valida a versão do esquema de um CSV de resultados
+///
+/// Verifica que a primeira coluna do cabeçalho é `schema_version` e que a primeira
+/// linha de dados traz exatamente `SCHEMA_VERSION`.
+///
+/// Retorna:
+/// - A versão encontrada, ou o motivo pelo qual o arquivo deve ser recusado
+pub fn read_schema_version<R: BufRead>(reader: R) -> Result<u32, SchemaError> {
+    let mut lines = reader.lines();
+    let header = lines.next().transpose()?.unwrap_or_default();
+    if header.split(',').next() != Some(SCHEMA_COLUMN) {
+        return Err(SchemaError::MissingVersionColumn);
+    }
+
+    let row = lines.next().transpose()?.ok_or(SchemaError::NoRows)?;
+    let value = row.split(',').next().unwrap_or_default().trim();
+    let found: u32 = value.parse().map_err(|_| SchemaError::InvalidVersion(value.to_string()))?;
+    if found != SCHEMA_VERSION {
+        return Err(SchemaError::UnsupportedVersion { found, supported: SCHEMA_VERSION });
+    }
+    Ok(found)
+}
+
+#[cfg(test)]
+/// Testes da validação de versão do esquema
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_schema_version() {
+        let current = format!("schema_version,cenario\n{},SmallChat\n", SCHEMA_VERSION);
+        assert_eq!(read_schema_version(current.as_bytes()).unwrap(), SCHEMA_VERSION);
+
+        let legacy = "cenario,padrao_trafego\nSmallChat,Constant\n";
+        assert!(matches!(read_schema_version(legacy.as_bytes()), Err(SchemaError::MissingVersionColumn)));
+
+        let future = format!("schema_version,cenario\n{},SmallChat\n", SCHEMA_VERSION + 1);
+        assert!(matches!(
+            read_schema_version(future.as_bytes()),
+            Err(SchemaError::UnsupportedVersion { found, .. }) if found == SCHEMA_VERSION + 1
+        ));
+
+        assert!(matches!(read_schema_version("schema_version,cenario\n".as_bytes()), Err(SchemaError::NoRows)));
+        assert!(matches!(read_schema_version("schema_version\nabc\n".as_bytes()), Err(SchemaError::InvalidVersion(_))));
+    }
+
+    #[test]
+    fn test_column_registry_is_unique_and_documented() {
+        assert_eq!(RESULT_COLUMNS[0].name, SCHEMA_COLUMN);
+        let mut names: Vec<&str> = RESULT_COLUMNS.iter().map(|column| column.name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), RESULT_COLUMNS.len());
+        assert!(RESULT_COLUMNS.iter().all(|column| !column.unit.is_empty() && !column.description.is_empty()));
+        // Colunas de tempo declaram a unidade do sufixo
+        for column in RESULT_COLUMNS.iter().filter(|column| column.name.contains("_ms_")) {
+            assert_eq!(column.unit, "ms", "{}", column.name);
+        }
+        assert_eq!(result_header().split(',').count(), RESULT_COLUMNS.len());
+    }
+
+    #[test]
+    fn test_cells_format_csv_fields_and_check_types() {
+        let cells = [Cell::from(3usize), Cell::Real(0.123456, 4), Cell::Real(f64::NAN, 2), Cell::from(true),
+                     Cell::from("AES-GCM"), Cell::from(None::<usize>)];
+        assert_eq!(csv_row(&cells), "3,0.1235,NaN,true,AES-GCM,");
+        assert!(Cell::Empty.fits(ColumnType::Integer));
+        assert!(Cell::Real(1.0, 1).fits(ColumnType::Real));
+        assert!(!Cell::from(1usize).fits(ColumnType::Real));
+        assert!(!Cell::from("x").fits(ColumnType::Boolean));
+    }
+
+    #[test]
+    fn test_python_readers_support_current_schema() {
+        // Os scripts recusam versões fora de SCHEMAS_SUPORTADOS: cada incremento de
+        // SCHEMA_VERSION precisa chegar aos dois conjuntos
+        let scripts = [
+            ("gerar_graficos.py", include_str!("../../analysis/gerar_graficos.py")),
+            ("testes_hipoteses.py", include_str!("../../analysis/testes_hipoteses.py")),
+        ];
+        for (name, source) in scripts {
+            let line = source.lines()
+                .find_map(|line| line.strip_prefix("SCHEMAS_SUPORTADOS = "))
+                .unwrap_or_else(|| panic!("{} sem SCHEMAS_SUPORTADOS", name));
+            let supported: Vec<u32> = line.trim_matches(|c| c == '{' || c == '}')
+                .split(',')
+                .map(|version| version.trim().parse().unwrap())
+                .collect();
+            assert_eq!(supported, (1..=SCHEMA_VERSION).collect::<Vec<_>>(), "{}", name);
+        }
+    }
+}
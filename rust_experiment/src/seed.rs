@@ -0,0 +1,193 @@
+//! Sementes determinísticas por configuração
+//!
+//! Cada célula da matriz recebe a sua própria semente, derivada da semente base
+//! (`--seed`) e da tupla (cenário, padrão, acordo, cifra). Como a derivação não
+//! depende da ordem de execução nem do número de workers, uma célula isolada pode
+//! ser reexecutada com a mesma semente base e reproduz os mesmos fluxos aleatórios.
+//!
+//! O fluxo criptográfico (chaves X25519 e nonces) pode, em vez disso, vir direto do
+//! sistema operacional (`EntropySource::Os`, `--os-rng`), abrindo mão da reprodução.
+
+use rand::SeedableRng;
+use rand::rngs::{OsRng, StdRng};
+use rand_core::{CryptoRng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// Fluxo aleatório independente dentro de uma sessão
+///
+/// Fluxos separados impedem que o consumo de um (ex: consultas ao padrão de
+/// tráfego, que dependem do relógio) desloque os valores sorteados pelos outros.
+#[derive(Debug, Clone, Copy)]
+pub enum SeedStream {
+    Messages, // Tipos, tamanhos e conteúdo das mensagens
+    Traffic,  // Decisões do padrão de tráfego
+    Crypto,   // Chaves X25519, nonces e injeção de falhas
+    Members,  // Saídas de membros da sala (--member-leave-rate)
+    Keys,     // Chaves de Bob compartilhadas pela configuração (--keygen-policy cached)
+    Split,    // Divisão das amostras em metades (--split-half)
+    Order,    // Ordem de execução das configurações (--shuffle-configs)
+}
+
+impl SeedStream {
+    fn label(&self) -> &'static [u8] {
+        match self {
+            SeedStream::Messages => b"mensagens",
+            SeedStream::Traffic => b"trafego",
+            SeedStream::Crypto => b"cripto",
+            SeedStream::Members => b"membros",
+            SeedStream::Keys => b"chaves",
+            SeedStream::Split => b"metades",
+            SeedStream::Order => b"ordem",
+        }
+    }
+}
+
+/// Origem da aleatoriedade criptográfica (chaves X25519 de Alice e Bob, nonces/IVs)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntropySource {
+    /// StdRng (ChaCha12) semeado pelo fluxo `SeedStream::Crypto`: reproduzível com `--seed`
+    #[default]
+    Seeded,
+    /// OsRng: cada byte vem do gerador do sistema operacional, via `getrandom`
+    Os,
+}
+
+impl EntropySource {
+    /// Descrição do gerador, como registrada nos metadados da execução
+    pub fn name(&self) -> &'static str {
+        match self {
+            EntropySource::Seeded => "StdRng (ChaCha12) semeado por config_seed",
+            EntropySource::Os => "OsRng (getrandom)",
+        }
+    }
+
+    /// Cria o gerador criptográfico de uma sessão; `seed` é ignorada com `Os`
+    pub fn session_rng(&self, seed: u64) -> SessionRng {
+        match self {
+            EntropySource::Seeded => SessionRng::Seeded(StdRng::seed_from_u64(seed)),
+            EntropySource::Os => SessionRng::Os(OsRng),
+        }
+    }
+}
+
+/// Gerador criptográfico de uma sessão, conforme a `EntropySource`
+///
+/// Tipo concreto (e não `Box<dyn CryptoRngCore>`): cada sorteio no trecho medido é
+/// um `match` de duas variantes, sem alocação nem chamada indireta.
+#[derive(Debug)]
+pub enum SessionRng {
+    Seeded(StdRng),
+    Os(OsRng),
+}
+
+impl RngCore for SessionRng {
+    fn next_u32(&mut self) -> u32 {
+        match self {
+            SessionRng::Seeded(rng) => rng.next_u32(),
+            SessionRng::Os(rng) => rng.next_u32(),
+        }
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        match self {
+            SessionRng::Seeded(rng) => rng.next_u64(),
+            SessionRng::Os(rng) => rng.next_u64(),
+        }
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        match self {
+            SessionRng::Seeded(rng) => rng.fill_bytes(dest),
+            SessionRng::Os(rng) => rng.fill_bytes(dest),
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        match self {
+            SessionRng::Seeded(rng) => rng.try_fill_bytes(dest),
+            SessionRng::Os(rng) => rng.try_fill_bytes(dest),
+        }
+    }
+}
+
+// Os dois geradores envolvidos são criptograficamente seguros
+impl CryptoRng for SessionRng {}
+
+/// Chamada de sistema por trás do `getrandom` nesta plataforma
+///
+/// É a fonte do `OsRng` e também do `randombytes` do PQClean, usado pelos pares e
+/// encapsulamentos Kyber em qualquer modo.
+pub fn os_entropy_backend() -> &'static str {
+    if cfg!(any(target_os = "linux", target_os = "android", target_os = "freebsd")) {
+        "getrandom(2)"
+    } else if cfg!(any(target_os = "macos", target_os = "ios", target_os = "openbsd")) {
+        "getentropy(2)"
+    } else if cfg!(target_os = "windows") {
+        "BCryptGenRandom / ProcessPrng"
+    } else {
+        "gerador padrão do sistema (/dev/urandom)"
+    }
+}
+
+/// Primeiros 8 bytes de um SHA-256, como u64 big-endian
+fn digest_u64(hasher: Sha256) -> u64 {
+    let digest = hasher.finalize();
+    u64::from_be_bytes(digest[..8].try_into().expect("SHA-256 tem ao menos 8 bytes"))
+}
+
+/// Semente de uma configuração: semente base XOR hash da tupla da configuração
+///
+/// O hash é um SHA-256 (estável entre versões do Rust, ao contrário do `DefaultHasher`)
+/// com os campos separados por um byte nulo.
+pub fn config_seed(base_seed: u64, scenario: &str, pattern: &str, agreement: &str, cipher: &str) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(b"MATRIX_CONFIG_SEED");
+    for field in [scenario, pattern, agreement, cipher] {
+        hasher.update([0u8]);
+        hasher.update(field.as_bytes());
+    }
+    base_seed ^ digest_u64(hasher)
+}
+
+/// Semente de um fluxo de uma sessão (repetição e remetente) de uma configuração
+pub fn stream_seed(config_seed: u64, repetition: usize, sender: usize, stream: SeedStream) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(config_seed.to_be_bytes());
+    hasher.update((repetition as u64).to_be_bytes());
+    hasher.update((sender as u64).to_be_bytes());
+    hasher.update(stream.label());
+    digest_u64(hasher)
+}
+
+#[cfg(test)]
+/// Testes da derivação de sementes
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_seed_is_per_cell_and_stable() {
+        let seed = config_seed(42, "SmallChat", "Constant", "Olm-Híbrido", "AES-GCM");
+        assert_eq!(seed, config_seed(42, "SmallChat", "Constant", "Olm-Híbrido", "AES-GCM"));
+        assert_ne!(seed, config_seed(42, "SmallChat", "Constant", "Olm-Híbrido", "ChaCha20"));
+        assert_ne!(seed, config_seed(43, "SmallChat", "Constant", "Olm-Híbrido", "AES-GCM"));
+        // A separação dos campos evita colisões por concatenação
+        assert_ne!(config_seed(0, "ab", "c", "d", "e"), config_seed(0, "a", "bc", "d", "e"));
+
+        let messages = stream_seed(seed, 0, 0, SeedStream::Messages);
+        assert_ne!(messages, stream_seed(seed, 0, 0, SeedStream::Traffic));
+        assert_ne!(messages, stream_seed(seed, 1, 0, SeedStream::Messages));
+        assert_ne!(messages, stream_seed(seed, 0, 1, SeedStream::Messages));
+    }
+
+    #[test]
+    fn test_entropy_source_reproducibility() {
+        let draw = |source: EntropySource| {
+            let mut bytes = [0u8; 32];
+            source.session_rng(7).fill_bytes(&mut bytes);
+            bytes
+        };
+        assert_eq!(draw(EntropySource::Seeded), draw(EntropySource::Seeded));
+        // O gerador do sistema ignora a semente
+        assert_ne!(draw(EntropySource::Os), draw(EntropySource::Os));
+    }
+}
@@ -0,0 +1,214 @@
+//! Utilitários estatísticos reutilizáveis sobre séries de valores
+//!
+//! Funções puras, sem estado, aplicadas às saídas em série do experimento
+//! (ex: `--bandwidth-series`) e aos diagnósticos (ex: `inter-arrival`) antes da
+//! gravação, para que os gráficos fiquem legíveis sem pós-processamento.
+//!
+//! `DurationSamples` guarda tempos medidos em nanossegundos inteiros e só os converte
+//! para f64, na unidade pedida (`TimeUnit`), no momento da análise ou da gravação.
+
+use crate::error::ExperimentError;
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+/// Unidade de conversão das amostras de tempo para f64
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeUnit {
+    Millis,
+}
+
+impl TimeUnit {
+    /// Converte nanossegundos inteiros para esta unidade
+    pub fn convert(self, nanos: u64) -> f64 {
+        match self {
+            TimeUnit::Millis => nanos as f64 / 1e6,
+        }
+    }
+}
+
+/// Amostras de tempo em nanossegundos inteiros (uma por repetição, por exemplo)
+///
+/// Somas e agrupamentos são exatos; a conversão para f64 acontece apenas em `to_unit`.
+/// Durações acima de `u64::MAX` ns (mais de 584 anos) são saturadas.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DurationSamples {
+    nanos: Vec<u64>,
+}
+
+impl DurationSamples {
+    /// Coleção vazia com espaço para `capacity` amostras
+    pub fn with_capacity(capacity: usize) -> Self {
+        DurationSamples { nanos: Vec::with_capacity(capacity) }
+    }
+
+    /// Coleção a partir de nanossegundos já medidos
+    pub fn from_nanos(nanos: Vec<u64>) -> Self {
+        DurationSamples { nanos }
+    }
+
+    /// Acrescenta uma amostra
+    pub fn push(&mut self, duration: Duration) {
+        self.nanos.push(u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX));
+    }
+
+    pub fn len(&self) -> usize {
+        self.nanos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nanos.is_empty()
+    }
+
+    /// Amostras em nanossegundos inteiros, exatamente como medidas
+    pub fn nanos(&self) -> &[u64] {
+        &self.nanos
+    }
+
+    /// Amostras convertidas para f64 na unidade pedida, entrada das funções estatísticas
+    pub fn to_unit(&self, unit: TimeUnit) -> Vec<f64> {
+        self.nanos.iter().map(|&nanos| unit.convert(nanos)).collect()
+    }
+}
+
+/// Média móvel simples à direita (janela de `window` pontos terminando em cada índice)
+///
+/// A saída tem o mesmo tamanho da entrada: os primeiros `window - 1` pontos usam a
+/// média dos pontos disponíveis até ali, sem preenchimento artificial. Uma janela de
+/// 1 devolve a própria série.
+pub fn moving_average(data: &[f64], window: NonZeroUsize) -> Vec<f64> {
+    let window = window.get();
+    let mut sum = 0.0;
+    data.iter()
+        .enumerate()
+        .map(|(i, &value)| {
+            sum += value;
+            if i >= window {
+                sum -= data[i - window];
+            }
+            sum / (i + 1).min(window) as f64
+        })
+        .collect()
+}
+
+/// Média ponderada: soma de `value * weight` sobre a soma dos pesos
+///
+/// Pares com valor ou peso não finito, ou peso negativo, são ignorados; sem peso
+/// positivo restante (inclusive com dados vazios), o resultado é NaN. Com pesos
+/// iguais, coincide com a média aritmética. Os pares são formados pela posição, e
+/// o excedente da fatia mais longa é ignorado.
+pub fn weighted_mean(values: &[f64], weights: &[f64]) -> f64 {
+    let (sum, total_weight) = values.iter()
+        .zip(weights)
+        .filter(|(value, weight)| value.is_finite() && weight.is_finite() && **weight >= 0.0)
+        .fold((0.0, 0.0), |(sum, total), (value, weight)| (sum + value * weight, total + weight));
+    if total_weight > 0.0 { sum / total_weight } else { f64::NAN }
+}
+
+/// Classe de um histograma: intervalo `[start, end)` (a última inclui `end`) e contagem
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HistogramBin {
+    pub start: f64,
+    pub end: f64,
+    pub count: usize,
+}
+
+/// Histograma de `bins` classes de largura igual entre o mínimo e o máximo dos dados
+///
+/// Dados vazios resultam em nenhuma classe; dados todos iguais, em uma única classe
+/// degenerada com todas as observações. Valores não finitos são ignorados. Pedir
+/// 0 classes é `ExperimentError::Stats`.
+pub fn histogram(data: &[f64], bins: usize) -> Result<Vec<HistogramBin>, ExperimentError> {
+    if bins == 0 {
+        return Err(ExperimentError::Stats("histograma com 0 classes".to_string()));
+    }
+    let finite: Vec<f64> = data.iter().copied().filter(|value| value.is_finite()).collect();
+    let Some(min) = finite.iter().copied().reduce(f64::min) else { return Ok(Vec::new()) };
+    let max = finite.iter().copied().fold(min, f64::max);
+    if max == min || bins == 1 {
+        return Ok(vec![HistogramBin { start: min, end: max, count: finite.len() }]);
+    }
+    let width = (max - min) / bins as f64;
+    let mut result: Vec<HistogramBin> = (0..bins)
+        .map(|i| HistogramBin { start: min + i as f64 * width, end: min + (i + 1) as f64 * width, count: 0 })
+        .collect();
+    result[bins - 1].end = max;
+    for value in finite {
+        let index = (((value - min) / width) as usize).min(bins - 1);
+        result[index].count += 1;
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+/// Testes da média móvel contra o cálculo direto de cada janela
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_moving_average_matches_direct_windows() {
+        let data: Vec<f64> = (0..50).map(|i| (i as f64 * 0.9).sin() * 10.0 + i as f64).collect();
+        for window in [1, 3, 7, 50, 80] {
+            let smoothed = moving_average(&data, NonZeroUsize::new(window).unwrap());
+            assert_eq!(smoothed.len(), data.len());
+            for (i, value) in smoothed.iter().enumerate() {
+                let start = (i + 1).saturating_sub(window);
+                let direct = data[start..=i].iter().sum::<f64>() / (i + 1 - start) as f64;
+                assert!((value - direct).abs() < 1e-9, "janela {} índice {}", window, i);
+            }
+        }
+
+        assert_eq!(moving_average(&[1.0, 3.0, 5.0], NonZeroUsize::MIN), [1.0, 3.0, 5.0]);
+        assert_eq!(moving_average(&[2.0, 4.0, 6.0, 8.0], NonZeroUsize::new(2).unwrap()), [2.0, 3.0, 5.0, 7.0]);
+        assert!(moving_average(&[], NonZeroUsize::new(4).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_duration_samples_keep_exact_nanoseconds() {
+        // As amostras guardam os nanossegundos medidos, e somas sobre elas são exatas
+        let mut samples = DurationSamples::with_capacity(10_000);
+        for _ in 0..10_000 {
+            samples.push(Duration::from_nanos(100_001));
+        }
+        assert_eq!(samples.len(), 10_000);
+        assert!(samples.nanos().iter().all(|&nanos| nanos == 100_001));
+        assert_eq!(samples.nanos().iter().sum::<u64>(), 1_000_010_000);
+
+        // A conversão acontece só em to_unit, uma divisão por amostra
+        let samples = DurationSamples::from_nanos(vec![1_500_000, 250, 3]);
+        assert_eq!(samples.to_unit(TimeUnit::Millis), [1.5, 0.00025, 0.000003]);
+
+        let mut saturated = DurationSamples::default();
+        saturated.push(Duration::MAX);
+        assert_eq!(saturated.nanos(), [u64::MAX]);
+    }
+
+    #[test]
+    fn test_weighted_mean_lets_heavy_values_dominate() {
+        // Uma mensagem de 1 MB a 900 µs e nove de 1 kB a 10 µs: média simples 99 µs
+        let times = [900.0, 10.0, 10.0, 10.0, 10.0, 10.0, 10.0, 10.0, 10.0, 10.0];
+        let mut sizes = [1_000.0; 10];
+        sizes[0] = 1_000_000.0;
+        let weighted = weighted_mean(&times, &sizes);
+        assert!((weighted - (900.0 * 1e6 + 9.0 * 10.0 * 1e3) / 1_009_000.0).abs() < 1e-9);
+        assert!(weighted > 890.0);
+
+        assert_eq!(weighted_mean(&[2.0, 4.0], &[3.0, 3.0]), 3.0);
+        assert_eq!(weighted_mean(&[2.0, f64::NAN, 4.0], &[1.0, 5.0, -1.0]), 2.0);
+        assert!(weighted_mean(&[], &[]).is_nan());
+        assert!(weighted_mean(&[1.0, 2.0], &[0.0, 0.0]).is_nan());
+    }
+
+    #[test]
+    fn test_histogram_counts_every_value_once() {
+        let data = [0.0, 0.5, 1.0, 2.5, 9.99, 10.0, f64::NAN];
+        let bins = histogram(&data, 4).unwrap();
+        assert_eq!(bins.len(), 4);
+        assert_eq!(bins.iter().map(|bin| bin.count).collect::<Vec<_>>(), [3, 1, 0, 2]);
+        assert_eq!((bins[0].start, bins[3].end), (0.0, 10.0));
+        assert!(bins.windows(2).all(|pair| pair[0].end == pair[1].start));
+
+        assert!(histogram(&[], 5).unwrap().is_empty());
+        assert_eq!(histogram(&[2.0, 2.0], 5).unwrap(), [HistogramBin { start: 2.0, end: 2.0, count: 2 }]);
+        assert!(matches!(histogram(&data, 0), Err(ExperimentError::Stats(_))));
+    }
+}
@@ -0,0 +1,127 @@
+//! Exportação de métricas ao vivo para painéis (`--metrics-endpoint`)
+//!
+//! Em execuções longas num servidor, o progresso e as métricas da última configuração
+//! são publicados pela fachada do crate `metrics` e enviados periodicamente, no formato
+//! de texto do Prometheus, pelo `metrics-exporter-prometheus` a um Pushgateway
+//! (`http://host:9091/metrics/job/pq_crypto_matrix`) e, a partir dele, ao Grafana.
+//! Cada envio é um `POST` que substitui os valores anteriores das mesmas métricas.
+//!
+//! Sem a opção, nenhum recorder é instalado: as chamadas da fachada não fazem nada e o
+//! custo é nulo.
+
+use metrics::{counter, describe_counter, describe_gauge, gauge};
+use metrics_exporter_prometheus::PrometheusBuilder;
+use std::io;
+use std::time::Duration;
+
+use crate::error::ExperimentError;
+
+/// Intervalo entre envios ao Pushgateway, feitos numa thread do exportador
+pub const PUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Instantâneo das métricas publicadas ao fim de cada configuração
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricsSnapshot {
+    pub configs_completed: usize,  // Configurações concluídas até agora
+    pub configs_total: usize,      // Configurações da execução
+    pub elapsed_s: f64,            // Tempo de parede desde o início da execução
+    pub throughput_msgs_s: f64,    // Throughput agregado da última configuração
+    pub kem_ms_mean: f64,          // Tempo médio de KEM da última configuração
+}
+
+impl MetricsSnapshot {
+    /// Publica o instantâneo no recorder global; o próximo envio leva os novos valores
+    pub fn record(&self) {
+        counter!("pqc_configs_completed").absolute(self.configs_completed as u64);
+        gauge!("pqc_configs_total").set(self.configs_total as f64);
+        gauge!("pqc_elapsed_seconds").set(self.elapsed_s);
+        gauge!("pqc_throughput_msgs_per_second").set(self.throughput_msgs_s);
+        gauge!("pqc_kem_ms_mean").set(self.kem_ms_mean);
+    }
+}
+
+/// Destino das métricas: um Pushgateway acessível por HTTP ou HTTPS
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricsExporter {
+    url: String,
+}
+
+impl MetricsExporter {
+    /// Valida uma URL `http://` ou `https://` com host (`ExperimentError::Config` se inválida)
+    pub fn new(url: &str) -> Result<Self, ExperimentError> {
+        let rest = url
+            .strip_prefix("http://")
+            .or_else(|| url.strip_prefix("https://"))
+            .ok_or_else(|| ExperimentError::Config(format!(
+                "URL de métricas deve começar com http:// ou https:// (recebido '{}')", url
+            )))?;
+        if rest.split('/').next().unwrap_or_default().is_empty() {
+            return Err(ExperimentError::Config(format!("URL de métricas sem host: '{}'", url)));
+        }
+        Ok(MetricsExporter { url: url.to_string() })
+    }
+
+    /// URL de destino, para registro nos metadados
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Instala o recorder global do Prometheus com envio periódico ao Pushgateway
+    ///
+    /// Os envios rodam em segundo plano; falhas de rede não interrompem a execução.
+    pub fn install(&self) -> Result<(), ExperimentError> {
+        PrometheusBuilder::new()
+            .with_push_gateway(&self.url, PUSH_INTERVAL, None, None, true)
+            .and_then(PrometheusBuilder::install)
+            .map_err(|e| ExperimentError::Io(io::Error::other(format!("exportador de métricas não instalado: {}", e))))?;
+        describe_counter!("pqc_configs_completed", "Configuracoes concluidas");
+        describe_gauge!("pqc_configs_total", "Configuracoes da execucao");
+        describe_gauge!("pqc_elapsed_seconds", "Tempo de parede desde o inicio");
+        describe_gauge!("pqc_throughput_msgs_per_second", "Throughput agregado da ultima configuracao");
+        describe_gauge!("pqc_kem_ms_mean", "Tempo medio de KEM da ultima configuracao");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_metrics_exporter_pushes_prometheus_text() {
+        assert!(MetricsExporter::new("ftp://example.com").is_err());
+        assert!(MetricsExporter::new("http:///metrics").is_err());
+        assert!(MetricsExporter::new("https://example.com/metrics/job/pq").is_ok());
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/metrics/job/pq", listener.local_addr().unwrap());
+        let exporter = MetricsExporter::new(&url).unwrap();
+        exporter.install().unwrap();
+        MetricsSnapshot {
+            configs_completed: 3,
+            configs_total: 30,
+            elapsed_s: 12.0,
+            throughput_msgs_s: 250.0,
+            kem_ms_mean: 1.5,
+        }
+        .record();
+
+        // O primeiro envio com os valores publicados chega em até PUSH_INTERVAL
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 4096];
+        while !String::from_utf8_lossy(&request).contains("pqc_kem_ms_mean 1.5\n") {
+            let read = stream.read(&mut buffer).unwrap();
+            assert!(read > 0, "conexão encerrada antes do corpo completo");
+            request.extend_from_slice(&buffer[..read]);
+        }
+        stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").unwrap();
+
+        let request = String::from_utf8(request).unwrap();
+        assert!(request.starts_with("POST /metrics/job/pq HTTP/1.1\r\n"));
+        assert!(request.contains("# TYPE pqc_configs_completed counter\npqc_configs_completed 3\n"));
+        assert!(request.contains("pqc_throughput_msgs_per_second 250\n"));
+    }
+}
@@ -0,0 +1,118 @@
+//! Monitoramento da frequência da CPU (detecção de throttling térmico)
+//!
+//! Execuções longas em notebooks sofrem throttling térmico: a frequência cai aos
+//! poucos e os tempos medidos derivam para cima, contaminando a distribuição com
+//! falsos "outliers". O monitor amostra a frequência média dos núcleos nas
+//! fronteiras entre configurações e, ao final, procura uma queda sustentada.
+
+use sysinfo::{CpuExt, CpuRefreshKind, RefreshKind, System, SystemExt};
+
+/// Número mínimo de amostras para avaliar a tendência (duas por terço)
+pub const MIN_SAMPLES: usize = 6;
+
+/// Queda relativa padrão que caracteriza throttling (10% da frequência inicial)
+pub const DEFAULT_THRESHOLD: f64 = 0.10;
+
+/// Amostrador da frequência da CPU via `sysinfo`
+pub struct FrequencyMonitor {
+    system: System,
+    samples: Vec<f64>,
+}
+
+impl FrequencyMonitor {
+    /// Cria o monitor, carregando apenas as informações de frequência da CPU
+    pub fn new() -> Self {
+        let refresh = RefreshKind::new().with_cpu(CpuRefreshKind::new().with_frequency());
+        FrequencyMonitor { system: System::new_with_specifics(refresh), samples: Vec::new() }
+    }
+
+    /// Registra a frequência média atual dos núcleos, em MHz
+    ///
+    /// Retorna `None` (sem registrar) quando o sistema não expõe a frequência,
+    /// como em algumas máquinas virtuais.
+    pub fn sample(&mut self) -> Option<f64> {
+        self.system.refresh_cpu_specifics(CpuRefreshKind::new().with_frequency());
+        let cpus = self.system.cpus();
+        let total: u64 = cpus.iter().map(|cpu| cpu.frequency()).sum();
+        if cpus.is_empty() || total == 0 {
+            return None;
+        }
+        let mean_mhz = total as f64 / cpus.len() as f64;
+        self.samples.push(mean_mhz);
+        Some(mean_mhz)
+    }
+
+    /// Amostras registradas até agora, em ordem cronológica
+    pub fn samples(&self) -> &[f64] {
+        &self.samples
+    }
+}
+
+impl Default for FrequencyMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Resultado da análise de tendência da frequência
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThrottleCheck {
+    pub samples: usize,      // Amostras analisadas
+    pub initial_mhz: f64,    // Média do primeiro terço das amostras
+    pub final_mhz: f64,      // Média do último terço das amostras
+    pub drop_fraction: f64,  // Queda relativa: (inicial - final) / inicial
+    pub suspected: bool,     // Queda sustentada acima do limiar
+}
+
+/// Procura uma queda sustentada de frequência nas amostras
+///
+/// Divide as amostras em terços e compara as médias: há suspeita de throttling
+/// quando a média cai de um terço para o seguinte (tendência monotônica) e a
+/// queda total supera `threshold`. Uma queda isolada no meio da execução, seguida
+/// de recuperação, não conta. Retorna `None` com menos de `MIN_SAMPLES` amostras.
+pub fn detect_throttle(samples: &[f64], threshold: f64) -> Option<ThrottleCheck> {
+    if samples.len() < MIN_SAMPLES {
+        return None;
+    }
+    let third = samples.len() / 3;
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+    let initial_mhz = mean(&samples[..third]);
+    let middle_mhz = mean(&samples[third..samples.len() - third]);
+    let final_mhz = mean(&samples[samples.len() - third..]);
+    let drop_fraction = (initial_mhz - final_mhz) / initial_mhz;
+
+    Some(ThrottleCheck {
+        samples: samples.len(),
+        initial_mhz,
+        final_mhz,
+        drop_fraction,
+        suspected: initial_mhz >= middle_mhz && middle_mhz >= final_mhz && drop_fraction > threshold,
+    })
+}
+
+#[cfg(test)]
+/// Testes da detecção: queda sustentada, queda transitória e frequência estável
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_throttle() {
+        // Queda gradual de 3000 para 2400 MHz (20%)
+        let throttled: Vec<f64> = (0..12).map(|i| 3000.0 - 50.0 * i as f64).collect();
+        let check = detect_throttle(&throttled, DEFAULT_THRESHOLD).unwrap();
+        assert!(check.suspected);
+        assert!(check.drop_fraction > 0.1 && check.final_mhz < check.initial_mhz);
+        // A mesma queda abaixo de um limiar maior não é sinalizada
+        assert!(!detect_throttle(&throttled, 0.5).unwrap().suspected);
+
+        // Queda transitória no meio, com recuperação: não é throttling sustentado
+        let dip = [3000.0, 3000.0, 2000.0, 2000.0, 3000.0, 3000.0];
+        assert!(!detect_throttle(&dip, DEFAULT_THRESHOLD).unwrap().suspected);
+
+        let stable = [2100.0; 9];
+        let check = detect_throttle(&stable, DEFAULT_THRESHOLD).unwrap();
+        assert!(!check.suspected && check.drop_fraction == 0.0);
+
+        assert_eq!(detect_throttle(&stable[..MIN_SAMPLES - 1], DEFAULT_THRESHOLD), None);
+    }
+}
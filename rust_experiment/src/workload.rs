@@ -50,14 +50,18 @@
 //! ===================================
 //! - Distribuição de tipos de mensagem baseada em observações empíricas
 //! - Padrões de tráfego temporal (constante, rajada, periódico, realista)
-//! - Cenários de uso diferenciados (chat pequeno, grupo médio, canal grande, sistema)
+//! - Cenários de uso diferenciados (chat pequeno, grupo médio, canal grande, sistema, conversa 1:1)
 //! - Rotação de chaves baseada em cenários de uso real do Matrix/Element
 //! - Tamanhos de mensagem realistas para texto, imagem, arquivo e voz
 //! - Modo burst vs. regular inspirado em Rammos et al. (2021) para análise energética
 //! - Frequências de mensagem baseadas em tratamentos empíricos (10-50 msg/min)
 //! - Pausas estratégicas em rajadas para evitar throttling (a cada 50 mensagens)
 
-use rand::Rng;
+use crate::corpus::PayloadCorpus;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 /// Tipos de mensagens que podem ser simuladas no experimento
@@ -66,13 +70,54 @@ use std::time::{Duration, Instant};
 /// - File: mensagem contendo bytes de arquivo
 /// - System: mensagem de sistema (notificações, logs)
 /// - Voice: mensagem de voz (simulada como bytes)
+///
+/// As mídias são `Arc<[u8]>` para que um arquivo do corpus seja compartilhado pelas
+/// mensagens que o usam, em vez de copiado a cada uma.
 #[derive(Debug, Clone, PartialEq)]
 pub enum MessageType {
     Text(String),
-    Image(Vec<u8>),
-    File(Vec<u8>),
+    Image(Arc<[u8]>),
+    File(Arc<[u8]>),
     System(String),
-    Voice(Vec<u8>),
+    Voice(Arc<[u8]>),
+}
+
+/// Rótulos dos tipos de mensagem, na ordem de `MessageKind`
+pub const MESSAGE_KINDS: [&str; 5] = ["texto", "imagem", "arquivo", "sistema", "voz"];
+
+/// Tipo de uma mensagem, sem o conteúdo; a ordem das variantes é a de `MESSAGE_KINDS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageKind {
+    Text,
+    Image,
+    File,
+    System,
+    Voice,
+}
+
+impl MessageKind {
+    /// Índice do tipo em `MESSAGE_KINDS` e nos vetores de agregação por tipo
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+impl MessageType {
+    /// Tipo da mensagem
+    pub fn kind(&self) -> MessageKind {
+        match self {
+            MessageType::Text(_) => MessageKind::Text,
+            MessageType::Image(_) => MessageKind::Image,
+            MessageType::File(_) => MessageKind::File,
+            MessageType::System(_) => MessageKind::System,
+            MessageType::Voice(_) => MessageKind::Voice,
+        }
+    }
+
+    /// Índice do tipo da mensagem em `MESSAGE_KINDS`, para agregações por tipo
+    pub fn kind_index(&self) -> usize {
+        self.kind().index()
+    }
 }
 
 /// Padrões de tráfego para simular diferentes comportamentos de envio de mensagens
@@ -90,17 +135,54 @@ pub enum TrafficPattern {
     Realistic,     // Combinação de padrões reais
 }
 
+impl TrafficPattern {
+    /// Todos os padrões de tráfego, na ordem canônica da matriz
+    pub const ALL: [TrafficPattern; 5] = [
+        TrafficPattern::Constant,
+        TrafficPattern::Burst,
+        TrafficPattern::Periodic,
+        TrafficPattern::Random,
+        TrafficPattern::Realistic,
+    ];
+}
+
 /// Cenários de uso para simular diferentes tipos de salas ou canais
 /// - SmallChat: sala pequena (P2P ou grupo pequeno)
 /// - MediumGroup: grupo médio
 /// - LargeChannel: canal grande
 /// - SystemChannel: canal de sistema
+/// - DirectMessage: conversa 1:1 cifrada apenas com Olm (sem sessão de grupo)
 #[derive(Debug, Clone, PartialEq)]
 pub enum UsageScenario {
     SmallChat,     // Sala pequena (5-10 usuários)
     MediumGroup,   // Grupo médio (20-50 usuários)
     LargeChannel,  // Canal grande (100+ usuários)
     SystemChannel, // Canal de sistema (1-5 usuários)
+    DirectMessage, // Conversa direta entre dois usuários (Olm puro)
+}
+
+impl UsageScenario {
+    /// Todos os cenários, na ordem canônica da matriz
+    ///
+    /// DirectMessage vem por último para preservar os índices das configurações dos
+    /// cenários de grupo (arquivos de replay e resultados anteriores).
+    pub const ALL: [UsageScenario; 5] = [
+        UsageScenario::SmallChat,
+        UsageScenario::MediumGroup,
+        UsageScenario::LargeChannel,
+        UsageScenario::SystemChannel,
+        UsageScenario::DirectMessage,
+    ];
+
+    /// Se cada mensagem do cenário é cifrada com uma chave própria da catraca
+    ///
+    /// Na conversa 1:1, o Olm segue o Double Ratchet: toda mensagem avança a catraca
+    /// simétrica e recebe uma chave derivada (como `--per-message-kdf`), e cada rotação
+    /// modela um passo da catraca DH, quando a vez de falar passa ao outro participante.
+    /// Os cenários de grupo compartilham uma sessão Megolm entre as rotações.
+    pub fn ratchets_per_message(&self) -> bool {
+        matches!(self, UsageScenario::DirectMessage)
+    }
 }
 
 /// Estrutura de configuração para um workload específico
@@ -112,10 +194,151 @@ pub struct WorkloadConfig {
     pub rotation_interval: usize,   // Intervalo de rotação de chaves
 }
 
+/// Distribuição dos tipos de mensagem de um cenário
+///
+/// Pares (tipo, probabilidade), na ordem em que o sorteio de `MessageGenerator`
+/// percorre os tipos. É a única fonte das proporções: o sorteio, o modo de tamanho
+/// fixo, o tamanho médio esperado (`get_expected_message_bytes`) e os testes partem
+/// dela. Baseada em estudos empíricos de aplicações como WhatsApp e WeChat
+/// (Seufert et al., 2015, 2023; Deng et al., 2017).
+pub fn message_mix(scenario: &UsageScenario) -> &'static [(MessageKind, f64)] {
+    match scenario {
+        // Baseado em padrões de chat P2P/pequenos grupos observados empiricamente
+        // Seufert et al. (2015): grupos pequenos têm alta proporção de texto
+        // Predominância de texto (~85%), com mídia ocasional (~12%) e voz (~3%)
+        UsageScenario::SmallChat => &[
+            (MessageKind::Text, 0.85),   // 85% texto (conversas casuais)
+            (MessageKind::Image, 0.12),  // 12% imagem (compartilhamento casual)
+            (MessageKind::Voice, 0.03),  // 3% voz (mensagens rápidas)
+        ],
+        // Grupos médios têm mais compartilhamento de mídia e coordenação
+        // Baseado em análise de grupos WhatsApp (Seufert et al., 2023)
+        // Padrão observado: texto (~70%), mídia (~25%), arquivos (~5%)
+        UsageScenario::MediumGroup => &[
+            (MessageKind::Text, 0.70),   // 70% texto (discussões, coordenação)
+            (MessageKind::Image, 0.18),  // 18% imagem (compartilhamento ativo)
+            (MessageKind::File, 0.07),   // 7% arquivo (documentos, links)
+            (MessageKind::Voice, 0.05),  // 5% voz (mensagens longas)
+        ],
+        // Canais grandes têm mais conteúdo estruturado e anúncios
+        // Dataset de 76M mensagens (Seufert et al., 2023): grupos grandes = mais mídia
+        // Padrão: texto (~60%), mídia (~30%), sistema (~10%)
+        UsageScenario::LargeChannel => &[
+            (MessageKind::Text, 0.60),   // 60% texto (discussões, anúncios)
+            (MessageKind::Image, 0.22),  // 22% imagem (conteúdo visual)
+            (MessageKind::File, 0.08),   // 8% arquivo (documentos, mídia)
+            (MessageKind::System, 0.10), // 10% sistema (moderação, bots)
+        ],
+        // Canais de sistema têm padrão diferente: mais automação e logs
+        // Padrão: sistema (~50%), texto (~25%), arquivos (~25%)
+        UsageScenario::SystemChannel => &[
+            (MessageKind::Text, 0.25),   // 25% texto (comandos, feedback)
+            (MessageKind::System, 0.50), // 50% sistema (logs, notificações)
+            (MessageKind::File, 0.15),   // 15% arquivo (logs, backups)
+            (MessageKind::Image, 0.10),  // 10% imagem (capturas, relatórios)
+        ],
+        // Conversas 1:1 são ainda mais dominadas por texto que os grupos pequenos
+        // (Seufert et al., 2015): respostas curtas, fotos ocasionais e áudios
+        // Padrão: texto (~90%), imagem (~6%), voz (~4%)
+        UsageScenario::DirectMessage => &[
+            (MessageKind::Text, 0.90),   // 90% texto (diálogo)
+            (MessageKind::Image, 0.06),  // 6% imagem (fotos pessoais)
+            (MessageKind::Voice, 0.04),  // 4% voz (áudios curtos)
+        ],
+    }
+}
+
+/// Comprimentos-alvo dos textos gerados, em caracteres, com suas probabilidades
+///
+/// Seufert et al. (2023): análise de 76M mensagens mostra predominância de textos curtos.
+/// Maioria das mensagens são curtas (10-50 chars), algumas médias (50-200), poucas longas (200+)
+const TEXT_LENGTHS: [(f64, usize); 5] = [
+    (0.45, 15),   // 45% mensagens muito curtas (emojis, "ok", "sim")
+    (0.35, 50),   // 35% mensagens curtas (respostas simples)
+    (0.15, 150),  // 15% mensagens médias (explicações)
+    (0.04, 300),  // 4% mensagens longas (descrições detalhadas)
+    (0.01, 500),  // 1% mensagens muito longas (textos complexos)
+];
+
+/// Tamanhos das imagens geradas, em bytes, com suas probabilidades
+///
+/// Considera a compressão automática feita pelos apps (WhatsApp, Telegram, etc.).
+/// Baseado em dataset de 76M mensagens do WhatsApp (Seufert et al., 2023)
+const IMAGE_SIZES: [(f64, usize); 5] = [
+    (0.40, 15_000),   // 40% imagens pequenas (thumbnails, emojis customizados)
+    (0.35, 50_000),   // 35% imagens médias (fotos comprimidas)
+    (0.20, 150_000),  // 20% imagens grandes (fotos alta qualidade)
+    (0.04, 500_000),  // 4% imagens muito grandes (screenshots, documentos)
+    (0.01, 1_000_000), // 1% imagens enormes (fotos originais)
+];
+
+/// Tamanhos dos arquivos gerados, em bytes, com suas probabilidades
+const FILE_SIZES: [(f64, usize); 5] = [
+    (0.30, 10_000),    // 30% arquivos pequenos (documentos de texto, JSON)
+    (0.25, 100_000),   // 25% arquivos médios (PDFs, planilhas)
+    (0.20, 500_000),   // 20% arquivos grandes (apresentações, código)
+    (0.15, 2_000_000), // 15% arquivos muito grandes (vídeos curtos, zip)
+    (0.10, 10_000_000), // 10% arquivos enormes (vídeos, backups)
+];
+
+/// Durações das mensagens de voz, em segundos, com suas probabilidades
+const VOICE_DURATIONS: [(f64, usize); 5] = [
+    (0.50, 3),   // 50% mensagens muito curtas (3s)
+    (0.30, 8),   // 30% mensagens curtas (8s)
+    (0.15, 15),  // 15% mensagens médias (15s)
+    (0.04, 30),  // 4% mensagens longas (30s)
+    (0.01, 60),  // 1% mensagens muito longas (60s)
+];
+
+/// Taxa das mensagens de voz: ~6KB/s para codec comprimido (Opus, AAC)
+const VOICE_BYTES_PER_SECOND: usize = 6_000;
+
+/// Mensagens de sistema (notificações, logs) baseadas em padrões reais
+const SYSTEM_MESSAGES: [&str; 24] = [
+    // Notificações de usuário (padrão Matrix/Element)
+    "User joined the room",
+    "User left the room",
+    "User changed their display name",
+    "User changed their avatar",
+    "User was invited to the room",
+    "User was kicked from the room",
+    // Eventos de sala
+    "Room topic changed",
+    "Room name changed",
+    "Room settings updated",
+    "Room was made public",
+    "Room was made private",
+    "Message was deleted",
+    "Message was edited",
+    // Eventos técnicos/segurança
+    "End-to-end encryption enabled",
+    "Device verification completed",
+    "Backup key verification required",
+    "New device detected",
+    "Keys rotated for security",
+    // Eventos de sistema
+    "Server maintenance scheduled",
+    "Sync completed",
+    "Connection restored",
+    "Rate limit exceeded",
+    "Upload completed",
+    "Download completed",
+];
+
+/// Valor esperado de uma distribuição discreta de pares (probabilidade, valor)
+fn distribution_mean(distribution: &[(f64, usize)]) -> f64 {
+    distribution.iter().map(|&(probability, value)| probability * value as f64).sum()
+}
+
 /// Gerador de mensagens realistas, parametrizado por cenário
 pub struct MessageGenerator {
     scenario: UsageScenario,        // Cenário de uso atual
-    rng: rand::rngs::ThreadRng,     // Gerador de números aleatórios
+    rng: StdRng,                    // Gerador de números aleatórios
+    fixed_size: Option<usize>,      // Tamanho fixo dos payloads (modo de varredura por tamanho)
+    corpus: Option<Arc<PayloadCorpus>>, // Payloads reais lidos do disco (--corpus)
+    corpus_cursors: BTreeMap<u32, usize>, // Próximo arquivo de cada classe de tamanho do corpus
+    chat_cursor: Option<usize>,     // Próxima linha do registro de conversa do corpus
+    dominant_probability: Option<f64>, // Probabilidade do byte 0x00 nas mídias (--payload-entropy)
 }
 
 // Implementa o gerador de mensagens baseado no cenário de uso
@@ -124,72 +347,109 @@ impl MessageGenerator {
     pub fn new(scenario: UsageScenario) -> Self {
         Self {
             scenario,
-            rng: rand::thread_rng(),
+            rng: StdRng::from_entropy(),
+            fixed_size: None,
+            corpus: None,
+            corpus_cursors: BTreeMap::new(),
+            chat_cursor: None,
+            dominant_probability: None,
         }
     }
 
+    /// Semeia o gerador, tornando a sequência de mensagens reproduzível
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Fixa o tamanho de todos os payloads gerados em `size` bytes
+    ///
+    /// Nesse modo o gerador emite apenas mensagens de texto e imagem: o texto é
+    /// mantido como texto e os demais tipos do cenário viram imagens, sempre com
+    /// exatamente `size` bytes. Isola o custo por byte das cifras do overhead fixo
+    /// por mensagem, permitindo curvas de throughput versus tamanho.
+    pub fn with_fixed_size(mut self, size: Option<usize>) -> Self {
+        self.fixed_size = size;
+        self
+    }
+
+    /// Tira o conteúdo das mensagens de um corpus de arquivos reais
+    ///
+    /// A escolha do tipo e do tamanho continua seguindo as distribuições do cenário;
+    /// cada classe de tamanho é percorrida em ciclo a partir de uma posição sorteada,
+    /// para que sessões diferentes não comecem todas pelo mesmo arquivo.
+    pub fn with_corpus(mut self, corpus: Option<Arc<PayloadCorpus>>) -> Self {
+        self.corpus = corpus;
+        self
+    }
+
+    /// Limita a entropia das mídias aleatórias a `bits` bits por byte (0 a 8)
+    ///
+    /// Sem limite (`None`), as mídias são bytes uniformes: 8 bits por byte, incompressíveis.
+    /// Com limite, cada byte vem de uma fonte sem memória em que o byte 0x00 tem
+    /// probabilidade `p` e os outros 255 valores dividem `1 - p` igualmente, com `p`
+    /// escolhido para que a entropia de Shannon da fonte seja exatamente `bits`.
+    /// Textos, mensagens de sistema e arquivos do corpus não são afetados.
+    pub fn with_entropy(mut self, bits: Option<f64>) -> Self {
+        self.dominant_probability = bits.map(dominant_probability);
+        self
+    }
+
     /// Gera uma mensagem realista baseada no cenário de uso
     /// A distribuição dos tipos de mensagem depende do cenário, baseada em estudos empíricos
     /// de aplicações como WhatsApp e WeChat (Seufert et al., 2015, 2023; Deng et al., 2017)
     pub fn generate_message(&mut self) -> MessageType {
-        match self.scenario {
-            UsageScenario::SmallChat => {
-                // Baseado em padrões de chat P2P/pequenos grupos observados empiricamente
-                // Seufert et al. (2015): grupos pequenos têm alta proporção de texto
-                // Predominância de texto (~85%), com mídia ocasional (~12%) e voz (~3%)
-                let rand_val: f64 = self.rng.gen_range(0.0..1.0);
-                if rand_val < 0.85 {         // 85% texto (conversas casuais)
-                    MessageType::Text(self.generate_text_message())
-                } else if rand_val < 0.97 {  // 12% imagem (compartilhamento casual)
-                    MessageType::Image(self.generate_image_message())
-                } else {                     // 3% voz (mensagens rápidas)
-                    MessageType::Voice(self.generate_voice_message())
-                }
-            }
-            UsageScenario::MediumGroup => {
-                // Grupos médios têm mais compartilhamento de mídia e coordenação
-                // Baseado em análise de grupos WhatsApp (Seufert et al., 2023)
-                // Padrão observado: texto (~70%), mídia (~25%), arquivos (~5%)
-                let rand_val: f64 = self.rng.gen_range(0.0..1.0);
-                if rand_val < 0.70 {             // 70% texto (discussões, coordenação)   
-                    MessageType::Text(self.generate_text_message())
-                } else if rand_val < 0.88 {      // 18% imagem (compartilhamento ativo)
-                    MessageType::Image(self.generate_image_message())
-                } else if rand_val < 0.95 {      // 7% arquivo (documentos, links)
-                    MessageType::File(self.generate_file_message())
-                } else {                         // 5% voz (mensagens longas)
-                    MessageType::Voice(self.generate_voice_message())
-                }
-            }
-            UsageScenario::LargeChannel => {
-                // Canais grandes têm mais conteúdo estruturado e anúncios
-                // Dataset de 76M mensagens (Seufert et al., 2023): grupos grandes = mais mídia
-                // Padrão: texto (~60%), mídia (~30%), sistema (~10%)
-                let rand_val: f64 = self.rng.gen_range(0.0..1.0);
-                if rand_val < 0.60 {         // 60% texto (discussões, anúncios)
-                    MessageType::Text(self.generate_text_message())
-                } else if rand_val < 0.82 {  // 22% imagem (conteúdo visual)
-                    MessageType::Image(self.generate_image_message())
-                } else if rand_val < 0.90 {  // 8% arquivo (documentos, mídia)
-                    MessageType::File(self.generate_file_message())
-                } else {                     // 10% sistema (moderação, bots)
-                    MessageType::System(self.generate_system_message())
-                }
+        if let Some(size) = self.fixed_size {
+            return self.generate_fixed_size_message(size);
+        }
+
+        match self.draw_kind() {
+            MessageKind::Text => MessageType::Text(self.generate_text_message()),
+            MessageKind::Image => MessageType::Image(self.generate_image_message()),
+            MessageKind::File => MessageType::File(self.generate_file_message()),
+            MessageKind::System => MessageType::System(self.generate_system_message()),
+            MessageKind::Voice => MessageType::Voice(self.generate_voice_message()),
+        }
+    }
+
+    /// Sorteia o tipo da próxima mensagem do cenário
+    ///
+    /// Consome um único valor do gerador, antes do payload, e permite verificar a
+    /// distribuição dos tipos sem gerar as mídias. Os tipos são percorridos na ordem
+    /// de `message_mix`, acumulando as probabilidades.
+    fn draw_kind(&mut self) -> MessageKind {
+        let rand_val: f64 = self.rng.gen_range(0.0..1.0);
+        let mix = message_mix(&self.scenario);
+        let mut cumulative = 0.0;
+        for &(kind, probability) in mix {
+            cumulative += probability;
+            if rand_val < cumulative {
+                return kind;
             }
-            UsageScenario::SystemChannel => {
-                // Canais de sistema têm padrão diferente: mais automação e logs
-                // Padrão: sistema (~50%), texto (~25%), arquivos (~25%)
-                let rand_val: f64 = self.rng.gen_range(0.0..1.0);
-                if rand_val < 0.25 {         // 25% texto (comandos, feedback)
-                    MessageType::Text(self.generate_text_message())
-                } else if rand_val < 0.75 {  // 50% sistema (logs, notificações)
-                    MessageType::System(self.generate_system_message())
-                } else if rand_val < 0.90 {  // 15% arquivo (logs, backups)
-                    MessageType::File(self.generate_file_message())
-                } else {                     // 10% imagem (capturas, relatórios)
-                    MessageType::Image(self.generate_image_message())
-                }
+        }
+        mix[mix.len() - 1].0
+    }
+
+    /// Gera uma mensagem de texto ou imagem com exatamente `size` bytes
+    ///
+    /// A proporção de texto segue o cenário; os demais tipos são substituídos por imagens.
+    fn generate_fixed_size_message(&mut self, size: usize) -> MessageType {
+        let text_share: f64 = message_mix(&self.scenario).iter()
+            .filter(|(kind, _)| *kind == MessageKind::Text)
+            .map(|(_, probability)| probability)
+            .sum();
+
+        if self.rng.gen_range(0.0..1.0) < text_share {
+            // Repete o texto gerado até atingir o tamanho; ASCII, então o corte é seguro
+            let mut text = String::with_capacity(size + 16);
+            while text.len() < size {
+                if !text.is_empty() { text.push(' '); }
+                text.push_str(&self.generate_text_message());
             }
+            text.truncate(size);
+            MessageType::Text(text)
+        } else {
+            MessageType::Image(self.payload(size).into())
         }
     }
 
@@ -197,22 +457,16 @@ impl MessageGenerator {
     /// Tamanhos baseados em análise empírica de Zhang et al. (2015), Seufert et al. (2023)
     /// e observações de tráfego real de aplicações de mensagens instantâneas
     fn generate_text_message(&mut self) -> String {
+        if let Some(line) = self.next_chat_line() {
+            return line;
+        }
+
         // Distribuição realista de tamanhos de mensagem de texto observada em estudos
-        // Seufert et al. (2023): análise de 76M mensagens mostra predominância de textos curtos
-        // Maioria das mensagens são curtas (10-50 chars), algumas médias (50-200), poucas longas (200+)
-        let length_distribution = [
-            (0.45, 15),   // 45% mensagens muito curtas (emojis, "ok", "sim")
-            (0.35, 50),   // 35% mensagens curtas (respostas simples)
-            (0.15, 150),  // 15% mensagens médias (explicações)
-            (0.04, 300),  // 4% mensagens longas (descrições detalhadas)
-            (0.01, 500),  // 1% mensagens muito longas (textos complexos)
-        ];
-        
         let rand_val: f64 = self.rng.gen_range(0.0..1.0);
         let mut cumulative = 0.0;
         let mut target_length = 50; // default
         
-        for (probability, length) in length_distribution.iter() {
+        for (probability, length) in TEXT_LENGTHS.iter() {
             cumulative += probability;
             if rand_val < cumulative {
                 target_length = *length;
@@ -242,23 +496,13 @@ impl MessageGenerator {
 
     /// Gera bytes simulando uma imagem (tamanho realista baseado em estudos empíricos)
     /// Distribuição baseada em análise de tráfego de IM apps (Zhang et al., 2015; Seufert et al., 2023)
-    fn generate_image_message(&mut self) -> Vec<u8> {
+    fn generate_image_message(&mut self) -> Arc<[u8]> {
         // Distribuição realista de tamanhos de imagem em apps de mensagens
-        // Considera compressão automática feita pelos apps (WhatsApp, Telegram, etc.)
-        // Baseado em dataset de 76M mensagens do WhatsApp (Seufert et al., 2023)
-        let size_distribution = [
-            (0.40, 15_000),   // 40% imagens pequenas (thumbnails, emojis customizados)
-            (0.35, 50_000),   // 35% imagens médias (fotos comprimidas)
-            (0.20, 150_000),  // 20% imagens grandes (fotos alta qualidade)
-            (0.04, 500_000),  // 4% imagens muito grandes (screenshots, documentos)
-            (0.01, 1_000_000), // 1% imagens enormes (fotos originais)
-        ];
-        
         let rand_val: f64 = self.rng.gen_range(0.0..1.0);
         let mut cumulative = 0.0;
         let mut target_size = 50_000; // default
         
-        for (probability, size) in size_distribution.iter() {
+        for (probability, size) in IMAGE_SIZES.iter() {
             cumulative += probability;
             if rand_val < cumulative {
                 target_size = *size;
@@ -266,25 +510,17 @@ impl MessageGenerator {
             }
         }
         
-        (0..target_size).map(|_| self.rng.gen_range(0..256) as u8).collect()
+        self.media_payload(target_size)
     }
 
     /// Gera bytes simulando um arquivo (tamanho realista baseado em padrões observados)
-    fn generate_file_message(&mut self) -> Vec<u8> {
+    fn generate_file_message(&mut self) -> Arc<[u8]> {
         // Distribuição de arquivos típicos em aplicações de mensagens
-        let size_distribution = [
-            (0.30, 10_000),    // 30% arquivos pequenos (documentos de texto, JSON)
-            (0.25, 100_000),   // 25% arquivos médios (PDFs, planilhas)
-            (0.20, 500_000),   // 20% arquivos grandes (apresentações, código)
-            (0.15, 2_000_000), // 15% arquivos muito grandes (vídeos curtos, zip)
-            (0.10, 10_000_000), // 10% arquivos enormes (vídeos, backups)
-        ];
-        
         let rand_val: f64 = self.rng.gen_range(0.0..1.0);
         let mut cumulative = 0.0;
         let mut target_size = 100_000; // default
         
-        for (probability, size) in size_distribution.iter() {
+        for (probability, size) in FILE_SIZES.iter() {
             cumulative += probability;
             if rand_val < cumulative {
                 target_size = *size;
@@ -292,60 +528,22 @@ impl MessageGenerator {
             }
         }
         
-        (0..target_size).map(|_| self.rng.gen_range(0..256) as u8).collect()
+        self.media_payload(target_size)
     }
 
     /// Gera mensagem de sistema (notificações, logs) baseada em padrões reais
     fn generate_system_message(&mut self) -> String {
-        let messages = [
-            // Notificações de usuário (padrão Matrix/Element)
-            "User joined the room",
-            "User left the room",
-            "User changed their display name",
-            "User changed their avatar",
-            "User was invited to the room",
-            "User was kicked from the room",
-            // Eventos de sala
-            "Room topic changed",
-            "Room name changed", 
-            "Room settings updated",
-            "Room was made public",
-            "Room was made private",
-            "Message was deleted",
-            "Message was edited",
-            // Eventos técnicos/segurança
-            "End-to-end encryption enabled",
-            "Device verification completed",
-            "Backup key verification required",
-            "New device detected",
-            "Keys rotated for security",
-            // Eventos de sistema
-            "Server maintenance scheduled",
-            "Sync completed",
-            "Connection restored",
-            "Rate limit exceeded",
-            "Upload completed",
-            "Download completed"
-        ];
-        messages[self.rng.gen_range(0..messages.len())].to_string()
+        SYSTEM_MESSAGES[self.rng.gen_range(0..SYSTEM_MESSAGES.len())].to_string()
     }
 
     /// Gera bytes simulando uma mensagem de voz (baseado em padrões de áudio comprimido)
-    fn generate_voice_message(&mut self) -> Vec<u8> {
+    fn generate_voice_message(&mut self) -> Arc<[u8]> {
         // Mensagens de voz típicas: 3-30 segundos, ~4-8 KB por segundo (codec comprimido)
-        let duration_distribution = [
-            (0.50, 3),   // 50% mensagens muito curtas (3s)
-            (0.30, 8),   // 30% mensagens curtas (8s)
-            (0.15, 15),  // 15% mensagens médias (15s)
-            (0.04, 30),  // 4% mensagens longas (30s)
-            (0.01, 60),  // 1% mensagens muito longas (60s)
-        ];
-        
         let rand_val: f64 = self.rng.gen_range(0.0..1.0);
         let mut cumulative = 0.0;
         let mut duration_seconds = 8; // default
         
-        for (probability, duration) in duration_distribution.iter() {
+        for (probability, duration) in VOICE_DURATIONS.iter() {
             cumulative += probability;
             if rand_val < cumulative {
                 duration_seconds = *duration;
@@ -353,9 +551,66 @@ impl MessageGenerator {
             }
         }
         
-        let bytes_per_second = 6_000; // ~6KB/s para codec comprimido (Opus, AAC)
-        let size = duration_seconds * bytes_per_second;
-        (0..size).map(|_| self.rng.gen_range(0..256) as u8).collect()
+        let size = duration_seconds * VOICE_BYTES_PER_SECOND;
+        self.media_payload(size)
+    }
+
+    /// Conteúdo de uma mídia de tamanho aproximado `target_size`
+    ///
+    /// Com corpus, é um arquivo real da classe de tamanho mais próxima, com o seu
+    /// tamanho original e compartilhado sem cópia; sem corpus, `target_size` bytes aleatórios.
+    fn media_payload(&mut self, target_size: usize) -> Arc<[u8]> {
+        match self.next_corpus_file(target_size) {
+            Some(file) => file,
+            None => self.random_payload(target_size).into(),
+        }
+    }
+
+    /// Payload com exatamente `size` bytes
+    ///
+    /// Com corpus, o arquivo da classe mais próxima é repetido ou truncado até `size`;
+    /// sem corpus, são bytes aleatórios. Usado pelo modo de tamanho fixo e pelo replay.
+    pub fn payload(&mut self, size: usize) -> Vec<u8> {
+        match self.next_corpus_file(size) {
+            Some(file) => file.iter().copied().cycle().take(size).collect(),
+            None => self.random_payload(size),
+        }
+    }
+
+    /// Próximo arquivo do corpus na classe de tamanho mais próxima de `size`
+    fn next_corpus_file(&mut self, size: usize) -> Option<Arc<[u8]>> {
+        let corpus = self.corpus.clone()?;
+        let class = corpus.nearest_class(size)?;
+        let files = corpus.class_files(class);
+        let cursor = *self.corpus_cursors.entry(class).or_insert_with(|| self.rng.gen_range(0..files.len()));
+        self.corpus_cursors.insert(class, (cursor + 1) % files.len());
+        Some(Arc::clone(&files[cursor]))
+    }
+
+    /// Próxima linha do registro de conversa do corpus, se houver
+    fn next_chat_line(&mut self) -> Option<String> {
+        let corpus = self.corpus.as_ref()?;
+        let lines = corpus.chat_lines();
+        if lines.is_empty() {
+            return None;
+        }
+        let cursor = *self.chat_cursor.get_or_insert_with(|| self.rng.gen_range(0..lines.len()));
+        self.chat_cursor = Some((cursor + 1) % lines.len());
+        Some(lines[cursor].clone())
+    }
+
+    /// Gera `size` bytes aleatórios (conteúdo de mídia)
+    ///
+    /// Usado pelos geradores de imagem, arquivo e voz e pelos microbenchmarks,
+    /// que precisam de payloads de tamanho fixo. Por padrão os bytes são uniformes e
+    /// incompressíveis; com `with_entropy`, seguem a fonte de entropia limitada.
+    pub fn random_payload(&mut self, size: usize) -> Vec<u8> {
+        match self.dominant_probability {
+            None => (0..size).map(|_| self.rng.gen_range(0..256) as u8).collect(),
+            Some(p) => (0..size)
+                .map(|_| if self.rng.gen_bool(p) { 0 } else { self.rng.gen_range(1..256) as u8 })
+                .collect(),
+        }
     }
 
     /// Retorna o tamanho da mensagem em bytes
@@ -373,18 +628,41 @@ impl MessageGenerator {
     pub fn get_message_bytes(&self, message: &MessageType) -> Vec<u8> {
         match message {
             MessageType::Text(text) => text.as_bytes().to_vec(),
-            MessageType::Image(data) => data.clone(),
-            MessageType::File(data) => data.clone(),
-            MessageType::Voice(data) => data.clone(),
+            MessageType::Image(data) => data.to_vec(),
+            MessageType::File(data) => data.to_vec(),
+            MessageType::Voice(data) => data.to_vec(),
             MessageType::System(text) => text.as_bytes().to_vec(),
         }
     }
 }
 
+/// Entropia de Shannon, em bits por byte, da fonte com byte 0x00 de probabilidade `p`
+/// e os outros 255 valores equiprováveis
+pub fn byte_source_entropy(p: f64) -> f64 {
+    let term = |q: f64| if q > 0.0 { -q * q.log2() } else { 0.0 };
+    term(p) + 255.0 * term((1.0 - p) / 255.0)
+}
+
+/// Probabilidade do byte 0x00 para que a fonte tenha `bits` bits de entropia por byte
+///
+/// A entropia decresce de 8 bits (`p = 1/256`, bytes uniformes) a 0 (`p = 1`, só zeros);
+/// a inversa é obtida por bisseção nesse intervalo.
+fn dominant_probability(bits: f64) -> f64 {
+    let (mut low, mut high) = (1.0 / 256.0, 1.0);
+    for _ in 0..64 {
+        let mid = (low + high) / 2.0;
+        if byte_source_entropy(mid) > bits { low = mid } else { high = mid }
+    }
+    (low + high) / 2.0
+}
+
+/// Intervalo entre mensagens do padrão Constant, também a base da rotação por tempo
+const CONSTANT_SEND_INTERVAL: Duration = Duration::from_millis(100);
+
 /// Gerador de padrões de tráfego para simular diferentes ritmos de envio de mensagens
 pub struct TrafficGenerator {
     pattern: TrafficPattern,
-    rng: rand::rngs::ThreadRng,
+    rng: StdRng,
     last_send: Instant,
     burst_count: usize,
     periodic_phase: f64,
@@ -402,19 +680,28 @@ impl TrafficGenerator {
     pub fn new(pattern: TrafficPattern) -> Self {
         Self {
             pattern,
-            rng: rand::thread_rng(),
+            rng: StdRng::from_entropy(),
             last_send: Instant::now(),
             burst_count: 0,
             periodic_phase: 0.0,
         }
     }
 
+    /// Semeia os sorteios do padrão de tráfego
+    ///
+    /// Os padrões Constant, Burst, Periodic e Realistic também dependem do relógio,
+    /// então as decisões de envio só são reproduzidas exatamente com `--replay`.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
     /// Decide se deve enviar uma mensagem no instante atual, conforme o padrão
     pub fn should_send_message(&mut self, current_time: Instant) -> bool {
         match self.pattern {
             TrafficPattern::Constant => {
-                // Envia mensagem a cada 100ms
-                current_time.duration_since(self.last_send) >= Duration::from_millis(100)
+                // Envia mensagem a cada CONSTANT_SEND_INTERVAL (100ms)
+                current_time.duration_since(self.last_send) >= CONSTANT_SEND_INTERVAL
             }
             TrafficPattern::Burst => {
                 // Implementação inspirada em Rammos et al. (2021): modo burst com pausas estratégicas
@@ -477,13 +764,43 @@ impl TrafficGenerator {
     }
 }
 
+/// Tempo simulado sem envio após o qual `simulate_inter_arrivals` desiste do padrão
+const MAX_SIMULATED_IDLE: Duration = Duration::from_secs(3600);
+
+/// Intervalos entre envios consecutivos de um padrão de tráfego, sob relógio simulado
+///
+/// Consulta o gerador a cada `poll_interval` de tempo simulado, sem dormir, até obter
+/// `sends` envios; o primeiro intervalo é medido a partir do início da simulação. Serve
+/// para verificar empiricamente a forma temporal de cada padrão (subcomando
+/// `inter-arrival`). Um padrão que passe `MAX_SIMULATED_IDLE` sem enviar encerra a
+/// simulação com os intervalos já obtidos.
+pub fn simulate_inter_arrivals(pattern: TrafficPattern, seed: u64, sends: usize, poll_interval: Duration) -> Vec<Duration> {
+    let mut generator = TrafficGenerator::new(pattern).with_seed(seed);
+    let start = Instant::now();
+    let mut now = Duration::ZERO;
+    let mut last_send = Duration::ZERO;
+    let mut gaps = Vec::with_capacity(sends);
+    while gaps.len() < sends && now - last_send < MAX_SIMULATED_IDLE {
+        now += poll_interval;
+        if generator.should_send_message(start + now) {
+            gaps.push(now - last_send);
+            last_send = now;
+        }
+    }
+    gaps
+}
+
 /// Retorna o intervalo de rotação de chave recomendado para cada cenário
+///
+/// No DirectMessage, o intervalo é o tamanho médio de uma vez de falar: cada rotação
+/// é um passo da catraca DH do Olm, e não o descarte de uma sessão de grupo.
 pub fn get_rotation_config(scenario: &UsageScenario) -> usize {
     match scenario {
         UsageScenario::SmallChat => 100,    // Rotação menos frequente
         UsageScenario::MediumGroup => 50,   // Rotação moderada
         UsageScenario::LargeChannel => 25,  // Rotação mais frequente
         UsageScenario::SystemChannel => 10, // Rotação muito frequente
+        UsageScenario::DirectMessage => 5,  // Passo DH a cada troca de turno
     }
 }
 
@@ -494,9 +811,131 @@ pub fn get_message_count_config(scenario: &UsageScenario) -> usize {
         UsageScenario::MediumGroup => 250,  // Mensagens moderadas
         UsageScenario::LargeChannel => 500, // Muitas mensagens
         UsageScenario::SystemChannel => 1000, // Muitas mensagens de sistema
+        UsageScenario::DirectMessage => 200,  // Diálogo entre duas pessoas
+    }
+}
+
+/// Retorna o número de membros da sala em cada cenário
+///
+/// Define o tamanho do re-key completo quando um membro sai (`--member-leave-rate`):
+/// a nova sessão Megolm é distribuída a todos os membros restantes. Com `--group-fanout`,
+/// também define o fan-out de cada rotação periódica.
+pub fn get_group_size(scenario: &UsageScenario) -> usize {
+    match scenario {
+        UsageScenario::SmallChat => 5,       // Conversa entre poucos amigos
+        UsageScenario::MediumGroup => 25,    // Grupo de trabalho ou de família
+        UsageScenario::LargeChannel => 200,  // Comunidade ou canal público
+        UsageScenario::SystemChannel => 3,   // Poucos operadores e bots
+        UsageScenario::DirectMessage => 2,   // Apenas os dois participantes
+    }
+}
+
+/// Idade máxima da sessão na rotação híbrida: 7 dias, o padrão do Megolm
+const MAX_SESSION_AGE: Duration = Duration::from_secs(7 * 86400);
+
+/// Tamanho médio de uma mensagem de cada tipo, em bytes, na ordem de `MESSAGE_KINDS`
+///
+/// Valor esperado das tabelas de tamanho do `MessageGenerator` (imagens ~84 KB e
+/// arquivos ~1.4 MB em média); o texto usa o comprimento-alvo do sorteio.
+fn expected_kind_bytes() -> [f64; MESSAGE_KINDS.len()] {
+    let system = SYSTEM_MESSAGES.iter().map(|message| message.len()).sum::<usize>() as f64
+        / SYSTEM_MESSAGES.len() as f64;
+    [
+        distribution_mean(&TEXT_LENGTHS),
+        distribution_mean(&IMAGE_SIZES),
+        distribution_mean(&FILE_SIZES),
+        system,
+        distribution_mean(&VOICE_DURATIONS) * VOICE_BYTES_PER_SECOND as f64,
+    ]
+}
+
+/// Retorna o tamanho médio esperado (em bytes) de uma mensagem em cada cenário
+///
+/// Combina a distribuição de tipos do cenário (`message_mix`) com o tamanho médio
+/// de cada tipo, das mesmas tabelas que o `MessageGenerator` sorteia; define a
+/// rotação por volume.
+pub fn get_expected_message_bytes(scenario: &UsageScenario) -> usize {
+    let kind_bytes = expected_kind_bytes();
+    message_mix(scenario).iter()
+        .map(|&(kind, probability)| probability * kind_bytes[kind.index()])
+        .sum::<f64>()
+        .round() as usize
+}
+
+/// Estratégia de rotação da sessão de grupo (Megolm)
+///
+/// A rotação por troca de participantes é modelada à parte, pelo re-key completo
+/// disparado por `--member-leave-rate`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RotationStrategy {
+    /// Rotaciona a cada N mensagens
+    ByCount(usize),
+    /// Rotaciona quando a sessão atinge a idade indicada
+    ByTime(Duration),
+    /// Rotaciona após N bytes cifrados na sessão
+    ByBytes(usize),
+    /// A cada N mensagens ou quando a sessão atinge a idade máxima (comportamento original)
+    Hybrid { messages: usize, max_age: Duration },
+}
+
+impl RotationStrategy {
+    /// Decide se a sessão atual deve ser rotacionada antes da próxima mensagem
+    ///
+    /// Parâmetros:
+    /// - messages: mensagens cifradas desde a última rotação
+    /// - age: tempo desde a última rotação
+    /// - bytes: bytes cifrados desde a última rotação
+    pub fn should_rotate(&self, messages: usize, age: Duration, bytes: usize) -> bool {
+        match *self {
+            RotationStrategy::ByCount(limit) => messages >= limit,
+            RotationStrategy::ByTime(period) => age >= period,
+            RotationStrategy::ByBytes(limit) => bytes >= limit,
+            RotationStrategy::Hybrid { messages: limit, max_age } => messages >= limit || age >= max_age,
+        }
+    }
+
+    /// Nome da estratégia com seus parâmetros, como registrado no CSV
+    pub fn name(&self) -> String {
+        match self {
+            RotationStrategy::ByCount(limit) => format!("count:{}", limit),
+            RotationStrategy::ByTime(period) => format!("time:{}ms", period.as_millis()),
+            RotationStrategy::ByBytes(limit) => format!("bytes:{}", limit),
+            RotationStrategy::Hybrid { messages, max_age } => {
+                format!("hybrid:{}msgs|{}s", messages, max_age.as_secs())
+            }
+        }
     }
 }
 
+/// Tipo de estratégia de rotação, selecionável na linha de comando
+///
+/// Os parâmetros vêm do cenário, de modo que as estratégias sejam comparáveis:
+/// para um intervalo de N mensagens, a rotação por tempo usa N × 100 ms (o ritmo
+/// do padrão Constant) e a por volume, N × o tamanho médio esperado das mensagens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum RotationPolicy {
+    /// A cada N mensagens
+    Count,
+    /// Pela idade da sessão
+    Time,
+    /// Pelo volume cifrado na sessão
+    Bytes,
+    /// A cada N mensagens ou a cada 7 dias (comportamento original)
+    #[default]
+    Hybrid,
+}
+
+impl RotationPolicy {
+    /// Estratégia concreta para um cenário com intervalo de `messages` mensagens
+    pub fn strategy(&self, scenario: &UsageScenario, messages: usize) -> RotationStrategy {
+        match self {
+            RotationPolicy::Count => RotationStrategy::ByCount(messages),
+            RotationPolicy::Time => RotationStrategy::ByTime(CONSTANT_SEND_INTERVAL * messages as u32),
+            RotationPolicy::Bytes => RotationStrategy::ByBytes(messages * get_expected_message_bytes(scenario)),
+            RotationPolicy::Hybrid => RotationStrategy::Hybrid { messages, max_age: MAX_SESSION_AGE },
+        }
+    }
+}
 
 #[cfg(test)]
 /// Testes unitários para o módulo de workload realista
@@ -509,15 +948,149 @@ mod tests {
     fn test_message_generator() {
         let mut generator = MessageGenerator::new(UsageScenario::SmallChat);
         let message = generator.generate_message();
-        assert!(matches!(message, MessageType::Text(_) | MessageType::Image(_) | MessageType::File(_)));
+        // SmallChat gera texto, imagem e voz (3%)
+        assert!(matches!(message, MessageType::Text(_) | MessageType::Image(_) | MessageType::Voice(_)));
+    }
+
+    #[test]
+    fn test_message_type_distribution_matches_documented_probabilities() {
+        // Probabilidades de message_mix, na ordem de MESSAGE_KINDS; tipos fora do
+        // cenário têm probabilidade zero
+        const DRAWS: usize = 20_000;
+
+        for scenario in UsageScenario::ALL {
+            let mut expected = [0.0; MESSAGE_KINDS.len()];
+            for &(kind, probability) in message_mix(&scenario) {
+                expected[kind.index()] += probability;
+            }
+            assert!((expected.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            let mut generator = MessageGenerator::new(scenario.clone()).with_seed(42);
+            let mut observed = [0usize; MESSAGE_KINDS.len()];
+            for _ in 0..DRAWS {
+                observed[generator.draw_kind().index()] += 1;
+            }
+
+            // Tipos com probabilidade zero nunca podem aparecer; os demais entram no
+            // qui-quadrado de aderência
+            let mut chi_square = 0.0;
+            let mut categories = 0;
+            for (kind, (&count, &probability)) in observed.iter().zip(&expected).enumerate() {
+                if probability == 0.0 {
+                    assert_eq!(count, 0, "{:?} gerou {} mensagens de {}", scenario, count, MESSAGE_KINDS[kind]);
+                    continue;
+                }
+                let expected_count = probability * DRAWS as f64;
+                chi_square += (count as f64 - expected_count).powi(2) / expected_count;
+                categories += 1;
+            }
+            // Valores críticos do qui-quadrado a 0,1% para 2 e 3 graus de liberdade
+            let critical = match categories - 1 {
+                2 => 13.816,
+                3 => 16.266,
+                df => panic!("graus de liberdade inesperados: {}", df),
+            };
+            assert!(chi_square < critical,
+                    "{:?}: qui-quadrado {:.2} >= {} (observado {:?})", scenario, chi_square, critical, observed);
+        }
+    }
+
+    #[test]
+    fn test_fixed_size_message_generator() {
+        for size in [0, 1, 64, 1024, 16 * 1024] {
+            let mut generator = MessageGenerator::new(UsageScenario::MediumGroup).with_fixed_size(Some(size));
+            for _ in 0..20 {
+                let message = generator.generate_message();
+                assert!(matches!(message, MessageType::Text(_) | MessageType::Image(_)));
+                assert_eq!(generator.get_message_size(&message), size);
+            }
+        }
+    }
+
+    #[test]
+    fn test_generator_draws_content_from_corpus() {
+        let dir = std::env::temp_dir().join(format!("pq_workload_corpus_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a.bin"), vec![1u8; 1000]).unwrap();
+        std::fs::write(dir.join("b.bin"), vec![2u8; 100_000]).unwrap();
+        std::fs::write(dir.join(crate::corpus::CHAT_LOG), "oi\ntudo bem?\n").unwrap();
+        let corpus = Arc::new(PayloadCorpus::load(&dir).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let mut generator = MessageGenerator::new(UsageScenario::MediumGroup)
+            .with_seed(3)
+            .with_corpus(Some(Arc::clone(&corpus)));
+        let (mut texts, mut media) = (0, 0);
+        for _ in 0..300 {
+            match generator.generate_message() {
+                MessageType::Text(text) => {
+                    assert!(corpus.chat_lines().contains(&text), "texto fora do corpus: {}", text);
+                    texts += 1;
+                }
+                MessageType::Image(data) | MessageType::File(data) | MessageType::Voice(data) => {
+                    // A mídia é o próprio arquivo do corpus, compartilhado e não copiado
+                    let class = corpus.nearest_class(data.len()).unwrap();
+                    assert!(corpus.class_files(class).iter().any(|file| Arc::ptr_eq(file, &data)));
+                    media += 1;
+                }
+                MessageType::System(_) => {}
+            }
+        }
+        assert!(texts > 0 && media > 0, "{} textos, {} mídias", texts, media);
+    }
+
+    #[test]
+    fn test_payload_entropy_matches_target() {
+        // Entropia empírica de 1 MB de mídia para alguns alvos
+        let empirical = |bytes: &[u8]| {
+            let mut counts = [0usize; 256];
+            bytes.iter().for_each(|&byte| counts[byte as usize] += 1);
+            counts.iter().filter(|&&count| count > 0).map(|&count| {
+                let q = count as f64 / bytes.len() as f64;
+                -q * q.log2()
+            }).sum::<f64>()
+        };
+        for bits in [0.0, 1.0, 4.0, 7.5, 8.0] {
+            let mut generator = MessageGenerator::new(UsageScenario::SmallChat).with_seed(7).with_entropy(Some(bits));
+            assert!((byte_source_entropy(dominant_probability(bits)) - bits).abs() < 1e-9);
+            let payload = generator.random_payload(1 << 20);
+            assert!((empirical(&payload) - bits).abs() < 0.01, "alvo {} bits", bits);
+        }
+
+        // Sem limite, a sequência é a mesma de antes (semente reproduzível)
+        let mut default = MessageGenerator::new(UsageScenario::SmallChat).with_seed(7);
+        let mut unlimited = MessageGenerator::new(UsageScenario::SmallChat).with_seed(7).with_entropy(None);
+        assert_eq!(default.random_payload(64), unlimited.random_payload(64));
     }
 
     #[test]
     fn test_traffic_generator() {
         let mut generator = TrafficGenerator::new(TrafficPattern::Constant);
-        let should_send = generator.should_send_message(Instant::now());
-        // Deve retornar true ou false, não deve panick
-        assert!(should_send || !should_send);
+        // Padrão constante: nada é enviado antes de 100ms desde a criação
+        assert!(!generator.should_send_message(Instant::now()));
+        // ... e a mensagem é liberada assim que o intervalo é atingido
+        assert!(generator.should_send_message(Instant::now() + Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_simulated_inter_arrivals_follow_pattern() {
+        let poll = Duration::from_millis(1);
+        // Random: 30% por consulta, intervalos geométricos de média 1 / 0.3 consultas
+        let gaps = simulate_inter_arrivals(TrafficPattern::Random, 7, 5000, poll);
+        assert_eq!(gaps.len(), 5000);
+        assert!(gaps.iter().all(|gap| *gap >= poll && gap.as_nanos() % poll.as_nanos() == 0));
+        let mean_polls = gaps.iter().map(|gap| gap.as_secs_f64() * 1000.0).sum::<f64>() / 5000.0;
+        assert!((mean_polls - 1.0 / 0.3).abs() < 0.15, "{}", mean_polls);
+
+        // Burst: rajadas de envios em consultas consecutivas separadas por pausas de ~1 s
+        let gaps = simulate_inter_arrivals(TrafficPattern::Burst, 7, 200, poll);
+        let pauses = gaps.iter().filter(|gap| **gap >= Duration::from_millis(900)).count();
+        let in_burst = gaps.iter().filter(|gap| **gap < Duration::from_millis(100)).count();
+        assert_eq!(pauses + in_burst, gaps.len());
+        assert!(pauses > 0 && in_burst > 3 * pauses, "{} pausas, {} em rajada", pauses, in_burst);
+
+        // Mesma semente, mesmos intervalos
+        assert_eq!(simulate_inter_arrivals(TrafficPattern::Realistic, 3, 100, poll),
+                   simulate_inter_arrivals(TrafficPattern::Realistic, 3, 100, poll));
     }
 
     #[test]
@@ -525,4 +1098,61 @@ mod tests {
         assert_eq!(get_rotation_config(&UsageScenario::SmallChat), 100);
         assert_eq!(get_rotation_config(&UsageScenario::LargeChannel), 25);
     }
+
+    #[test]
+    fn test_rotation_strategies() {
+        let hour = Duration::from_secs(3600);
+        let scenario = UsageScenario::SmallChat;
+
+        // Híbrida: o comportamento original (N mensagens ou 7 dias)
+        let hybrid = RotationPolicy::Hybrid.strategy(&scenario, 100);
+        assert!(!hybrid.should_rotate(99, hour, usize::MAX));
+        assert!(hybrid.should_rotate(100, Duration::ZERO, 0));
+        assert!(hybrid.should_rotate(1, MAX_SESSION_AGE, 0));
+
+        let time = RotationPolicy::Time.strategy(&scenario, 100);
+        assert_eq!(time, RotationStrategy::ByTime(Duration::from_secs(10)));
+        assert!(!time.should_rotate(1000, Duration::from_secs(9), 0));
+
+        let bytes = RotationPolicy::Bytes.strategy(&scenario, 100);
+        let limit = 100 * get_expected_message_bytes(&scenario);
+        assert!(bytes.should_rotate(0, Duration::ZERO, limit));
+        assert!(!bytes.should_rotate(1000, hour, limit - 1));
+
+        assert_eq!(RotationPolicy::Count.strategy(&scenario, 25).name(), "count:25");
+        assert_eq!(hybrid.name(), "hybrid:100msgs|604800s");
+    }
+
+    #[test]
+    fn test_expected_message_bytes_follow_generator_tables() {
+        for scenario in UsageScenario::ALL {
+            let total: f64 = message_mix(&scenario).iter().map(|(_, probability)| probability).sum();
+            assert!((total - 1.0).abs() < 1e-9, "{:?}", scenario);
+        }
+        // SmallChat: 85% textos de ~64 caracteres, 12% imagens de 83,5 KB e 3% áudios de 47,7 KB
+        assert_eq!(get_expected_message_bytes(&UsageScenario::SmallChat), 11_505);
+        assert!(get_expected_message_bytes(&UsageScenario::SystemChannel)
+            > get_expected_message_bytes(&UsageScenario::MediumGroup));
+        // DirectMessage: menos mídia que o SmallChat, sem tamanho fixado à parte
+        assert!(get_expected_message_bytes(&UsageScenario::DirectMessage)
+            < get_expected_message_bytes(&UsageScenario::SmallChat));
+    }
+
+    #[test]
+    fn test_group_size_grows_with_scenario() {
+        assert_eq!(get_group_size(&UsageScenario::SmallChat), 5);
+        assert!(get_group_size(&UsageScenario::MediumGroup) > get_group_size(&UsageScenario::SmallChat));
+        assert!(get_group_size(&UsageScenario::LargeChannel) > get_group_size(&UsageScenario::MediumGroup));
+    }
+
+    #[test]
+    fn test_direct_message_is_pairwise_ratchet() {
+        let scenario = UsageScenario::DirectMessage;
+        assert_eq!(get_group_size(&scenario), 2);
+        assert!(scenario.ratchets_per_message());
+        let groups: Vec<UsageScenario> = UsageScenario::ALL.into_iter().filter(|group| *group != scenario).collect();
+        assert!(groups.iter().all(|group| !group.ratchets_per_message()));
+        // Passo DH a cada vez de falar: rotação mais frequente que em qualquer grupo
+        assert!(groups.iter().all(|group| get_rotation_config(group) > get_rotation_config(&scenario)));
+    }
 }
\ No newline at end of file
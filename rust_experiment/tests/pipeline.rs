@@ -0,0 +1,62 @@
+//! Teste de integração do pipeline completo do experimento
+//!
+//! Executa o binário com `--quick` em uma pasta temporária e valida o CSV gerado:
+//! uma linha por configuração da matriz reduzida e métricas principais finitas e positivas.
+
+use pq_crypto_matrix::crypto::{AGREEMENTS, CIPHERS};
+use pq_crypto_matrix::results::read_schema_version;
+use std::fs;
+use std::io::BufReader;
+use std::process::Command;
+
+/// Padrões de tráfego da matriz (Constant, Burst, Periodic, Random, Realistic)
+const PADROES_TRAFEGO: usize = 5;
+
+#[test]
+fn test_quick_run_writes_full_matrix() {
+    let dir = std::env::temp_dir().join(format!("pq_crypto_matrix_pipeline_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    // A pasta de análise aponta para o diretório temporário: sem gerar_graficos.py,
+    // a etapa de gráficos é ignorada
+    let output = Command::new(env!("CARGO_BIN_EXE_pq_crypto_matrix"))
+        .args(["--quick", "--jobs", "4"])
+        .arg("--output-dir").arg(&dir)
+        .arg("--analysis-dir").arg(&dir)
+        .arg("--plots-dir").arg(&dir)
+        .output()
+        .expect("Não foi possível executar o experimento");
+    assert!(output.status.success(), "experimento falhou: {}", String::from_utf8_lossy(&output.stderr));
+
+    let csv_path = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .find(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.starts_with("resultados_normality_check_") && name.ends_with(".csv")
+        })
+        .expect("CSV de resultados não encontrado");
+
+    let file = fs::File::open(&csv_path).unwrap();
+    read_schema_version(BufReader::new(file)).expect("Versão de esquema inválida");
+
+    let content = fs::read_to_string(&csv_path).unwrap();
+    let mut lines = content.lines();
+    let header: Vec<&str> = lines.next().unwrap().split(',').collect();
+    let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+
+    // Matriz reduzida: apenas SmallChat, todos os padrões, acordos e cifras
+    assert_eq!(rows.len(), PADROES_TRAFEGO * AGREEMENTS.len() * CIPHERS.len());
+
+    for column in ["kem_ms_mean", "cipher_ms_mean", "cipher_only_ms_mean", "kem_bw_bytes_mean", "msg_bw_bytes_mean", "kem_bw_bps", "msg_bw_bps", "storage_bytes"] {
+        let index = header.iter().position(|name| *name == column)
+            .unwrap_or_else(|| panic!("coluna ausente: {}", column));
+        for row in &rows {
+            assert_eq!(row.len(), header.len());
+            let value: f64 = row[index].parse().unwrap();
+            assert!(value.is_finite() && value > 0.0, "{} = {} em {:?}", column, value, row);
+        }
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}